@@ -13,7 +13,7 @@ use actix_web::{
     },
     error::Error,
     guard::Guard,
-    http::header::{DispositionType, ContentEncoding},
+    http::header::{ContentEncoding, DispositionType},
     HttpRequest,
 };
 use futures_core::future::LocalBoxFuture;
@@ -232,26 +232,30 @@ impl Files {
         self
     }
 
-    /// Specifies how to serve pre compressed files.
-    /// 
-    /// Default is empty(not to serve).
-    /// Priority of which encoding to be serving first is determined by the order of the vec,
-    /// first value will be prefer if client accept.
-    /// 
+    /// Serves pre-compressed variants of files to clients that accept their encoding.
+    ///
+    /// Each `(encoding, extension)` pair names a variant found next to a file by appending
+    /// `extension` to its name, e.g. `app.js.br` for `app.js` with `(Brotli, ".br")`. Among the
+    /// variants whose encoding the request's `Accept-Encoding` header accepts, ranked by their
+    /// quality value and then by the order given here, the first that exists is served with its
+    /// `Content-Encoding` set. Variants are served as they are; nothing is compressed or
+    /// decompressed on the fly. Otherwise, the file itself is served. All file responses get a
+    /// `Vary: Accept-Encoding` header.
+    ///
+    /// By default, no pre-compressed variants are served.
+    ///
     /// # Examples
     /// ```
     /// use actix_files::Files;
     /// use actix_web::http::header::ContentEncoding;
-    /// 
-    /// let files_service = Files::new("/", "./static")
-    ///     .use_precompressed(vec![
-    ///         (ContentEncoding::Brotli, ".br"), 
-    ///         (ContentEncoding::Gzip, ".gz")
-    ///     ]);
+    ///
+    /// let files_service = Files::new("/", "./static").use_precompressed(vec![
+    ///     (ContentEncoding::Brotli, ".br"),
+    ///     (ContentEncoding::Gzip, ".gz"),
+    /// ]);
     /// ```
     pub fn use_precompressed(mut self, value: Vec<(ContentEncoding, &'static str)>) -> Self {
         self.use_precompressed = value;
-        
         self
     }
 
@@ -238,41 +238,50 @@ impl NamedFile {
         Self::from_file(file, path)
     }
 
-    /// Attempt to open precompressed version of path asynchronously.
-    /// 
+    /// Attempts to open a pre-compressed variant of a file, falling back to the file itself.
+    ///
+    /// For each `(encoding, extension)` pair, in order, the file at `path` with `extension`
+    /// appended is tried. The first that exists is opened with its content encoding set to
+    /// `encoding`, and with the content type and disposition of `path`. The variant is served as
+    /// is; it is never decompressed. If no variant exists, `path` itself is opened.
+    ///
     /// # Examples
     /// ```
     /// use actix_files::NamedFile;
     /// use actix_web::http::header::ContentEncoding;
-    /// 
-    /// # async fn open() {
-    /// // file1 should be exactly the same as file2 if foo.txt.br exist.
-    /// let file1 = NamedFile::open_compressed("foo.txt", encodings: &vec![
-    ///     (ContentEncoding::Brotli, ".br"),
-    ///     (ContentEncoding::Gzip, ".gz")
-    /// ]).await.unwrap();
-    /// let file2 = NamedFile::open_async("foo.txt.br").await.unwrap().set_content_encoding(ContentEncoding::Brotli);
+    ///
+    /// # async fn open() -> std::io::Result<()> {
+    /// // opens `app.js.br` if it exists, then `app.js.gz`, then `app.js`
+    /// let file = NamedFile::open_compressed(
+    ///     "app.js",
+    ///     &[(ContentEncoding::Brotli, ".br"), (ContentEncoding::Gzip, ".gz")],
+    /// )
+    /// .await?;
+    /// # Ok(())
     /// # }
     /// ```
-    pub async fn open_compressed<P: AsRef<Path>>(path: P, encodings: &[(ContentEncoding, &'static str)]) -> io::Result<NamedFile> {
-        use std::ffi::OsString;
-        for encoding in encodings {
-            let mut extended_path = OsString::from(path.as_ref().as_os_str());
-            extended_path.push(encoding.1);
-            let open_result = {
+    pub async fn open_compressed<P: AsRef<Path>>(
+        path: P,
+        encodings: &[(ContentEncoding, &'static str)],
+    ) -> io::Result<NamedFile> {
+        for &(encoding, extension) in encodings {
+            let mut variant = path.as_ref().as_os_str().to_owned();
+            variant.push(extension);
+
+            let file = {
                 #[cfg(not(feature = "experimental-io-uring"))]
                 {
-                    File::open(&extended_path)
+                    File::open(&variant)
                 }
-    
+
                 #[cfg(feature = "experimental-io-uring")]
                 {
-                    File::open(&extended_path).await
+                    File::open(&variant).await
                 }
             };
-            if let Ok(file) = open_result {
-                let named_file = Self::from_file(file, path)?;
-                return Ok(named_file.set_content_encoding(encoding.0.clone()))
+
+            if let Ok(file) = file {
+                return Ok(Self::from_file(file, path)?.set_content_encoding(encoding));
             }
         }
 
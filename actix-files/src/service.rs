@@ -5,8 +5,13 @@ use actix_web::{
     dev::{self, Service, ServiceRequest, ServiceResponse},
     error::Error,
     guard::Guard,
-    http::{header::{self, ContentEncoding}, Method},
-    HttpResponse,
+    http::{
+        header::{
+            self, AcceptEncoding, ContentEncoding, Encoding, HeaderValue, Preference, Quality,
+        },
+        Method,
+    },
+    HttpMessage as _, HttpResponse,
 };
 use futures_core::future::LocalBoxFuture;
 
@@ -39,7 +44,7 @@ pub struct FilesServiceInner {
     pub(crate) file_flags: named::Flags,
     pub(crate) guards: Option<Rc<dyn Guard>>,
     pub(crate) hidden_files: bool,
-    pub(crate) use_precompressed: Vec<(ContentEncoding, &'static str)>
+    pub(crate) use_precompressed: Vec<(ContentEncoding, &'static str)>,
 }
 
 impl fmt::Debug for FilesServiceInner {
@@ -75,10 +80,59 @@ impl FilesService {
         named_file.flags = self.file_flags;
 
         let (req, _) = req.into_parts();
-        let res = named_file.into_response(&req);
+        let mut res = named_file.into_response(&req);
+
+        // the selected variant depends on the request's accepted encodings
+        if !self.use_precompressed.is_empty() {
+            res.headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+        }
+
         ServiceResponse::new(req, res)
     }
 
+    /// Returns the pre-compressed variants that the client accepts, most preferred first.
+    ///
+    /// Variants are ranked by the quality the client's `Accept-Encoding` header gives their
+    /// encoding, then by the order in which they were configured.
+    fn acceptable_precompressed(
+        &self,
+        req: &ServiceRequest,
+    ) -> Vec<(ContentEncoding, &'static str)> {
+        let accept = match req.get_header::<AcceptEncoding>() {
+            Some(accept) if !self.use_precompressed.is_empty() => accept,
+            _ => return Vec::new(),
+        };
+
+        let quality = |encoding: ContentEncoding| {
+            let mut any = Quality::ZERO;
+
+            for item in accept.iter() {
+                match item.item {
+                    Preference::Specific(Encoding::Known(enc)) if enc == encoding => {
+                        return item.quality
+                    }
+                    Preference::Any => any = item.quality,
+                    _ => {}
+                }
+            }
+
+            any
+        };
+
+        let mut variants = self
+            .use_precompressed
+            .iter()
+            .map(|&(encoding, ext)| (quality(encoding), (encoding, ext)))
+            .filter(|(quality, _)| *quality > Quality::ZERO)
+            .collect::<Vec<_>>();
+
+        // stable sort keeps configured order for equal qualities
+        variants.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        variants.into_iter().map(|(_, variant)| variant).collect()
+    }
+
     fn show_index(&self, req: ServiceRequest, path: PathBuf) -> ServiceResponse {
         let dir = Directory::new(self.directory.clone(), path);
 
@@ -145,17 +199,8 @@ impl Service<ServiceRequest> for FilesService {
                 return this.handle_err(err, req).await;
             }
 
-            let mut available_encoding: Vec<(ContentEncoding, &'static str)> = Vec::new();
-            let accept_encoding = req.headers().get(header::ACCEPT_ENCODING);
-            if let Some(accept_encoding) = accept_encoding {
-                let accept_encoding = accept_encoding.to_str().unwrap_or("");
-                for encoding in &this.use_precompressed {
-                    if accept_encoding.contains(encoding.0.as_str()) {
-                        available_encoding.push(encoding.clone());
-                    }
-                }
-            }
-            
+            let encodings = this.acceptable_precompressed(&req);
+
             if path.is_dir() {
                 if this.redirect_to_slash
                     && !req.path().ends_with('/')
@@ -173,7 +218,7 @@ impl Service<ServiceRequest> for FilesService {
                 match this.index {
                     Some(ref index) => {
                         let named_path = path.join(index);
-                        match NamedFile::open_compressed(named_path, &available_encoding).await {
+                        match NamedFile::open_compressed(named_path, &encodings).await {
                             Ok(named_file) => Ok(this.serve_named_file(req, named_file)),
                             Err(_) if this.show_index => Ok(this.show_index(req, path)),
                             Err(err) => this.handle_err(err, req).await,
@@ -186,19 +231,8 @@ impl Service<ServiceRequest> for FilesService {
                     )),
                 }
             } else {
-                match NamedFile::open_compressed(&path, &available_encoding).await {
-                    Ok(mut named_file) => {
-                        if let Some(ref mime_override) = this.mime_override {
-                            let new_disposition =
-                                mime_override(&named_file.content_type.type_());
-                            named_file.content_disposition.disposition = new_disposition;
-                        }
-                        named_file.flags = this.file_flags;
-
-                        let (req, _) = req.into_parts();
-                        let res = named_file.into_response(&req);
-                        Ok(ServiceResponse::new(req, res))
-                    }
+                match NamedFile::open_compressed(&path, &encodings).await {
+                    Ok(named_file) => Ok(this.serve_named_file(req, named_file)),
                     Err(err) => this.handle_err(err, req).await,
                 }
             }
@@ -1,7 +1,7 @@
 use actix_files::{Files, NamedFile};
 use actix_web::{
     http::{
-        header::{self, HeaderValue},
+        header::{self, ContentEncoding, HeaderValue},
         StatusCode,
     },
     test::{self, TestRequest},
@@ -64,3 +64,51 @@ async fn partial_range_response_encoding() {
         "identity"
     );
 }
+
+#[actix_web::test]
+async fn precompressed_variants() {
+    let srv = test::init_service(App::new().service(
+        Files::new("/", "./tests/fixtures/precompressed").use_precompressed(vec![
+            (ContentEncoding::Brotli, ".br"),
+            (ContentEncoding::Gzip, ".gz"),
+        ]),
+    ))
+    .await;
+
+    let get = |accept_encoding: Option<&'static str>| {
+        let mut req = TestRequest::with_uri("/app.js");
+        if let Some(accept_encoding) = accept_encoding {
+            req = req.insert_header((header::ACCEPT_ENCODING, accept_encoding));
+        }
+        req.to_request()
+    };
+
+    for (accept_encoding, encoding, file) in [
+        (Some("br"), Some("br"), "app.js.br"),
+        (Some("gzip, deflate, br"), Some("br"), "app.js.br"),
+        (Some("br;q=0.5, gzip"), Some("gzip"), "app.js.gz"),
+        (Some("br;q=0, *"), Some("gzip"), "app.js.gz"),
+        (Some("deflate"), None, "app.js"),
+        (None, None, "app.js"),
+    ] {
+        let res = test::call_service(&srv, get(accept_encoding)).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()
+                .get(header::CONTENT_ENCODING)
+                .map(|val| val.to_str().unwrap()),
+            encoding,
+            "{:?}",
+            accept_encoding
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/javascript"
+        );
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+
+        let expected =
+            std::fs::read(format!("./tests/fixtures/precompressed/{}", file)).unwrap();
+        assert_eq!(test::read_body(res).await, expected);
+    }
+}
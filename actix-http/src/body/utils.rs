@@ -3,6 +3,7 @@ use std::task::Poll;
 use actix_rt::pin;
 use actix_utils::future::poll_fn;
 use bytes::{Bytes, BytesMut};
+use derive_more::{Display, Error};
 use futures_core::ready;
 
 use super::{BodySize, MessageBody};
@@ -52,6 +53,68 @@ pub async fn to_bytes<B: MessageBody>(body: B) -> Result<Bytes, B::Error> {
     Ok(buf.freeze())
 }
 
+/// Error type returned from [`to_bytes_limited`] when body produced exceeds limit.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "limit exceeded while collecting body bytes")]
+#[non_exhaustive]
+pub struct BodyLimitExceeded;
+
+/// Collects the bytes produced by a `MessageBody` implementation into `Bytes` up to a limit.
+///
+/// If the body's size hint or the bytes collected exceed `limit`, the rest of the body is left
+/// unread and `Err(BodyLimitExceeded)` is returned. Errors produced by the body stream itself are
+/// returned in the inner `Result`.
+///
+/// # Examples
+/// ```
+/// use actix_http::body::{self, to_bytes_limited};
+/// use bytes::Bytes;
+///
+/// # async fn test_to_bytes_limited() {
+/// let body = Bytes::from_static(b"123");
+/// let bytes = to_bytes_limited(body, 10).await.unwrap().unwrap();
+/// assert_eq!(bytes, b"123"[..]);
+///
+/// let body = Bytes::from_static(b"123");
+/// assert!(to_bytes_limited(body, 2).await.is_err());
+/// # }
+/// ```
+pub async fn to_bytes_limited<B: MessageBody>(
+    body: B,
+    limit: usize,
+) -> Result<Result<Bytes, B::Error>, BodyLimitExceeded> {
+    let cap = match body.size() {
+        BodySize::None | BodySize::Sized(0) => return Ok(Ok(Bytes::new())),
+        BodySize::Sized(size) if size as usize > limit => return Err(BodyLimitExceeded),
+        BodySize::Sized(size) => size as usize,
+        // good enough first guess for chunk size
+        BodySize::Stream => 32_768.min(limit),
+    };
+
+    let mut buf = BytesMut::with_capacity(cap);
+
+    pin!(body);
+
+    let res = poll_fn(|cx| loop {
+        let body = body.as_mut();
+
+        match ready!(body.poll_next(cx)) {
+            Some(Ok(bytes)) => {
+                if buf.len() + bytes.len() > limit {
+                    return Poll::Ready(Err(BodyLimitExceeded));
+                }
+
+                buf.extend_from_slice(&bytes)
+            }
+            None => return Poll::Ready(Ok(Ok(()))),
+            Some(Err(err)) => return Poll::Ready(Ok(Err(err))),
+        }
+    })
+    .await?;
+
+    Ok(res.map(|()| buf.freeze()))
+}
+
 #[cfg(test)]
 mod test {
     use futures_util::{stream, StreamExt as _};
@@ -74,4 +137,28 @@ mod test {
         let bytes = to_bytes(body).await.unwrap();
         assert_eq!(bytes, b"123abc"[..]);
     }
+
+    #[actix_rt::test]
+    async fn test_to_bytes_limited() {
+        let bytes = to_bytes_limited((), 0).await.unwrap().unwrap();
+        assert!(bytes.is_empty());
+
+        let body = Bytes::from_static(b"123");
+        let bytes = to_bytes_limited(body, 3).await.unwrap().unwrap();
+        assert_eq!(bytes, b"123"[..]);
+
+        let body = Bytes::from_static(b"123");
+        to_bytes_limited(body, 2).await.unwrap_err();
+
+        let stream = stream::iter(vec![Bytes::from_static(b"123"), Bytes::from_static(b"abc")])
+            .map(Ok::<_, Error>);
+        let body = BodyStream::new(stream);
+        let bytes = to_bytes_limited(body, 6).await.unwrap().unwrap();
+        assert_eq!(bytes, b"123abc"[..]);
+
+        let stream = stream::iter(vec![Bytes::from_static(b"123"), Bytes::from_static(b"abc")])
+            .map(Ok::<_, Error>);
+        let body = BodyStream::new(stream);
+        to_bytes_limited(body, 5).await.unwrap_err();
+    }
 }
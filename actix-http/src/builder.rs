@@ -19,6 +19,14 @@ pub struct HttpServiceBuilder<T, S, X = ExpectHandler, U = UpgradeHandler> {
     client_disconnect_timeout: Duration,
     secure: bool,
     local_addr: Option<net::SocketAddr>,
+    max_header_count: usize,
+    max_header_size: usize,
+    h1_pipeline_concurrency: usize,
+    h1_framing_strictness: h1::FramingStrictness,
+    h1_header_folding: h1::HeaderFolding,
+    h1_payload_metrics: Option<h1::PayloadMetrics>,
+    h1_memory_budget: Option<h1::MemoryBudget>,
+    proxy_protocol: bool,
     expect: X,
     upgrade: Option<U>,
     on_connect_ext: Option<Rc<ConnectCallback<T>>>,
@@ -40,6 +48,14 @@ where
             client_disconnect_timeout: Duration::ZERO,
             secure: false,
             local_addr: None,
+            max_header_count: h1::DEFAULT_MAX_HEADER_COUNT,
+            max_header_size: h1::DEFAULT_MAX_HEADER_SIZE,
+            h1_pipeline_concurrency: 1,
+            h1_framing_strictness: h1::FramingStrictness::default(),
+            h1_header_folding: h1::HeaderFolding::default(),
+            h1_payload_metrics: None,
+            h1_memory_budget: None,
+            proxy_protocol: false,
 
             // dispatcher parts
             expect: ExpectHandler,
@@ -85,12 +101,17 @@ where
         self
     }
 
-    /// Set client request timeout (for first request).
+    /// Set client request timeout.
     ///
     /// Defines a timeout for reading client request header. If the client does not transmit the
     /// request head within this duration, the connection is terminated with a `408 Request Timeout`
     /// response error.
     ///
+    /// The timeout applies to the first request on a connection from the moment the connection is
+    /// established and to follow-up requests from the moment their first bytes are received after
+    /// the keep-alive phase. It is independent of the keep-alive timeout and does not apply to
+    /// reading request bodies.
+    ///
     /// A duration of zero disables the timeout.
     ///
     /// By default, the client timeout is 5 seconds.
@@ -124,6 +145,99 @@ where
         self.client_disconnect_timeout(dur)
     }
 
+    /// Set the maximum number of header fields accepted in an HTTP/1 request head.
+    ///
+    /// Requests with more header fields are rejected with a `431 Request Header Fields Too Large`
+    /// response error.
+    ///
+    /// By default, up to 96 header fields are accepted.
+    pub fn max_header_count(mut self, count: usize) -> Self {
+        self.max_header_count = count;
+        self
+    }
+
+    /// Set the maximum size, in bytes, of an HTTP/1 request head, including the request line.
+    ///
+    /// Requests with a larger head are rejected with a `431 Request Header Fields Too Large`
+    /// response error.
+    ///
+    /// By default, the limit is 128KiB.
+    pub fn max_header_size(mut self, size: usize) -> Self {
+        self.max_header_size = size;
+        self
+    }
+
+    /// Set the maximum number of pipelined HTTP/1 requests processed concurrently on a connection.
+    ///
+    /// By default, pipelined requests are processed one after another. With a limit greater than
+    /// one, `GET` and `HEAD` requests without a body that are received while an earlier `GET` or
+    /// `HEAD` request is still being processed have their service called straight away, as long
+    /// as all other earlier requests have had their service called already. Their responses are
+    /// buffered until all earlier responses have been written, so responses are always sent in
+    /// request order. At most `limit - 1` such responses are buffered per connection.
+    ///
+    /// Has no effect on HTTP/2 connections, which multiplex requests natively.
+    pub fn h1_pipeline_concurrency(mut self, limit: usize) -> Self {
+        self.h1_pipeline_concurrency = limit;
+        self
+    }
+
+    /// Set how strictly the headers that determine the length of HTTP/1 request bodies are checked.
+    ///
+    /// Requests that fail the checks are rejected with a `400 Bad Request` response and the
+    /// connection is closed. See [`h1::FramingStrictness`] for the available levels.
+    ///
+    /// By default, [`FramingStrictness::Strict`](h1::FramingStrictness::Strict) is used.
+    pub fn h1_framing_strictness(mut self, strictness: h1::FramingStrictness) -> Self {
+        self.h1_framing_strictness = strictness;
+        self
+    }
+
+    /// Set how obsolete line folding in HTTP/1 request headers is handled.
+    ///
+    /// Rejected requests receive a `400 Bad Request` response and the connection is closed. See
+    /// [`h1::HeaderFolding`] for the available options.
+    ///
+    /// By default, [`HeaderFolding::Reject`](h1::HeaderFolding::Reject) is used.
+    pub fn h1_header_folding(mut self, folding: h1::HeaderFolding) -> Self {
+        self.h1_header_folding = folding;
+        self
+    }
+
+    /// Set counters to be updated by the payloads of HTTP/1 requests.
+    ///
+    /// Pass a clone of the same [`h1::PayloadMetrics`] to the services of all workers to aggregate
+    /// their payload statistics. By default, no metrics are collected.
+    pub fn h1_payload_metrics(mut self, metrics: h1::PayloadMetrics) -> Self {
+        self.h1_payload_metrics = Some(metrics);
+        self
+    }
+
+    /// Set a limit on the bytes buffered by HTTP/1 connections.
+    ///
+    /// Pass a clone of the same [`h1::MemoryBudget`] to the services of all workers to share the
+    /// budget between them. When it is exceeded, the idle connections using the most memory are
+    /// closed with a `503 Service Unavailable` response. By default, memory is not limited.
+    pub fn h1_memory_budget(mut self, budget: h1::MemoryBudget) -> Self {
+        self.h1_memory_budget = Some(budget);
+        self
+    }
+
+    /// Set whether TCP connections start with a PROXY protocol header.
+    ///
+    /// When enabled, the [`tcp`](HttpService::tcp) and `tcp_auto_h2c` services read a version 1
+    /// (text) or version 2 (binary) PROXY protocol header, as sent by load balancers such as
+    /// HAProxy, before any HTTP data. The source address in the header is used as the peer address
+    /// of the connection's requests. Connections that do not start with a valid header, or that
+    /// do not send one within the client request timeout, are closed.
+    ///
+    /// Only enable this for listeners that are exclusively reachable through a proxy that adds
+    /// the header, since otherwise clients can spoof their address. By default, this is disabled.
+    pub fn proxy_protocol(mut self, enabled: bool) -> Self {
+        self.proxy_protocol = enabled;
+        self
+    }
+
     /// Provide service for `EXPECT: 100-Continue` support.
     ///
     /// Service get called with request that contains `EXPECT` header.
@@ -142,6 +256,14 @@ where
             client_disconnect_timeout: self.client_disconnect_timeout,
             secure: self.secure,
             local_addr: self.local_addr,
+            max_header_count: self.max_header_count,
+            max_header_size: self.max_header_size,
+            h1_pipeline_concurrency: self.h1_pipeline_concurrency,
+            h1_framing_strictness: self.h1_framing_strictness,
+            h1_header_folding: self.h1_header_folding,
+            h1_payload_metrics: self.h1_payload_metrics,
+            h1_memory_budget: self.h1_memory_budget,
+            proxy_protocol: self.proxy_protocol,
             expect: expect.into_factory(),
             upgrade: self.upgrade,
             on_connect_ext: self.on_connect_ext,
@@ -166,6 +288,14 @@ where
             client_disconnect_timeout: self.client_disconnect_timeout,
             secure: self.secure,
             local_addr: self.local_addr,
+            max_header_count: self.max_header_count,
+            max_header_size: self.max_header_size,
+            h1_pipeline_concurrency: self.h1_pipeline_concurrency,
+            h1_framing_strictness: self.h1_framing_strictness,
+            h1_header_folding: self.h1_header_folding,
+            h1_payload_metrics: self.h1_payload_metrics,
+            h1_memory_budget: self.h1_memory_budget,
+            proxy_protocol: self.proxy_protocol,
             expect: self.expect,
             upgrade: Some(upgrade.into_factory()),
             on_connect_ext: self.on_connect_ext,
@@ -195,12 +325,20 @@ where
         S::InitError: fmt::Debug,
         S::Response: Into<Response<B>>,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_limits(
             self.keep_alive,
             self.client_request_timeout,
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
+            self.max_header_count,
+            self.max_header_size,
+            self.h1_pipeline_concurrency,
+            self.h1_framing_strictness,
+            self.h1_header_folding,
+            self.h1_payload_metrics,
+            self.h1_memory_budget,
+            self.proxy_protocol,
         );
 
         H1Service::with_config(cfg, service.into_factory())
@@ -220,12 +358,20 @@ where
 
         B: MessageBody + 'static,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_limits(
             self.keep_alive,
             self.client_request_timeout,
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
+            self.max_header_count,
+            self.max_header_size,
+            self.h1_pipeline_concurrency,
+            self.h1_framing_strictness,
+            self.h1_header_folding,
+            self.h1_payload_metrics,
+            self.h1_memory_budget,
+            self.proxy_protocol,
         );
 
         crate::h2::H2Service::with_config(cfg, service.into_factory())
@@ -242,12 +388,20 @@ where
 
         B: MessageBody + 'static,
     {
-        let cfg = ServiceConfig::new(
+        let cfg = ServiceConfig::with_limits(
             self.keep_alive,
             self.client_request_timeout,
             self.client_disconnect_timeout,
             self.secure,
             self.local_addr,
+            self.max_header_count,
+            self.max_header_size,
+            self.h1_pipeline_concurrency,
+            self.h1_framing_strictness,
+            self.h1_header_folding,
+            self.h1_payload_metrics,
+            self.h1_memory_budget,
+            self.proxy_protocol,
         );
 
         HttpService::with_config(cfg, service.into_factory())
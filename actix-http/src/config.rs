@@ -1,12 +1,19 @@
 use std::{
-    net,
+    cmp, net,
     rc::Rc,
     time::{Duration, Instant},
 };
 
 use bytes::BytesMut;
 
-use crate::{date::DateService, KeepAlive};
+use crate::{
+    date::DateService,
+    h1::{
+        FramingStrictness, HeaderFolding, MemoryBudget, PayloadMetrics, MAX_BUFFER_SIZE,
+        MAX_HEADERS,
+    },
+    KeepAlive,
+};
 
 /// HTTP service configuration.
 #[derive(Debug, Clone)]
@@ -19,6 +26,14 @@ struct Inner {
     client_disconnect_timeout: Duration,
     secure: bool,
     local_addr: Option<std::net::SocketAddr>,
+    max_header_count: usize,
+    max_header_size: usize,
+    h1_pipeline_concurrency: usize,
+    h1_framing_strictness: FramingStrictness,
+    h1_header_folding: HeaderFolding,
+    h1_payload_metrics: Option<PayloadMetrics>,
+    h1_memory_budget: Option<MemoryBudget>,
+    proxy_protocol: bool,
     date_service: DateService,
 }
 
@@ -42,6 +57,39 @@ impl ServiceConfig {
         client_disconnect_timeout: Duration,
         secure: bool,
         local_addr: Option<net::SocketAddr>,
+    ) -> ServiceConfig {
+        Self::with_limits(
+            keep_alive,
+            client_request_timeout,
+            client_disconnect_timeout,
+            secure,
+            local_addr,
+            MAX_HEADERS,
+            MAX_BUFFER_SIZE,
+            1,
+            FramingStrictness::default(),
+            HeaderFolding::default(),
+            None,
+            None,
+            false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn with_limits(
+        keep_alive: KeepAlive,
+        client_request_timeout: Duration,
+        client_disconnect_timeout: Duration,
+        secure: bool,
+        local_addr: Option<net::SocketAddr>,
+        max_header_count: usize,
+        max_header_size: usize,
+        h1_pipeline_concurrency: usize,
+        h1_framing_strictness: FramingStrictness,
+        h1_header_folding: HeaderFolding,
+        h1_payload_metrics: Option<PayloadMetrics>,
+        h1_memory_budget: Option<MemoryBudget>,
+        proxy_protocol: bool,
     ) -> ServiceConfig {
         ServiceConfig(Rc::new(Inner {
             keep_alive: keep_alive.normalize(),
@@ -49,6 +97,14 @@ impl ServiceConfig {
             client_disconnect_timeout,
             secure,
             local_addr,
+            max_header_count,
+            max_header_size,
+            h1_pipeline_concurrency: cmp::max(h1_pipeline_concurrency, 1),
+            h1_framing_strictness,
+            h1_header_folding,
+            h1_payload_metrics,
+            h1_memory_budget,
+            proxy_protocol,
             date_service: DateService::new(),
         }))
     }
@@ -67,6 +123,54 @@ impl ServiceConfig {
         self.0.local_addr
     }
 
+    /// Maximum number of header fields accepted in an HTTP/1 request head.
+    #[inline]
+    pub fn max_header_count(&self) -> usize {
+        self.0.max_header_count
+    }
+
+    /// Maximum size, in bytes, of an HTTP/1 request head, including the request line.
+    #[inline]
+    pub fn max_header_size(&self) -> usize {
+        self.0.max_header_size
+    }
+
+    /// Maximum number of pipelined HTTP/1 requests processed concurrently on a connection.
+    #[inline]
+    pub fn h1_pipeline_concurrency(&self) -> usize {
+        self.0.h1_pipeline_concurrency
+    }
+
+    /// Strictness of checks on the headers that determine the length of HTTP/1 request bodies.
+    #[inline]
+    pub fn h1_framing_strictness(&self) -> FramingStrictness {
+        self.0.h1_framing_strictness
+    }
+
+    /// Handling of obsolete line folding in HTTP/1 request headers.
+    #[inline]
+    pub fn h1_header_folding(&self) -> HeaderFolding {
+        self.0.h1_header_folding
+    }
+
+    /// Counters updated by the payloads of HTTP/1 requests, if enabled.
+    #[inline]
+    pub fn h1_payload_metrics(&self) -> Option<&PayloadMetrics> {
+        self.0.h1_payload_metrics.as_ref()
+    }
+
+    /// Budget shared by the buffers of HTTP/1 connections, if enabled.
+    #[inline]
+    pub fn h1_memory_budget(&self) -> Option<&MemoryBudget> {
+        self.0.h1_memory_budget.as_ref()
+    }
+
+    /// Returns `true` if TCP connections start with a PROXY protocol header.
+    #[inline]
+    pub fn proxy_protocol(&self) -> bool {
+        self.0.proxy_protocol
+    }
+
     /// Connection keep-alive setting.
     #[inline]
     pub fn keep_alive(&self) -> KeepAlive {
@@ -86,7 +190,7 @@ impl ServiceConfig {
     }
 
     /// Creates a time object representing the deadline for the client to finish sending the head of
-    /// its first request.
+    /// a request.
     ///
     /// Returns `None` if this `ServiceConfig was` constructed with `client_request_timeout: 0`.
     pub fn client_request_deadline(&self) -> Option<Instant> {
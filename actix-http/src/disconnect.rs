@@ -0,0 +1,110 @@
+use std::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Debug, Default)]
+struct Inner {
+    disconnected: Cell<bool>,
+    wakers: RefCell<Vec<Waker>>,
+}
+
+/// Future that resolves when the client disconnects before a response has been fully sent.
+///
+/// HTTP/1 connections add a `ResponseDisconnect` to the extensions of each request. It resolves
+/// if the connection is dropped, for example because writing to the socket failed, before the
+/// response body has been completely written. Producers of expensive streaming responses can use
+/// it to stop early. If the response is sent successfully, it never resolves.
+///
+/// A `ResponseDisconnect` created through [`Default`] never resolves.
+#[derive(Debug, Clone, Default)]
+pub struct ResponseDisconnect {
+    inner: Rc<Inner>,
+}
+
+impl ResponseDisconnect {
+    /// Creates a new handle along with the trigger used by the dispatcher to resolve it.
+    pub(crate) fn new() -> (Self, DisconnectTrigger) {
+        let inner = Rc::new(Inner::default());
+
+        (
+            ResponseDisconnect {
+                inner: Rc::clone(&inner),
+            },
+            DisconnectTrigger { inner: Some(inner) },
+        )
+    }
+
+    /// Returns true if the client has disconnected before the response was fully sent.
+    pub fn is_disconnected(&self) -> bool {
+        self.inner.disconnected.get()
+    }
+}
+
+impl Future for ResponseDisconnect {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.is_disconnected() {
+            return Poll::Ready(());
+        }
+
+        let mut wakers = self.inner.wakers.borrow_mut();
+
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Resolves the associated [`ResponseDisconnect`] when dropped, unless it was disarmed first.
+#[derive(Debug)]
+pub(crate) struct DisconnectTrigger {
+    inner: Option<Rc<Inner>>,
+}
+
+impl DisconnectTrigger {
+    /// Marks the response as fully sent so that dropping this trigger has no effect.
+    pub(crate) fn disarm(mut self) {
+        self.inner = None;
+    }
+}
+
+impl Drop for DisconnectTrigger {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.disconnected.set(true);
+
+            for waker in inner.wakers.borrow_mut().drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt as _;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn resolves_on_drop() {
+        let (mut handle, trigger) = ResponseDisconnect::new();
+        assert!((&mut handle).now_or_never().is_none());
+
+        drop(trigger);
+        assert!(handle.is_disconnected());
+        assert!(handle.now_or_never().is_some());
+
+        let (handle, trigger) = ResponseDisconnect::new();
+        trigger.disarm();
+        assert!(!handle.is_disconnected());
+        assert!(handle.now_or_never().is_none());
+    }
+}
@@ -101,12 +101,14 @@ where
 
         loop {
             if let Some(ref mut fut) = this.fut {
-                let (chunk, decoder) = ready!(Pin::new(fut).poll(cx)).map_err(|_| {
-                    PayloadError::Io(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Blocking task was cancelled unexpectedly",
-                    ))
-                })??;
+                let (chunk, decoder) = ready!(Pin::new(fut).poll(cx))
+                    .map_err(|_| {
+                        PayloadError::Io(io::Error::new(
+                            io::ErrorKind::Other,
+                            "Blocking task was cancelled unexpectedly",
+                        ))
+                    })?
+                    .map_err(decode_error)?;
 
                 *this.decoder = Some(decoder);
                 this.fut.take();
@@ -126,7 +128,7 @@ where
                 Some(Ok(chunk)) => {
                     if let Some(mut decoder) = this.decoder.take() {
                         if chunk.len() < MAX_CHUNK_SIZE_DECODE_IN_PLACE {
-                            let chunk = decoder.feed_data(chunk)?;
+                            let chunk = decoder.feed_data(chunk).map_err(decode_error)?;
                             *this.decoder = Some(decoder);
 
                             if let Some(chunk) = chunk {
@@ -148,11 +150,13 @@ where
                 None => {
                     *this.eof = true;
 
+                    // finishing the decoder also verifies trailers, such as the CRC32 and size at the
+                    // end of gzip streams, so truncated or corrupted payloads fail here
                     return if let Some(mut decoder) = this.decoder.take() {
                         match decoder.feed_eof() {
                             Ok(Some(res)) => Poll::Ready(Some(Ok(res))),
                             Ok(None) => Poll::Ready(None),
-                            Err(err) => Poll::Ready(Some(Err(err.into()))),
+                            Err(err) => Poll::Ready(Some(Err(decode_error(err)))),
                         }
                     } else {
                         Poll::Ready(None)
@@ -163,6 +167,14 @@ where
     }
 }
 
+/// Converts an error returned by a [`ContentDecoder`] into a payload error.
+///
+/// Decoders only write to in-memory buffers, so their errors are caused by invalid input.
+fn decode_error(err: io::Error) -> PayloadError {
+    tracing::debug!("failed to decode payload: {}", err);
+    PayloadError::EncodingCorrupted
+}
+
 enum ContentDecoder {
     #[cfg(feature = "compress-gzip")]
     Deflate(Box<ZlibDecoder<Writer>>),
@@ -302,3 +314,58 @@ impl ContentDecoder {
         }
     }
 }
+
+#[cfg(all(test, feature = "compress-gzip"))]
+mod tests {
+    use futures_util::{stream, StreamExt as _};
+
+    use super::*;
+
+    async fn decode(encoding: ContentEncoding, data: &[u8]) -> (Vec<u8>, Option<PayloadError>) {
+        let chunks = data
+            .chunks(7)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let mut decoder = Decoder::new(stream::iter(chunks), encoding);
+
+        let mut decoded = Vec::new();
+
+        while let Some(item) = decoder.next().await {
+            match item {
+                Ok(chunk) => decoded.extend_from_slice(&chunk),
+                Err(err) => return (decoded, Some(err)),
+            }
+        }
+
+        (decoded, None)
+    }
+
+    #[actix_rt::test]
+    async fn gzip_trailer() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let data = b"abcdefghijklmnopqrstuvwxyz".repeat(100);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data).unwrap();
+        let gzip = encoder.finish().unwrap();
+
+        let (decoded, err) = decode(ContentEncoding::Gzip, &gzip).await;
+        assert_eq!(decoded, data);
+        assert!(err.is_none());
+
+        let len = gzip.len();
+        let mut bad_crc = gzip.clone();
+        bad_crc[len - 8] ^= 0xFF;
+        let mut bad_size = gzip.clone();
+        bad_size[len - 1] ^= 0xFF;
+        let truncated = gzip[..len - 3].to_vec();
+
+        for gzip in [bad_crc, bad_size, truncated] {
+            // decoded bytes are streamed before the trailer is verified
+            let (decoded, err) = decode(ContentEncoding::Gzip, &gzip).await;
+            assert_eq!(decoded, data);
+            assert!(matches!(err, Some(PayloadError::EncodingCorrupted)));
+        }
+    }
+}
@@ -30,12 +30,26 @@ use crate::{
 
 const MAX_CHUNK_SIZE_ENCODE_IN_PLACE: usize = 1024;
 
+/// Largest slice of a body chunk that is compressed per poll.
+///
+/// Larger chunks are split so that the compressed output can be written to the socket before the
+/// rest of the chunk is compressed.
+const MAX_CHUNK_SIZE_ENCODE_STEP: usize = 32_768;
+
 pin_project! {
+    /// Compressing wrapper around a response body.
+    ///
+    /// The wrapped body is only polled for its next chunk once the compressed output of previous
+    /// chunks has been taken by the caller. Since the HTTP/1 dispatcher stops polling the body while
+    /// its write buffer is full, a body that produces chunks faster than they can be compressed and
+    /// written to the socket is slowed down instead of being buffered.
     pub struct Encoder<B> {
         #[pin]
         body: EncoderBody<B>,
         encoder: Option<ContentEncoder>,
         fut: Option<JoinHandle<Result<ContentEncoder, io::Error>>>,
+        // remainder of a body chunk that is larger than `MAX_CHUNK_SIZE_ENCODE_STEP`
+        pending: Option<Bytes>,
         eof: bool,
     }
 }
@@ -48,6 +62,7 @@ impl<B: MessageBody> Encoder<B> {
             },
             encoder: None,
             fut: None,
+            pending: None,
             eof: true,
         }
     }
@@ -77,6 +92,7 @@ impl<B: MessageBody> Encoder<B> {
                     body,
                     encoder: Some(enc),
                     fut: None,
+                    pending: None,
                     eof: false,
                 };
             }
@@ -86,6 +102,7 @@ impl<B: MessageBody> Encoder<B> {
             body,
             encoder: None,
             fut: None,
+            pending: None,
             eof: false,
         }
     }
@@ -190,13 +207,21 @@ where
                 }
             }
 
-            let result = ready!(this.body.as_mut().poll_next(cx));
+            // finish compressing the current chunk before pulling the next one from the body
+            let result = match this.pending.take() {
+                Some(chunk) => Some(Ok(chunk)),
+                None => ready!(this.body.as_mut().poll_next(cx)),
+            };
 
             match result {
                 Some(Err(err)) => return Poll::Ready(Some(Err(err))),
 
-                Some(Ok(chunk)) => {
+                Some(Ok(mut chunk)) => {
                     if let Some(mut encoder) = this.encoder.take() {
+                        if chunk.len() > MAX_CHUNK_SIZE_ENCODE_STEP {
+                            *this.pending = Some(chunk.split_off(MAX_CHUNK_SIZE_ENCODE_STEP));
+                        }
+
                         if chunk.len() < MAX_CHUNK_SIZE_ENCODE_IN_PLACE {
                             encoder.write(&chunk).map_err(EncoderError::Io)?;
                             let chunk = encoder.take();
@@ -256,8 +281,20 @@ where
 fn update_head(encoding: ContentEncoding, head: &mut ResponseHead) {
     head.headers_mut()
         .insert(header::CONTENT_ENCODING, encoding.to_header_value());
-    head.headers_mut()
-        .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+    // the response may already vary on accept-encoding, e.g., when set by the app
+    let listed = head
+        .headers()
+        .get_all(header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .any(|val| val == "*" || val.eq_ignore_ascii_case("accept-encoding"));
+
+    if !listed {
+        head.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+    }
 
     head.no_chunking(false);
 }
@@ -428,3 +465,72 @@ impl From<EncoderError> for crate::Error {
         crate::Error::new_encoder().with_cause(err)
     }
 }
+
+#[cfg(all(test, feature = "compress-gzip"))]
+mod tests {
+    use std::{cell::Cell, convert::Infallible, rc::Rc};
+
+    use actix_utils::future::poll_fn;
+
+    use super::*;
+    use crate::body::BodyStream;
+
+    /// Incompressible bytes, so that compressed output tracks input closely.
+    fn noise(len: usize, seed: &mut u32) -> Bytes {
+        (0..len)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 17;
+                *seed ^= *seed << 5;
+                *seed as u8
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[actix_rt::test]
+    async fn compression_pulls_body_on_demand() {
+        const CHUNK_SIZE: usize = 256 * 1024;
+
+        let pulled = Rc::new(Cell::new(0));
+
+        // fast producer that never runs out of chunks
+        let body = BodyStream::new(futures_util::stream::poll_fn({
+            let pulled = Rc::clone(&pulled);
+            let mut seed = 1;
+
+            move |_| {
+                pulled.set(pulled.get() + CHUNK_SIZE);
+                Poll::Ready(Some(Ok::<_, Infallible>(noise(CHUNK_SIZE, &mut seed))))
+            }
+        }));
+
+        let mut head = ResponseHead::new(StatusCode::OK);
+        let mut encoder = Encoder::response(ContentEncoding::Gzip, &mut head, body);
+
+        let mut emitted = 0;
+
+        for _ in 0..64 {
+            let chunk = poll_fn(|cx| Pin::new(&mut encoder).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap();
+
+            // large body chunks are compressed in steps
+            assert!(
+                chunk.len() <= 2 * MAX_CHUNK_SIZE_ENCODE_STEP,
+                "{}",
+                chunk.len()
+            );
+            emitted += chunk.len();
+
+            // body is not pulled further than needed to produce the output taken so far
+            assert!(
+                pulled.get() <= emitted + CHUNK_SIZE + 2 * MAX_CHUNK_SIZE_ENCODE_STEP,
+                "pulled {} bytes but only emitted {}",
+                pulled.get(),
+                emitted,
+            );
+        }
+    }
+}
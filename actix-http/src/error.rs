@@ -275,6 +275,14 @@ pub enum PayloadError {
     #[display(fmt = "Payload length is unknown.")]
     UnknownLength,
 
+    /// Payload does not match the checksum sent with it.
+    #[display(fmt = "Payload does not match its checksum.")]
+    ChecksumMismatch,
+
+    /// Payload chunk was rejected by a validator.
+    #[display(fmt = "Payload chunk was rejected by a validator.")]
+    InvalidChunk,
+
     /// HTTP/2 payload error.
     #[cfg(feature = "http2")]
     #[display(fmt = "{}", _0)]
@@ -293,6 +301,8 @@ impl std::error::Error for PayloadError {
             PayloadError::EncodingCorrupted => None,
             PayloadError::Overflow => None,
             PayloadError::UnknownLength => None,
+            PayloadError::ChecksumMismatch => None,
+            PayloadError::InvalidChunk => None,
             #[cfg(feature = "http2")]
             PayloadError::Http2Payload(err) => Some(err),
             PayloadError::Io(err) => Some(err),
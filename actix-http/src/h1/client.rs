@@ -73,7 +73,11 @@ impl ClientCodec {
         ClientCodec {
             inner: ClientCodecInner {
                 config,
-                decoder: decoder::MessageDecoder::default(),
+                // response framing is not checked strictly, for compatibility with servers
+                decoder: decoder::MessageDecoder::with_limits(decoder::HeadLimits {
+                    framing: decoder::FramingStrictness::Lenient,
+                    ..decoder::HeadLimits::default()
+                }),
                 payload: None,
                 version: Version::HTTP_11,
                 conn_type: ConnectionType::Close,
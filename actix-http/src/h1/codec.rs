@@ -1,4 +1,4 @@
-use std::{fmt, io};
+use std::{convert::TryFrom, fmt, io};
 
 use bitflags::bitflags;
 use bytes::BytesMut;
@@ -59,10 +59,17 @@ impl Codec {
             Flags::empty()
         };
 
+        let decoder = decoder::MessageDecoder::with_limits(decoder::HeadLimits {
+            max_headers: config.max_header_count(),
+            max_size: config.max_header_size(),
+            framing: config.h1_framing_strictness(),
+            header_folding: config.h1_header_folding(),
+        });
+
         Codec {
             config,
             flags,
-            decoder: decoder::MessageDecoder::default(),
+            decoder,
             payload: None,
             version: Version::HTTP_11,
             conn_type: ConnectionType::Close,
@@ -100,6 +107,15 @@ impl Codec {
         }
     }
 
+    /// Returns the declared length of the last request's payload, if it has a fixed length.
+    #[inline]
+    pub(crate) fn payload_length(&self) -> Option<usize> {
+        self.payload
+            .as_ref()
+            .and_then(PayloadDecoder::remaining_length)
+            .and_then(|len| usize::try_from(len).ok())
+    }
+
     #[inline]
     pub fn config(&self) -> &ServiceConfig {
         &self.config
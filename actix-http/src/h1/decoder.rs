@@ -6,16 +6,97 @@ use http::{
     header::{self, HeaderName, HeaderValue},
     Method, StatusCode, Uri, Version,
 };
+use smallvec::{smallvec, SmallVec};
 use tracing::{debug, error, trace};
 
 use super::chunked::ChunkedState;
 use crate::{error::ParseError, header::HeaderMap, ConnectionType, Request, ResponseHead};
 
 pub(crate) const MAX_BUFFER_SIZE: usize = 131_072;
-const MAX_HEADERS: usize = 96;
+pub(crate) const MAX_HEADERS: usize = 96;
+
+/// Default limit on the number of header fields in an HTTP/1 request head.
+pub const DEFAULT_MAX_HEADER_COUNT: usize = MAX_HEADERS;
+
+/// Default limit on the size, in bytes, of an HTTP/1 request head.
+pub const DEFAULT_MAX_HEADER_SIZE: usize = MAX_BUFFER_SIZE;
+
+/// How strictly the headers that determine the length of a message body are checked.
+///
+/// Disagreement between these headers is the basis of request smuggling, where a proxy in front of
+/// the server and the server itself find the end of a request in different places. Messages with
+/// differing `Content-Length` values are always rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramingStrictness {
+    /// Reject messages with both `Content-Length` and `Transfer-Encoding: chunked` headers and
+    /// messages with more than one `Content-Length` header.
+    Strict,
+
+    /// Accept messages with both `Content-Length` and `Transfer-Encoding: chunked` headers, in
+    /// which case the chunked encoding takes precedence, and messages with repeated but identical
+    /// `Content-Length` headers, as allowed by RFC 7230 §3.3.
+    Lenient,
+}
+
+impl Default for FramingStrictness {
+    fn default() -> Self {
+        Self::Strict
+    }
+}
+
+/// How obsolete line folding in request headers is handled.
+///
+/// Obsolete line folding continues a header value on the next line, which starts with a space or
+/// tab. It was deprecated by RFC 7230 §3.2.4 because recipients that do not support it can
+/// interpret the continuation line as a separate header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFolding {
+    /// Reject requests with folded headers.
+    Reject,
+
+    /// Accept requests with folded headers, replacing each line fold with spaces before the head
+    /// is parsed, as allowed by RFC 7230 §3.2.4. Intended for legacy clients.
+    Normalize,
+}
+
+impl Default for HeaderFolding {
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
+/// Limits enforced while parsing a message head.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HeadLimits {
+    /// Maximum number of header fields.
+    pub(crate) max_headers: usize,
+
+    /// Maximum size of the head, in bytes, including the start line.
+    pub(crate) max_size: usize,
+
+    /// Strictness of checks on the headers that determine the body length.
+    pub(crate) framing: FramingStrictness,
+
+    /// Handling of obsolete line folding in request headers.
+    pub(crate) header_folding: HeaderFolding,
+}
+
+impl Default for HeadLimits {
+    fn default() -> Self {
+        Self {
+            max_headers: MAX_HEADERS,
+            max_size: MAX_BUFFER_SIZE,
+            framing: FramingStrictness::default(),
+            header_folding: HeaderFolding::default(),
+        }
+    }
+}
 
 /// Incoming message decoder
-pub(crate) struct MessageDecoder<T: MessageType>(PhantomData<T>);
+pub(crate) struct MessageDecoder<T: MessageType> {
+    limits: HeadLimits,
+    _phantom: PhantomData<T>,
+}
 
 #[derive(Debug)]
 /// Incoming request type
@@ -25,9 +106,18 @@ pub(crate) enum PayloadType {
     Stream(PayloadDecoder),
 }
 
+impl<T: MessageType> MessageDecoder<T> {
+    pub(crate) fn with_limits(limits: HeadLimits) -> Self {
+        MessageDecoder {
+            limits,
+            _phantom: PhantomData,
+        }
+    }
+}
+
 impl<T: MessageType> Default for MessageDecoder<T> {
     fn default() -> Self {
-        MessageDecoder(PhantomData)
+        Self::with_limits(HeadLimits::default())
     }
 }
 
@@ -36,7 +126,7 @@ impl<T: MessageType> Decoder for MessageDecoder<T> {
     type Error = ParseError;
 
     fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        T::decode(src)
+        T::decode(src, &self.limits)
     }
 }
 
@@ -70,13 +160,17 @@ pub(crate) trait MessageType: Sized {
 
     fn headers_mut(&mut self) -> &mut HeaderMap;
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError>;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError>;
 
     fn set_headers(
         &mut self,
         slice: &Bytes,
         raw_headers: &[HeaderIndex],
         version: Version,
+        framing: FramingStrictness,
     ) -> Result<PayloadLength, ParseError> {
         let mut ka = None;
         let mut has_upgrade_websocket = false;
@@ -100,7 +194,9 @@ pub(crate) trait MessageType: Sized {
                 };
 
                 match name {
-                    header::CONTENT_LENGTH if content_length.is_some() => {
+                    header::CONTENT_LENGTH
+                        if content_length.is_some() && framing == FramingStrictness::Strict =>
+                    {
                         debug!("multiple Content-Length");
                         return Err(ParseError::Header);
                     }
@@ -113,6 +209,11 @@ pub(crate) trait MessageType: Sized {
 
                         Ok(val) => {
                             if let Ok(len) = val.parse::<u64>() {
+                                if content_length.map_or(false, |prev| prev != len) {
+                                    debug!("conflicting Content-Length: {:?}", val);
+                                    return Err(ParseError::Header);
+                                }
+
                                 // accept 0 lengths here and remove them in `decode` after all
                                 // headers have been processed to prevent request smuggling issues
                                 content_length = Some(len);
@@ -196,6 +297,11 @@ pub(crate) trait MessageType: Sized {
             self.set_expect()
         }
 
+        if chunked && content_length.is_some() && framing == FramingStrictness::Strict {
+            debug!("both Content-Length and Transfer-Encoding: chunked");
+            return Err(ParseError::Header);
+        }
+
         // https://datatracker.ietf.org/doc/html/rfc7230#section-3.3.3
         if chunked {
             // Chunked encoding
@@ -230,22 +336,29 @@ impl MessageType for Request {
         &mut self.head_mut().headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
-        let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        let mut headers: SmallVec<[HeaderIndex; MAX_HEADERS]> =
+            smallvec![EMPTY_HEADER_INDEX; limits.max_headers];
+
+        // folded headers are rejected by the parser unless they are normalized first
+        if limits.header_folding == HeaderFolding::Normalize {
+            normalize_obs_folds(src);
+        }
 
         let (len, method, uri, ver, h_len) = {
-            // SAFETY:
-            // Create an uninitialized array of `MaybeUninit`. The `assume_init` is safe because the
-            // type we are claiming to have initialized here is a bunch of `MaybeUninit`s, which
-            // do not require initialization.
-            let mut parsed = unsafe {
-                MaybeUninit::<[MaybeUninit<httparse::Header<'_>>; MAX_HEADERS]>::uninit()
-                    .assume_init()
-            };
+            let mut parsed = uninit_headers(limits.max_headers);
 
             let mut req = httparse::Request::new(&mut []);
 
             match req.parse_with_uninit_headers(src, &mut parsed)? {
+                httparse::Status::Complete(len) if len > limits.max_size => {
+                    trace!("request head exceeds maximum size, closing");
+                    return Err(ParseError::TooLarge);
+                }
+
                 httparse::Status::Complete(len) => {
                     let method = Method::from_bytes(req.method.unwrap().as_bytes())
                         .map_err(|_| ParseError::Method)?;
@@ -261,8 +374,8 @@ impl MessageType for Request {
                 }
 
                 httparse::Status::Partial => {
-                    return if src.len() >= MAX_BUFFER_SIZE {
-                        trace!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                    return if src.len() >= limits.max_size {
+                        trace!("maximum head size of unprocessed data reached, closing");
                         Err(ParseError::TooLarge)
                     } else {
                         // Return None to notify more read are needed for parsing request
@@ -275,8 +388,12 @@ impl MessageType for Request {
         let mut msg = Request::new();
 
         // convert headers
-        let mut length =
-            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], ver)?;
+        let mut length = msg.set_headers(
+            &src.split_to(len).freeze(),
+            &headers[..h_len],
+            ver,
+            limits.framing,
+        )?;
 
         // disallow HTTP/1.0 POST requests that do not contain a Content-Length headers
         // see https://datatracker.ietf.org/doc/html/rfc1945#section-7.2.2
@@ -330,18 +447,15 @@ impl MessageType for ResponseHead {
         &mut self.headers
     }
 
-    fn decode(src: &mut BytesMut) -> Result<Option<(Self, PayloadType)>, ParseError> {
-        let mut headers: [HeaderIndex; MAX_HEADERS] = EMPTY_HEADER_INDEX_ARRAY;
+    fn decode(
+        src: &mut BytesMut,
+        limits: &HeadLimits,
+    ) -> Result<Option<(Self, PayloadType)>, ParseError> {
+        let mut headers: SmallVec<[HeaderIndex; MAX_HEADERS]> =
+            smallvec![EMPTY_HEADER_INDEX; limits.max_headers];
 
         let (len, ver, status, h_len) = {
-            // SAFETY:
-            // Create an uninitialized array of `MaybeUninit`. The `assume_init` is safe because the
-            // type we are claiming to have initialized here is a bunch of `MaybeUninit`s, which
-            // do not require initialization.
-            let mut parsed = unsafe {
-                MaybeUninit::<[MaybeUninit<httparse::Header<'_>>; MAX_HEADERS]>::uninit()
-                    .assume_init()
-            };
+            let mut parsed = uninit_headers(limits.max_headers);
 
             let mut res = httparse::Response::new(&mut []);
 
@@ -349,6 +463,11 @@ impl MessageType for ResponseHead {
             config.allow_spaces_after_header_name_in_responses(true);
 
             match config.parse_response_with_uninit_headers(&mut res, src, &mut parsed)? {
+                httparse::Status::Complete(len) if len > limits.max_size => {
+                    error!("response head exceeds maximum size, closing");
+                    return Err(ParseError::TooLarge);
+                }
+
                 httparse::Status::Complete(len) => {
                     let version = if res.version.unwrap() == 1 {
                         Version::HTTP_11
@@ -364,8 +483,8 @@ impl MessageType for ResponseHead {
                 }
 
                 httparse::Status::Partial => {
-                    return if src.len() >= MAX_BUFFER_SIZE {
-                        error!("MAX_BUFFER_SIZE unprocessed data reached, closing");
+                    return if src.len() >= limits.max_size {
+                        error!("maximum head size of unprocessed data reached, closing");
                         Err(ParseError::TooLarge)
                     } else {
                         Ok(None)
@@ -378,8 +497,12 @@ impl MessageType for ResponseHead {
         msg.version = ver;
 
         // convert headers
-        let mut length =
-            msg.set_headers(&src.split_to(len).freeze(), &headers[..h_len], ver)?;
+        let mut length = msg.set_headers(
+            &src.split_to(len).freeze(),
+            &headers[..h_len],
+            ver,
+            limits.framing,
+        )?;
 
         // Remove CL value if 0 now that all headers and HTTP/1.0 special cases are processed.
         // Protects against some request smuggling attacks.
@@ -419,8 +542,66 @@ pub(crate) const EMPTY_HEADER_INDEX: HeaderIndex = HeaderIndex {
     value: (0, 0),
 };
 
-pub(crate) const EMPTY_HEADER_INDEX_ARRAY: [HeaderIndex; MAX_HEADERS] =
-    [EMPTY_HEADER_INDEX; MAX_HEADERS];
+/// Replaces each obsolete line fold in the header section of `src` with spaces.
+///
+/// The request line and anything after the end of the head are left untouched. Like `httparse`,
+/// empty lines before the request line are skipped. Only complete lines are normalized, so the
+/// buffer can be normalized again as more of the head is read.
+fn normalize_obs_folds(src: &mut [u8]) {
+    let find_lf = |src: &[u8], from: usize| src[from..].iter().position(|&b| b == b'\n');
+
+    let mut line_start = 0;
+
+    // skip empty lines before the request line
+    loop {
+        match src.get(line_start..line_start + 2) {
+            Some(b"\r\n") => line_start += 2,
+            _ if src.get(line_start) == Some(&b'\n') => line_start += 1,
+            _ => break,
+        }
+    }
+
+    line_start = match find_lf(src, line_start) {
+        Some(idx) => line_start + idx + 1,
+        None => return,
+    };
+
+    while let Some(idx) = find_lf(src, line_start) {
+        let line_end = line_start + idx;
+
+        // an empty line ends the head
+        if matches!(&src[line_start..line_end], b"" | b"\r") {
+            return;
+        }
+
+        match src.get(line_end + 1) {
+            Some(b' ') | Some(b'\t') => {
+                // join the continuation line onto the current line and scan it again
+                src[line_end] = b' ';
+                if src[line_end - 1] == b'\r' {
+                    src[line_end - 1] = b' ';
+                }
+            }
+
+            Some(_) => line_start = line_end + 1,
+
+            // whether the next line is a continuation is not known yet
+            None => return,
+        }
+    }
+}
+
+/// Creates a buffer of `n` uninitialized headers for `httparse` to parse into.
+fn uninit_headers<'a>(n: usize) -> SmallVec<[MaybeUninit<httparse::Header<'a>>; MAX_HEADERS]> {
+    let mut headers = SmallVec::new();
+    headers.reserve_exact(n);
+
+    // SAFETY: capacity was reserved above and the elements are `MaybeUninit`s, which do not
+    // require initialization.
+    unsafe { headers.set_len(n) };
+
+    headers
+}
 
 impl HeaderIndex {
     pub(crate) fn record(
@@ -474,6 +655,14 @@ impl PayloadDecoder {
     pub fn eof() -> PayloadDecoder {
         PayloadDecoder { kind: Kind::Eof }
     }
+
+    /// Returns the number of bytes left to decode for fixed-length payloads.
+    pub(crate) fn remaining_length(&self) -> Option<u64> {
+        match self.kind {
+            Kind::Length(len) => Some(len),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -796,6 +985,42 @@ mod tests {
         assert_eq!(req.path(), "/test");
     }
 
+    #[test]
+    fn test_head_limits() {
+        let headers = (0..150)
+            .map(|n| format!("h{}: v\r\n", n))
+            .collect::<String>();
+        let head = format!("GET /test HTTP/1.1\r\n{}\r\n", headers);
+
+        let mut reader = MessageDecoder::<Request>::default();
+        let err = reader
+            .decode(&mut BytesMut::from(head.as_str()))
+            .unwrap_err();
+        assert!(matches!(err, ParseError::TooLarge));
+
+        // header counts above the default are supported
+        let mut reader = MessageDecoder::<Request>::with_limits(HeadLimits {
+            max_headers: 150,
+            ..HeadLimits::default()
+        });
+        let (req, _) = reader
+            .decode(&mut BytesMut::from(head.as_str()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(req.headers().len(), 150);
+
+        let mut reader = MessageDecoder::<Request>::with_limits(HeadLimits {
+            max_size: 64,
+            ..HeadLimits::default()
+        });
+        let mut buf = BytesMut::from("GET /test HTTP/1.1\r\n");
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+
+        buf.extend(format!("a: {}\r\n\r\n", "x".repeat(64)).as_bytes());
+        let err = reader.decode(&mut buf).unwrap_err();
+        assert!(matches!(err, ParseError::TooLarge));
+    }
+
     #[test]
     fn test_headers_split_field() {
         let mut buf = BytesMut::from("GET /test HTTP/1.1\r\n");
@@ -1098,6 +1323,89 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn hrs_content_length_and_chunked() {
+        let req = "POST / HTTP/1.1\r\n\
+            Host: example.com\r\n\
+            Content-Length: 4\r\n\
+            Transfer-Encoding: chunked\r\n\
+            \r\n\
+            0\r\n\
+            \r\n";
+
+        expect_parse_err!(&mut BytesMut::from(req));
+
+        // chunked encoding takes precedence when lenient
+        let mut reader = MessageDecoder::<Request>::with_limits(HeadLimits {
+            framing: FramingStrictness::Lenient,
+            ..HeadLimits::default()
+        });
+        let (_req, pl) = reader.decode(&mut BytesMut::from(req)).unwrap().unwrap();
+        assert!(matches!(pl, PayloadType::Payload(pl) if pl == PayloadDecoder::chunked()));
+    }
+
+    #[test]
+    fn hrs_obs_fold() {
+        let req = "GET / HTTP/1.1\r\n\
+            X-Folded: foo\r\n \
+            bar\r\n\
+            X-Other: baz\r\n\
+            \r\n";
+
+        expect_parse_err!(&mut BytesMut::from(req));
+
+        let mut reader = MessageDecoder::<Request>::with_limits(HeadLimits {
+            header_folding: HeaderFolding::Normalize,
+            ..HeadLimits::default()
+        });
+        let (req, _) = reader.decode(&mut BytesMut::from(req)).unwrap().unwrap();
+        assert_eq!(req.headers().get("x-folded").unwrap(), "foo   bar");
+        assert_eq!(req.headers().get("x-other").unwrap(), "baz");
+
+        // empty lines before the request line are skipped
+        let mut buf = BytesMut::from("\r\n\nGET / HTTP/1.1\r\nX-Folded: foo\r\n bar\r\n\r\n");
+        let (req, _) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().get("x-folded").unwrap(), "foo   bar");
+
+        // folds are recognized across reads and the body is left untouched
+        let mut buf = BytesMut::from("POST / HTTP/1.1\r\nX-Folded: foo\r\n");
+        assert!(reader.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"\tbar\r\nContent-Length: 6\r\n\r\n\r\n bar");
+        let (req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(req.headers().get("x-folded").unwrap(), "foo  \tbar");
+        assert!(matches!(pl, PayloadType::Payload(pl) if pl == PayloadDecoder::length(6)));
+        assert_eq!(&buf[..], b"\r\n bar");
+    }
+
+    #[test]
+    fn hrs_repeated_content_length_lenient() {
+        let mut reader = MessageDecoder::<Request>::with_limits(HeadLimits {
+            framing: FramingStrictness::Lenient,
+            ..HeadLimits::default()
+        });
+
+        let mut buf = BytesMut::from(
+            "POST / HTTP/1.1\r\n\
+            Content-Length: 4\r\n\
+            Content-Length: 4\r\n\
+            \r\n\
+            abcd",
+        );
+        let (_req, pl) = reader.decode(&mut buf).unwrap().unwrap();
+        assert!(matches!(pl, PayloadType::Payload(pl) if pl == PayloadDecoder::length(4)));
+
+        // conflicting values are rejected regardless of strictness
+        let mut buf = BytesMut::from(
+            "POST / HTTP/1.1\r\n\
+            Content-Length: 4\r\n\
+            Content-Length: 2\r\n\
+            \r\n\
+            abcd",
+        );
+        assert!(matches!(reader.decode(&mut buf), Err(ParseError::Header)));
+    }
+
     #[test]
     fn hrs_content_length_plus() {
         expect_parse_err!(&mut BytesMut::from(
@@ -1,4 +1,5 @@
 use std::{
+    cmp,
     collections::VecDeque,
     fmt,
     future::Future,
@@ -22,15 +23,18 @@ use tracing::{error, trace};
 use crate::{
     body::{BodySize, BoxBody, MessageBody},
     config::ServiceConfig,
+    disconnect::{DisconnectTrigger, ResponseDisconnect},
     error::{DispatchError, ParseError, PayloadError},
     service::HttpFlow,
-    Error, Extensions, OnConnectData, Request, Response, StatusCode,
+    ConnectionType, Error, Extensions, HttpMessage as _, Method, OnConnectData, Request,
+    Response, StatusCode,
 };
 
 use super::{
     codec::Codec,
     decoder::MAX_BUFFER_SIZE,
-    payload::{Payload, PayloadSender, PayloadStatus},
+    memory_budget::{ConnectionMemory, MemoryBudget},
+    payload::{BufferedBytes, Payload, PayloadSender, PayloadStatus},
     timer::TimerState,
     Message, MessageType,
 };
@@ -58,6 +62,9 @@ bitflags! {
 
         /// Set if write-half is disconnected.
         const WRITE_DISCONNECT = 0b0010_0000;
+
+        /// Set while the request being handled may have side effects.
+        const UNSAFE_IN_FLIGHT = 0b0100_0000;
     }
 }
 
@@ -157,7 +164,13 @@ pin_project! {
         pub(super) state: State<S, B, X>,
         // when Some(_) dispatcher is in state of receiving request payload
         payload: Option<PayloadSender>,
-        messages: VecDeque<DispatcherMessage>,
+        messages: VecDeque<DispatcherMessage<S::Future>>,
+        // resolves the current request's `ResponseDisconnect` if dropped before response is sent
+        disconnect: Option<DisconnectTrigger>,
+        // accounts for buffered bytes when a memory budget is configured
+        memory: Option<ConnectionMemory>,
+        // bytes buffered in the latest request payload, tracked for the memory budget
+        payload_buffer: Option<BufferedBytes>,
 
         head_timer: TimerState,
         ka_timer: TimerState,
@@ -168,14 +181,72 @@ pin_project! {
         write_buf: BytesMut,
         codec: Codec,
     }
+
+    impl<T, S, B, X, U> PinnedDrop for InnerDispatcher<T, S, B, X, U>
+    where
+        S: Service<Request>,
+        S::Error: Into<Response<BoxBody>>,
+
+        B: MessageBody,
+
+        X: Service<Request, Response = Request>,
+        X::Error: Into<Response<BoxBody>>,
+
+        U: Service<(Request, Framed<T, Codec>), Response = ()>,
+        U::Error: fmt::Display,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            // the connection is torn down while a request payload is still being received, for
+            // example when the server's shutdown timeout expires; fail the payload so that
+            // readers outside of the dispatcher do not wait for it forever
+            if let Some(mut payload) = this.project().payload.take() {
+                payload.set_error(PayloadError::Io(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "connection closed before the request payload was fully received",
+                )));
+            }
+        }
+    }
+}
+
+/// Returns whether `req` has a method that is not expected to have side effects.
+fn is_safe(req: &Request) -> bool {
+    matches!(*req.method(), Method::GET | Method::HEAD)
 }
 
-enum DispatcherMessage {
-    Item(Request),
+enum DispatcherMessage<F: Future> {
+    Item(Request, DisconnectTrigger),
+    /// Pipelined request whose service call was started before earlier responses were sent.
+    Call(PrefetchedCall<F>, DisconnectTrigger),
     Upgrade(Request),
     Error(Response<()>),
 }
 
+/// Service call of a pipelined request, started early as allowed by
+/// [`ServiceConfig::h1_pipeline_concurrency`].
+///
+/// Its output is held here until all earlier responses have been sent.
+enum PrefetchedCall<F: Future> {
+    Pending(Pin<Box<F>>),
+    Done(F::Output),
+}
+
+impl<F: Future> PrefetchedCall<F> {
+    fn new(fut: F, cx: &mut Context<'_>) -> Self {
+        let mut call = PrefetchedCall::Pending(Box::pin(fut));
+        call.poll(cx);
+        call
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) {
+        if let PrefetchedCall::Pending(fut) = self {
+            if let Poll::Ready(res) = fut.as_mut().poll(cx) {
+                *self = PrefetchedCall::Done(res);
+            }
+        }
+    }
+}
+
 pin_project! {
     #[project = StateProj]
     pub(super) enum State<S, B, X>
@@ -187,6 +258,7 @@ pin_project! {
         None,
         ExpectCall { #[pin] fut: X::Future },
         ServiceCall { #[pin] fut: S::Future },
+        PrefetchedCall { fut: Pin<Box<S::Future>> },
         SendPayload { #[pin] body: B },
         SendErrorPayload { #[pin] body: BoxBody },
     }
@@ -218,6 +290,9 @@ where
             Self::ServiceCall { .. } => {
                 f.debug_struct("State::ServiceCall").finish_non_exhaustive()
             }
+            Self::PrefetchedCall { .. } => f
+                .debug_struct("State::PrefetchedCall")
+                .finish_non_exhaustive(),
             Self::SendPayload { .. } => {
                 f.debug_struct("State::SendPayload").finish_non_exhaustive()
             }
@@ -228,6 +303,13 @@ where
     }
 }
 
+/// Disarms the current request's disconnect trigger once its response has been fully sent.
+fn response_sent(disconnect: &mut Option<DisconnectTrigger>) {
+    if let Some(disconnect) = disconnect.take() {
+        disconnect.disarm();
+    }
+}
+
 #[derive(Debug)]
 enum PollResponse {
     Upgrade(Request),
@@ -272,6 +354,9 @@ where
                     state: State::None,
                     payload: None,
                     messages: VecDeque::new(),
+                    disconnect: None,
+                    memory: config.h1_memory_budget().map(MemoryBudget::register),
+                    payload_buffer: None,
 
                     head_timer: TimerState::new(config.client_request_deadline().is_some()),
                     ka_timer: TimerState::new(config.keep_alive().enabled()),
@@ -391,6 +476,7 @@ where
         this.state.set(match size {
             BodySize::None | BodySize::Sized(0) => {
                 this.flags.insert(Flags::FINISHED);
+                response_sent(this.disconnect);
                 State::None
             }
             _ => State::SendPayload { body },
@@ -409,6 +495,7 @@ where
         this.state.set(match size {
             BodySize::None | BodySize::Sized(0) => {
                 this.flags.insert(Flags::FINISHED);
+                response_sent(this.disconnect);
                 State::None
             }
             _ => State::SendErrorPayload { body },
@@ -417,6 +504,60 @@ where
         Ok(())
     }
 
+    /// Returns `true` if a response is being written to the connection.
+    fn is_responding(&self) -> bool {
+        !self.write_buf.is_empty()
+            || matches!(
+                self.state,
+                State::SendPayload { .. } | State::SendErrorPayload { .. }
+            )
+    }
+
+    /// Records the bytes buffered by the connection with the memory budget, if any.
+    fn update_memory(&self, cx: &mut Context<'_>) {
+        if let Some(ref memory) = self.memory {
+            let bytes = self.read_buf.len()
+                + self.write_buf.len()
+                + self.payload_buffer.as_ref().map_or(0, BufferedBytes::get);
+
+            memory.update(bytes, !self.is_responding(), cx.waker());
+        }
+    }
+
+    /// Closes a connection evicted by the memory budget.
+    ///
+    /// The current request is dropped and, unless a response has already been started, answered
+    /// with `503 Service Unavailable`.
+    fn evict(mut self: Pin<&mut Self>) -> Result<(), DispatchError> {
+        trace!("connection evicted by memory budget; start shutdown");
+
+        let responding = self.is_responding();
+        let mut this = self.as_mut().project();
+
+        if let Some(mut payload) = this.payload.take() {
+            payload.set_error(PayloadError::Io(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "connection evicted to reduce server memory usage",
+            )));
+        }
+
+        this.messages.clear();
+        this.read_buf.clear();
+        this.flags.insert(Flags::READ_DISCONNECT | Flags::SHUTDOWN);
+
+        if !responding {
+            this.state.set(State::None);
+            *this.disconnect = None;
+
+            let mut res = Response::new(StatusCode::SERVICE_UNAVAILABLE);
+            res.head_mut().set_connection_type(ConnectionType::Close);
+            let (res, body) = res.replace_body(());
+            self.send_error_response(res, body)?;
+        }
+
+        Ok(())
+    }
+
     fn send_continue(self: Pin<&mut Self>) {
         self.project()
             .write_buf
@@ -427,13 +568,23 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Result<PollResponse, DispatchError> {
+        // drive service calls of pipelined requests that were started early
+        for msg in self.as_mut().project().messages.iter_mut() {
+            if let DispatcherMessage::Call(call, _) = msg {
+                call.poll(cx);
+            }
+        }
+
         'res: loop {
             let mut this = self.as_mut().project();
             match this.state.as_mut().project() {
                 // no future is in InnerDispatcher state; pop next message
                 StateProj::None => match this.messages.pop_front() {
                     // handle request message
-                    Some(DispatcherMessage::Item(req)) => {
+                    Some(DispatcherMessage::Item(req, disconnect)) => {
+                        *this.disconnect = Some(disconnect);
+                        this.flags.set(Flags::UNSAFE_IN_FLIGHT, !is_safe(&req));
+
                         // Handle `EXPECT: 100-Continue` header
                         if req.head().expect() {
                             // set InnerDispatcher state and continue loop to poll it
@@ -446,6 +597,29 @@ where
                         };
                     }
 
+                    // handle request message whose service call was already started
+                    Some(DispatcherMessage::Call(call, disconnect)) => {
+                        *this.disconnect = Some(disconnect);
+                        this.flags.remove(Flags::UNSAFE_IN_FLIGHT);
+
+                        match call {
+                            PrefetchedCall::Done(Ok(res)) => {
+                                let (res, body) = res.into().replace_body(());
+                                self.as_mut().send_response(res, body)?;
+                            }
+
+                            PrefetchedCall::Done(Err(err)) => {
+                                let res: Response<BoxBody> = err.into();
+                                let (res, body) = res.replace_body(());
+                                self.as_mut().send_error_response(res, body)?;
+                            }
+
+                            PrefetchedCall::Pending(fut) => {
+                                this.state.set(State::PrefetchedCall { fut });
+                            }
+                        }
+                    }
+
                     // handle error message
                     Some(DispatcherMessage::Error(res)) => {
                         // send_response would update InnerDispatcher state to SendPayload or None
@@ -496,6 +670,26 @@ where
                     }
                 }
 
+                // same as ServiceCall but for a boxed, early started call
+                StateProj::PrefetchedCall { fut } => match fut.as_mut().poll(cx) {
+                    Poll::Ready(Ok(res)) => {
+                        let (res, body) = res.into().replace_body(());
+                        self.as_mut().send_response(res, body)?;
+                    }
+
+                    Poll::Ready(Err(err)) => {
+                        let res: Response<BoxBody> = err.into();
+                        let (res, body) = res.replace_body(());
+                        self.as_mut().send_error_response(res, body)?;
+                    }
+
+                    Poll::Pending => {
+                        if !self.as_mut().poll_request(cx)? {
+                            return Ok(PollResponse::DoNothing);
+                        }
+                    }
+                },
+
                 StateProj::SendPayload { mut body } => {
                     // keep populate writer buffer until buffer size limit hit,
                     // get blocked or finished.
@@ -513,6 +707,7 @@ where
                                 // set state to None and handle next message
                                 this.state.set(State::None);
                                 this.flags.insert(Flags::FINISHED);
+                                response_sent(this.disconnect);
 
                                 continue 'res;
                             }
@@ -550,6 +745,7 @@ where
                                 // set state to None and handle next message
                                 this.state.set(State::None);
                                 this.flags.insert(Flags::FINISHED);
+                                response_sent(this.disconnect);
 
                                 continue 'res;
                             }
@@ -601,12 +797,16 @@ where
     fn handle_request(
         mut self: Pin<&mut Self>,
         req: Request,
+        disconnect: DisconnectTrigger,
         cx: &mut Context<'_>,
     ) -> Result<(), DispatchError> {
         // initialize dispatcher state
         {
             let mut this = self.as_mut().project();
 
+            *this.disconnect = Some(disconnect);
+            this.flags.set(Flags::UNSAFE_IN_FLIGHT, !is_safe(&req));
+
             // Handle `EXPECT: 100-Continue` header
             if req.head().expect() {
                 // set dispatcher state to call expect handler
@@ -709,13 +909,21 @@ where
 
                     match msg {
                         Message::Item(mut req) => {
-                            // head timer only applies to first request on connection
+                            // head timer is only active until request head is fully received
                             this.head_timer.clear(line!());
 
                             req.head_mut().peer_addr = *this.peer_addr;
 
                             req.conn_data = this.conn_data.as_ref().map(Rc::clone);
 
+                            let (handle, disconnect) = ResponseDisconnect::new();
+                            req.extensions_mut().insert(handle);
+
+                            // only safe requests without a body may be processed concurrently
+                            let prefetchable = this.codec.message_type() == MessageType::None
+                                && is_safe(&req)
+                                && !req.head().expect();
+
                             match this.codec.message_type() {
                                 // request has no payload
                                 MessageType::None => {}
@@ -724,6 +932,7 @@ where
                                 // Everything remaining in read buffer will be handed to
                                 // upgraded Request.
                                 MessageType::Stream if this.flow.upgrade.is_some() => {
+                                    disconnect.disarm();
                                     this.messages.push_back(DispatcherMessage::Upgrade(req));
                                     break;
                                 }
@@ -735,7 +944,20 @@ where
                                     // to sink new chunked request data to state. Payload is
                                     // attached to Request and passed to Service::call where the
                                     // state can be collected and consumed.
-                                    let (sender, payload) = Payload::create(false);
+                                    let (mut sender, payload) = Payload::create(false);
+
+                                    if let Some(len) = this.codec.payload_length() {
+                                        sender.set_length(len);
+                                    }
+
+                                    if let Some(metrics) = this.config.h1_payload_metrics() {
+                                        sender.set_metrics(metrics.clone());
+                                    }
+
+                                    if this.memory.is_some() {
+                                        *this.payload_buffer = Some(sender.buffered_bytes());
+                                    }
+
                                     *req.payload() = crate::Payload::H1 { payload };
                                     *this.payload = Some(sender);
                                 }
@@ -743,10 +965,25 @@ where
 
                             // handle request early when no future in InnerDispatcher state.
                             if this.state.is_none() {
-                                self.as_mut().handle_request(req, cx)?;
+                                self.as_mut().handle_request(req, disconnect, cx)?;
                                 this = self.as_mut().project();
+                            } else if prefetchable
+                                // the request being handled must not have side effects
+                                && !this.flags.contains(Flags::UNSAFE_IN_FLIGHT)
+                                // earlier requests that are only queued must be handled first
+                                && this
+                                    .messages
+                                    .iter()
+                                    .all(|msg| matches!(msg, DispatcherMessage::Call(..)))
+                                && this.config.h1_pipeline_concurrency()
+                                    > 1 + this.messages.len()
+                            {
+                                let call = PrefetchedCall::new(this.flow.service.call(req), cx);
+                                this.messages
+                                    .push_back(DispatcherMessage::Call(call, disconnect));
                             } else {
-                                this.messages.push_back(DispatcherMessage::Item(req));
+                                this.messages
+                                    .push_back(DispatcherMessage::Item(req, disconnect));
                             }
                         }
 
@@ -957,9 +1194,12 @@ where
 
         let mut read_some = false;
 
+        // a configured head size limit above the default must still be reachable
+        let max_buffer_size = cmp::max(MAX_BUFFER_SIZE, this.codec.config().max_header_size());
+
         loop {
             // Return early when read buf exceed decoder's max buffer size.
-            if this.read_buf.len() >= MAX_BUFFER_SIZE {
+            if this.read_buf.len() >= max_buffer_size {
                 // At this point it's not known IO stream is still scheduled to be waked up so
                 // force wake up dispatcher just in case.
                 //
@@ -1095,6 +1335,15 @@ where
 
                 inner.as_mut().poll_timers(cx)?;
 
+                if !inner.flags.contains(Flags::SHUTDOWN)
+                    && inner
+                        .memory
+                        .as_ref()
+                        .map_or(false, ConnectionMemory::is_evicted)
+                {
+                    inner.as_mut().evict()?;
+                }
+
                 let poll = if inner.flags.contains(Flags::SHUTDOWN) {
                     if inner.flags.contains(Flags::WRITE_DISCONNECT) {
                         Poll::Ready(Ok(()))
@@ -1111,9 +1360,20 @@ where
 
                     // after reading something from stream, clear keep-alive timer
                     if !inner.read_buf.is_empty() && inner.flags.contains(Flags::KEEP_ALIVE) {
+                        let deadline = inner.config.client_request_deadline();
+
                         let inner = inner.as_mut().project();
                         inner.flags.remove(Flags::KEEP_ALIVE);
                         inner.ka_timer.clear(line!());
+
+                        // follow-up request heads are subject to the same timeout as the first
+                        if let Some(deadline) = deadline {
+                            inner.head_timer.set_and_init(
+                                cx,
+                                sleep_until(deadline.into()),
+                                line!(),
+                            );
+                        }
                     }
 
                     if !inner.flags.contains(Flags::STARTED) {
@@ -1236,6 +1496,8 @@ where
                     Poll::Pending
                 };
 
+                inner.update_memory(cx);
+
                 trace!("end flags: {:?}", &inner.flags);
 
                 poll
@@ -409,6 +409,91 @@ async fn keep_alive_follow_up_req() {
     .await;
 }
 
+#[actix_rt::test]
+async fn slow_follow_up_req_head() {
+    let mut buf = TestBuffer::new("GET /abcd HTTP/1.1\r\n\r\n");
+
+    let cfg = ServiceConfig::new(
+        KeepAlive::Timeout(Duration::from_millis(1000)),
+        Duration::from_millis(100),
+        Duration::ZERO,
+        false,
+        None,
+    );
+    let services = HttpFlow::new(echo_path_service(), ExpectHandler, None);
+
+    let h1 = Dispatcher::<_, _, _, _, UpgradeHandler>::new(
+        buf.clone(),
+        services,
+        cfg,
+        None,
+        OnConnectData::default(),
+    );
+    pin!(h1);
+
+    lazy(|cx| {
+        assert!(
+            h1.as_mut().poll(cx).is_pending(),
+            "keep-alive should prevent poll from resolving"
+        );
+
+        // discard first response
+        let _ = buf.take_write_buf();
+
+        // start sending follow-up request head but don't finish it
+        buf.extend_read_buf("GET /efg HTTP/1.1\r\n");
+
+        assert!(
+            h1.as_mut().poll(cx).is_pending(),
+            "incomplete request head should not resolve dispatcher"
+        );
+        assert!(buf.write_buf_slice().is_empty());
+    })
+    .await;
+
+    // trickle in another header, still within keep-alive timeout
+    sleep(Duration::from_millis(60)).await;
+
+    lazy(|cx| {
+        buf.extend_read_buf("Host: localhost\r\n");
+
+        assert!(h1.as_mut().poll(cx).is_pending());
+        assert!(buf.write_buf_slice().is_empty());
+    })
+    .await;
+
+    // sleep longer than client request timeout but less than keep-alive timeout
+    sleep(Duration::from_millis(60)).await;
+
+    lazy(|cx| {
+        assert!(
+            h1.as_mut().poll(cx).is_ready(),
+            "client request timeout should have closed connection",
+        );
+
+        let mut res = buf.take_write_buf().to_vec();
+        stabilize_date_header(&mut res);
+        let res = &res[..];
+
+        let exp = b"\
+                HTTP/1.1 408 Request Timeout\r\n\
+                content-length: 0\r\n\
+                date: Thu, 01 Jan 1970 12:34:56 UTC\r\n\r\n\
+                ";
+
+        assert_eq!(
+            res,
+            exp,
+            "\nexpected response not in write buffer:\n\
+               response: {:?}\n\
+               expected: {:?}",
+            String::from_utf8_lossy(res),
+            String::from_utf8_lossy(exp)
+        );
+    })
+    .await;
+}
+
 #[actix_rt::test]
 async fn req_parse_err() {
     lazy(|cx| {
@@ -927,6 +1012,67 @@ async fn handler_drop_payload() {
     .await;
 }
 
+#[actix_rt::test]
+async fn dropped_connection_fails_payload() {
+    use std::{cell::RefCell, rc::Rc};
+
+    use futures_util::StreamExt as _;
+
+    use crate::{error::PayloadError, Payload};
+
+    let buf = TestBuffer::new(http_msg(
+        r"
+        POST / HTTP/1.1
+        Content-Length: 10
+
+        abc
+        ",
+    ));
+
+    // payload is handed to a reader that outlives the connection
+    let stash = Rc::new(RefCell::new(None::<Payload>));
+
+    let services = HttpFlow::new(
+        fn_service({
+            let stash = Rc::clone(&stash);
+            move |mut req: Request| {
+                *stash.borrow_mut() = Some(req.take_payload());
+                std::future::pending::<Result<Response<()>, Error>>()
+            }
+        }),
+        ExpectHandler,
+        None::<UpgradeHandler>,
+    );
+
+    let h1 = Dispatcher::new(
+        buf,
+        services,
+        ServiceConfig::default(),
+        None,
+        OnConnectData::default(),
+    );
+
+    let mut h1 = Box::pin(h1);
+    lazy(|cx| assert!(h1.as_mut().poll(cx).is_pending())).await;
+
+    // e.g., the server's shutdown timeout expires
+    drop(h1);
+
+    let mut payload = match stash.borrow_mut().take().unwrap() {
+        Payload::H1 { payload } => payload,
+        _ => panic!("expected an h1 payload"),
+    };
+    assert_eq!(payload.next().await.unwrap().unwrap(), "abc");
+    assert_eq!(payload.ended_cleanly(), Some(false));
+
+    match payload.next().await {
+        Some(Err(PayloadError::Io(err))) => {
+            assert_eq!(err.kind(), std::io::ErrorKind::ConnectionAborted)
+        }
+        res => panic!("unexpected payload item: {:?}", res),
+    }
+}
+
 fn http_msg(msg: impl AsRef<str>) -> BytesMut {
     let mut msg = msg
         .as_ref()
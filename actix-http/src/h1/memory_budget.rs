@@ -0,0 +1,235 @@
+//! Memory budget shared by the buffers of HTTP/1 connections.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::Waker,
+};
+
+/// Limit on the bytes buffered by the HTTP/1 connections of a server.
+///
+/// Once registered with
+/// [`HttpServiceBuilder::h1_memory_budget`](crate::HttpServiceBuilder::h1_memory_budget), each
+/// connection accounts for the bytes it holds in its read and write buffers and in the buffer of
+/// its current request payload. When the total exceeds the budget, the idle connections that
+/// consume the most memory are evicted until enough memory would be freed. A connection is idle
+/// when it has not started sending a response; an evicted connection drops its current request,
+/// fails its payload, responds with `503 Service Unavailable` and is closed.
+///
+/// Clones share the same budget, so one instance can be registered with the services on all
+/// worker threads. Usage is updated whenever a connection is polled, so the budget may be
+/// exceeded briefly.
+#[derive(Clone)]
+pub struct MemoryBudget(Arc<Inner>);
+
+struct Inner {
+    limit: usize,
+    used: AtomicUsize,
+    evictions: AtomicU64,
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<u64, Arc<Usage>>>,
+}
+
+/// Memory used by one connection.
+#[derive(Default)]
+struct Usage {
+    bytes: AtomicUsize,
+    idle: AtomicBool,
+    evicted: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl MemoryBudget {
+    /// Constructs a new budget of `limit` bytes.
+    pub fn new(limit: usize) -> Self {
+        Self(Arc::new(Inner {
+            limit,
+            used: AtomicUsize::new(0),
+            evictions: AtomicU64::new(0),
+            next_id: AtomicU64::new(0),
+            connections: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    /// Returns the budget, in bytes.
+    pub fn limit(&self) -> usize {
+        self.0.limit
+    }
+
+    /// Returns the number of bytes currently buffered by all connections.
+    pub fn used(&self) -> usize {
+        self.0.used.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of connections evicted so far.
+    pub fn evictions(&self) -> u64 {
+        self.0.evictions.load(Ordering::Relaxed)
+    }
+
+    /// Registers a new connection, which uses no memory until it is updated.
+    pub(crate) fn register(&self) -> ConnectionMemory {
+        let id = self.0.next_id.fetch_add(1, Ordering::Relaxed);
+        let usage = Arc::new(Usage::default());
+
+        self.0
+            .connections
+            .lock()
+            .unwrap()
+            .insert(id, Arc::clone(&usage));
+
+        ConnectionMemory {
+            budget: self.clone(),
+            id,
+            usage,
+        }
+    }
+
+    /// Evicts the idle connections using the most memory until the memory they use covers the
+    /// amount by which the budget is exceeded.
+    fn evict(&self) {
+        let connections = self.0.connections.lock().unwrap();
+
+        let mut excess = match self.used().checked_sub(self.0.limit) {
+            Some(excess) if excess > 0 => excess,
+            _ => return,
+        };
+
+        let mut candidates = Vec::new();
+
+        for usage in connections.values() {
+            let bytes = usage.bytes.load(Ordering::Relaxed);
+
+            if usage.evicted.load(Ordering::Relaxed) {
+                // memory of connections already being evicted is about to be freed
+                excess = excess.saturating_sub(bytes);
+            } else if bytes > 0 && usage.idle.load(Ordering::Relaxed) {
+                candidates.push((bytes, usage));
+            }
+        }
+
+        candidates.sort_unstable_by(|(a, _), (b, _)| b.cmp(a));
+
+        for (bytes, usage) in candidates {
+            if excess == 0 {
+                break;
+            }
+
+            usage.evicted.store(true, Ordering::Relaxed);
+            self.0.evictions.fetch_add(1, Ordering::Relaxed);
+
+            if let Some(waker) = usage.waker.lock().unwrap().take() {
+                waker.wake();
+            }
+
+            excess = excess.saturating_sub(bytes);
+        }
+    }
+}
+
+impl fmt::Debug for MemoryBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryBudget")
+            .field("limit", &self.0.limit)
+            .field("used", &self.used())
+            .field("evictions", &self.evictions())
+            .finish()
+    }
+}
+
+/// Handle through which a connection accounts for its memory in a [`MemoryBudget`].
+///
+/// Dropping the handle releases the connection's memory.
+pub(crate) struct ConnectionMemory {
+    budget: MemoryBudget,
+    id: u64,
+    usage: Arc<Usage>,
+}
+
+impl ConnectionMemory {
+    /// Records that the connection buffers `bytes` bytes and whether it is idle, evicting
+    /// connections if the budget is exceeded.
+    ///
+    /// `waker` is woken if the connection is evicted.
+    pub(crate) fn update(&self, bytes: usize, idle: bool, waker: &Waker) {
+        self.usage.idle.store(idle, Ordering::Relaxed);
+
+        {
+            let mut current = self.usage.waker.lock().unwrap();
+            if !current.as_ref().map_or(false, |w| w.will_wake(waker)) {
+                *current = Some(waker.clone());
+            }
+        }
+
+        let prev = self.usage.bytes.swap(bytes, Ordering::Relaxed);
+
+        if bytes > prev {
+            let used = self
+                .budget
+                .0
+                .used
+                .fetch_add(bytes - prev, Ordering::Relaxed);
+
+            if used + bytes - prev > self.budget.0.limit {
+                self.budget.evict();
+            }
+        } else {
+            self.budget
+                .0
+                .used
+                .fetch_sub(prev - bytes, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns `true` if the connection has been evicted.
+    pub(crate) fn is_evicted(&self) -> bool {
+        self.usage.evicted.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for ConnectionMemory {
+    fn drop(&mut self) {
+        self.budget.0.connections.lock().unwrap().remove(&self.id);
+
+        let bytes = self.usage.bytes.swap(0, Ordering::Relaxed);
+        self.budget.0.used.fetch_sub(bytes, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::task::noop_waker;
+
+    use super::*;
+
+    #[test]
+    fn evicts_largest_idle_connections() {
+        let budget = MemoryBudget::new(100);
+        let waker = noop_waker();
+
+        let small = budget.register();
+        let large = budget.register();
+        let busy = budget.register();
+
+        small.update(30, true, &waker);
+        large.update(50, true, &waker);
+        busy.update(60, false, &waker);
+        assert_eq!(budget.used(), 140);
+
+        // only the largest idle connection is needed to cover the excess
+        assert!(!small.is_evicted());
+        assert!(large.is_evicted());
+        assert!(!busy.is_evicted());
+        assert_eq!(budget.evictions(), 1);
+
+        drop(large);
+        assert_eq!(budget.used(), 90);
+
+        small.update(10, true, &waker);
+        assert_eq!(budget.used(), 70);
+        assert_eq!(budget.evictions(), 1);
+    }
+}
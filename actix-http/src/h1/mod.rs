@@ -11,7 +11,9 @@ mod dispatcher;
 mod dispatcher_tests;
 mod encoder;
 mod expect;
+mod memory_budget;
 mod payload;
+mod payload_metrics;
 mod service;
 mod timer;
 mod upgrade;
@@ -19,9 +21,17 @@ mod utils;
 
 pub use self::client::{ClientCodec, ClientPayloadCodec};
 pub use self::codec::Codec;
+pub use self::decoder::{
+    FramingStrictness, HeaderFolding, DEFAULT_MAX_HEADER_COUNT, DEFAULT_MAX_HEADER_SIZE,
+};
+pub(crate) use self::decoder::{MAX_BUFFER_SIZE, MAX_HEADERS};
 pub use self::dispatcher::Dispatcher;
 pub use self::expect::ExpectHandler;
-pub use self::payload::Payload;
+pub use self::memory_budget::MemoryBudget;
+pub use self::payload::{
+    BufCursor, LengthPrefixed, OverflowPolicy, Payload, PrefixFormat, Priority, ReadAhead,
+};
+pub use self::payload_metrics::{PayloadMetrics, PayloadMetricsSnapshot};
 pub use self::service::{H1Service, H1ServiceHandler};
 pub use self::upgrade::UpgradeHandler;
 pub use self::utils::SendResponse;
@@ -3,15 +3,22 @@
 use std::{
     cell::RefCell,
     collections::VecDeque,
+    fmt,
+    future::Future,
+    io,
     pin::Pin,
     rc::{Rc, Weak},
     task::{Context, Poll, Waker},
+    time::Duration,
 };
 
-use bytes::Bytes;
-use futures_core::Stream;
+use actix_rt::time::{sleep_until, Instant, Sleep};
+use actix_utils::future::poll_fn;
+use bytes::{Buf, Bytes, BytesMut};
+use futures_core::{ready, Stream};
 
-use crate::error::PayloadError;
+use super::PayloadMetrics;
+use crate::{error::PayloadError, header::HeaderMap};
 
 /// max buffer size 32k
 pub(crate) const MAX_BUFFER_SIZE: usize = 32_768;
@@ -23,15 +30,106 @@ pub enum PayloadStatus {
     Dropped,
 }
 
+/// Behavior of a [`Payload`] when the sender feeds more bytes than its limit allows.
+///
+/// See [`Payload::set_limit`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Yield a [`PayloadError::Overflow`] error to the reader.
+    Error,
+
+    /// Stop reading from the connection and never resume.
+    ///
+    /// Bytes received before the limit was reached are still yielded to the reader, after which
+    /// the payload stays pending until the connection is closed or times out.
+    Pause,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        Self::Error
+    }
+}
+
+/// Priority of a chunk fed with [`feed_data_priority`](PayloadSender::feed_data_priority).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Chunk is yielded in the order it was fed.
+    Normal,
+
+    /// Chunk is yielded before any buffered normal priority chunks.
+    ///
+    /// High priority chunks are yielded in the order they were fed.
+    High,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+/// Format of the length prefix of records read with
+/// [`read_length_prefixed`](Payload::read_length_prefixed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefixFormat {
+    /// 2 byte, big-endian length.
+    U16Be,
+
+    /// 2 byte, little-endian length.
+    U16Le,
+
+    /// 4 byte, big-endian length.
+    U32Be,
+
+    /// 4 byte, little-endian length.
+    U32Le,
+}
+
+impl PrefixFormat {
+    /// Returns the length of the prefix, in bytes.
+    fn len(self) -> usize {
+        match self {
+            Self::U16Be | Self::U16Le => 2,
+            Self::U32Be | Self::U32Le => 4,
+        }
+    }
+
+    /// Decodes the record length from a prefix of the right length.
+    fn decode(self, prefix: &[u8]) -> usize {
+        match self {
+            Self::U16Be => u16::from_be_bytes([prefix[0], prefix[1]]) as usize,
+            Self::U16Le => u16::from_le_bytes([prefix[0], prefix[1]]) as usize,
+            Self::U32Be => {
+                u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize
+            }
+            Self::U32Le => {
+                u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize
+            }
+        }
+    }
+}
+
 /// Buffered stream of bytes chunks
 ///
 /// Payload stores chunks in a vector. First chunk can be received with `poll_next`. Payload does
 /// not notify current task when new data is available.
 ///
 /// Payload can be used as `Response` body stream.
+///
+/// With the `tracing` crate feature enabled, each non-empty payload opens a `payload` span that
+/// is closed once the payload has been read to the end or has failed, recording the number of
+/// bytes read. Events are emitted within it when backpressure is applied or released and when
+/// the payload exceeds its [limit](Self::set_limit).
 #[derive(Debug)]
 pub struct Payload {
     inner: Rc<RefCell<Inner>>,
+    /// Bytes read ahead by [`fill_buf`](Self::fill_buf) that have not been consumed yet.
+    buf: BytesMut,
+    progress: Option<Progress>,
+    validator: Option<Validator>,
+    /// Number of bytes read, less those put back.
+    offset: u64,
 }
 
 impl Payload {
@@ -45,224 +143,1679 @@ impl Payload {
 
         (
             PayloadSender::new(Rc::downgrade(&shared)),
-            Payload { inner: shared },
+            Payload {
+                inner: shared,
+                buf: BytesMut::new(),
+                progress: None,
+                validator: None,
+                offset: 0,
+            },
         )
     }
 
+    /// Creates a payload stream that yields chunks of exactly the given `sizes`, in order,
+    /// regardless of how data is fed to it.
+    ///
+    /// Chunks fed with normal priority are buffered until the next chunk size is reached, so tests
+    /// of code that depends on chunk boundaries do not depend on how their input was split. A
+    /// final, shorter chunk is yielded once EOF is fed. After the last size, data is yielded as it
+    /// is fed.
+    ///
+    /// # Panics
+    /// Panics if any of the `sizes` is zero.
+    #[cfg(any(test, feature = "__fault-injection"))]
+    pub fn create_with_chunking(eof: bool, sizes: Vec<usize>) -> (PayloadSender, Payload) {
+        assert!(
+            sizes.iter().all(|&size| size > 0),
+            "chunk sizes must be greater than zero"
+        );
+
+        let (sender, payload) = Payload::create(eof);
+
+        payload.inner.borrow_mut().chunking = Some(Chunking {
+            sizes: sizes.into(),
+            pending: BytesMut::new(),
+        });
+
+        (sender, payload)
+    }
+
     /// Creates an empty payload.
     pub(crate) fn empty() -> Payload {
         Payload {
             inner: Rc::new(RefCell::new(Inner::new(true))),
+            buf: BytesMut::new(),
+            progress: None,
+            validator: None,
+            offset: 0,
         }
     }
 
     /// Length of the data in this payload
     #[cfg(test)]
     pub fn len(&self) -> usize {
-        self.inner.borrow().len()
+        self.buf.len() + self.inner.borrow().len()
     }
 
     /// Is payload empty
     #[cfg(test)]
     pub fn is_empty(&self) -> bool {
-        self.inner.borrow().len() == 0
+        self.len() == 0
+    }
+
+    /// Returns the number of payload bytes that have not yet been read, if the total length of the
+    /// payload is known.
+    ///
+    /// The total length is known for requests with a `Content-Length` header. Returns `None` for
+    /// chunked or otherwise unknown-length payloads.
+    #[inline]
+    pub fn remaining(&self) -> Option<usize> {
+        let inner = self.inner.borrow();
+        inner
+            .length
+            .map(|length| length.saturating_sub(inner.delivered) + self.buf.len())
+    }
+
+    /// Returns whether the payload ended cleanly, once all of it has been read.
+    ///
+    /// Returns `Some(true)` if the sender signalled the end of the payload, which for HTTP/1
+    /// requests means that the decoder saw the end of the body as given by its `Content-Length`
+    /// or final chunk. Returns `Some(false)` if the payload ended with an error instead, e.g.,
+    /// because the connection was closed or reset before the end of the body. Returns `None`
+    /// while data is still buffered or expected, so that it is already known when the final
+    /// chunk is yielded.
+    pub fn ended_cleanly(&self) -> Option<bool> {
+        let inner = self.inner.borrow();
+
+        if inner.ended_cleanly.is_some() {
+            inner.ended_cleanly
+        } else if !inner.items.is_empty() || !self.buf.is_empty() {
+            None
+        } else if inner.err.is_some() {
+            Some(false)
+        } else if inner.eof {
+            Some(true)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of bytes read from the payload so far.
+    ///
+    /// Bytes count as read in the same way as for [`on_progress`](Self::on_progress): once they
+    /// are yielded by the payload stream or [`poll_take`](Self::poll_take), or
+    /// [consumed](Self::consume) after a [`fill_buf`](Self::fill_buf). Bytes put back with
+    /// [`unread_data`](Self::unread_data) are subtracted again, so the offset always points just
+    /// past the last byte the reader kept. Protocols can record it to resume from the same
+    /// position after a reconnection.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
     }
 
     /// Put unused data back to payload
     #[inline]
     pub fn unread_data(&mut self, data: Bytes) {
+        self.offset = self.offset.saturating_sub(data.len() as u64);
+
+        if let Some(progress) = self.progress.as_mut() {
+            progress.unread(data.len());
+        }
+
+        if !self.buf.is_empty() {
+            // keep unread data ahead of read ahead bytes
+            let mut buf = BytesMut::from(&data[..]);
+            buf.extend_from_slice(&self.buf);
+            self.buf = buf;
+            return;
+        }
+
         self.inner.borrow_mut().unread_data(data);
     }
-}
 
-impl Stream for Payload {
-    type Item = Result<Bytes, PayloadError>;
+    /// Reads until at least `min` bytes are buffered and returns all buffered bytes.
+    ///
+    /// Bytes are accumulated in a buffer owned by the payload, which is only advanced by
+    /// [`consume`](Self::consume). This allows byte-oriented parsers to look ahead into the
+    /// payload across chunk boundaries without putting data back with `unread_data`. Buffered
+    /// bytes are yielded by the payload stream before any further chunks.
+    ///
+    /// Fewer than `min` bytes are returned only if the payload ends first.
+    pub async fn fill_buf(&mut self, min: usize) -> Result<&[u8], PayloadError> {
+        let inner = &self.inner;
 
-    fn poll_next(
-        self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
-    ) -> Poll<Option<Result<Bytes, PayloadError>>> {
-        Pin::new(&mut *self.inner.borrow_mut()).poll_next(cx)
+        while self.buf.len() < min {
+            let item =
+                poll_fn(|cx| Inner::update(inner, |inner| Pin::new(inner).poll_next(cx))).await;
+
+            match item {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return Err(err),
+                None => break,
+            }
+        }
+
+        Ok(&self.buf)
     }
-}
 
-/// Sender part of the payload stream
-pub struct PayloadSender {
-    inner: Weak<RefCell<Inner>>,
-}
+    /// Advances the buffer filled by [`fill_buf`](Self::fill_buf) past its first `n` bytes.
+    ///
+    /// # Panics
+    /// Panics if `n` is larger than the number of buffered bytes.
+    pub fn consume(&mut self, n: usize) {
+        assert!(
+            n <= self.buf.len(),
+            "cannot consume {} bytes, only {} are buffered",
+            n,
+            self.buf.len()
+        );
 
-impl PayloadSender {
-    fn new(inner: Weak<RefCell<Inner>>) -> Self {
-        Self { inner }
+        self.buf.advance(n);
+        self.track_progress(n);
     }
 
-    #[inline]
-    pub fn set_error(&mut self, err: PayloadError) {
-        if let Some(shared) = self.inner.upgrade() {
-            shared.borrow_mut().set_error(err)
+    /// Reads up to and including the first occurrence of `delim`.
+    ///
+    /// Bytes are accumulated across chunks in the same buffer as [`fill_buf`](Self::fill_buf), so
+    /// a delimiter split across chunk boundaries is found and bytes after it are kept for further
+    /// reads. Fails with [`PayloadError::Overflow`] once `max` bytes are buffered without finding
+    /// the delimiter, or if the delimiter ends beyond `max` bytes. If the payload ends first, the
+    /// remaining bytes are returned without a delimiter; empty bytes are returned once the
+    /// payload has ended.
+    ///
+    /// # Panics
+    /// Panics if `delim` is empty.
+    pub async fn read_until(
+        &mut self,
+        delim: &[u8],
+        max: usize,
+    ) -> Result<Bytes, PayloadError> {
+        assert!(!delim.is_empty(), "delimiter must not be empty");
+
+        let inner = &self.inner;
+        let mut searched: usize = 0;
+
+        loop {
+            // a delimiter may start within the bytes searched before the last chunk
+            let start = searched.saturating_sub(delim.len() - 1);
+
+            if let Some(pos) = self.buf[start..]
+                .windows(delim.len())
+                .position(|window| window == delim)
+            {
+                let end = start + pos + delim.len();
+
+                if end > max {
+                    return Err(PayloadError::Overflow);
+                }
+
+                self.track_progress(end);
+                return Ok(self.buf.split_to(end).freeze());
+            }
+
+            if self.buf.len() >= max {
+                return Err(PayloadError::Overflow);
+            }
+
+            searched = self.buf.len();
+
+            let item =
+                poll_fn(|cx| Inner::update(inner, |inner| Pin::new(inner).poll_next(cx))).await;
+
+            match item {
+                Some(Ok(chunk)) => self.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => return Err(err),
+                None => {
+                    self.track_progress(self.buf.len());
+                    return Ok(self.buf.split().freeze());
+                }
+            }
         }
     }
 
-    #[inline]
-    pub fn feed_eof(&mut self) {
-        if let Some(shared) = self.inner.upgrade() {
-            shared.borrow_mut().feed_eof()
+    /// Starts reading the next `n` bytes ahead, returning a handle that can be awaited to take them
+    /// or cancelled to leave them unread.
+    ///
+    /// This allows reading of the next frame to start speculatively while the current one is
+    /// still being processed. Bytes read ahead are kept in the same buffer as
+    /// [`fill_buf`](Self::fill_buf) and only count as read once the handle completes. When the
+    /// handle is [cancelled](ReadAhead::cancel) or dropped before completing, they stay at the
+    /// front of the payload, as if put back with [`unread_data`](Self::unread_data), so that
+    /// they are neither lost nor yielded twice.
+    ///
+    /// Fewer than `n` bytes are returned only if the payload ends first.
+    pub fn read_ahead(&mut self, n: usize) -> ReadAhead<'_> {
+        ReadAhead { payload: self, n }
+    }
+
+    /// Returns a cursor that reads the currently buffered bytes through the [`Buf`] interface.
+    ///
+    /// This allows libraries that parse from a [`Buf`] to read the payload without copying it
+    /// into a contiguous buffer first. The cursor takes the chunks buffered at the time of the
+    /// call, including bytes left by [`fill_buf`](Self::fill_buf), and advances through them
+    /// without copying; use `fill_buf` or [`read_ahead`](Self::read_ahead) beforehand to wait for
+    /// enough bytes to arrive. Bytes count as read once the cursor advances past them. Bytes not
+    /// advanced past when the cursor is dropped stay at the front of the payload.
+    pub fn buf_cursor(&mut self) -> BufCursor<'_> {
+        let mut chunks = VecDeque::new();
+
+        if !self.buf.is_empty() {
+            chunks.push_back(self.buf.split().freeze());
+        }
+
+        Inner::update(&self.inner, |inner| {
+            while let Some(chunk) = inner.pop_front() {
+                if !chunk.is_empty() {
+                    chunks.push_back(chunk);
+                }
+            }
+        });
+
+        BufCursor {
+            remaining: chunks.iter().map(Bytes::len).sum(),
+            chunks,
+            payload: self,
         }
     }
 
-    #[inline]
-    pub fn feed_data(&mut self, data: Bytes) {
-        if let Some(shared) = self.inner.upgrade() {
-            shared.borrow_mut().feed_data(data)
+    /// Returns a stream of records, each consisting of a length prefix in the given format
+    /// followed by that many bytes.
+    ///
+    /// Records are accumulated across chunks in the same buffer as [`fill_buf`](Self::fill_buf)
+    /// and yielded without their prefix; a record only counts as read once it is yielded. The
+    /// stream ends when the payload ends between records and fails with
+    /// [`PayloadError::Incomplete`] if it ends within one. A prefix announcing a record longer
+    /// than `max` bytes fails the stream with [`PayloadError::Overflow`] before any of the record
+    /// is buffered. Bytes of a record that has not been completely received when the stream is
+    /// dropped stay at the front of the payload.
+    pub fn read_length_prefixed(
+        &mut self,
+        format: PrefixFormat,
+        max: usize,
+    ) -> LengthPrefixed<'_> {
+        LengthPrefixed {
+            payload: self,
+            format,
+            max,
+            done: false,
         }
     }
 
-    #[inline]
-    pub fn need_read(&self, cx: &mut Context<'_>) -> PayloadStatus {
-        // we check need_read only if Payload (other side) is alive,
-        // otherwise always return true (consume payload)
-        if let Some(shared) = self.inner.upgrade() {
-            if shared.borrow().need_read {
-                PayloadStatus::Read
-            } else {
-                shared.borrow_mut().register_io(cx);
-                PayloadStatus::Pause
-            }
+    /// Polls for at most `k` bytes from the front chunk of the payload.
+    ///
+    /// If the front chunk is longer than `k`, its first `k` bytes are split off without copying
+    /// and the remainder stays at the front of the payload. Otherwise, this behaves like
+    /// `poll_next`. Bytes are never coalesced across chunks, so fewer than `k` bytes may be
+    /// returned even when more are buffered.
+    pub fn poll_take(
+        &mut self,
+        cx: &mut Context<'_>,
+        k: usize,
+    ) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        let item = if !self.buf.is_empty() {
+            let n = k.min(self.buf.len());
+            Poll::Ready(Some(Ok(self.buf.split_to(n).freeze())))
         } else {
-            PayloadStatus::Dropped
-        }
+            Inner::update(&self.inner, |inner| inner.poll_take(cx, k))
+        };
+
+        self.track_item(item)
     }
-}
 
-#[derive(Debug)]
-struct Inner {
-    len: usize,
-    eof: bool,
-    err: Option<PayloadError>,
-    need_read: bool,
-    items: VecDeque<Bytes>,
-    task: Option<Waker>,
-    io_task: Option<Waker>,
-}
+    /// Registers a callback that is invoked with the cumulative number of bytes read from the
+    /// payload each time another `step` bytes have been read.
+    ///
+    /// Bytes count as read once they are yielded by the payload stream or
+    /// [`poll_take`](Self::poll_take), or [consumed](Self::consume) after a
+    /// [`fill_buf`](Self::fill_buf). The callback is invoked at most once per chunk, so a chunk
+    /// that crosses several milestones results in a single call. Bytes put back with
+    /// [`unread_data`](Self::unread_data) are not counted twice. The callback is dropped, and never
+    /// invoked again, once the payload has ended or failed.
+    ///
+    /// Replaces any previously registered callback.
+    ///
+    /// # Panics
+    /// Panics if `step` is zero.
+    pub fn on_progress(&mut self, step: usize, f: impl Fn(usize) + 'static) {
+        assert!(step > 0, "progress step must be greater than zero");
 
-impl Inner {
-    fn new(eof: bool) -> Self {
-        Inner {
-            eof,
-            len: 0,
-            err: None,
-            items: VecDeque::new(),
-            need_read: true,
-            task: None,
-            io_task: None,
-        }
+        self.progress = Some(Progress {
+            step,
+            read: 0,
+            next: step,
+            f: Box::new(f),
+        });
     }
 
-    /// Wake up future waiting for payload data to be available.
-    fn wake(&mut self) {
-        if let Some(waker) = self.task.take() {
-            waker.wake();
+    /// Updates the progress callback with a poll result of the payload stream.
+    fn track_item(
+        &mut self,
+        item: Poll<Option<Result<Bytes, PayloadError>>>,
+    ) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        match item {
+            Poll::Ready(Some(Ok(ref chunk))) => {
+                if let Some(validator) = self.validator.as_mut() {
+                    if !(validator.0)(chunk) {
+                        self.validator = None;
+                        self.progress = None;
+                        self.force_eof(false);
+                        self.inner.borrow_mut().ended_cleanly = Some(false);
+                        return Poll::Ready(Some(Err(PayloadError::InvalidChunk)));
+                    }
+                }
+
+                self.track_progress(chunk.len());
+            }
+            Poll::Ready(_) => {
+                self.progress = None;
+                self.validator = None;
+            }
+            Poll::Pending => {}
         }
+
+        item
     }
 
-    /// Wake up future feeding data to Payload.
-    fn wake_io(&mut self) {
-        if let Some(waker) = self.io_task.take() {
-            waker.wake();
+    fn track_progress(&mut self, n: usize) {
+        self.offset += n as u64;
+
+        if let Some(progress) = self.progress.as_mut() {
+            progress.advance(n);
         }
     }
 
-    /// Register future waiting data from payload.
-    /// Waker would be used in `Inner::wake`
-    fn register(&mut self, cx: &mut Context<'_>) {
-        if self
-            .task
-            .as_ref()
-            .map_or(true, |w| !cx.waker().will_wake(w))
-        {
-            self.task = Some(cx.waker().clone());
-        }
+    /// Registers a validator that is run on each chunk before it is yielded.
+    ///
+    /// This is for formats whose chunks must satisfy an invariant relative to the chunks before
+    /// them, such as increasing sequence numbers; the validator may keep state across calls. It
+    /// is run on the chunks yielded by the payload stream and [`poll_take`](Self::poll_take). On
+    /// the first chunk it rejects, the payload yields a [`PayloadError::InvalidChunk`] error in
+    /// place of that chunk, then ends as if with [`force_eof(false)`](Self::force_eof), so the
+    /// rest of the body is not read, except that [`ended_cleanly`](Self::ended_cleanly) returns
+    /// `Some(false)`. A rejected chunk does not count as read.
+    ///
+    /// Replaces any previously registered validator.
+    pub fn validate_chunks(&mut self, f: impl FnMut(&[u8]) -> bool + 'static) {
+        self.validator = Some(Validator(Box::new(f)));
     }
 
-    // Register future feeding data to payload.
-    /// Waker would be used in `Inner::wake_io`
-    fn register_io(&mut self, cx: &mut Context<'_>) {
-        if self
-            .io_task
-            .as_ref()
-            .map_or(true, |w| !cx.waker().will_wake(w))
-        {
-            self.io_task = Some(cx.waker().clone());
-        }
+    /// Sets the maximum number of bytes this payload accepts from its sender.
+    ///
+    /// What happens to bytes beyond the limit is determined by the [overflow
+    /// policy](Self::set_overflow_policy). By default, there is no limit.
+    #[inline]
+    pub fn set_limit(&mut self, limit: usize) {
+        self.inner.borrow_mut().limit = limit;
     }
 
+    /// Sets the minimum rate, in bytes per second, at which the sender must feed this payload.
+    ///
+    /// The rate is measured over a sliding `window` while the reader is waiting for data. Once a
+    /// full window has passed since this method was called, the payload yields a
+    /// [`PayloadError::Io`] error with a [`TimedOut`](io::ErrorKind::TimedOut) kind as soon as
+    /// fewer than `bytes_per_sec * window` bytes were received within the last window. Unlike a
+    /// total timeout, this allows large uploads to take as long as needed while still rejecting
+    /// senders that trickle bytes.
+    pub fn set_min_rate(&mut self, bytes_per_sec: usize, window: Duration) {
+        self.inner.borrow_mut().min_rate = Some(MinRate::new(bytes_per_sec, window));
+    }
+
+    /// Sets the maximum time the sender may go without feeding this payload any bytes.
+    ///
+    /// The timer starts when this method is called and restarts whenever a chunk is received.
+    /// Once it expires while the reader is waiting for data, the payload yields a
+    /// [`PayloadError::Io`] error with a [`TimedOut`](io::ErrorKind::TimedOut) kind. Unlike a
+    /// total timeout, this allows uploads to take as long as needed as long as they never stall.
+    pub fn set_idle_timeout(&mut self, timeout: Duration) {
+        self.inner.borrow_mut().idle_timeout = Some(IdleTimeout::new(timeout));
+    }
+
+    /// Ends the payload early, as seen by the reader, discarding any buffered bytes.
+    ///
+    /// This is for readers that reach a logical end of the data, such as an end marker of a
+    /// streaming format, before the end of the body. Afterwards, the payload stream yields `None`
+    /// and [`ended_cleanly`](Self::ended_cleanly) returns `Some(true)`; a pending error is
+    /// discarded too. Discarded bytes do not count as read.
+    ///
+    /// If `drain` is true, the sender keeps reading the rest of the body and discards it, which
+    /// lets an HTTP/1 connection be reused for further requests once it has been received.
+    /// Otherwise, the rest of the body is not read, as if the payload had been dropped.
+    pub fn force_eof(&mut self, drain: bool) {
+        self.buf.clear();
+        Inner::update(&self.inner, |inner| inner.force_eof(drain));
+    }
+
+    /// Takes the trailers fed by the sender along with EOF, if any.
+    ///
+    /// Returns `None` until the sender has called
+    /// [`feed_eof_with_trailers`](PayloadSender::feed_eof_with_trailers).
     #[inline]
-    fn set_error(&mut self, err: PayloadError) {
-        self.err = Some(err);
+    pub fn take_trailers(&mut self) -> Option<HeaderMap> {
+        self.inner.borrow_mut().trailers.take()
     }
 
+    /// Asks the sender to stop reading payload data until [`resume`](Self::resume) is called.
+    ///
+    /// Unlike the automatic backpressure applied when the internal buffer is full, this takes
+    /// effect regardless of how much data is buffered: the sender parks even if the buffer is
+    /// empty. Already buffered data can still be read.
     #[inline]
-    fn feed_eof(&mut self) {
-        self.eof = true;
+    pub fn pause(&mut self) {
+        self.inner.borrow_mut().paused = true;
     }
 
+    /// Allows the sender to continue reading payload data after a call to [`pause`](Self::pause).
     #[inline]
-    fn feed_data(&mut self, data: Bytes) {
-        self.len += data.len();
-        self.items.push_back(data);
-        self.need_read = self.len < MAX_BUFFER_SIZE;
-        self.wake();
+    pub fn resume(&mut self) {
+        Inner::update(&self.inner, |inner| {
+            inner.paused = false;
+            inner.wake_io();
+        });
     }
 
-    #[cfg(test)]
-    fn len(&self) -> usize {
-        self.len
+    /// Returns true if reading has been paused with [`pause`](Self::pause).
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.inner.borrow().paused
     }
 
+    /// Sets behavior for when more bytes than the [limit](Self::set_limit) are fed to this payload.
+    ///
+    /// Defaults to [`OverflowPolicy::Error`].
+    #[inline]
+    pub fn set_overflow_policy(&mut self, policy: OverflowPolicy) {
+        self.inner.borrow_mut().overflow_policy = policy;
+    }
+}
+
+impl Stream for Payload {
+    type Item = Result<Bytes, PayloadError>;
+
     fn poll_next(
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<Bytes, PayloadError>>> {
-        if let Some(data) = self.items.pop_front() {
-            self.len -= data.len();
-            self.need_read = self.len < MAX_BUFFER_SIZE;
-
-            if self.need_read && !self.eof {
-                self.register(cx);
-            }
-            self.wake_io();
-            Poll::Ready(Some(Ok(data)))
-        } else if let Some(err) = self.err.take() {
-            Poll::Ready(Some(Err(err)))
-        } else if self.eof {
-            Poll::Ready(None)
+        let item = if !self.buf.is_empty() {
+            Poll::Ready(Some(Ok(self.buf.split().freeze())))
         } else {
-            self.need_read = true;
-            self.register(cx);
-            self.wake_io();
-            Poll::Pending
-        }
-    }
+            Inner::update(&self.inner, |inner| Pin::new(inner).poll_next(cx))
+        };
 
-    fn unread_data(&mut self, data: Bytes) {
-        self.len += data.len();
-        self.items.push_front(data);
+        self.track_item(item)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use actix_utils::future::poll_fn;
-    use static_assertions::{assert_impl_all, assert_not_impl_any};
+/// Handle for bytes being read ahead, returned by [`Payload::read_ahead`].
+///
+/// Resolves to the bytes read ahead once enough have arrived.
+#[derive(Debug)]
+#[must_use = "bytes are only read ahead while the handle is polled"]
+pub struct ReadAhead<'a> {
+    payload: &'a mut Payload,
+    n: usize,
+}
 
-    use super::*;
+impl ReadAhead<'_> {
+    /// Returns the bytes read ahead so far.
+    pub fn buffered(&self) -> &[u8] {
+        let buf = &self.payload.buf;
+        &buf[..self.n.min(buf.len())]
+    }
 
-    assert_impl_all!(Payload: Unpin);
-    assert_not_impl_any!(Payload: Send, Sync);
+    /// Cancels reading ahead, leaving the bytes read so far at the front of the payload.
+    pub fn cancel(self) {}
+}
 
-    assert_impl_all!(Inner: Unpin, Send, Sync);
+impl Future for ReadAhead<'_> {
+    type Output = Result<Bytes, PayloadError>;
 
-    #[actix_rt::test]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let n = self.n;
+        let payload = &mut *self.payload;
+
+        while payload.buf.len() < n {
+            let item = Inner::update(&payload.inner, |inner| Pin::new(inner).poll_next(cx));
+
+            match item {
+                Poll::Ready(Some(Ok(chunk))) => payload.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                Poll::Ready(None) => break,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let len = n.min(payload.buf.len());
+        payload.track_progress(len);
+        Poll::Ready(Ok(payload.buf.split_to(len).freeze()))
+    }
+}
+
+/// Cursor over the bytes buffered in a payload, returned by [`Payload::buf_cursor`].
+///
+/// Implements [`Buf`] by advancing through the buffered chunks; [`chunk`](Buf::chunk) returns
+/// the rest of the front chunk.
+#[derive(Debug)]
+pub struct BufCursor<'a> {
+    payload: &'a mut Payload,
+    chunks: VecDeque<Bytes>,
+    remaining: usize,
+}
+
+impl Buf for BufCursor<'_> {
+    fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.chunks.front().map_or(&[], |chunk| chunk)
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.remaining,
+            "cannot advance {} bytes, only {} are buffered",
+            cnt,
+            self.remaining
+        );
+
+        self.remaining -= cnt;
+        self.payload.track_progress(cnt);
+
+        while cnt > 0 {
+            let front = self.chunks.front_mut().unwrap();
+
+            if front.len() > cnt {
+                front.advance(cnt);
+                break;
+            }
+
+            cnt -= front.len();
+            self.chunks.pop_front();
+        }
+    }
+}
+
+impl Drop for BufCursor<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.payload.inner.borrow_mut();
+
+        while let Some(chunk) = self.chunks.pop_back() {
+            inner.unread_data(chunk);
+        }
+    }
+}
+
+/// Stream of length-prefixed records, returned by [`Payload::read_length_prefixed`].
+#[derive(Debug)]
+#[must_use = "streams do nothing unless polled"]
+pub struct LengthPrefixed<'a> {
+    payload: &'a mut Payload,
+    format: PrefixFormat,
+    max: usize,
+    done: bool,
+}
+
+impl LengthPrefixed<'_> {
+    /// Reads into the payload's buffer until it holds at least `n` bytes, returning `false` if
+    /// the payload ends first.
+    fn poll_fill(
+        &mut self,
+        cx: &mut Context<'_>,
+        n: usize,
+    ) -> Poll<Result<bool, PayloadError>> {
+        let payload = &mut *self.payload;
+
+        while payload.buf.len() < n {
+            let item = Inner::update(&payload.inner, |inner| Pin::new(inner).poll_next(cx));
+
+            match item {
+                Poll::Ready(Some(Ok(chunk))) => payload.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                Poll::Ready(None) => return Poll::Ready(Ok(false)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Poll::Ready(Ok(true))
+    }
+}
+
+impl Stream for LengthPrefixed<'_> {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+
+        let prefix_len = self.format.len();
+
+        let res = match ready!(self.poll_fill(cx, prefix_len)) {
+            Ok(true) => {
+                let record_len = self.format.decode(&self.payload.buf[..prefix_len]);
+
+                if record_len > self.max {
+                    Err(PayloadError::Overflow)
+                } else {
+                    let len = prefix_len + record_len;
+
+                    match ready!(self.poll_fill(cx, len)) {
+                        Ok(true) => Ok(len),
+                        Ok(false) => Err(PayloadError::Incomplete(None)),
+                        Err(err) => Err(err),
+                    }
+                }
+            }
+
+            // payload ended between records
+            Ok(false) if self.payload.buf.is_empty() => {
+                self.done = true;
+                return Poll::Ready(None);
+            }
+
+            Ok(false) => Err(PayloadError::Incomplete(None)),
+            Err(err) => Err(err),
+        };
+
+        match res {
+            Ok(len) => {
+                let payload = &mut *self.payload;
+                payload.track_progress(len);
+
+                let mut record = payload.buf.split_to(len);
+                record.advance(prefix_len);
+                Poll::Ready(Some(Ok(record.freeze())))
+            }
+
+            Err(err) => {
+                self.done = true;
+                Poll::Ready(Some(Err(err)))
+            }
+        }
+    }
+}
+
+/// Chunk validator registered with [`Payload::validate_chunks`].
+struct Validator(Box<dyn FnMut(&[u8]) -> bool>);
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator").finish_non_exhaustive()
+    }
+}
+
+/// Progress callback registered with [`Payload::on_progress`].
+struct Progress {
+    step: usize,
+    /// Number of bytes read, less those put back.
+    read: usize,
+    /// Number of read bytes at which the callback is next invoked.
+    next: usize,
+    f: Box<dyn Fn(usize)>,
+}
+
+impl Progress {
+    fn advance(&mut self, n: usize) {
+        self.read += n;
+
+        if self.read >= self.next {
+            (self.f)(self.read);
+            self.next = (self.read / self.step + 1).saturating_mul(self.step);
+        }
+    }
+
+    fn unread(&mut self, n: usize) {
+        self.read = self.read.saturating_sub(n);
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("step", &self.step)
+            .field("read", &self.read)
+            .field("next", &self.next)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Sender part of the payload stream
+pub struct PayloadSender {
+    inner: Weak<RefCell<Inner>>,
+}
+
+impl PayloadSender {
+    fn new(inner: Weak<RefCell<Inner>>) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub fn set_error(&mut self, err: PayloadError) {
+        if let Some(shared) = self.inner.upgrade() {
+            // the reader may live on another task than the one feeding the payload
+            Inner::update(&shared, |inner| {
+                inner.set_error(err);
+                inner.wake();
+            })
+        }
+    }
+
+    #[inline]
+    pub fn feed_eof(&mut self) {
+        if let Some(shared) = self.inner.upgrade() {
+            Inner::update(&shared, |inner| {
+                inner.feed_eof();
+                inner.wake();
+            })
+        }
+    }
+
+    /// Makes the payload update `metrics` as it is fed and read.
+    pub(crate) fn set_metrics(&mut self, metrics: PayloadMetrics) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().metrics = Some(metrics);
+        }
+    }
+
+    /// Sets the total length of the payload, once known, for use by [`Payload::remaining`].
+    #[inline]
+    pub fn set_length(&mut self, length: usize) {
+        if let Some(shared) = self.inner.upgrade() {
+            shared.borrow_mut().length = Some(length);
+        }
+    }
+
+    /// Signals EOF and makes `trailers` available to the receiver through
+    /// [`Payload::take_trailers`].
+    #[inline]
+    pub fn feed_eof_with_trailers(&mut self, trailers: HeaderMap) {
+        if let Some(shared) = self.inner.upgrade() {
+            Inner::update(&shared, |inner| {
+                inner.trailers = Some(trailers);
+                inner.feed_eof();
+                inner.wake();
+            })
+        }
+    }
+
+    #[inline]
+    pub fn feed_data(&mut self, data: Bytes) {
+        self.feed_data_priority(data, Priority::Normal);
+    }
+
+    /// Feeds a chunk with the given priority.
+    ///
+    /// High priority chunks jump the queue of buffered normal priority chunks, which allows control
+    /// frames of a protocol multiplexed over the payload to be read ahead of pending data. Limits
+    /// and backpressure apply to chunks of either priority.
+    #[inline]
+    pub fn feed_data_priority(&mut self, data: Bytes, priority: Priority) {
+        if let Some(shared) = self.inner.upgrade() {
+            Inner::update(&shared, |inner| inner.feed_data(data, priority))
+        }
+    }
+
+    /// Makes the payload yield `err` once `n` more bytes have been fed to it.
+    ///
+    /// Unlike [`set_error`](Self::set_error), bytes fed before the offset is reached are still
+    /// yielded to the reader. Bytes beyond the offset are discarded.
+    #[cfg(any(test, feature = "__fault-injection"))]
+    pub fn fail_after(&mut self, n: usize, err: PayloadError) {
+        if let Some(shared) = self.inner.upgrade() {
+            Inner::update(&shared, |inner| inner.fail_after(n, err))
+        }
+    }
+
+    /// Returns a handle to the number of bytes buffered in the payload, which remains usable after
+    /// the sender is dropped.
+    pub(crate) fn buffered_bytes(&self) -> BufferedBytes {
+        BufferedBytes(self.inner.clone())
+    }
+
+    #[inline]
+    pub fn need_read(&self, cx: &mut Context<'_>) -> PayloadStatus {
+        // we check need_read only if Payload (other side) is alive,
+        // otherwise always return true (consume payload)
+        if let Some(shared) = self.inner.upgrade() {
+            let need_read = {
+                let inner = shared.borrow();
+                inner.need_read && !inner.paused
+            };
+
+            if need_read {
+                shared.borrow_mut().backpressure_released();
+                PayloadStatus::Read
+            } else {
+                let mut inner = shared.borrow_mut();
+                inner.backpressure();
+                inner.register_io(cx);
+                PayloadStatus::Pause
+            }
+        } else {
+            PayloadStatus::Dropped
+        }
+    }
+}
+
+/// Number of bytes buffered in a payload that have not been read yet.
+pub(crate) struct BufferedBytes(Weak<RefCell<Inner>>);
+
+impl BufferedBytes {
+    /// Returns the number of buffered bytes, or zero once the payload has been dropped.
+    pub(crate) fn get(&self) -> usize {
+        self.0.upgrade().map_or(0, |shared| shared.borrow().len)
+    }
+}
+
+/// Span covering the read of a payload, along with events for backpressure and overflow.
+///
+/// Does nothing unless the `tracing` crate feature is enabled.
+#[derive(Debug, Default)]
+struct PayloadTrace {
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+    #[cfg(feature = "tracing")]
+    backpressure: bool,
+}
+
+#[cfg(feature = "tracing")]
+impl PayloadTrace {
+    fn new() -> Self {
+        Self {
+            span: Some(tracing::debug_span!(
+                "payload",
+                bytes = tracing::field::Empty
+            )),
+            backpressure: false,
+        }
+    }
+
+    fn backpressure(&mut self, buffered: usize) {
+        if let (Some(span), false) = (&self.span, self.backpressure) {
+            tracing::debug!(parent: span, buffered, "payload backpressure applied");
+            self.backpressure = true;
+        }
+    }
+
+    fn backpressure_released(&mut self) {
+        if let (Some(span), true) = (&self.span, self.backpressure) {
+            tracing::debug!(parent: span, "payload backpressure released");
+            self.backpressure = false;
+        }
+    }
+
+    fn overflow(&self, limit: usize, received: usize) {
+        if let Some(span) = &self.span {
+            tracing::debug!(parent: span, limit, received, "payload overflowed its limit");
+        }
+    }
+
+    /// Closes the span once the payload has been fully read or has failed.
+    fn finish(&mut self, bytes: usize, err: Option<&PayloadError>) {
+        if let Some(span) = self.span.take() {
+            span.record("bytes", bytes);
+
+            match err {
+                Some(err) => tracing::debug!(parent: &span, bytes, %err, "payload read failed"),
+                None => tracing::debug!(parent: &span, bytes, "payload read complete"),
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl PayloadTrace {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn backpressure(&mut self, _buffered: usize) {}
+
+    fn backpressure_released(&mut self) {}
+
+    fn overflow(&self, _limit: usize, _received: usize) {}
+
+    fn finish(&mut self, _bytes: usize, _err: Option<&PayloadError>) {}
+}
+
+/// Sliding window of received bytes used to enforce [`Payload::set_min_rate`].
+#[derive(Debug)]
+struct MinRate {
+    /// Number of bytes that must be received within each window.
+    min_bytes: u128,
+    window: Duration,
+    /// Time at which the first full window ends.
+    grace_until: Instant,
+    /// Sizes of chunks received within the last window, oldest first.
+    samples: VecDeque<(Instant, usize)>,
+    received: u128,
+    timer: Pin<Box<Sleep>>,
+}
+
+impl MinRate {
+    fn new(bytes_per_sec: usize, window: Duration) -> Self {
+        let now = Instant::now();
+
+        MinRate {
+            min_bytes: bytes_per_sec as u128 * window.as_millis() / 1000,
+            window,
+            grace_until: now + window,
+            samples: VecDeque::new(),
+            received: 0,
+            timer: Box::pin(sleep_until(now + window)),
+        }
+    }
+
+    fn record(&mut self, now: Instant, len: usize) {
+        self.expire(now);
+        self.samples.push_back((now, len));
+        self.received += len as u128;
+    }
+
+    /// Removes samples that are no longer within the window ending at `now`.
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(at, len)) = self.samples.front() {
+            if at + self.window > now {
+                break;
+            }
+
+            self.received -= len as u128;
+            self.samples.pop_front();
+        }
+    }
+
+    /// Returns true if the rate dropped below the minimum, otherwise arranges for the current task
+    /// to be woken when it would if no more bytes are received.
+    fn poll_exceeded(&mut self, cx: &mut Context<'_>) -> bool {
+        let now = Instant::now();
+        self.expire(now);
+
+        if now >= self.grace_until && self.received < self.min_bytes {
+            return true;
+        }
+
+        // during the first window, the rate may already be below the minimum; it is then
+        // exceeded once that window ends
+        let mut deadline = self.grace_until;
+
+        // otherwise, find when enough samples will have expired for the rate to drop below the
+        // minimum
+        if self.received >= self.min_bytes {
+            let mut received = self.received;
+
+            for &(at, len) in &self.samples {
+                received -= len as u128;
+
+                if received < self.min_bytes {
+                    deadline = deadline.max(at + self.window);
+                    break;
+                }
+            }
+        }
+
+        self.timer.as_mut().reset(deadline);
+        let _ = self.timer.as_mut().poll(cx);
+
+        false
+    }
+}
+
+/// Timer used to enforce [`Payload::set_idle_timeout`].
+#[derive(Debug)]
+struct IdleTimeout {
+    timeout: Duration,
+    timer: Pin<Box<Sleep>>,
+}
+
+impl IdleTimeout {
+    fn new(timeout: Duration) -> Self {
+        IdleTimeout {
+            timeout,
+            timer: Box::pin(sleep_until(Instant::now() + timeout)),
+        }
+    }
+
+    fn record(&mut self, now: Instant) {
+        let deadline = now + self.timeout;
+        self.timer.as_mut().reset(deadline);
+    }
+
+    /// Returns true if the timeout expired, otherwise arranges for the current task to be woken
+    /// when it does.
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> bool {
+        self.timer.as_mut().poll(cx).is_ready()
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    len: usize,
+    eof: bool,
+    err: Option<PayloadError>,
+    need_read: bool,
+    items: VecDeque<Bytes>,
+    /// Number of high priority chunks at the front of `items`.
+    high: usize,
+    task: Option<Waker>,
+    io_task: Option<Waker>,
+    /// Wakers taken from `task` and `io_task`, to be woken once `Inner` is no longer borrowed.
+    woken: Option<Waker>,
+    woken_io: Option<Waker>,
+    /// Total number of bytes fed by sender.
+    fed: usize,
+    limit: usize,
+    overflow_policy: OverflowPolicy,
+    /// Set when limit is exceeded under [`OverflowPolicy::Pause`].
+    overflowed: bool,
+    /// Set while reading is paused by [`Payload::pause`].
+    paused: bool,
+    /// Number of bytes remaining until the injected error is set.
+    #[cfg(any(test, feature = "__fault-injection"))]
+    fail_after: Option<(usize, PayloadError)>,
+    /// Chunk sizes set by [`Payload::create_with_chunking`].
+    #[cfg(any(test, feature = "__fault-injection"))]
+    chunking: Option<Chunking>,
+    min_rate: Option<MinRate>,
+    idle_timeout: Option<IdleTimeout>,
+    /// Trailers fed by sender along with EOF.
+    trailers: Option<HeaderMap>,
+    /// Total length of payload, if known.
+    length: Option<usize>,
+    /// Number of bytes yielded to reader.
+    delivered: usize,
+    /// Set once the reader has been yielded the end of the payload or an error.
+    ended_cleanly: Option<bool>,
+    trace: PayloadTrace,
+    metrics: Option<PayloadMetrics>,
+    /// Set while backpressure is applied to the sender.
+    backpressured: bool,
+    /// Set by [`Payload::force_eof`]; further fed bytes are discarded.
+    discard: bool,
+}
+
+impl Inner {
+    fn new(eof: bool) -> Self {
+        Inner {
+            eof,
+            len: 0,
+            err: None,
+            items: VecDeque::new(),
+            high: 0,
+            need_read: true,
+            task: None,
+            io_task: None,
+            woken: None,
+            woken_io: None,
+            fed: 0,
+            limit: usize::MAX,
+            overflow_policy: OverflowPolicy::default(),
+            overflowed: false,
+            paused: false,
+            #[cfg(any(test, feature = "__fault-injection"))]
+            fail_after: None,
+            #[cfg(any(test, feature = "__fault-injection"))]
+            chunking: None,
+            min_rate: None,
+            idle_timeout: None,
+            trailers: None,
+            length: None,
+            delivered: 0,
+            ended_cleanly: None,
+            trace: if eof {
+                PayloadTrace::default()
+            } else {
+                PayloadTrace::new()
+            },
+            metrics: None,
+            backpressured: false,
+            discard: false,
+        }
+    }
+
+    /// Runs `f` on the shared state, then wakes any tasks it notified.
+    ///
+    /// Wakers are only invoked after the borrow of `shared` is released. A registered waker may
+    /// belong to a reader future that has since been dropped (e.g., after losing a `select!`) and
+    /// an executor may poll other futures from within `wake`, so waking must not happen while
+    /// `Inner` is borrowed.
+    fn update<R>(shared: &RefCell<Inner>, f: impl FnOnce(&mut Inner) -> R) -> R {
+        let (res, woken, woken_io) = {
+            let mut inner = shared.borrow_mut();
+            let res = f(&mut inner);
+            (res, inner.woken.take(), inner.woken_io.take())
+        };
+
+        if let Some(waker) = woken {
+            waker.wake();
+        }
+
+        if let Some(waker) = woken_io {
+            waker.wake();
+        }
+
+        res
+    }
+
+    /// Wake up future waiting for payload data to be available.
+    ///
+    /// The waker is invoked by [`Inner::update`].
+    fn wake(&mut self) {
+        if let Some(waker) = self.task.take() {
+            self.woken = Some(waker);
+        }
+    }
+
+    /// Wake up future feeding data to Payload.
+    ///
+    /// The waker is invoked by [`Inner::update`].
+    fn wake_io(&mut self) {
+        if let Some(waker) = self.io_task.take() {
+            self.woken_io = Some(waker);
+        }
+    }
+
+    /// Register future waiting data from payload.
+    /// Waker would be used in `Inner::wake`
+    fn register(&mut self, cx: &mut Context<'_>) {
+        if self
+            .task
+            .as_ref()
+            .map_or(true, |w| !cx.waker().will_wake(w))
+        {
+            self.task = Some(cx.waker().clone());
+        }
+    }
+
+    // Register future feeding data to payload.
+    /// Waker would be used in `Inner::wake_io`
+    fn register_io(&mut self, cx: &mut Context<'_>) {
+        if self
+            .io_task
+            .as_ref()
+            .map_or(true, |w| !cx.waker().will_wake(w))
+        {
+            self.io_task = Some(cx.waker().clone());
+        }
+    }
+
+    #[inline]
+    fn set_error(&mut self, err: PayloadError) {
+        self.err = Some(err);
+    }
+
+    #[cfg(any(test, feature = "__fault-injection"))]
+    fn fail_after(&mut self, n: usize, err: PayloadError) {
+        if n == 0 {
+            self.set_error(err);
+            self.wake();
+        } else {
+            self.fail_after = Some((n, err));
+        }
+    }
+
+    /// Truncates `data` at the injected error offset, returning the error if it is reached.
+    #[cfg(any(test, feature = "__fault-injection"))]
+    fn take_injected_err(&mut self, mut data: Bytes) -> (Bytes, Option<PayloadError>) {
+        match self.fail_after.take() {
+            Some((remaining, err)) if data.len() >= remaining => {
+                data.truncate(remaining);
+                (data, Some(err))
+            }
+            Some((remaining, err)) => {
+                self.fail_after = Some((remaining - data.len(), err));
+                (data, None)
+            }
+            None => (data, None),
+        }
+    }
+
+    fn backpressure(&mut self) {
+        let buffered = self.len;
+        self.trace.backpressure(buffered);
+
+        if !self.backpressured {
+            self.backpressured = true;
+
+            if let Some(metrics) = &self.metrics {
+                metrics.backpressure();
+            }
+        }
+    }
+
+    fn backpressure_released(&mut self) {
+        self.trace.backpressure_released();
+        self.backpressured = false;
+    }
+
+    #[inline]
+    fn feed_eof(&mut self) {
+        #[cfg(any(test, feature = "__fault-injection"))]
+        if let Some(chunking) = self.chunking.as_mut() {
+            chunking.flush(&mut self.items);
+        }
+
+        self.eof = true;
+    }
+
+    #[inline]
+    fn feed_data(&mut self, data: Bytes, priority: Priority) {
+        if self.overflowed || self.err.is_some() || self.discard {
+            return;
+        }
+
+        #[cfg(any(test, feature = "__fault-injection"))]
+        let (data, injected_err) = self.take_injected_err(data);
+
+        self.fed += data.len();
+
+        if self.fed > self.limit {
+            self.trace.overflow(self.limit, self.fed);
+
+            match self.overflow_policy {
+                OverflowPolicy::Error => self.err = Some(PayloadError::Overflow),
+                OverflowPolicy::Pause => {
+                    self.overflowed = true;
+                    self.need_read = false;
+                }
+            }
+
+            self.wake();
+            return;
+        }
+
+        if let Some(min_rate) = self.min_rate.as_mut() {
+            min_rate.record(Instant::now(), data.len());
+        }
+
+        if let Some(idle_timeout) = self.idle_timeout.as_mut() {
+            idle_timeout.record(Instant::now());
+        }
+
+        self.len += data.len();
+
+        if let Some(metrics) = &self.metrics {
+            metrics.received(data.len());
+        }
+
+        match priority {
+            Priority::Normal => self.push_back(data),
+            Priority::High => {
+                self.items.insert(self.high, data);
+                self.high += 1;
+            }
+        }
+
+        self.need_read = self.len < MAX_BUFFER_SIZE;
+
+        #[cfg(any(test, feature = "__fault-injection"))]
+        if let Some(err) = injected_err {
+            self.set_error(err);
+        }
+
+        self.wake();
+    }
+
+    fn force_eof(&mut self, drain: bool) {
+        if let Some(metrics) = &self.metrics {
+            metrics.unbuffered(self.len);
+        }
+
+        self.items.clear();
+        self.high = 0;
+        self.len = 0;
+        self.err = None;
+        self.eof = true;
+        self.discard = true;
+
+        #[cfg(any(test, feature = "__fault-injection"))]
+        {
+            self.fail_after = None;
+            self.chunking = None;
+        }
+
+        self.paused = false;
+        self.need_read = drain;
+
+        if drain {
+            self.wake_io();
+        }
+    }
+
+    fn push_back(&mut self, data: Bytes) {
+        #[cfg(any(test, feature = "__fault-injection"))]
+        if let Some(chunking) = self.chunking.as_mut() {
+            chunking.push_back(data, &mut self.items);
+            return;
+        }
+
+        self.items.push_back(data);
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Takes the front chunk, if any, without registering interest in further data.
+    fn pop_front(&mut self) -> Option<Bytes> {
+        let data = self.items.pop_front()?;
+
+        self.high = self.high.saturating_sub(1);
+        self.len -= data.len();
+        self.delivered += data.len();
+
+        if let Some(metrics) = &self.metrics {
+            metrics.unbuffered(data.len());
+        }
+
+        self.need_read = self.len < MAX_BUFFER_SIZE && !self.overflowed;
+        self.wake_io();
+
+        Some(data)
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        if let Some(data) = self.pop_front() {
+            if self.need_read && !self.eof {
+                self.register(cx);
+            }
+            Poll::Ready(Some(Ok(data)))
+        } else if let Some(err) = self.err.take() {
+            let delivered = self.delivered;
+            self.trace.finish(delivered, Some(&err));
+            self.ended_cleanly = Some(false);
+            Poll::Ready(Some(Err(err)))
+        } else if self.eof {
+            let delivered = self.delivered;
+            self.trace.finish(delivered, None);
+            // a payload ended early by a rejected chunk stays unclean
+            self.ended_cleanly.get_or_insert(true);
+            Poll::Ready(None)
+        } else if self
+            .min_rate
+            .as_mut()
+            .map_or(false, |min_rate| min_rate.poll_exceeded(cx))
+        {
+            self.min_rate = None;
+            self.ended_cleanly = Some(false);
+            Poll::Ready(Some(Err(PayloadError::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "payload was received at less than the minimum rate",
+            )))))
+        } else if self
+            .idle_timeout
+            .as_mut()
+            .map_or(false, |idle_timeout| idle_timeout.poll_expired(cx))
+        {
+            self.idle_timeout = None;
+            self.ended_cleanly = Some(false);
+            Poll::Ready(Some(Err(PayloadError::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "no payload bytes were received within the idle timeout",
+            )))))
+        } else {
+            self.need_read = !self.overflowed;
+            self.register(cx);
+            self.wake_io();
+            Poll::Pending
+        }
+    }
+
+    fn poll_take(
+        &mut self,
+        cx: &mut Context<'_>,
+        k: usize,
+    ) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        match self.items.front_mut() {
+            Some(front) if front.len() > k => {
+                let data = front.split_to(k);
+                self.len -= data.len();
+                self.delivered += data.len();
+
+                if let Some(metrics) = &self.metrics {
+                    metrics.unbuffered(data.len());
+                }
+
+                self.need_read = self.len < MAX_BUFFER_SIZE && !self.overflowed;
+
+                if self.need_read && !self.eof {
+                    self.register(cx);
+                }
+                self.wake_io();
+                Poll::Ready(Some(Ok(data)))
+            }
+            _ => Pin::new(self).poll_next(cx),
+        }
+    }
+
+    fn unread_data(&mut self, data: Bytes) {
+        // unread data is yielded first, so ahead of high priority chunks too
+        self.high += 1;
+        self.len += data.len();
+        self.delivered = self.delivered.saturating_sub(data.len());
+
+        if let Some(metrics) = &self.metrics {
+            metrics.buffered(data.len());
+        }
+
+        self.items.push_front(data);
+    }
+}
+
+/// Re-chunks fed data for [`Payload::create_with_chunking`].
+#[cfg(any(test, feature = "__fault-injection"))]
+#[derive(Debug)]
+struct Chunking {
+    /// Sizes of the chunks still to be yielded.
+    sizes: VecDeque<usize>,
+    /// Data fed since the last complete chunk.
+    pending: BytesMut,
+}
+
+#[cfg(any(test, feature = "__fault-injection"))]
+impl Chunking {
+    fn push_back(&mut self, data: Bytes, items: &mut VecDeque<Bytes>) {
+        if self.sizes.is_empty() {
+            items.push_back(data);
+            return;
+        }
+
+        self.pending.extend_from_slice(&data);
+
+        while let Some(&size) = self.sizes.front() {
+            if self.pending.len() < size {
+                return;
+            }
+
+            self.sizes.pop_front();
+            items.push_back(self.pending.split_to(size).freeze());
+        }
+
+        // data beyond the last size is yielded as it was fed
+        self.flush(items);
+    }
+
+    fn flush(&mut self, items: &mut VecDeque<Bytes>) {
+        if !self.pending.is_empty() {
+            items.push_back(self.pending.split().freeze());
+        }
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // bytes that were never read are no longer buffered either
+        if let Some(metrics) = &self.metrics {
+            metrics.unbuffered(self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use actix_utils::future::poll_fn;
+    use bytes::BytesMut;
+    use futures_util::{
+        future::lazy,
+        task::{waker, ArcWake},
+        StreamExt as _,
+    };
+    use static_assertions::{assert_impl_all, assert_not_impl_any};
+
+    use super::*;
+    use crate::h1::PayloadMetricsSnapshot;
+
+    assert_impl_all!(Payload: Unpin);
+    assert_not_impl_any!(Payload: Send, Sync);
+
+    assert_impl_all!(Inner: Unpin, Send, Sync);
+
+    thread_local! {
+        static SHARED: RefCell<Weak<RefCell<Inner>>> = RefCell::new(Weak::new());
+    }
+
+    /// Counts wakes and checks that the payload is not borrowed while being woken.
+    struct CheckingWaker(AtomicUsize);
+
+    impl ArcWake for CheckingWaker {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+
+            SHARED.with(|shared| {
+                if let Some(inner) = shared.borrow().upgrade() {
+                    assert!(inner.try_borrow_mut().is_ok());
+                }
+            });
+        }
+    }
+
+    #[test]
+    fn test_dropped_reader() {
+        let (mut sender, mut payload) = Payload::create(false);
+        SHARED.with(|shared| *shared.borrow_mut() = Rc::downgrade(&payload.inner));
+
+        let counter = Arc::new(CheckingWaker(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        // reader is dropped mid-poll, leaving its waker registered
+        let mut next = payload.next();
+        assert!(Pin::new(&mut next).poll(&mut cx).is_pending());
+        drop(next);
+
+        sender.feed_data(Bytes::from("data"));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        sender.feed_data(Bytes::from("line1"));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+
+        let chunk = Pin::new(&mut payload.next()).poll(&mut cx);
+        assert!(matches!(chunk, Poll::Ready(Some(Ok(ref chunk))) if chunk == "data"));
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        let counter = Arc::new(CheckingWaker(AtomicUsize::new(0)));
+        let waker = waker(Arc::clone(&counter));
+        let mut cx = Context::from_waker(&waker);
+
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Read);
+
+        payload.pause();
+        assert!(payload.is_paused());
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+
+        // draining the buffer does not resume reading
+        sender.feed_data(Bytes::from("data"));
+        let chunk = Pin::new(&mut payload.next()).poll(&mut cx);
+        assert!(matches!(chunk, Poll::Ready(Some(Ok(ref chunk))) if chunk == "data"));
+        assert!(Pin::new(&mut payload.next()).poll(&mut cx).is_pending());
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+
+        let wakes = counter.0.load(Ordering::SeqCst);
+        payload.resume();
+        assert!(!payload.is_paused());
+        assert_eq!(counter.0.load(Ordering::SeqCst), wakes + 1);
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Read);
+    }
+
+    #[actix_rt::test]
+    async fn test_remaining() {
+        let (mut sender, mut payload) = Payload::create(false);
+        assert_eq!(payload.remaining(), None);
+
+        sender.set_length(10);
+        assert_eq!(payload.remaining(), Some(10));
+
+        sender.feed_data(Bytes::from("data"));
+        sender.feed_data(Bytes::from("123456"));
+        assert_eq!(payload.remaining(), Some(10));
+
+        let chunk = poll_fn(|cx| payload.poll_take(cx, 3))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk, "dat");
+        assert_eq!(payload.remaining(), Some(7));
+
+        let chunk = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(chunk, "a");
+        assert_eq!(payload.remaining(), Some(6));
+
+        payload.unread_data(chunk);
+        assert_eq!(payload.remaining(), Some(7));
+
+        sender.feed_eof();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+            chunk.unwrap();
+        }
+        assert_eq!(payload.remaining(), Some(0));
+    }
+
+    #[actix_rt::test]
+    async fn test_trailers() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        sender.feed_data(Bytes::from("data"));
+        assert!(payload.take_trailers().is_none());
+
+        let mut trailers = HeaderMap::new();
+        trailers.insert(
+            crate::header::HeaderName::from_static("x-checksum"),
+            crate::header::HeaderValue::from_static("abc"),
+        );
+        sender.feed_eof_with_trailers(trailers);
+
+        assert_eq!(
+            Bytes::from("data"),
+            poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap()
+        );
+        assert!(poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .is_none());
+
+        let trailers = payload.take_trailers().unwrap();
+        assert_eq!(trailers.get("x-checksum").unwrap(), "abc");
+        assert!(payload.take_trailers().is_none());
+    }
+
+    #[actix_rt::test]
     async fn test_unread_data() {
         let (_, mut payload) = Payload::create(false);
 
@@ -278,4 +1831,826 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[actix_rt::test]
+    async fn test_feed_data_priority() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        sender.feed_data(Bytes::from("data 1"));
+        sender.feed_data(Bytes::from("data 2"));
+        sender.feed_data_priority(Bytes::from("control 1"), Priority::High);
+        sender.feed_data_priority(Bytes::from("control 2"), Priority::High);
+        sender.feed_data_priority(Bytes::from("data 3"), Priority::Normal);
+        sender.feed_eof();
+
+        assert_eq!(payload.next().await.unwrap().unwrap(), "control 1");
+
+        // unread data stays at the front, ahead of high priority chunks
+        payload.unread_data(Bytes::from("control 1"));
+        assert_eq!(payload.next().await.unwrap().unwrap(), "control 1");
+
+        // high priority chunks fed after normal ones are read are still read first
+        assert_eq!(payload.next().await.unwrap().unwrap(), "control 2");
+        sender.feed_data_priority(Bytes::from("control 3"), Priority::High);
+
+        let mut read = Vec::new();
+        while let Some(chunk) = payload.next().await {
+            read.push(chunk.unwrap());
+        }
+        assert_eq!(read, ["control 3", "data 1", "data 2", "data 3"]);
+    }
+
+    #[actix_rt::test]
+    async fn test_fill_buf() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        // 4-byte big-endian length prefix split across chunks, followed by the body
+        sender.feed_data(Bytes::from_static(b"\0\0"));
+        sender.feed_data(Bytes::from_static(b"\0\x0bhello"));
+        sender.feed_data(Bytes::from_static(b" world"));
+        sender.feed_data(Bytes::from_static(b"trailing"));
+        sender.feed_eof();
+
+        let prefix = payload.fill_buf(4).await.unwrap();
+        assert_eq!(prefix, b"\0\0\0\x0bhello");
+        let len = u32::from_be_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        payload.consume(4);
+
+        let body = payload.fill_buf(len).await.unwrap();
+        assert_eq!(&body[..len], b"hello world");
+        payload.consume(len);
+
+        // rest of the buffer is yielded before further chunks
+        assert_eq!(payload.fill_buf(2).await.unwrap(), b"trailing");
+        payload.consume(1);
+        payload.unread_data(Bytes::from_static(b"t"));
+        assert_eq!(payload.next().await.unwrap().unwrap(), "trailing");
+
+        // fewer bytes than requested at the end of the payload
+        assert!(payload.fill_buf(1).await.unwrap().is_empty());
+        assert!(payload.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_read_ahead() {
+        use futures_util::FutureExt as _;
+
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"frame1;fr"));
+
+        let frame = payload.read_until(b";", 16).await.unwrap();
+        assert_eq!(frame, "frame1;");
+
+        // the next frame has only partially arrived
+        let mut ahead = payload.read_ahead(16);
+        assert!((&mut ahead).now_or_never().is_none());
+        assert_eq!(ahead.buffered(), b"fr");
+
+        sender.feed_data(Bytes::from_static(b"ame2;"));
+        assert!((&mut ahead).now_or_never().is_none());
+        assert_eq!(ahead.buffered(), b"frame2;");
+
+        // processing decides to stop; nothing is lost or counted as read
+        ahead.cancel();
+        assert_eq!(payload.offset(), 7);
+
+        sender.feed_data(Bytes::from_static(b"frame3;"));
+        sender.feed_eof();
+        let mut rest = Vec::new();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+            rest.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(rest, b"frame2;frame3;");
+        assert_eq!(payload.offset(), 21);
+
+        // completed read ahead takes the bytes
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"abc"));
+        sender.feed_data(Bytes::from_static(b"def"));
+        sender.feed_eof();
+
+        assert_eq!(payload.read_ahead(4).await.unwrap(), "abcd");
+        assert_eq!(payload.offset(), 4);
+        assert_eq!(payload.read_ahead(4).await.unwrap(), "ef");
+        assert!(payload.read_ahead(4).await.unwrap().is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_read_length_prefixed() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        // records and their prefixes are split across chunks
+        sender.feed_data(Bytes::from_static(b"\x00\x00\x00\x03one\x00\x00"));
+        sender.feed_data(Bytes::from_static(b"\x00\x00\x00\x00"));
+        sender.feed_data(Bytes::from_static(b"\x00\x05th"));
+        sender.feed_data(Bytes::from_static(b"ree"));
+        sender.feed_eof();
+
+        let records = payload
+            .read_length_prefixed(PrefixFormat::U32Be, 5)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(records, ["one", "", "three"]);
+        assert_eq!(payload.offset(), 20);
+
+        // truncated record
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"\x02\x00ab\x03\x00a"));
+        sender.feed_eof();
+
+        let mut records = payload.read_length_prefixed(PrefixFormat::U16Le, 16);
+        assert_eq!(records.next().await.unwrap().unwrap(), "ab");
+        assert!(matches!(
+            records.next().await.unwrap(),
+            Err(PayloadError::Incomplete(None))
+        ));
+        assert!(records.next().await.is_none());
+
+        // record longer than the limit, announced before its bytes arrive
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"\x00\x02ab\xFF\xFF"));
+
+        let mut records = payload.read_length_prefixed(PrefixFormat::U16Be, 4);
+        assert_eq!(records.next().await.unwrap().unwrap(), "ab");
+        assert!(matches!(
+            records.next().await.unwrap(),
+            Err(PayloadError::Overflow)
+        ));
+        assert!(records.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_chunks() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        // each chunk starts with a sequence number, which must increase
+        let mut last = None;
+        payload.validate_chunks(move |chunk| {
+            let seq = chunk[0];
+            let valid = last.map_or(true, |last| seq > last);
+            last = Some(seq);
+            valid
+        });
+
+        sender.feed_data(Bytes::from_static(b"\x01one"));
+        sender.feed_data(Bytes::from_static(b"\x02two"));
+        sender.feed_data(Bytes::from_static(b"\x01one"));
+        sender.feed_data(Bytes::from_static(b"\x03three"));
+
+        assert_eq!(payload.next().await.unwrap().unwrap(), "\x01one");
+        assert_eq!(payload.next().await.unwrap().unwrap(), "\x02two");
+        assert!(matches!(
+            payload.next().await.unwrap(),
+            Err(PayloadError::InvalidChunk)
+        ));
+        assert_eq!(payload.offset(), 8);
+        assert_eq!(payload.ended_cleanly(), Some(false));
+
+        // nothing after the rejected chunk is processed
+        assert!(payload.next().await.is_none());
+        assert_eq!(payload.ended_cleanly(), Some(false));
+        let waker = futures_util::task::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+    }
+
+    #[actix_rt::test]
+    async fn test_buf_cursor() {
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"\x00\x05he"));
+        sender.feed_data(Bytes::from_static(b"llo"));
+        sender.feed_data(Bytes::from_static(b" wor"));
+        sender.feed_data(Bytes::from_static(b"ld"));
+
+        // a length-prefixed frame split across chunks
+        let mut buf = payload.buf_cursor();
+        assert_eq!(buf.remaining(), 13);
+        assert_eq!(buf.chunk(), b"\x00\x05he");
+
+        let len = buf.get_u16() as usize;
+        assert_eq!(buf.copy_to_bytes(len), "hello");
+        assert_eq!(buf.chunk(), b" wor");
+
+        buf.advance(1);
+        assert_eq!(buf.chunk(), b"wor");
+        assert_eq!(buf.remaining(), 5);
+        drop(buf);
+
+        // bytes not advanced past are yielded next
+        assert_eq!(payload.offset(), 8);
+        assert_eq!(payload.fill_buf(5).await.unwrap(), b"world");
+
+        sender.feed_data(Bytes::from_static(b"!"));
+        sender.feed_eof();
+
+        let mut buf = payload.buf_cursor();
+        let mut rest = vec![0; buf.remaining()];
+        buf.copy_to_slice(&mut rest);
+        assert_eq!(rest, b"world!");
+        assert!(!buf.has_remaining());
+        assert!(buf.chunk().is_empty());
+        drop(buf);
+
+        assert_eq!(payload.offset(), 14);
+        assert!(poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_force_eof() {
+        use futures_util::task::noop_waker;
+
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"record1\nrecord2\nEND\nre"));
+        sender.feed_data(Bytes::from_static(b"dundant"));
+
+        let mut records = Vec::new();
+        loop {
+            let line = payload.read_until(b"\n", 64).await.unwrap();
+            if line == "END\n" {
+                payload.force_eof(true);
+                break;
+            }
+            records.push(line);
+        }
+        assert_eq!(records, ["record1\n", "record2\n"]);
+
+        // the rest of the body is drained without being buffered
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Read);
+        sender.feed_data(Bytes::from_static(b"more data"));
+        assert_eq!(sender.buffered_bytes().get(), 0);
+
+        assert!(poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .is_none());
+        assert!(poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .is_none());
+        assert_eq!(payload.offset(), 20);
+        assert_eq!(payload.ended_cleanly(), Some(true));
+
+        // without draining, the rest of the body is not read
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"abc"));
+        sender.set_error(PayloadError::Incomplete(None));
+        payload.force_eof(false);
+
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+        assert!(poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_read_until() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        // delimiter split across chunks
+        sender.feed_data(Bytes::from_static(b"preamble\r"));
+        sender.feed_data(Bytes::from_static(b"\n-"));
+        sender.feed_data(Bytes::from_static(b"-boundarypart one\r\n--boundary"));
+        sender.feed_data(Bytes::from_static(b"part two"));
+        sender.feed_eof();
+
+        let delim = b"\r\n--boundary";
+        assert_eq!(
+            payload.read_until(delim, 64).await.unwrap(),
+            "preamble\r\n--boundary"
+        );
+        assert_eq!(payload.offset(), 20);
+        assert_eq!(
+            payload.read_until(delim, 64).await.unwrap(),
+            "part one\r\n--boundary"
+        );
+
+        // remaining bytes without a delimiter at the end of the payload
+        assert_eq!(payload.read_until(delim, 64).await.unwrap(), "part two");
+        assert!(payload.read_until(delim, 64).await.unwrap().is_empty());
+
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"0123456789"));
+        sender.feed_data(Bytes::from_static(b"; rest"));
+
+        // delimiter ends beyond the limit
+        assert!(matches!(
+            payload.read_until(b";", 10).await,
+            Err(PayloadError::Overflow)
+        ));
+        assert_eq!(payload.read_until(b";", 11).await.unwrap(), "0123456789;");
+
+        // limit is reached while waiting for the delimiter
+        sender.feed_data(Bytes::from_static(b" and more"));
+        assert!(matches!(
+            payload.read_until(b";", 8).await,
+            Err(PayloadError::Overflow)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn test_ended_cleanly() {
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from("data"));
+        assert_eq!(payload.ended_cleanly(), None);
+
+        sender.feed_eof();
+        assert_eq!(payload.ended_cleanly(), None);
+
+        // known as soon as the final chunk is read
+        assert_eq!(payload.next().await.unwrap().unwrap(), "data");
+        assert_eq!(payload.ended_cleanly(), Some(true));
+        assert!(payload.next().await.is_none());
+        assert_eq!(payload.ended_cleanly(), Some(true));
+
+        // connection reset before the end of the body
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.feed_data(Bytes::from("data"));
+        sender.set_error(PayloadError::Incomplete(None));
+
+        assert_eq!(payload.next().await.unwrap().unwrap(), "data");
+        assert_eq!(payload.ended_cleanly(), Some(false));
+        payload.next().await.unwrap().unwrap_err();
+        assert_eq!(payload.ended_cleanly(), Some(false));
+
+        let (_, payload) = Payload::create(true);
+        assert_eq!(payload.ended_cleanly(), Some(true));
+    }
+
+    #[actix_rt::test]
+    async fn test_poll_take() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        sender.feed_data(Bytes::from("hello"));
+        sender.feed_data(Bytes::from("world"));
+
+        let front = poll_fn(|cx| payload.poll_take(cx, 2))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(front, Bytes::from("he"));
+        assert_eq!(payload.len(), 8);
+
+        // remainder of front chunk is not coalesced with the next chunk
+        let rest = poll_fn(|cx| payload.poll_take(cx, 5))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rest, Bytes::from("llo"));
+
+        let next = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(next, Bytes::from("world"));
+        assert!(payload.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_fail_after() {
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.fail_after(6, PayloadError::Incomplete(None));
+
+        sender.feed_data(Bytes::from("data"));
+        sender.feed_data(Bytes::from("more data"));
+
+        // bytes beyond the offset are discarded
+        sender.feed_data(Bytes::from("data"));
+        assert_eq!(payload.len(), 6);
+
+        let mut buf = BytesMut::new();
+        let err = loop {
+            match poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+                Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                Some(Err(err)) => break err,
+                None => panic!("payload should yield an error"),
+            }
+        };
+
+        assert_eq!(buf, "datamo");
+        assert!(matches!(err, PayloadError::Incomplete(None)));
+    }
+
+    #[actix_rt::test]
+    async fn test_min_rate() {
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_min_rate(1_000, Duration::from_millis(100));
+
+        // 10 bytes every 20ms is half of the minimum rate
+        actix_rt::spawn(async move {
+            loop {
+                actix_rt::time::sleep(Duration::from_millis(20)).await;
+                sender.feed_data(Bytes::from_static(b"0123456789"));
+            }
+        });
+
+        let start = Instant::now();
+        let mut received = 0;
+
+        let err = loop {
+            match poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+                Some(Ok(chunk)) => received += chunk.len(),
+                Some(Err(err)) => break err,
+                None => panic!("payload should yield an error"),
+            }
+        };
+
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(received > 0);
+        assert!(matches!(err, PayloadError::Io(err) if err.kind() == io::ErrorKind::TimedOut));
+    }
+
+    #[actix_rt::test]
+    async fn test_min_rate_grace_period() {
+        tokio::time::pause();
+
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_min_rate(1_000, Duration::from_millis(100));
+        let start = Instant::now();
+
+        // far less than the minimum is received during the first window
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        sender.feed_data(Bytes::from_static(b"0123456789"));
+
+        let chunk = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await;
+        assert_eq!(chunk.unwrap().unwrap(), "0123456789");
+
+        // the rate is exceeded once the first window ends, not a window after the chunk
+        let err = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(err, PayloadError::Io(err) if err.kind() == io::ErrorKind::TimedOut));
+
+        // timers have millisecond resolution
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100) && elapsed <= Duration::from_millis(101));
+
+        drop(sender);
+    }
+
+    #[actix_rt::test]
+    async fn test_min_rate_satisfied() {
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_min_rate(1_000, Duration::from_millis(100));
+
+        actix_rt::spawn(async move {
+            for _ in 0..15 {
+                actix_rt::time::sleep(Duration::from_millis(20)).await;
+                sender.feed_data(Bytes::from_static(&[0; 100]));
+            }
+
+            sender.feed_eof();
+        });
+
+        let mut received = 0;
+
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+            received += chunk.unwrap().len();
+        }
+
+        assert_eq!(received, 1_500);
+    }
+
+    #[actix_rt::test]
+    async fn test_idle_timeout() {
+        tokio::time::pause();
+
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_idle_timeout(Duration::from_millis(50));
+
+        // chunks 30ms apart keep the payload alive, then the sender stalls
+        actix_rt::spawn(async move {
+            for _ in 0..5 {
+                actix_rt::time::sleep(Duration::from_millis(30)).await;
+                sender.feed_data(Bytes::from_static(b"data"));
+            }
+
+            actix_rt::time::sleep(Duration::from_secs(10)).await;
+            drop(sender);
+        });
+
+        let start = Instant::now();
+        let mut received = 0;
+
+        let err = loop {
+            match poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+                Some(Ok(chunk)) => received += chunk.len(),
+                Some(Err(err)) => break err,
+                None => panic!("payload should yield an error"),
+            }
+        };
+
+        assert_eq!(received, 20);
+        assert!(matches!(err, PayloadError::Io(err) if err.kind() == io::ErrorKind::TimedOut));
+
+        // each timer rounds up to the next millisecond
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200) && elapsed < Duration::from_millis(210));
+    }
+
+    #[actix_rt::test]
+    async fn test_overflow_error() {
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_limit(8);
+
+        sender.feed_data(Bytes::from("data"));
+        sender.feed_data(Bytes::from("more data"));
+
+        assert_eq!(
+            Bytes::from("data"),
+            poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap()
+        );
+        assert!(matches!(
+            poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+                .await
+                .unwrap(),
+            Err(PayloadError::Overflow)
+        ));
+
+        // further data is discarded
+        sender.feed_data(Bytes::from("data"));
+        assert!(payload.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_overflow_pause() {
+        let (mut sender, mut payload) = Payload::create(false);
+        payload.set_limit(8);
+        payload.set_overflow_policy(OverflowPolicy::Pause);
+
+        sender.feed_data(Bytes::from("data"));
+        sender.feed_data(Bytes::from("more data"));
+
+        assert_eq!(
+            Bytes::from("data"),
+            poll_fn(|cx| Pin::new(&mut payload).poll_next(cx))
+                .await
+                .unwrap()
+                .unwrap()
+        );
+
+        lazy(|cx| {
+            assert!(Pin::new(&mut payload).poll_next(cx).is_pending());
+
+            // payload never asks for more data even though its buffer is empty
+            assert_eq!(sender.need_read(cx), PayloadStatus::Pause);
+
+            sender.feed_data(Bytes::from("data"));
+            assert!(Pin::new(&mut payload).poll_next(cx).is_pending());
+            assert_eq!(sender.need_read(cx), PayloadStatus::Pause);
+        })
+        .await;
+    }
+
+    #[actix_rt::test]
+    async fn test_create_with_chunking() {
+        let (mut sender, mut payload) = Payload::create_with_chunking(false, vec![3, 1, 5]);
+
+        sender.feed_data(Bytes::from_static(b"ab"));
+        sender.feed_data(Bytes::from_static(b"cdef"));
+        sender.feed_data(Bytes::from_static(b"g"));
+        sender.feed_data(Bytes::from_static(b"hijklm"));
+        sender.feed_data(Bytes::from_static(b"no"));
+        sender.feed_eof();
+
+        let mut chunks = Vec::new();
+        while let Some(chunk) = payload.next().await {
+            chunks.push(chunk.unwrap());
+        }
+        assert_eq!(chunks, ["abc", "d", "efghi", "jklm", "no"]);
+
+        // a partial chunk is yielded at EOF
+        let (mut sender, mut payload) = Payload::create_with_chunking(false, vec![4]);
+        sender.feed_data(Bytes::from_static(b"ab"));
+        sender.feed_eof();
+
+        assert_eq!(payload.next().await.unwrap().unwrap(), "ab");
+        assert!(payload.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_on_progress() {
+        let (mut sender, mut payload) = Payload::create(false);
+
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        payload.on_progress(10, {
+            let calls = Rc::clone(&calls);
+            move |read| calls.borrow_mut().push(read)
+        });
+
+        for _ in 0..7 {
+            sender.feed_data(Bytes::from_static(b"abcd"));
+        }
+
+        // 12 bytes cross the first milestone
+        for _ in 0..3 {
+            payload.next().await.unwrap().unwrap();
+        }
+        assert_eq!(*calls.borrow(), [12]);
+
+        // put back bytes are not counted twice
+        payload.unread_data(Bytes::from_static(b"cd"));
+        payload.next().await.unwrap().unwrap();
+        assert_eq!(*calls.borrow(), [12]);
+
+        // consumed bytes count as read, but reading ahead does not
+        assert_eq!(payload.fill_buf(8).await.unwrap().len(), 8);
+        assert_eq!(*calls.borrow(), [12]);
+        payload.consume(8);
+        assert_eq!(*calls.borrow(), [12, 20]);
+
+        sender.feed_data(Bytes::from(vec![0; 25]));
+        sender.feed_eof();
+
+        // a chunk crossing several milestones results in a single call
+        while payload.next().await.is_some() {}
+        assert_eq!(*calls.borrow(), [12, 20, 53]);
+
+        // the callback is dropped once the payload ends
+        assert_eq!(Rc::strong_count(&calls), 1);
+    }
+
+    #[actix_rt::test]
+    async fn test_offset() {
+        let (mut sender, mut payload) = Payload::create(false);
+        assert_eq!(payload.offset(), 0);
+
+        sender.feed_data(Bytes::from_static(b"abcd"));
+        sender.feed_data(Bytes::from_static(b"efgh"));
+
+        payload.next().await.unwrap().unwrap();
+        assert_eq!(payload.offset(), 4);
+
+        // put back bytes are subtracted and counted again once read
+        payload.unread_data(Bytes::from_static(b"cd"));
+        assert_eq!(payload.offset(), 2);
+        assert_eq!(payload.next().await.unwrap().unwrap(), "cd");
+        assert_eq!(payload.offset(), 4);
+
+        // reading ahead does not advance the offset, consuming does
+        assert_eq!(payload.fill_buf(4).await.unwrap(), b"efgh");
+        assert_eq!(payload.offset(), 4);
+        payload.consume(1);
+        assert_eq!(payload.offset(), 5);
+
+        // the offset is kept once the payload ends
+        sender.feed_eof();
+        while payload.next().await.is_some() {}
+        assert_eq!(payload.offset(), 8);
+    }
+
+    #[test]
+    fn test_metrics() {
+        let counter = Arc::new(CheckingWaker(AtomicUsize::new(0)));
+        let waker = waker(counter);
+        let mut cx = Context::from_waker(&waker);
+
+        let metrics = PayloadMetrics::new();
+
+        let (mut sender, mut payload) = Payload::create(false);
+        sender.set_metrics(metrics.clone());
+
+        sender.feed_data(Bytes::from(vec![0; MAX_BUFFER_SIZE]));
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.bytes_read, MAX_BUFFER_SIZE as u64);
+        assert_eq!(snapshot.buffered_bytes, MAX_BUFFER_SIZE);
+        assert_eq!(snapshot.backpressure_events, 1);
+
+        assert!(Pin::new(&mut payload).poll_next(&mut cx).is_ready());
+        assert_eq!(sender.need_read(&mut cx), PayloadStatus::Read);
+        assert_eq!(metrics.snapshot().buffered_bytes, 0);
+
+        payload.unread_data(Bytes::from("data"));
+        sender.feed_data(Bytes::from("more"));
+        assert_eq!(metrics.snapshot().buffered_bytes, 8);
+
+        // unread bytes are no longer buffered once the payload is dropped
+        drop(payload);
+        assert_eq!(
+            metrics.snapshot(),
+            PayloadMetricsSnapshot {
+                bytes_read: MAX_BUFFER_SIZE as u64 + 4,
+                buffered_bytes: 0,
+                backpressure_events: 1,
+            }
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing() {
+        use std::{fmt, sync::Mutex};
+
+        use futures_util::task::noop_waker;
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Records spans and events as `name key=value ...` lines.
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct Line(String);
+
+        impl Visit for Line {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    self.0 = format!("{:?}{}", value, self.0);
+                } else {
+                    self.0 += &format!(" {}={:?}", field.name(), value);
+                }
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("span {}", span.metadata().name()));
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+                let mut line = Line(String::from("record"));
+                values.record(&mut line);
+                self.0.lock().unwrap().push(line.0);
+            }
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut line = Line(String::new());
+                event.record(&mut line);
+                self.0.lock().unwrap().push(line.0);
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let recorder = Recorder::default();
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let (mut sender, mut payload) = Payload::create(false);
+            payload.set_limit(40_000);
+
+            sender.feed_data(Bytes::from(vec![0; MAX_BUFFER_SIZE]));
+            assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+            assert_eq!(sender.need_read(&mut cx), PayloadStatus::Pause);
+
+            assert!(Pin::new(&mut payload).poll_next(&mut cx).is_ready());
+            assert_eq!(sender.need_read(&mut cx), PayloadStatus::Read);
+
+            sender.feed_data(Bytes::from(vec![0; 10_000]));
+            assert!(matches!(
+                Pin::new(&mut payload).poll_next(&mut cx),
+                Poll::Ready(Some(Err(PayloadError::Overflow)))
+            ));
+
+            let (mut sender, mut payload) = Payload::create(false);
+            sender.feed_data(Bytes::from("data"));
+            sender.feed_eof();
+            assert!(Pin::new(&mut payload).poll_next(&mut cx).is_ready());
+            assert!(matches!(
+                Pin::new(&mut payload).poll_next(&mut cx),
+                Poll::Ready(None)
+            ));
+
+            // empty payloads are not traced
+            let _ = Payload::create(true);
+        });
+
+        assert_eq!(
+            *recorder.0.lock().unwrap(),
+            [
+                "span payload",
+                "payload backpressure applied buffered=32768",
+                "payload backpressure released",
+                "payload overflowed its limit limit=40000 received=42768",
+                "record bytes=32768",
+                "payload read failed bytes=32768 err=Payload reached size limit.",
+                "span payload",
+                "record bytes=4",
+                "payload read complete bytes=4",
+            ]
+        );
+    }
 }
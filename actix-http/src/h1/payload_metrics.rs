@@ -0,0 +1,100 @@
+//! Aggregate statistics of HTTP/1 request payloads.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Counters summarizing the HTTP/1 request payloads of a server.
+///
+/// Metrics are opt-in: once registered with
+/// [`HttpServiceBuilder::h1_payload_metrics`](crate::HttpServiceBuilder::h1_payload_metrics), the
+/// payload of each request served by the service updates the counters as it is read. Clones share
+/// the same counters, so one instance can be registered with the services on all worker threads
+/// and also be kept to read the totals with [`snapshot`](Self::snapshot).
+///
+/// This is meant for quick operational visibility, e.g. from a `/metrics` endpoint, and not as a
+/// replacement for a full metrics integration.
+#[derive(Debug, Clone, Default)]
+pub struct PayloadMetrics(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    bytes_read: AtomicU64,
+    buffered_bytes: AtomicUsize,
+    backpressure_events: AtomicU64,
+}
+
+impl PayloadMetrics {
+    /// Constructs a new set of counters, all starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current values of the counters.
+    ///
+    /// Counters are read individually, so the values of a snapshot taken while payloads are being
+    /// read may be slightly inconsistent with each other.
+    pub fn snapshot(&self) -> PayloadMetricsSnapshot {
+        PayloadMetricsSnapshot {
+            bytes_read: self.0.bytes_read.load(Ordering::Relaxed),
+            buffered_bytes: self.0.buffered_bytes.load(Ordering::Relaxed),
+            backpressure_events: self.0.backpressure_events.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Records `n` bytes received from the connection and buffered in a payload.
+    pub(crate) fn received(&self, n: usize) {
+        self.0.bytes_read.fetch_add(n as u64, Ordering::Relaxed);
+        self.buffered(n);
+    }
+
+    /// Records `n` bytes added to the buffer of a payload.
+    pub(crate) fn buffered(&self, n: usize) {
+        self.0.buffered_bytes.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Records `n` bytes removed from the buffer of a payload, by reading or dropping it.
+    pub(crate) fn unbuffered(&self, n: usize) {
+        self.0.buffered_bytes.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Records the start of backpressure on a connection.
+    pub(crate) fn backpressure(&self) {
+        self.0.backpressure_events.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Values of [`PayloadMetrics`] counters at one point in time.
+///
+/// The `Display` implementation formats the values as lines of `name value` pairs, in the style
+/// of a `/metrics` endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PayloadMetricsSnapshot {
+    /// Total number of payload bytes received from connections and buffered for reading.
+    pub bytes_read: u64,
+
+    /// Number of received payload bytes currently buffered, across all requests, that have not
+    /// been read by the application yet.
+    pub buffered_bytes: usize,
+
+    /// Number of times backpressure was applied to a connection because its payload buffer was
+    /// full or reading was paused.
+    pub backpressure_events: u64,
+}
+
+impl fmt::Display for PayloadMetricsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "payload_bytes_read_total {}", self.bytes_read)?;
+        writeln!(f, "payload_buffered_bytes {}", self.buffered_bytes)?;
+        writeln!(
+            f,
+            "payload_backpressure_events_total {}",
+            self.backpressure_events
+        )
+    }
+}
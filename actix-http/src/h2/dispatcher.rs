@@ -17,7 +17,7 @@ use bytes::{Bytes, BytesMut};
 use futures_core::ready;
 use h2::{
     server::{Connection, SendResponse},
-    Ping, PingPong,
+    Ping, PingPong, SendStream,
 };
 use pin_project_lite::pin_project;
 use tracing::{error, trace, warn};
@@ -25,11 +25,13 @@ use tracing::{error, trace, warn};
 use crate::{
     body::{BodySize, BoxBody, MessageBody},
     config::ServiceConfig,
+    h2::ServerPush,
     header::{
         HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRANSFER_ENCODING, UPGRADE,
     },
     service::HttpFlow,
-    Extensions, Method, OnConnectData, Payload, Request, Response, ResponseHead,
+    Extensions, HttpMessage as _, Method, OnConnectData, Payload, Request, Response,
+    ResponseHead,
 };
 
 const CHUNK_SIZE: usize = 16_384;
@@ -127,6 +129,9 @@ where
                     head.headers = parts.headers.into();
                     head.peer_addr = this.peer_addr;
 
+                    let push = ServerPush::new(&head.uri, &head.headers);
+                    req.extensions_mut().insert(push.clone());
+
                     req.conn_data = this.conn_data.as_ref().map(Rc::clone);
 
                     let fut = this.flow.service.call(req);
@@ -136,10 +141,12 @@ where
                     actix_rt::spawn(async move {
                         // resolve service call and send response.
                         let res = match fut.await {
-                            Ok(res) => handle_response(res.into(), tx, config, head_req).await,
+                            Ok(res) => {
+                                handle_response(res.into(), tx, config, head_req, push).await
+                            }
                             Err(err) => {
                                 let res: Response<BoxBody> = err.into();
-                                handle_response(res, tx, config, head_req).await
+                                handle_response(res, tx, config, head_req, push).await
                             }
                         };
 
@@ -197,6 +204,7 @@ where
     }
 }
 
+#[derive(Debug)]
 enum DispatchError {
     SendResponse(h2::Error),
     SendData(h2::Error),
@@ -208,10 +216,43 @@ async fn handle_response<B>(
     mut tx: SendResponse<Bytes>,
     config: ServiceConfig,
     head_req: bool,
+    push: ServerPush,
 ) -> Result<(), DispatchError>
 where
     B: MessageBody,
 {
+    // push promises must be sent before the response that references them
+    for (req, res) in push.take() {
+        let head_req = req.method() == Method::HEAD;
+
+        match tx.push_request(req) {
+            Ok(mut tx) => {
+                let config = config.clone();
+
+                actix_rt::spawn(async move {
+                    let (res, body) = res.replace_body(());
+
+                    let mut size = body.size();
+                    let res = prepare_response(config, res.head(), &mut size);
+                    let eof_or_head = size.is_eof() || head_req;
+
+                    let res = match tx.send_response(res, eof_or_head) {
+                        Ok(_) if eof_or_head => Ok(()),
+                        Ok(stream) => send_body(stream, body).await,
+                        Err(err) => Err(DispatchError::SendResponse(err)),
+                    };
+
+                    if let Err(err) = res {
+                        trace!("Error sending HTTP/2 pushed response: {:?}", err);
+                    }
+                });
+            }
+
+            // client disabled server push or stream limit was reached
+            Err(err) => trace!("HTTP/2 server push was not sent: {:?}", err),
+        }
+    }
+
     let (res, body) = res.replace_body(());
 
     // prepare response.
@@ -220,7 +261,7 @@ where
     let eof_or_head = size.is_eof() || head_req;
 
     // send response head and return on eof.
-    let mut stream = tx
+    let stream = tx
         .send_response(res, eof_or_head)
         .map_err(DispatchError::SendResponse)?;
 
@@ -228,6 +269,13 @@ where
         return Ok(());
     }
 
+    send_body(stream, body).await
+}
+
+async fn send_body<B>(mut stream: SendStream<Bytes>, body: B) -> Result<(), DispatchError>
+where
+    B: MessageBody,
+{
     // poll response body and send chunks to client
     actix_rt::pin!(body);
 
@@ -21,9 +21,11 @@ use crate::{
 };
 
 mod dispatcher;
+mod push;
 mod service;
 
 pub use self::dispatcher::Dispatcher;
+pub use self::push::{PushPromise, ServerPush};
 pub use self::service::H2Service;
 
 /// HTTP/2 peer stream.
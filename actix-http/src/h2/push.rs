@@ -0,0 +1,173 @@
+//! HTTP/2 server push.
+
+use std::{cell::RefCell, convert::TryFrom, fmt, mem, rc::Rc};
+
+use http::uri::{Authority, PathAndQuery, Scheme};
+
+use crate::{
+    body::BoxBody,
+    error::HttpError,
+    header::{HeaderMap, HOST},
+    Method, Response, Uri,
+};
+
+type PushQueue = Rc<RefCell<Vec<(http::Request<()>, Response<BoxBody>)>>>;
+
+/// Handle for initiating HTTP/2 server pushes related to a request.
+///
+/// The HTTP/2 dispatcher inserts a `ServerPush` into the extensions of every request it handles.
+/// Promised requests are sent to the client as `PUSH_PROMISE` frames just before the response to
+/// the originating request. Pushes that the client has disabled or refuses are silently dropped.
+#[derive(Clone)]
+pub struct ServerPush {
+    scheme: Option<Scheme>,
+    authority: Option<Authority>,
+    queue: PushQueue,
+}
+
+impl ServerPush {
+    pub(crate) fn new(uri: &Uri, headers: &HeaderMap) -> Self {
+        let authority = uri.authority().cloned().or_else(|| {
+            headers
+                .get(&HOST)
+                .and_then(|host| Authority::try_from(host.as_bytes()).ok())
+        });
+
+        Self {
+            scheme: uri.scheme().cloned(),
+            authority,
+            queue: Rc::default(),
+        }
+    }
+
+    /// Creates a promised request for the resource at `path`.
+    ///
+    /// The path is resolved against the scheme and authority of the originating request. Only safe
+    /// and cacheable methods (i.e., `GET` and `HEAD`) can be pushed; other promises are dropped when
+    /// the response is sent.
+    ///
+    /// # Errors
+    /// Returns an error if `path` is not a valid URI path.
+    pub fn promise(
+        &self,
+        method: Method,
+        path: &str,
+        headers: HeaderMap,
+    ) -> Result<PushPromise, HttpError> {
+        let mut uri = Uri::builder().path_and_query(PathAndQuery::try_from(path)?);
+
+        if let (Some(scheme), Some(authority)) = (&self.scheme, &self.authority) {
+            uri = uri.scheme(scheme.clone()).authority(authority.clone());
+        }
+
+        let mut req = http::Request::builder().method(method).uri(uri.build()?);
+
+        for (name, value) in headers.iter() {
+            req = req.header(name, value);
+        }
+
+        Ok(PushPromise {
+            req: req.body(())?,
+            queue: Rc::clone(&self.queue),
+        })
+    }
+
+    /// Takes all promises for which a response has been supplied.
+    pub(crate) fn take(&self) -> Vec<(http::Request<()>, Response<BoxBody>)> {
+        mem::take(&mut *self.queue.borrow_mut())
+    }
+}
+
+impl fmt::Debug for ServerPush {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerPush")
+            .field("scheme", &self.scheme)
+            .field("authority", &self.authority)
+            .field("queued", &self.queue.borrow().len())
+            .finish()
+    }
+}
+
+/// A promised request awaiting its response.
+///
+/// Created using [`ServerPush::promise`]. The promise is only sent to the client once a response is
+/// supplied with [`respond`](Self::respond); dropping it cancels the push.
+pub struct PushPromise {
+    req: http::Request<()>,
+    queue: PushQueue,
+}
+
+impl PushPromise {
+    /// Returns the method of the promised request.
+    pub fn method(&self) -> &Method {
+        self.req.method()
+    }
+
+    /// Returns the URI of the promised request.
+    pub fn uri(&self) -> &Uri {
+        self.req.uri()
+    }
+
+    /// Supplies the response to the promised request, queueing it to be pushed.
+    pub fn respond(self, res: impl Into<Response<BoxBody>>) {
+        self.queue.borrow_mut().push((self.req, res.into()));
+    }
+}
+
+impl fmt::Debug for PushPromise {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PushPromise")
+            .field("method", self.req.method())
+            .field("uri", self.req.uri())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::HeaderValue;
+
+    #[test]
+    fn promise_resolves_against_request_uri() {
+        let uri = Uri::from_static("https://example.com/index.html");
+        let push = ServerPush::new(&uri, &HeaderMap::new());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(crate::header::ACCEPT, HeaderValue::from_static("text/css"));
+
+        let promise = push.promise(Method::GET, "/style.css", headers).unwrap();
+        assert_eq!(promise.method(), Method::GET);
+        assert_eq!(promise.uri(), "https://example.com/style.css");
+
+        promise.respond(Response::ok());
+
+        let pushes = push.take();
+        assert_eq!(pushes.len(), 1);
+        assert_eq!(pushes[0].0.headers()["accept"], "text/css");
+        assert!(push.take().is_empty());
+    }
+
+    #[test]
+    fn promise_uses_host_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("example.com"));
+        let push = ServerPush::new(&Uri::from_static("https://example.com/"), &headers);
+        assert_eq!(push.authority.unwrap(), "example.com");
+
+        let push = ServerPush::new(&Uri::from_static("/"), &headers);
+        assert_eq!(push.authority.unwrap(), "example.com");
+        assert!(push.scheme.is_none());
+    }
+
+    #[test]
+    fn dropped_promise_is_not_queued() {
+        let push = ServerPush::new(&Uri::from_static("/"), &HeaderMap::new());
+        let promise = push.promise(Method::GET, "/a", HeaderMap::new()).unwrap();
+        drop(promise);
+        assert!(push.take().is_empty());
+
+        push.promise(Method::GET, "not a path", HeaderMap::new())
+            .unwrap_err();
+    }
+}
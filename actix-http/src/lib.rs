@@ -35,6 +35,7 @@ pub mod body;
 mod builder;
 mod config;
 mod date;
+mod disconnect;
 #[cfg(feature = "__compress")]
 pub mod encoding;
 pub mod error;
@@ -50,6 +51,7 @@ mod message;
 #[cfg(test)]
 mod notify_on_drop;
 mod payload;
+mod proxy_protocol;
 mod requests;
 mod responses;
 mod service;
@@ -59,6 +61,7 @@ pub mod ws;
 
 pub use self::builder::HttpServiceBuilder;
 pub use self::config::ServiceConfig;
+pub use self::disconnect::ResponseDisconnect;
 pub use self::error::Error;
 pub use self::extensions::Extensions;
 pub use self::header::ContentEncoding;
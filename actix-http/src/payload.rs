@@ -1,7 +1,10 @@
 use std::{
-    mem,
+    cell::RefCell,
+    collections::VecDeque,
+    io, mem,
     pin::Pin,
-    task::{Context, Poll},
+    rc::Rc,
+    task::{Context, Poll, Waker},
 };
 
 use bytes::Bytes;
@@ -10,6 +13,10 @@ use pin_project_lite::pin_project;
 
 use crate::error::PayloadError;
 
+/// Number of chunks that receivers of a [broadcast](Payload::broadcast) payload can lag behind the
+/// fastest one.
+const BROADCAST_CAPACITY: usize = 16;
+
 /// A boxed payload stream.
 pub type BoxedPayloadStream = Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>;
 
@@ -73,6 +80,86 @@ impl<S> Payload<S> {
     pub fn take(&mut self) -> Payload<S> {
         mem::replace(self, Payload::None)
     }
+
+    /// Returns the number of payload bytes that have not yet been read, if known.
+    ///
+    /// See [`h1::Payload::remaining`](crate::h1::Payload::remaining). Always returns `None` for
+    /// HTTP/2 and custom stream payloads.
+    pub fn remaining(&self) -> Option<usize> {
+        match self {
+            Payload::None => Some(0),
+            Payload::H1 { payload } => payload.remaining(),
+            _ => None,
+        }
+    }
+
+    /// Asks the sender to stop reading payload data until [`resume`](Self::resume) is called.
+    ///
+    /// See [`h1::Payload::pause`](crate::h1::Payload::pause). Has no effect on HTTP/2 and custom
+    /// stream payloads.
+    pub fn pause(&mut self) {
+        if let Payload::H1 { payload } = self {
+            payload.pause();
+        }
+    }
+
+    /// Allows the sender to continue reading payload data after a call to [`pause`](Self::pause).
+    ///
+    /// See [`h1::Payload::resume`](crate::h1::Payload::resume).
+    pub fn resume(&mut self) {
+        if let Payload::H1 { payload } = self {
+            payload.resume();
+        }
+    }
+
+    /// Registers a callback that is invoked with the cumulative number of bytes read each time
+    /// another `step` bytes have been read.
+    ///
+    /// See [`h1::Payload::on_progress`](crate::h1::Payload::on_progress). Has no effect on HTTP/2
+    /// and custom stream payloads.
+    pub fn on_progress(&mut self, step: usize, f: impl Fn(usize) + 'static) {
+        if let Payload::H1 { payload } = self {
+            payload.on_progress(step, f);
+        }
+    }
+}
+
+impl<S> Payload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+{
+    /// Splits the payload into `n` payloads that each yield all of its chunks.
+    ///
+    /// Chunks are read from this payload as receivers ask for them and are buffered until every
+    /// receiver has read them. Once the fastest receiver is 16 chunks ahead of the slowest one,
+    /// no more chunks are read until the slowest receiver catches up. Dropping a receiver does not
+    /// affect the others.
+    ///
+    /// If this payload fails, every receiver yields an error after the chunks read before it.
+    /// Only one gets the original error; the others get a [`PayloadError::Io`] with its message,
+    /// unless the error is a variant without data.
+    pub fn broadcast(self, n: usize) -> Vec<Payload> {
+        let shared = Rc::new(RefCell::new(Broadcast {
+            source: Box::pin(self),
+            buf: VecDeque::new(),
+            start: 0,
+            receivers: (0..n).map(|_| Some(Receiver::default())).collect(),
+            eof: false,
+        }));
+
+        (0..n)
+            .map(|idx| {
+                let receiver = BroadcastPayload {
+                    shared: Rc::clone(&shared),
+                    idx,
+                };
+
+                Payload::Stream {
+                    payload: Box::pin(receiver) as BoxedPayloadStream,
+                }
+            })
+            .collect()
+    }
 }
 
 impl<S> Stream for Payload<S>
@@ -95,6 +182,157 @@ where
     }
 }
 
+/// State shared by the receivers of a broadcast payload.
+struct Broadcast<S> {
+    source: Pin<Box<Payload<S>>>,
+
+    /// Chunks that have not been read by every receiver yet.
+    buf: VecDeque<Bytes>,
+
+    /// Sequence number of the first chunk in `buf`.
+    start: usize,
+
+    /// Receivers, set to `None` when dropped.
+    receivers: Vec<Option<Receiver>>,
+
+    /// Set once `source` has ended or failed.
+    eof: bool,
+}
+
+#[derive(Default)]
+struct Receiver {
+    /// Sequence number of the next chunk to yield.
+    next: usize,
+    err: Option<PayloadError>,
+    waker: Option<Waker>,
+}
+
+impl<S> Broadcast<S> {
+    /// Drops buffered chunks that every receiver has read.
+    fn trim(&mut self) {
+        let min = self
+            .receivers
+            .iter()
+            .flatten()
+            .map(|receiver| receiver.next)
+            .min()
+            .unwrap_or(self.start + self.buf.len());
+
+        let read = min - self.start;
+
+        if read > 0 {
+            self.buf.drain(..read);
+            self.start = min;
+            self.wake_all();
+        }
+    }
+
+    fn wake_all(&mut self) {
+        for receiver in self.receivers.iter_mut().flatten() {
+            if let Some(waker) = receiver.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    fn fail(&mut self, idx: usize, err: PayloadError) {
+        for (i, receiver) in self.receivers.iter_mut().enumerate() {
+            if let Some(receiver) = receiver {
+                if i != idx {
+                    receiver.err = Some(copy_error(&err));
+                }
+            }
+        }
+
+        if let Some(receiver) = &mut self.receivers[idx] {
+            receiver.err = Some(err);
+        }
+    }
+}
+
+/// A receiver of a broadcast payload.
+struct BroadcastPayload<S> {
+    shared: Rc<RefCell<Broadcast<S>>>,
+    idx: usize,
+}
+
+impl<S> Stream for BroadcastPayload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>>,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.borrow_mut();
+        let shared = &mut *shared;
+
+        loop {
+            let next = shared.receivers[self.idx].as_ref().unwrap().next;
+
+            if let Some(chunk) = shared.buf.get(next - shared.start).cloned() {
+                shared.receivers[self.idx].as_mut().unwrap().next += 1;
+                shared.trim();
+                return Poll::Ready(Some(Ok(chunk)));
+            }
+
+            if shared.eof {
+                let err = shared.receivers[self.idx].as_mut().unwrap().err.take();
+                return Poll::Ready(err.map(Err));
+            }
+
+            // slowest receiver is too far behind
+            if shared.buf.len() >= BROADCAST_CAPACITY {
+                shared.receivers[self.idx].as_mut().unwrap().waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+
+            match shared.source.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => shared.buf.push_back(chunk),
+
+                Poll::Ready(Some(Err(err))) => {
+                    shared.eof = true;
+                    shared.fail(self.idx, err);
+                }
+
+                Poll::Ready(None) => shared.eof = true,
+
+                Poll::Pending => {
+                    shared.receivers[self.idx].as_mut().unwrap().waker =
+                        Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+
+            shared.wake_all();
+        }
+    }
+}
+
+impl<S> Drop for BroadcastPayload<S> {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.receivers[self.idx] = None;
+        shared.trim();
+
+        // the source may have registered this receiver's waker; let another receiver poll it
+        shared.wake_all();
+    }
+}
+
+/// Copies a payload error for additional receivers of a broadcast payload.
+fn copy_error(err: &PayloadError) -> PayloadError {
+    match err {
+        PayloadError::Incomplete(None) => PayloadError::Incomplete(None),
+        PayloadError::EncodingCorrupted => PayloadError::EncodingCorrupted,
+        PayloadError::Overflow => PayloadError::Overflow,
+        PayloadError::UnknownLength => PayloadError::UnknownLength,
+        PayloadError::ChecksumMismatch => PayloadError::ChecksumMismatch,
+        PayloadError::InvalidChunk => PayloadError::InvalidChunk,
+        PayloadError::Io(err) => PayloadError::Io(io::Error::new(err.kind(), err.to_string())),
+        err => PayloadError::Io(io::Error::new(io::ErrorKind::Other, err.to_string())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use static_assertions::{assert_impl_all, assert_not_impl_any};
@@ -103,4 +341,84 @@ mod tests {
 
     assert_impl_all!(Payload: Unpin);
     assert_not_impl_any!(Payload: Send, Sync);
+
+    fn chunks(n: usize) -> Payload {
+        let stream = futures_util::stream::iter((0..n).map(|i| Ok(Bytes::from(i.to_string()))));
+        Payload::from(Box::pin(stream) as BoxedPayloadStream)
+    }
+
+    #[actix_rt::test]
+    async fn broadcast() {
+        use futures_util::{FutureExt as _, StreamExt as _};
+
+        let mut receivers = chunks(40).broadcast(2);
+        let mut lagging = receivers.pop().unwrap();
+        let mut fast = receivers.pop().unwrap();
+
+        let mut fast_chunks = Vec::new();
+        let mut lagging_chunks = Vec::new();
+
+        // fast receiver can get up to the buffer capacity ahead
+        for _ in 0..BROADCAST_CAPACITY {
+            fast_chunks.push(fast.next().await.unwrap().unwrap());
+        }
+        assert!(fast.next().now_or_never().is_none());
+
+        // and continues once the lagging one catches up
+        for _ in 0..10 {
+            lagging_chunks.push(lagging.next().await.unwrap().unwrap());
+        }
+        while let Some(Some(chunk)) = fast.next().now_or_never() {
+            fast_chunks.push(chunk.unwrap());
+        }
+        assert_eq!(fast_chunks.len(), BROADCAST_CAPACITY + 10);
+
+        while let Some(chunk) = lagging.next().await {
+            lagging_chunks.push(chunk.unwrap());
+        }
+        while let Some(chunk) = fast.next().await {
+            fast_chunks.push(chunk.unwrap());
+        }
+
+        let expected = (0..40)
+            .map(|i| Bytes::from(i.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(fast_chunks, expected);
+        assert_eq!(lagging_chunks, expected);
+    }
+
+    #[actix_rt::test]
+    async fn broadcast_dropped_receiver() {
+        use futures_util::StreamExt as _;
+
+        let mut receivers = chunks(40).broadcast(3);
+        drop(receivers.pop());
+
+        let mut first = receivers.remove(0);
+        assert_eq!(first.next().await.unwrap().unwrap(), "0");
+
+        // remaining receivers are not held back by the dropped one
+        drop(receivers);
+        assert_eq!(first.count().await, 39);
+    }
+
+    #[actix_rt::test]
+    async fn broadcast_error() {
+        use futures_util::StreamExt as _;
+
+        let stream = futures_util::stream::iter(vec![
+            Ok(Bytes::from_static(b"data")),
+            Err(PayloadError::Overflow),
+        ]);
+        let receivers = Payload::from(Box::pin(stream) as BoxedPayloadStream).broadcast(2);
+
+        for mut receiver in receivers {
+            assert_eq!(receiver.next().await.unwrap().unwrap(), "data");
+            assert!(matches!(
+                receiver.next().await.unwrap(),
+                Err(PayloadError::Overflow)
+            ));
+            assert!(receiver.next().await.is_none());
+        }
+    }
 }
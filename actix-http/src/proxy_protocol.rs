@@ -0,0 +1,212 @@
+//! Reading of PROXY protocol headers from TCP connections.
+//!
+//! See <https://www.haproxy.org/download/2.8/doc/proxy-protocol.txt> for the specification.
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str,
+};
+
+use actix_rt::net::TcpStream;
+
+/// Signature that starts version 2 headers.
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Prefix that starts version 1 headers.
+const V1_PREFIX: &[u8] = b"PROXY ";
+
+/// Maximum length of a version 1 header, including the trailing CRLF.
+const V1_MAX_LEN: usize = 107;
+
+/// Reads the PROXY protocol header at the start of `io`, leaving the stream positioned at the
+/// first byte after it.
+///
+/// Returns the source address of the proxied connection, or `None` if the header does not carry
+/// one, e.g. for health checks sent by the proxy itself.
+pub(crate) async fn read_header(io: &TcpStream) -> io::Result<Option<SocketAddr>> {
+    let mut buf = [0; V1_MAX_LEN];
+
+    // the shortest v1 header is 15 bytes long and v2 headers are at least 16 bytes long; read
+    // no more than that so that no bytes after the header are consumed
+    read_exact(io, &mut buf[..15]).await?;
+
+    if buf[..12] == V2_SIGNATURE[..] {
+        read_exact(io, &mut buf[15..16]).await?;
+
+        let len = u16::from_be_bytes([buf[14], buf[15]]) as usize;
+        let mut addrs = vec![0; len];
+        read_exact(io, &mut addrs).await?;
+
+        return parse_v2(&buf[..16], &addrs);
+    }
+
+    if !buf.starts_with(V1_PREFIX) {
+        return Err(invalid(
+            "connection did not start with a PROXY protocol header",
+        ));
+    }
+
+    let mut len = 15;
+
+    while !buf[..len].ends_with(b"\r\n") {
+        if len == V1_MAX_LEN {
+            return Err(invalid("PROXY protocol header is too long"));
+        }
+
+        read_exact(io, &mut buf[len..len + 1]).await?;
+        len += 1;
+    }
+
+    parse_v1(&buf[..len - 2])
+}
+
+/// Parses a version 1 header line, without its trailing CRLF.
+fn parse_v1(line: &[u8]) -> io::Result<Option<SocketAddr>> {
+    let line = str::from_utf8(line).map_err(|_| invalid("invalid PROXY protocol header"))?;
+    let mut parts = line.split(' ').skip(1);
+
+    let is_v4 = match parts.next() {
+        Some("TCP4") => true,
+        Some("TCP6") => false,
+        // the rest of the line must be ignored for unknown protocols
+        Some("UNKNOWN") => return Ok(None),
+        _ => return Err(invalid("unsupported PROXY protocol family")),
+    };
+
+    // fields are source address, destination address, source port and destination port
+    let fields = (
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+        parts.next(),
+    );
+    let (src, src_port) = match fields {
+        (Some(src), Some(_), Some(src_port), Some(_), None) => (src, src_port),
+        _ => return Err(invalid("invalid PROXY protocol header")),
+    };
+
+    let ip = if is_v4 {
+        src.parse::<Ipv4Addr>().map(IpAddr::V4)
+    } else {
+        src.parse::<Ipv6Addr>().map(IpAddr::V6)
+    }
+    .map_err(|_| invalid("invalid PROXY protocol source address"))?;
+
+    let port = src_port
+        .parse::<u16>()
+        .map_err(|_| invalid("invalid PROXY protocol source port"))?;
+
+    Ok(Some(SocketAddr::new(ip, port)))
+}
+
+/// Parses a version 2 header, given its fixed 16 byte part and the address block that follows.
+fn parse_v2(header: &[u8], addrs: &[u8]) -> io::Result<Option<SocketAddr>> {
+    match header[12] {
+        // LOCAL command; connection was established by the proxy itself
+        0x20 => return Ok(None),
+        // PROXY command
+        0x21 => {}
+        _ => return Err(invalid("unsupported PROXY protocol version or command")),
+    }
+
+    // address family and transport protocol; anything but TCP over IPv4 or IPv6 carries no
+    // usable source address and must be accepted with the real connection address
+    let addr = match header[13] {
+        0x11 if addrs.len() >= 12 => {
+            let ip = Ipv4Addr::new(addrs[0], addrs[1], addrs[2], addrs[3]);
+            let port = u16::from_be_bytes([addrs[8], addrs[9]]);
+            SocketAddr::new(IpAddr::V4(ip), port)
+        }
+        0x21 if addrs.len() >= 36 => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(&addrs[..16]);
+            let port = u16::from_be_bytes([addrs[32], addrs[33]]);
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::from(octets)), port)
+        }
+        0x11 | 0x21 => return Err(invalid("PROXY protocol address block is too short")),
+        _ => return Ok(None),
+    };
+
+    Ok(Some(addr))
+}
+
+/// Reads exactly enough bytes from `io` to fill `buf`.
+async fn read_exact(io: &TcpStream, buf: &mut [u8]) -> io::Result<()> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        io.readable().await?;
+
+        match io.try_read(&mut buf[filled..]) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(())
+}
+
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v2(command: u8, family: u8, addrs: &[u8]) -> io::Result<Option<SocketAddr>> {
+        let mut header = V2_SIGNATURE.to_vec();
+        header.extend_from_slice(&[command, family]);
+        header.extend_from_slice(&(addrs.len() as u16).to_be_bytes());
+        parse_v2(&header, addrs)
+    }
+
+    #[test]
+    fn v1() {
+        let addr = parse_v1(b"PROXY TCP4 192.0.2.1 198.51.100.2 56324 443").unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+
+        let addr = parse_v1(b"PROXY TCP6 2001:db8::1 2001:db8::2 56324 443").unwrap();
+        assert_eq!(addr, Some("[2001:db8::1]:56324".parse().unwrap()));
+
+        assert_eq!(parse_v1(b"PROXY UNKNOWN").unwrap(), None);
+        assert_eq!(parse_v1(b"PROXY UNKNOWN ::1 ::1 1 2").unwrap(), None);
+
+        for line in [
+            &b"PROXY TCP4 192.0.2.1 198.51.100.2 56324"[..],
+            b"PROXY TCP4 192.0.2.1 198.51.100.2 56324 443 extra",
+            b"PROXY TCP4 2001:db8::1 198.51.100.2 56324 443",
+            b"PROXY TCP6 192.0.2.1 198.51.100.2 56324 443",
+            b"PROXY TCP4 192.0.2.1 198.51.100.2 65536 443",
+            b"PROXY UDP4 192.0.2.1 198.51.100.2 56324 443",
+        ] {
+            assert!(parse_v1(line).is_err());
+        }
+    }
+
+    #[test]
+    fn v2_addresses() {
+        let addrs = [192, 0, 2, 1, 198, 51, 100, 2, 0xDC, 0x04, 0x01, 0xBB];
+        let addr = v2(0x21, 0x11, &addrs).unwrap();
+        assert_eq!(addr, Some("192.0.2.1:56324".parse().unwrap()));
+
+        // TLVs after the addresses are ignored
+        let mut addrs = [0; 40];
+        addrs[..16].copy_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        addrs[32..34].copy_from_slice(&56324u16.to_be_bytes());
+        let addr = v2(0x21, 0x21, &addrs).unwrap();
+        assert_eq!(addr, Some("[2001:db8::1]:56324".parse().unwrap()));
+
+        assert_eq!(v2(0x20, 0x00, &[]).unwrap(), None);
+        assert_eq!(v2(0x21, 0x00, &[]).unwrap(), None);
+        assert_eq!(v2(0x21, 0x31, &[0; 216]).unwrap(), None);
+
+        assert!(v2(0x21, 0x11, &[0; 8]).is_err());
+        assert!(v2(0x22, 0x11, &[0; 12]).is_err());
+        assert!(v2(0x11, 0x11, &[0; 12]).is_err());
+    }
+}
@@ -9,7 +9,7 @@ use std::{
 };
 
 use actix_codec::{AsyncRead, AsyncWrite, Framed};
-use actix_rt::net::TcpStream;
+use actix_rt::{net::TcpStream, time::timeout};
 use actix_service::{
     fn_service, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
 };
@@ -21,7 +21,8 @@ use crate::{
     body::{BoxBody, MessageBody},
     builder::HttpServiceBuilder,
     error::DispatchError,
-    h1, ConnectCallback, OnConnectData, Protocol, Request, Response, ServiceConfig,
+    h1, proxy_protocol, ConnectCallback, OnConnectData, Protocol, Request, Response,
+    ServiceConfig,
 };
 
 /// A [`ServiceFactory`] for HTTP/1.1 and HTTP/2 connections.
@@ -207,9 +208,15 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        fn_service(|io: TcpStream| async {
-            let peer_addr = io.peer_addr().ok();
-            Ok((io, Protocol::Http1, peer_addr))
+        let cfg = self.cfg.clone();
+
+        fn_service(move |io: TcpStream| {
+            let cfg = cfg.clone();
+
+            async move {
+                let peer_addr = tcp_peer_addr(&io, &cfg).await?;
+                Ok((io, Protocol::Http1, peer_addr))
+            }
         })
         .and_then(self)
     }
@@ -226,30 +233,59 @@ where
         Error = DispatchError,
         InitError = (),
     > {
-        fn_service(move |io: TcpStream| async move {
-            // subset of HTTP/2 preface defined by RFC 9113 §3.4
-            // this subset was chosen to maximize likelihood that peeking only once will allow us to
-            // reliably determine version or else it should fallback to h1 and fail quickly if data
-            // on the wire is junk
-            const H2_PREFACE: &[u8] = b"PRI * HTTP/2";
+        let cfg = self.cfg.clone();
 
-            let mut buf = [0; 12];
+        fn_service(move |io: TcpStream| {
+            let cfg = cfg.clone();
 
-            io.peek(&mut buf).await?;
+            async move {
+                // subset of HTTP/2 preface defined by RFC 9113 §3.4
+                // this subset was chosen to maximize likelihood that peeking only once will allow
+                // us to reliably determine version or else it should fallback to h1 and fail
+                // quickly if data on the wire is junk
+                const H2_PREFACE: &[u8] = b"PRI * HTTP/2";
 
-            let proto = if buf == H2_PREFACE {
-                Protocol::Http2
-            } else {
-                Protocol::Http1
-            };
+                let peer_addr = tcp_peer_addr(&io, &cfg).await?;
+
+                let mut buf = [0; 12];
+
+                io.peek(&mut buf).await?;
+
+                let proto = if buf == H2_PREFACE {
+                    Protocol::Http2
+                } else {
+                    Protocol::Http1
+                };
 
-            let peer_addr = io.peer_addr().ok();
-            Ok((io, proto, peer_addr))
+                Ok((io, proto, peer_addr))
+            }
         })
         .and_then(self)
     }
 }
 
+/// Returns the peer address of a TCP connection, which is read from its PROXY protocol header
+/// if enabled by `cfg`.
+async fn tcp_peer_addr(
+    io: &TcpStream,
+    cfg: &ServiceConfig,
+) -> Result<Option<net::SocketAddr>, DispatchError> {
+    if !cfg.proxy_protocol() {
+        return Ok(io.peer_addr().ok());
+    }
+
+    let header = proxy_protocol::read_header(io);
+
+    let addr = match cfg.client_request_deadline() {
+        Some(deadline) => timeout(deadline - cfg.now(), header)
+            .await
+            .map_err(|_| DispatchError::SlowRequestTimeout)??,
+        None => header.await?,
+    };
+
+    Ok(addr.or_else(|| io.peer_addr().ok()))
+}
+
 /// Configuration options used when accepting TLS connection.
 #[cfg(any(feature = "openssl", feature = "rustls"))]
 #[derive(Debug, Default)]
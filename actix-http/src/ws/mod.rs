@@ -150,6 +150,95 @@ pub fn handshake(req: &RequestHead) -> Result<ResponseBuilder, HandshakeError> {
     Ok(handshake_response(req))
 }
 
+/// Verify WebSocket handshake request and create handshake response with a negotiated
+/// subprotocol.
+///
+/// The first subprotocol offered by the client in its `Sec-WebSocket-Protocol` header that is
+/// also in `protocols` is selected, set as the response's `Sec-WebSocket-Protocol` header, and
+/// returned alongside the response. If there is no match, the handshake proceeds without a
+/// subprotocol and `None` is returned; callers that require a subprotocol can use
+/// [`handshake_requiring_subprotocol`] instead.
+///
+/// # Examples
+/// ```
+/// use actix_http::{header, test::TestRequest, ws, StatusCode};
+///
+/// let req = TestRequest::default()
+///     .insert_header((header::UPGRADE, "websocket"))
+///     .insert_header((header::CONNECTION, "upgrade"))
+///     .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+///     .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+///     .insert_header((header::SEC_WEBSOCKET_PROTOCOL, "superchat, chat"))
+///     .finish();
+///
+/// let (mut res, protocol) =
+///     ws::handshake_with_subprotocols(req.head(), &["chat", "superchat"]).unwrap();
+/// assert_eq!(protocol, Some("superchat"));
+/// assert_eq!(res.finish().status(), StatusCode::SWITCHING_PROTOCOLS);
+/// ```
+pub fn handshake_with_subprotocols<'a>(
+    req: &RequestHead,
+    protocols: &[&'a str],
+) -> Result<(ResponseBuilder, Option<&'a str>), HandshakeError> {
+    verify_handshake(req)?;
+
+    let protocol = select_subprotocol(req, protocols);
+    let mut res = handshake_response(req);
+
+    if let Some(protocol) = protocol {
+        res.insert_header((header::SEC_WEBSOCKET_PROTOCOL, protocol));
+    }
+
+    Ok((res, protocol))
+}
+
+/// Verify WebSocket handshake request and create handshake response with a negotiated
+/// subprotocol, rejecting requests that offer none of `protocols`.
+///
+/// Like [`handshake_with_subprotocols`], except that the selected subprotocol is required. If the
+/// client offers none of `protocols`, a `400 Bad Request` response is returned as the error, as
+/// is the response for the [`HandshakeError`] of an invalid handshake request.
+///
+/// # Examples
+/// ```
+/// use actix_http::{header, test::TestRequest, ws, StatusCode};
+///
+/// let req = TestRequest::default()
+///     .insert_header((header::UPGRADE, "websocket"))
+///     .insert_header((header::CONNECTION, "upgrade"))
+///     .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+///     .insert_header((header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+///     .insert_header((header::SEC_WEBSOCKET_PROTOCOL, "mqtt"))
+///     .finish();
+///
+/// let res = ws::handshake_requiring_subprotocol(req.head(), &["chat", "superchat"]).unwrap_err();
+/// assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+/// ```
+pub fn handshake_requiring_subprotocol<'a>(
+    req: &RequestHead,
+    protocols: &[&'a str],
+) -> Result<(ResponseBuilder, &'a str), Response<BoxBody>> {
+    match handshake_with_subprotocols(req, protocols)? {
+        (res, Some(protocol)) => Ok((res, protocol)),
+        (_, None) => {
+            let mut res = Response::bad_request();
+            res.head_mut().reason = Some("No supported WebSocket subprotocol");
+            Err(res)
+        }
+    }
+}
+
+/// Selects the first subprotocol offered in the request's `Sec-WebSocket-Protocol` header that is
+/// also in `protocols`.
+pub fn select_subprotocol<'a>(req: &RequestHead, protocols: &[&'a str]) -> Option<&'a str> {
+    req.headers()
+        .get_all(header::SEC_WEBSOCKET_PROTOCOL)
+        .filter_map(|hdr| hdr.to_str().ok())
+        .flat_map(|hdr| hdr.split(','))
+        .map(str::trim)
+        .find_map(|offered| protocols.iter().copied().find(|&p| p == offered))
+}
+
 /// Verify WebSocket handshake request.
 pub fn verify_handshake(req: &RequestHead) -> Result<(), HandshakeError> {
     // WebSocket accepts only GET
@@ -334,6 +423,82 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_handshake_with_subprotocols() {
+        let req = || {
+            let mut req = TestRequest::default();
+            req.insert_header((header::UPGRADE, "websocket"))
+                .insert_header((header::CONNECTION, "upgrade"))
+                .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+                .insert_header((header::SEC_WEBSOCKET_KEY, "13"));
+            req
+        };
+
+        let supported = ["chat", "superchat"];
+
+        let mut offered = req();
+        offered.insert_header((header::SEC_WEBSOCKET_PROTOCOL, "v1.chat, chat"));
+        let offered = offered.finish();
+        let (mut res, protocol) =
+            handshake_with_subprotocols(offered.head(), &supported).unwrap();
+        assert_eq!(protocol, Some("chat"));
+        let res = res.finish();
+        assert_eq!(res.status(), StatusCode::SWITCHING_PROTOCOLS);
+        assert_eq!(
+            res.headers().get(header::SEC_WEBSOCKET_PROTOCOL).unwrap(),
+            "chat"
+        );
+
+        let mut unsupported = req();
+        unsupported.insert_header((header::SEC_WEBSOCKET_PROTOCOL, "mqtt"));
+        let unsupported = unsupported.finish();
+        let (mut res, protocol) =
+            handshake_with_subprotocols(unsupported.head(), &supported).unwrap();
+        assert_eq!(protocol, None);
+        assert!(!res
+            .finish()
+            .headers()
+            .contains_key(header::SEC_WEBSOCKET_PROTOCOL));
+
+        let missing = req().finish();
+        assert_eq!(select_subprotocol(missing.head(), &supported), None);
+    }
+
+    #[test]
+    fn test_handshake_requiring_subprotocol() {
+        let req = |protocols: Option<&str>| {
+            let mut req = TestRequest::default();
+            req.insert_header((header::UPGRADE, "websocket"))
+                .insert_header((header::CONNECTION, "upgrade"))
+                .insert_header((header::SEC_WEBSOCKET_VERSION, "13"))
+                .insert_header((header::SEC_WEBSOCKET_KEY, "13"));
+            if let Some(protocols) = protocols {
+                req.insert_header((header::SEC_WEBSOCKET_PROTOCOL, protocols));
+            }
+            req.finish()
+        };
+
+        let supported = ["chat", "superchat"];
+
+        let offered = req(Some("v1.chat, chat"));
+        let (mut res, protocol) =
+            handshake_requiring_subprotocol(offered.head(), &supported).unwrap();
+        assert_eq!(protocol, "chat");
+        assert_eq!(res.finish().status(), StatusCode::SWITCHING_PROTOCOLS);
+
+        for protocols in [Some("mqtt"), None] {
+            let res =
+                handshake_requiring_subprotocol(req(protocols).head(), &supported).unwrap_err();
+            assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        }
+
+        // invalid handshakes are rejected as usual
+        let not_upgrade = TestRequest::default().finish();
+        let res = handshake_requiring_subprotocol(not_upgrade.head(), &supported).unwrap_err();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.head().reason, Some("No WebSocket Upgrade header found"));
+    }
+
     #[test]
     fn test_ws_error_http_response() {
         let resp: Response<BoxBody> = HandshakeError::GetMethodRequired.into();
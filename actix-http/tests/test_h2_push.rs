@@ -0,0 +1,149 @@
+use std::{convert::Infallible, io};
+
+use actix_http::{
+    h2::ServerPush, header::HeaderMap, HttpMessage, HttpService, Method, Response,
+};
+use actix_server::Server;
+use bytes::{Bytes, BytesMut};
+
+async fn read_body(mut body: h2::RecvStream) -> Bytes {
+    let mut buf = BytesMut::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        body.flow_control().release_capacity(chunk.len()).unwrap();
+        buf.extend_from_slice(&chunk);
+    }
+
+    buf.freeze()
+}
+
+#[actix_rt::test]
+async fn h2_server_push() -> io::Result<()> {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+
+    let lst = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = lst.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        actix_rt::System::new().block_on(async move {
+            let srv = Server::build()
+                .disable_signals()
+                .workers(1)
+                .listen("h2_server_push", lst, || {
+                    HttpService::build()
+                        .h2(|req: actix_http::Request| async move {
+                            let push = req.extensions().get::<ServerPush>().cloned().unwrap();
+
+                            push.promise(Method::GET, "/style.css", HeaderMap::new())
+                                .unwrap()
+                                .respond(
+                                    Response::ok().set_body("body {}").map_into_boxed_body(),
+                                );
+
+                            // promise dropped without a response is never sent
+                            let _ = push.promise(Method::GET, "/unused", HeaderMap::new());
+
+                            Ok::<_, Infallible>(Response::ok().set_body("index"))
+                        })
+                        .tcp()
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+
+            srv.await
+        })
+    });
+
+    let handle = rx.recv().unwrap();
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    // pushed streams are only opened when the client advertises a concurrent stream limit
+    let (mut client, conn) = h2::client::Builder::new()
+        .max_concurrent_streams(100)
+        .handshake::<_, Bytes>(stream)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move { conn.await.unwrap() });
+
+    let req = ::http::Request::get("http://localhost/").body(()).unwrap();
+    let (mut res, _) = client.send_request(req, true).unwrap();
+    let mut pushes = res.push_promises();
+
+    let res = res.await.unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(read_body(res.into_body()).await, "index");
+
+    let (pushed_req, pushed_res) = pushes.push_promise().await.unwrap().unwrap().into_parts();
+    assert_eq!(pushed_req.method(), ::http::Method::GET);
+    assert_eq!(pushed_req.uri(), "http://localhost/style.css");
+
+    let pushed_res = pushed_res.await.unwrap();
+    assert_eq!(pushed_res.status().as_u16(), 200);
+    assert_eq!(read_body(pushed_res.into_body()).await, "body {}");
+
+    assert!(pushes.push_promise().await.is_none());
+
+    drop((client, pushes));
+    handle.stop(true).await;
+
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn h2_server_push_disabled_by_client() -> io::Result<()> {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+
+    let lst = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let addr = lst.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        actix_rt::System::new().block_on(async move {
+            let srv = Server::build()
+                .disable_signals()
+                .workers(1)
+                .listen("h2_server_push_disabled", lst, || {
+                    HttpService::build()
+                        .h2(|req: actix_http::Request| async move {
+                            let push = req.extensions().get::<ServerPush>().cloned().unwrap();
+
+                            push.promise(Method::GET, "/style.css", HeaderMap::new())
+                                .unwrap()
+                                .respond(Response::ok());
+
+                            Ok::<_, Infallible>(Response::ok().set_body("index"))
+                        })
+                        .tcp()
+                })?
+                .run();
+
+            tx.send(srv.handle()).unwrap();
+
+            srv.await
+        })
+    });
+
+    let handle = rx.recv().unwrap();
+
+    let stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let (mut client, conn) = h2::client::Builder::new()
+        .enable_push(false)
+        .handshake::<_, Bytes>(stream)
+        .await
+        .unwrap();
+    actix_rt::spawn(async move { conn.await.unwrap() });
+
+    let req = ::http::Request::get("http://localhost/").body(()).unwrap();
+    let (res, _) = client.send_request(req, true).unwrap();
+
+    // response is still sent when push is refused
+    let res = res.await.unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(read_body(res.into_body()).await, "index");
+
+    drop(client);
+    handle.stop(true).await;
+
+    Ok(())
+}
@@ -3,13 +3,20 @@
 use std::{
     convert::Infallible,
     io::{Read, Write},
-    net, thread,
+    net,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    task::Poll,
+    thread,
     time::{Duration, Instant},
 };
 
 use actix_http::{
     body::{self, BodyStream, BoxBody, SizedStream},
-    header, Error, HttpService, KeepAlive, Request, Response, StatusCode, Version,
+    h1, header, Error, HttpMessage as _, HttpService, KeepAlive, Request, Response,
+    ResponseDisconnect, StatusCode, Version,
 };
 use actix_http_test::test_server;
 use actix_rt::{net::TcpStream, time::sleep};
@@ -249,6 +256,472 @@ async fn http1_malformed_request() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn http1_header_limits() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .max_header_count(4)
+            .max_header_size(256)
+            .h1(|_| async { Ok::<_, Infallible>(Response::ok()) })
+            .tcp()
+    })
+    .await;
+
+    let request = |headers: &str| {
+        let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+        let req = format!("GET / HTTP/1.1\r\n{}connection: close\r\n\r\n", headers);
+        let _ = stream.write_all(req.as_bytes());
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        data
+    };
+
+    let data = request("a: 1\r\nb: 2\r\nc: 3\r\n");
+    assert!(data.starts_with("HTTP/1.1 200 OK"), "{}", data);
+
+    // too many headers
+    let data = request("a: 1\r\nb: 2\r\nc: 3\r\nd: 4\r\n");
+    assert!(
+        data.starts_with("HTTP/1.1 431 Request Header Fields Too Large"),
+        "{}",
+        data
+    );
+
+    // single oversized header
+    let data = request(&format!("a: {}\r\n", "x".repeat(300)));
+    assert!(
+        data.starts_with("HTTP/1.1 431 Request Header Fields Too Large"),
+        "{}",
+        data
+    );
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_framing_strictness() {
+    let request = |addr: net::SocketAddr, headers: &str| {
+        let mut stream = net::TcpStream::connect(addr).unwrap();
+
+        // a smuggled request follows the body if the length headers are read inconsistently
+        let req = format!(
+            "POST / HTTP/1.1\r\n{}\r\n0\r\n\r\nGET /smuggled HTTP/1.1\r\nconnection: close\r\n\r\n",
+            headers
+        );
+        let _ = stream.write_all(req.as_bytes());
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        data
+    };
+
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|req: Request| async move {
+                Ok::<_, Infallible>(Response::ok().set_body(req.path().to_owned()))
+            })
+            .tcp()
+    })
+    .await;
+
+    // connection is closed after the 400 response, so the smuggled request is never read
+    for headers in [
+        "content-length: 5\r\ntransfer-encoding: chunked\r\n",
+        "content-length: 5\r\ncontent-length: 3\r\n",
+    ] {
+        let data = request(srv.addr(), headers);
+        assert!(data.starts_with("HTTP/1.1 400 Bad Request"), "{}", data);
+        assert_eq!(data.matches("HTTP/1.1").count(), 1, "{}", data);
+    }
+
+    srv.stop().await;
+
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1_framing_strictness(h1::FramingStrictness::Lenient)
+            .h1(|req: Request| async move {
+                Ok::<_, Infallible>(Response::ok().set_body(req.path().to_owned()))
+            })
+            .tcp()
+    })
+    .await;
+
+    let data = request(
+        srv.addr(),
+        "content-length: 5\r\ntransfer-encoding: chunked\r\n",
+    );
+    assert!(data.starts_with("HTTP/1.1 200 OK"), "{}", data);
+    assert!(data.ends_with("/smuggled"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_header_folding() {
+    let request = |addr: net::SocketAddr| {
+        let mut stream = net::TcpStream::connect(addr).unwrap();
+        let _ = stream
+            .write_all(b"GET / HTTP/1.1\r\nx-folded: foo\r\n bar\r\nconnection: close\r\n\r\n");
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        data
+    };
+
+    let echo = |req: Request| async move {
+        let val = req.headers().get("x-folded").unwrap().as_bytes();
+        Ok::<_, Infallible>(Response::ok().set_body(val.to_vec()))
+    };
+
+    let mut srv = test_server(move || HttpService::build().h1(echo).tcp()).await;
+
+    let data = request(srv.addr());
+    assert!(data.starts_with("HTTP/1.1 400 Bad Request"), "{}", data);
+
+    srv.stop().await;
+
+    let mut srv = test_server(move || {
+        HttpService::build()
+            .h1_header_folding(h1::HeaderFolding::Normalize)
+            .h1(echo)
+            .tcp()
+    })
+    .await;
+
+    let data = request(srv.addr());
+    assert!(data.starts_with("HTTP/1.1 200 OK"), "{}", data);
+    assert!(data.ends_with("foo   bar"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_payload_metrics() {
+    let metrics = h1::PayloadMetrics::new();
+
+    let mut srv = test_server({
+        let metrics = metrics.clone();
+
+        move || {
+            HttpService::build()
+                .h1_payload_metrics(metrics.clone())
+                .h1(|mut req: Request| async move {
+                    let body = body::to_bytes(BodyStream::new(req.take_payload()))
+                        .await
+                        .unwrap();
+                    Ok::<_, Infallible>(Response::ok().set_body(body))
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    assert_eq!(metrics.snapshot(), h1::PayloadMetricsSnapshot::default());
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream
+        .write_all(b"POST / HTTP/1.1\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello");
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.ends_with("hello"), "{}", data);
+
+    let snapshot = metrics.snapshot();
+    assert_eq!(snapshot.bytes_read, 5);
+    assert_eq!(snapshot.buffered_bytes, 0);
+    assert_eq!(
+        snapshot.to_string(),
+        "payload_bytes_read_total 5\n\
+        payload_buffered_bytes 0\n\
+        payload_backpressure_events_total 0\n"
+    );
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_payload_remaining() {
+    let mut srv = test_server(|| {
+        HttpService::build()
+            .h1(|mut req: Request| async move {
+                let mut payload = req.take_payload();
+                let mut counts = vec![format!("{:?}", payload.remaining())];
+
+                while let Some(chunk) = payload.next().await {
+                    chunk.unwrap();
+                    counts.push(format!("{:?}", payload.remaining()));
+                }
+
+                Ok::<_, Infallible>(Response::ok().set_body(counts.join(",")))
+            })
+            .tcp()
+    })
+    .await;
+
+    let request = |req: &[u8]| {
+        let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+        let _ = stream.write_all(req);
+        let mut data = String::new();
+        let _ = stream.read_to_string(&mut data);
+        data
+    };
+
+    let data =
+        request(b"POST / HTTP/1.1\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello");
+    assert!(data.contains("\r\n\r\nSome(5),"), "{}", data);
+    assert!(data.ends_with(",Some(0)"), "{}", data);
+
+    let data = request(
+        b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n\
+        5\r\nhello\r\n0\r\n\r\n",
+    );
+    assert!(data.ends_with("\r\n\r\nNone,None"), "{}", data);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_response_disconnect() {
+    let produced = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    let mut srv = test_server({
+        let produced = Arc::clone(&produced);
+        let stopped = Arc::clone(&stopped);
+
+        move || {
+            let produced = Arc::clone(&produced);
+            let stopped = Arc::clone(&stopped);
+
+            HttpService::build()
+                .h1(move |req: Request| {
+                    let disconnect = req.extensions().get::<ResponseDisconnect>().cloned();
+                    let produced = Arc::clone(&produced);
+                    let stopped = Arc::clone(&stopped);
+
+                    async move {
+                        let disconnect = disconnect.unwrap();
+
+                        // producer keeps generating rows until the client goes away
+                        let rows = futures_util::stream::poll_fn(move |_| {
+                            produced.fetch_add(1, Ordering::SeqCst);
+                            Poll::Ready(Some(Ok::<_, Infallible>(Bytes::from_static(
+                                &[b'x'; 1024],
+                            ))))
+                        });
+
+                        actix_rt::spawn(async move {
+                            disconnect.await;
+                            stopped.store(true, Ordering::SeqCst);
+                        });
+
+                        Ok::<_, Infallible>(Response::ok().set_body(BodyStream::new(rows)))
+                    }
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    let _ = stream.write_all(b"GET / HTTP/1.1\r\n\r\n");
+    let mut data = [0; 1024];
+    let _ = stream.read(&mut data);
+    assert!(data.starts_with(b"HTTP/1.1 200 OK"));
+    drop(stream);
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !stopped.load(Ordering::SeqCst) {
+        assert!(Instant::now() < deadline, "disconnect was not signalled");
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    // production stops once the disconnect has been signalled
+    let count = produced.load(Ordering::SeqCst);
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(produced.load(Ordering::SeqCst), count);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_pipeline_concurrency() {
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+    let mut srv = test_server({
+        let in_flight = Arc::clone(&in_flight);
+        let max_in_flight = Arc::clone(&max_in_flight);
+
+        move || {
+            let in_flight = Arc::clone(&in_flight);
+            let max_in_flight = Arc::clone(&max_in_flight);
+
+            HttpService::build()
+                .h1_pipeline_concurrency(3)
+                .h1(move |req: Request| {
+                    let in_flight = Arc::clone(&in_flight);
+                    let max_in_flight = Arc::clone(&max_in_flight);
+
+                    async move {
+                        let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                        max_in_flight.fetch_max(current, Ordering::SeqCst);
+
+                        // earlier requests take longer to complete
+                        let delay = match req.path() {
+                            "/1" => 300,
+                            "/2" => 150,
+                            _ => 0,
+                        };
+                        sleep(Duration::from_millis(delay)).await;
+
+                        in_flight.fetch_sub(1, Ordering::SeqCst);
+                        Ok::<_, Infallible>(Response::ok().set_body(req.path().to_owned()))
+                    }
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(
+            b"GET /1 HTTP/1.1\r\n\r\n\
+            GET /2 HTTP/1.1\r\n\r\n\
+            GET /3 HTTP/1.1\r\nconnection: close\r\n\r\n",
+        )
+        .unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+
+    let bodies = data
+        .split("HTTP/1.1 200 OK\r\n")
+        .skip(1)
+        .map(|res| res.rsplit("\r\n\r\n").next().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(bodies, ["/1", "/2", "/3"]);
+
+    // all three requests were processed at the same time
+    assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_pipeline_concurrency_keeps_order_after_unsafe_request() {
+    let writes = Arc::new(AtomicUsize::new(0));
+
+    let mut srv = test_server({
+        let writes = Arc::clone(&writes);
+
+        move || {
+            let writes = Arc::clone(&writes);
+
+            HttpService::build()
+                .h1_pipeline_concurrency(3)
+                .h1(move |req: Request| {
+                    let writes = Arc::clone(&writes);
+
+                    async move {
+                        let body = match req.path() {
+                            "/slow" => {
+                                sleep(Duration::from_millis(100)).await;
+                                "slow".to_owned()
+                            }
+                            "/write" => {
+                                writes.fetch_add(1, Ordering::SeqCst);
+                                "written".to_owned()
+                            }
+                            _ => writes.load(Ordering::SeqCst).to_string(),
+                        };
+
+                        Ok::<_, Infallible>(Response::ok().set_body(body))
+                    }
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(
+            b"GET /slow HTTP/1.1\r\n\r\n\
+            POST /write HTTP/1.1\r\ncontent-length: 0\r\n\r\n\
+            GET /read HTTP/1.1\r\nconnection: close\r\n\r\n",
+        )
+        .unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+
+    let bodies = data
+        .split("HTTP/1.1 200 OK\r\n")
+        .skip(1)
+        .map(|res| res.rsplit("\r\n\r\n").next().unwrap())
+        .collect::<Vec<_>>();
+
+    // the `GET` following the queued `POST` sees its write
+    assert_eq!(bodies, ["slow", "written", "1"]);
+
+    srv.stop().await;
+}
+
+#[actix_rt::test]
+async fn h1_pipeline_concurrency_waits_for_unsafe_request() {
+    let writes = Arc::new(AtomicUsize::new(0));
+
+    let mut srv = test_server({
+        let writes = Arc::clone(&writes);
+
+        move || {
+            let writes = Arc::clone(&writes);
+
+            HttpService::build()
+                .h1_pipeline_concurrency(3)
+                .h1(move |req: Request| {
+                    let writes = Arc::clone(&writes);
+
+                    async move {
+                        let body = match req.path() {
+                            "/write" => {
+                                sleep(Duration::from_millis(100)).await;
+                                writes.fetch_add(1, Ordering::SeqCst);
+                                "written".to_owned()
+                            }
+                            _ => writes.load(Ordering::SeqCst).to_string(),
+                        };
+
+                        Ok::<_, Infallible>(Response::ok().set_body(body))
+                    }
+                })
+                .tcp()
+        }
+    })
+    .await;
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(
+            b"POST /write HTTP/1.1\r\ncontent-length: 0\r\n\r\n\
+            GET /read HTTP/1.1\r\nconnection: close\r\n\r\n",
+        )
+        .unwrap();
+
+    let mut data = String::new();
+    stream.read_to_string(&mut data).unwrap();
+
+    let bodies = data
+        .split("HTTP/1.1 200 OK\r\n")
+        .skip(1)
+        .map(|res| res.rsplit("\r\n\r\n").next().unwrap())
+        .collect::<Vec<_>>();
+
+    // the `GET` is not started while the `POST` is still being handled
+    assert_eq!(bodies, ["written", "1"]);
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn http1_keepalive() {
     let mut srv = test_server(|| {
@@ -17,6 +17,7 @@ use actix_web::{
 };
 use bytes::{Bytes, BytesMut};
 use futures_core::stream::{LocalBoxStream, Stream};
+use futures_util::{pin_mut, Sink, SinkExt as _, StreamExt as _};
 use local_waker::LocalWaker;
 
 use crate::error::MultipartError;
@@ -463,6 +464,52 @@ impl Field {
             .get_name()
             .expect("field name should be guaranteed to exist in multipart form-data")
     }
+
+    /// Streams the rest of the field's bytes into `sink`, completing when the field ends.
+    ///
+    /// Chunks are fed into the sink as they are read, without buffering, and no more of the field
+    /// is read while the sink is not ready to accept it. The sink is flushed, but not closed, once
+    /// the field ends so that it can be reused.
+    ///
+    /// # Errors
+    /// Returns the error if reading the field fails. If the sink fails, its error is wrapped in
+    /// [`MultipartError::Field`]. In either case, the rest of the field is not read.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_multipart::{Field, MultipartError};
+    /// use actix_web::web::Bytes;
+    /// use futures_util::sink;
+    ///
+    /// async fn upload(mut field: Field) -> Result<(), MultipartError> {
+    ///     // (e.g., a sink that uploads parts to an object store)
+    ///     let sink = sink::drain::<Bytes>();
+    ///
+    ///     field.stream_to(sink).await
+    /// }
+    /// ```
+    pub async fn stream_to<K>(&mut self, sink: K) -> Result<(), MultipartError>
+    where
+        K: Sink<Bytes>,
+        K::Error: Into<actix_web::Error>,
+    {
+        pin_mut!(sink);
+
+        while let Some(chunk) = self.next().await {
+            sink.feed(chunk?)
+                .await
+                .map_err(|err| self.sink_error(err))?;
+        }
+
+        sink.flush().await.map_err(|err| self.sink_error(err))
+    }
+
+    fn sink_error(&self, err: impl Into<actix_web::Error>) -> MultipartError {
+        MultipartError::Field {
+            field_name: self.name().to_owned(),
+            source: err.into(),
+        }
+    }
 }
 
 impl Stream for Field {
@@ -1122,6 +1169,32 @@ mod tests {
         }
     }
 
+    #[actix_rt::test]
+    async fn test_stream_to() {
+        let (bytes, headers) = create_simple_request_with_header();
+        let payload = SlowStream::new(bytes);
+
+        let mut multipart = Multipart::new(&headers, payload);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        let mut chunks = Vec::<Bytes>::new();
+        field.stream_to(&mut chunks).await.unwrap();
+        assert_eq!(chunks.concat(), b"test");
+        drop(field);
+
+        let mut field = multipart.next().await.unwrap().unwrap();
+        let sink = futures_util::sink::unfold((), |_, _: Bytes| async {
+            Err::<(), _>(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "upload failed",
+            ))
+        });
+        match field.stream_to(sink).await {
+            Err(MultipartError::Field { field_name, .. }) => assert_eq!(field_name, "file"),
+            _ => unreachable!(),
+        }
+    }
+
     #[actix_rt::test]
     async fn test_basic() {
         let (_, payload) = h1::Payload::create(false);
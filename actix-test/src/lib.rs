@@ -37,13 +37,30 @@ extern crate tls_openssl as openssl;
 #[cfg(feature = "rustls")]
 extern crate tls_rustls as rustls;
 
-use std::{fmt, net, thread, time::Duration};
+use std::{
+    cmp, fmt,
+    future::{ready, Future as _},
+    io, net,
+    pin::Pin,
+    task::{Context, Poll},
+    thread,
+    time::Duration,
+};
 
-use actix_codec::{AsyncRead, AsyncWrite, Framed};
+use actix_codec::{AsyncRead, AsyncWrite, Framed, ReadBuf};
 pub use actix_http::{body::to_bytes, test::TestBuffer};
-use actix_http::{header::HeaderMap, ws, HttpService, Method, Request, Response};
+use actix_http::{
+    error::DispatchError, header::HeaderMap, ws, HttpService, Method, Protocol, Request,
+    Response,
+};
 pub use actix_http_test::unused_addr;
-use actix_service::{map_config, IntoServiceFactory, ServiceFactory, ServiceFactoryExt as _};
+use actix_rt::{
+    net::TcpStream,
+    time::{sleep, Sleep},
+};
+use actix_service::{
+    fn_service, map_config, IntoServiceFactory, ServiceFactory, ServiceFactoryExt as _,
+};
 pub use actix_web::test::{
     call_and_read_body, call_and_read_body_json, call_service, init_service, ok_service,
     read_body, read_body_json, status_service, TestRequest,
@@ -153,12 +170,13 @@ where
             let factory = factory.clone();
             let srv_cfg = cfg.clone();
             let timeout = cfg.client_request_timeout;
+            let io_behavior = cfg.io_behavior;
 
             let builder = Server::build().workers(1).disable_signals().system_exit();
 
             let srv = match srv_cfg.stream {
-                StreamType::Tcp => match srv_cfg.tp {
-                    HttpVer::Http1 => builder.listen("test", tcp, move || {
+                StreamType::Tcp => match (srv_cfg.tp, io_behavior) {
+                    (HttpVer::Http1, None) => builder.listen("test", tcp, move || {
                         let app_cfg = AppConfig::__priv_test_new(
                             false,
                             local_addr.to_string(),
@@ -174,7 +192,7 @@ where
                             .h1(map_config(fac, move |_| app_cfg.clone()))
                             .tcp()
                     }),
-                    HttpVer::Http2 => builder.listen("test", tcp, move || {
+                    (HttpVer::Http2, None) => builder.listen("test", tcp, move || {
                         let app_cfg = AppConfig::__priv_test_new(
                             false,
                             local_addr.to_string(),
@@ -190,7 +208,7 @@ where
                             .h2(map_config(fac, move |_| app_cfg.clone()))
                             .tcp()
                     }),
-                    HttpVer::Both => builder.listen("test", tcp, move || {
+                    (HttpVer::Both, None) => builder.listen("test", tcp, move || {
                         let app_cfg = AppConfig::__priv_test_new(
                             false,
                             local_addr.to_string(),
@@ -206,6 +224,93 @@ where
                             .finish(map_config(fac, move |_| app_cfg.clone()))
                             .tcp()
                     }),
+                    (HttpVer::Http1, Some(io_behavior)) => {
+                        builder.listen("test", tcp, move || {
+                            let app_cfg = AppConfig::__priv_test_new(
+                                false,
+                                local_addr.to_string(),
+                                local_addr,
+                            );
+
+                            let fac = factory()
+                                .into_factory()
+                                .map_err(|err| err.into().error_response());
+
+                            fn_service(move |io: TcpStream| {
+                                let peer_addr = io.peer_addr().ok();
+                                let io = ShapedIo::new(io, io_behavior);
+                                ready(Ok::<_, DispatchError>((io, peer_addr)))
+                            })
+                            .and_then(
+                                HttpService::build()
+                                    .client_request_timeout(timeout)
+                                    .h1(map_config(fac, move |_| app_cfg.clone())),
+                            )
+                        })
+                    }
+                    (HttpVer::Http2, Some(io_behavior)) => {
+                        builder.listen("test", tcp, move || {
+                            let app_cfg = AppConfig::__priv_test_new(
+                                false,
+                                local_addr.to_string(),
+                                local_addr,
+                            );
+
+                            let fac = factory()
+                                .into_factory()
+                                .map_err(|err| err.into().error_response());
+
+                            fn_service(move |io: TcpStream| {
+                                let peer_addr = io.peer_addr().ok();
+                                let io = ShapedIo::new(io, io_behavior);
+                                ready(Ok::<_, DispatchError>((io, peer_addr)))
+                            })
+                            .and_then(
+                                HttpService::build()
+                                    .client_request_timeout(timeout)
+                                    .h2(map_config(fac, move |_| app_cfg.clone()))
+                                    .map_init_err(|_| ()),
+                            )
+                        })
+                    }
+                    (HttpVer::Both, Some(io_behavior)) => {
+                        builder.listen("test", tcp, move || {
+                            let app_cfg = AppConfig::__priv_test_new(
+                                false,
+                                local_addr.to_string(),
+                                local_addr,
+                            );
+
+                            let fac = factory()
+                                .into_factory()
+                                .map_err(|err| err.into().error_response());
+
+                            fn_service(move |io: TcpStream| async move {
+                                // select the protocol like `HttpService::tcp_auto_h2c` before
+                                // the connection is shaped, since peeking needs the raw stream
+                                const H2_PREFACE: &[u8] = b"PRI * HTTP/2";
+
+                                let peer_addr = io.peer_addr().ok();
+
+                                let mut buf = [0; 12];
+                                io.peek(&mut buf).await?;
+
+                                let proto = if buf == H2_PREFACE {
+                                    Protocol::Http2
+                                } else {
+                                    Protocol::Http1
+                                };
+
+                                let io = ShapedIo::new(io, io_behavior);
+                                Ok::<_, DispatchError>((io, proto, peer_addr))
+                            })
+                            .and_then(
+                                HttpService::build()
+                                    .client_request_timeout(timeout)
+                                    .finish(map_config(fac, move |_| app_cfg.clone())),
+                            )
+                        })
+                    }
                 },
                 #[cfg(feature = "openssl")]
                 StreamType::Openssl(acceptor) => match cfg.tp {
@@ -394,6 +499,7 @@ pub struct TestServerConfig {
     stream: StreamType,
     client_request_timeout: Duration,
     port: u16,
+    io_behavior: Option<IoBehavior>,
 }
 
 impl Default for TestServerConfig {
@@ -410,6 +516,7 @@ impl TestServerConfig {
             stream: StreamType::Tcp,
             client_request_timeout: Duration::from_secs(5),
             port: 0,
+            io_behavior: None,
         }
     }
 
@@ -452,6 +559,170 @@ impl TestServerConfig {
         self.port = port;
         self
     }
+
+    /// Shapes the I/O of server connections to resemble a real network.
+    ///
+    /// Every read from and write to a connection transfers at most `fragment.size` bytes and is
+    /// delayed by `latency.dur`. This makes request payloads arrive at handlers in many small
+    /// chunks, exercising partial-read and backpressure code paths. Only applies to plaintext
+    /// (non-TLS) connections.
+    ///
+    /// When the server accepts both HTTP versions, which is the default, a shaped connection is
+    /// served over HTTP/2 if it starts with the HTTP/2 connection preface (prior knowledge) and
+    /// over HTTP/1.x otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use actix_test::{Fragment, Latency};
+    /// use actix_web::{web, App};
+    /// use futures_util::StreamExt as _;
+    ///
+    /// # #[actix_rt::main]
+    /// # async fn main() {
+    /// let cfg = actix_test::config().h1().with_io_behavior(
+    ///     Fragment { size: 16 },
+    ///     Latency { dur: Duration::from_millis(1) },
+    /// );
+    ///
+    /// let srv = actix_test::start_with(cfg, || {
+    ///     App::new().default_service(web::to(|mut body: web::Payload| async move {
+    ///         let mut chunks = 0;
+    ///
+    ///         while let Some(chunk) = body.next().await {
+    ///             chunk.unwrap();
+    ///             chunks += 1;
+    ///         }
+    ///
+    ///         chunks.to_string()
+    ///     }))
+    /// });
+    ///
+    /// let mut res = srv.post("/").send_body("x".repeat(256)).await.unwrap();
+    /// let chunks: usize = std::str::from_utf8(&res.body().await.unwrap())
+    ///     .unwrap()
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// assert!(chunks >= 256 / 16, "only received {} chunks", chunks);
+    /// # }
+    /// ```
+    pub fn with_io_behavior(mut self, fragment: Fragment, latency: Latency) -> Self {
+        self.io_behavior = Some(IoBehavior {
+            fragment_size: cmp::max(fragment.size, 1),
+            latency: latency.dur,
+        });
+        self
+    }
+}
+
+/// Maximum number of bytes per read or write on a test server connection.
+///
+/// See [`TestServerConfig::with_io_behavior`].
+#[derive(Debug, Clone, Copy)]
+pub struct Fragment {
+    /// Maximum size of each read or write, in bytes.
+    pub size: usize,
+}
+
+/// Delay before each read or write on a test server connection.
+///
+/// See [`TestServerConfig::with_io_behavior`].
+#[derive(Debug, Clone, Copy)]
+pub struct Latency {
+    /// Duration of each delay.
+    pub dur: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IoBehavior {
+    fragment_size: usize,
+    latency: Duration,
+}
+
+/// Connection wrapper that applies an [`IoBehavior`] to reads and writes.
+struct ShapedIo<T> {
+    io: T,
+    behavior: IoBehavior,
+    read_delay: Option<Pin<Box<Sleep>>>,
+    write_delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<T> ShapedIo<T> {
+    fn new(io: T, behavior: IoBehavior) -> Self {
+        ShapedIo {
+            io,
+            behavior,
+            read_delay: None,
+            write_delay: None,
+        }
+    }
+
+    /// Waits for the pending delay, if any, to elapse.
+    fn poll_delay(delay: &mut Option<Pin<Box<Sleep>>>, cx: &mut Context<'_>) -> Poll<()> {
+        if let Some(sleep) = delay {
+            futures_core::ready!(sleep.as_mut().poll(cx));
+            *delay = None;
+        }
+
+        Poll::Ready(())
+    }
+
+    /// Arms the delay for the next operation.
+    fn arm_delay(delay: &mut Option<Pin<Box<Sleep>>>, latency: Duration) {
+        if !latency.is_zero() {
+            *delay = Some(Box::pin(sleep(latency)));
+        }
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ShapedIo<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        futures_core::ready!(Self::poll_delay(&mut this.read_delay, cx));
+
+        if buf.remaining() <= this.behavior.fragment_size {
+            futures_core::ready!(Pin::new(&mut this.io).poll_read(cx, buf))?;
+        } else {
+            let mut fragment = vec![0; this.behavior.fragment_size];
+            let mut fragment_buf = ReadBuf::new(&mut fragment);
+            futures_core::ready!(Pin::new(&mut this.io).poll_read(cx, &mut fragment_buf))?;
+            buf.put_slice(fragment_buf.filled());
+        }
+
+        Self::arm_delay(&mut this.read_delay, this.behavior.latency);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for ShapedIo<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        futures_core::ready!(Self::poll_delay(&mut this.write_delay, cx));
+
+        let len = cmp::min(buf.len(), this.behavior.fragment_size);
+        let n = futures_core::ready!(Pin::new(&mut this.io).poll_write(cx, &buf[..len]))?;
+
+        Self::arm_delay(&mut this.write_delay, this.behavior.latency);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
 }
 
 /// A basic HTTP server controller that simplifies the process of writing integration tests for
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+use actix_test::{Fragment, Latency};
+use actix_web::{
+    web::{self, Bytes},
+    App, HttpRequest,
+};
+use futures_util::StreamExt as _;
+
+fn shaped_config() -> actix_test::TestServerConfig {
+    actix_test::config().with_io_behavior(
+        Fragment { size: 16 },
+        Latency {
+            dur: Duration::from_millis(1),
+        },
+    )
+}
+
+/// Responds with the HTTP version, the number of payload chunks and the payload itself.
+async fn echo(req: HttpRequest, mut body: web::Payload) -> String {
+    let mut chunks = 0;
+    let mut payload = Vec::new();
+
+    while let Some(chunk) = body.next().await {
+        payload.extend_from_slice(&chunk.unwrap());
+        chunks += 1;
+    }
+
+    format!(
+        "{:?} {} {}",
+        req.version(),
+        chunks,
+        String::from_utf8(payload).unwrap()
+    )
+}
+
+fn parse_echo(body: &[u8]) -> (String, usize, String) {
+    let body = std::str::from_utf8(body).unwrap();
+    let mut parts = body.splitn(3, ' ');
+
+    (
+        parts.next().unwrap().to_owned(),
+        parts.next().unwrap().parse().unwrap(),
+        parts.next().unwrap().to_owned(),
+    )
+}
+
+#[actix_rt::test]
+async fn shaped_h1_payload_arrives_in_fragments() {
+    let srv = actix_test::start_with(shaped_config(), || {
+        App::new().default_service(web::to(echo))
+    });
+
+    let payload = "x".repeat(256);
+    let mut res = srv.post("/").send_body(payload.clone()).await.unwrap();
+    assert!(res.status().is_success());
+
+    let (version, chunks, body) = parse_echo(&res.body().await.unwrap());
+    assert_eq!(version, "HTTP/1.1");
+    assert!(chunks >= 256 / 16, "only received {} chunks", chunks);
+    assert_eq!(body, payload);
+}
+
+#[actix_rt::test]
+async fn shaped_h2_prior_knowledge_on_default_listener() {
+    let srv = actix_test::start_with(shaped_config(), || {
+        App::new().default_service(web::to(echo))
+    });
+
+    let stream = actix_rt::net::TcpStream::connect(srv.addr()).await.unwrap();
+    let (mut client, conn) = h2::client::handshake(stream).await.unwrap();
+    actix_rt::spawn(async move { conn.await.unwrap() });
+
+    let req = http::Request::post(srv.url("/")).body(()).unwrap();
+    let (res, mut send) = client.send_request(req, false).unwrap();
+
+    let payload = "x".repeat(256);
+    send.send_data(Bytes::from(payload.clone()), true).unwrap();
+
+    let res = res.await.unwrap();
+    assert!(res.status().is_success());
+
+    let mut res_body = res.into_body();
+    let mut body = Vec::new();
+
+    while let Some(chunk) = res_body.data().await {
+        let chunk = chunk.unwrap();
+        res_body.flow_control().release_capacity(chunk.len()).unwrap();
+        body.extend_from_slice(&chunk);
+    }
+
+    let (version, _, echoed) = parse_echo(&body);
+    assert_eq!(version, "HTTP/2.0");
+    assert_eq!(echoed, payload);
+}
@@ -1,6 +1,6 @@
 use std::{cell::RefCell, fmt, future::Future, rc::Rc};
 
-use actix_http::{body::MessageBody, Extensions, Request};
+use actix_http::{body::MessageBody, Extensions, Request, StatusCode};
 use actix_service::{
     apply, apply_fn_factory, boxed, IntoServiceFactory, ServiceFactory, ServiceFactoryExt,
     Transform,
@@ -8,7 +8,7 @@ use actix_service::{
 use futures_util::FutureExt as _;
 
 use crate::{
-    app_service::{AppEntry, AppInit, AppRoutingFactory},
+    app_service::{AppEntry, AppInit, AppRoutingFactory, ErrorPages},
     config::ServiceConfig,
     data::{Data, DataFactory, FnDataFactory},
     dev::ResourceDef,
@@ -19,6 +19,7 @@ use crate::{
         AppServiceFactory, BoxedHttpServiceFactory, HttpServiceFactory, ServiceFactoryWrapper,
         ServiceRequest, ServiceResponse,
     },
+    HttpRequest, HttpResponse,
 };
 
 /// The top-level builder for an Actix Web application.
@@ -30,6 +31,10 @@ pub struct App<T> {
     data_factories: Vec<FnDataFactory>,
     external: Vec<ResourceDef>,
     extensions: Extensions,
+    error_pages: ErrorPages,
+    /// Names of the registered middleware, innermost first.
+    #[cfg(debug_assertions)]
+    middleware: Vec<String>,
 }
 
 impl App<AppEntry> {
@@ -46,6 +51,9 @@ impl App<AppEntry> {
             factory_ref,
             external: Vec::new(),
             extensions: Extensions::new(),
+            error_pages: ErrorPages::default(),
+            #[cfg(debug_assertions)]
+            middleware: Vec::new(),
         }
     }
 }
@@ -281,6 +289,44 @@ where
         self
     }
 
+    /// Registers a page that replaces the bodies of error responses with the given status code.
+    ///
+    /// `page` builds the replacement response from the request. It is used for the responses of
+    /// the app's services that either carry an [`Error`], such as those produced from handler and
+    /// extractor errors, or have no body, such as the default `404 Not Found` and `405 Method Not
+    /// Allowed` responses generated by the router. This way, error responses generated by Actix
+    /// Web look the same as those of the app itself, e.g. as JSON or HTML error pages. Responses
+    /// with the same status code but a body of their own, and error responses transformed by a
+    /// [`Scope::error_handler`](crate::Scope::error_handler), are left as they are.
+    ///
+    /// Headers of the original response that are not set by the page, such as `Allow`, are kept,
+    /// except for `Content-Type`. The page's status code is used as is. The error of the original
+    /// response is kept, so that it can still be logged. Responses of middleware registered with
+    /// [`wrap`](Self::wrap) are not affected.
+    ///
+    /// Registering a page for a status code again replaces the previous one.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::StatusCode, web, App, HttpResponse};
+    ///
+    /// let app = App::new()
+    ///     .error_page(StatusCode::NOT_FOUND, |req| {
+    ///         HttpResponse::NotFound().json(serde_json::json!({
+    ///             "error": "not found",
+    ///             "path": req.path(),
+    ///         }))
+    ///     })
+    ///     .route("/", web::get().to(HttpResponse::Ok));
+    /// ```
+    pub fn error_page<F>(mut self, status: StatusCode, page: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> HttpResponse + 'static,
+    {
+        self.error_pages.insert(status, Box::new(page));
+        self
+    }
+
     /// Register an external resource.
     ///
     /// External resources are useful for URL generation purposes only
@@ -334,6 +380,10 @@ where
     /// middlewares are run in reverse order for `App` _and then_ in reverse order for the
     /// wrapped service.
     ///
+    /// In debug builds, the resulting order of the `App`'s middleware is logged at the debug level
+    /// when the app is started, outermost first. Requests pass through the middleware in that
+    /// order and responses in the reverse order. Nothing is recorded or logged in release builds.
+    ///
     /// # Examples
     /// ```
     /// use actix_web::{middleware, web, App};
@@ -370,6 +420,11 @@ where
             > + 'static,
         B: MessageBody,
     {
+        #[cfg(debug_assertions)]
+        let mut middleware = self.middleware;
+        #[cfg(debug_assertions)]
+        middleware.push(short_type_name(std::any::type_name::<M>()));
+
         App {
             endpoint: apply(mw, self.endpoint),
             data_factories: self.data_factories,
@@ -378,6 +433,9 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            error_pages: self.error_pages,
+            #[cfg(debug_assertions)]
+            middleware,
         }
     }
 
@@ -391,7 +449,10 @@ where
     ///
     /// Middleware can also be applied to individual `Scope`s and `Resource`s.
     ///
-    /// See [`App::wrap`] for details on how middlewares compose with each other.
+    /// See [`App::wrap`] for details on how middlewares compose with each other. To wrap the
+    /// request body stream, use [`ServiceRequest::map_payload`].
+    ///
+    /// [`ServiceRequest::map_payload`]: crate::dev::ServiceRequest::map_payload
     ///
     /// # Examples
     /// ```
@@ -433,6 +494,11 @@ where
         R: Future<Output = Result<ServiceResponse<B>, Error>>,
         B: MessageBody,
     {
+        #[cfg(debug_assertions)]
+        let mut middleware = self.middleware;
+        #[cfg(debug_assertions)]
+        middleware.push(String::from("wrap_fn"));
+
         App {
             endpoint: apply_fn_factory(self.endpoint, mw),
             data_factories: self.data_factories,
@@ -441,6 +507,9 @@ where
             factory_ref: self.factory_ref,
             external: self.external,
             extensions: self.extensions,
+            error_pages: self.error_pages,
+            #[cfg(debug_assertions)]
+            middleware,
         }
     }
 }
@@ -457,6 +526,9 @@ where
     B: MessageBody,
 {
     fn into_factory(self) -> AppInit<T, B> {
+        #[cfg(debug_assertions)]
+        log_middleware_order(&self.middleware);
+
         AppInit {
             async_data_factories: self.data_factories.into_boxed_slice().into(),
             endpoint: self.endpoint,
@@ -465,10 +537,56 @@ where
             default: self.default,
             factory_ref: self.factory_ref,
             extensions: RefCell::new(Some(self.extensions)),
+            error_pages: Rc::new(self.error_pages),
         }
     }
 }
 
+/// Logs the order in which requests and responses pass through the middleware of an app.
+#[cfg(debug_assertions)]
+fn log_middleware_order(middleware: &[String]) {
+    if middleware.is_empty() {
+        return;
+    }
+
+    let order = middleware
+        .iter()
+        .rev()
+        .map(String::as_str)
+        .chain(Some("app"))
+        .collect::<Vec<_>>();
+
+    log::debug!(
+        "middleware order, outermost first (requests flow forward, responses flow back): {}",
+        order.join(" -> ")
+    );
+}
+
+/// Strips module paths from a type name, e.g. `a::B<c::D>` becomes `B<D>`.
+#[cfg(debug_assertions)]
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut segment_start = 0;
+
+    for (idx, c) in name.char_indices() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            continue;
+        }
+
+        short.push_str(last_path_segment(&name[segment_start..idx]));
+        short.push(c);
+        segment_start = idx + c.len_utf8();
+    }
+
+    short.push_str(last_path_segment(&name[segment_start..]));
+    short
+}
+
+#[cfg(debug_assertions)]
+fn last_path_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_service::Service as _;
@@ -694,6 +812,126 @@ mod tests {
         assert_eq!(body, Bytes::from_static(b"https://youtube.com/watch/12345"));
     }
 
+    #[actix_rt::test]
+    async fn test_error_pages() {
+        let srv = init_service(
+            App::new()
+                .error_page(StatusCode::NOT_FOUND, |req| {
+                    HttpResponse::NotFound()
+                        .content_type("application/json")
+                        .body(format!(
+                            r#"{{"error":"not found","path":"{}"}}"#,
+                            req.path()
+                        ))
+                })
+                .error_page(StatusCode::METHOD_NOT_ALLOWED, |_| {
+                    HttpResponse::MethodNotAllowed().body("custom 405")
+                })
+                .error_page(StatusCode::BAD_REQUEST, |_| {
+                    HttpResponse::BadRequest().body("custom 400")
+                })
+                .service(web::resource("/item").route(web::get().to(HttpResponse::Ok)))
+                .route(
+                    "/number",
+                    web::post().to(|n: web::Json<u32>| async move { n.to_string() }),
+                )
+                .route(
+                    "/missing",
+                    web::get().to(|| HttpResponse::NotFound().body("own body")),
+                ),
+        )
+        .await;
+
+        // unmatched route
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let body = read_body(res).await;
+        assert_eq!(body, r#"{"error":"not found","path":"/unknown"}"#);
+
+        // method not matched; the allowed methods are kept
+        let req = TestRequest::post().uri("/item").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers().get(header::ALLOW).unwrap(), "GET");
+        assert_eq!(read_body(res).await, "custom 405");
+
+        // extractor error, which is kept
+        let req = TestRequest::post()
+            .uri("/number")
+            .insert_header(header::ContentType::json())
+            .set_payload("abc")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res.response().error().is_some());
+        assert_eq!(read_body(res).await, "custom 400");
+
+        // responses with bodies of their own are left alone
+        let req = TestRequest::with_uri("/missing").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "own body");
+
+        let req = TestRequest::post()
+            .uri("/number")
+            .insert_header(header::ContentType::json())
+            .set_payload("42")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "42");
+    }
+
+    #[actix_rt::test]
+    async fn test_error_pages_skip_scope_error_handler() {
+        let srv = init_service(
+            App::new()
+                .error_page(StatusCode::BAD_REQUEST, |_| {
+                    HttpResponse::BadRequest().body("custom 400")
+                })
+                .service(
+                    web::scope("/api")
+                        .error_handler(|_, _| {
+                            HttpResponse::BadRequest()
+                                .content_type("application/json")
+                                .body(r#"{"error":"bad number"}"#)
+                        })
+                        .route(
+                            "/number",
+                            web::post().to(|n: web::Json<u32>| async move { n.to_string() }),
+                        ),
+                )
+                .route(
+                    "/number",
+                    web::post().to(|n: web::Json<u32>| async move { n.to_string() }),
+                ),
+        )
+        .await;
+
+        // the scope's response is kept
+        let req = TestRequest::post()
+            .uri("/api/number")
+            .insert_header(header::ContentType::json())
+            .set_payload("abc")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(res.response().error().is_some());
+        assert_eq!(read_body(res).await, r#"{"error":"bad number"}"#);
+
+        // errors outside of the scope still get the page
+        let req = TestRequest::post()
+            .uri("/number")
+            .insert_header(header::ContentType::json())
+            .set_payload("abc")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "custom 400");
+    }
+
     #[test]
     fn can_be_returned_from_fn() {
         /// compile-only test for returning app type from function
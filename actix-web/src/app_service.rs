@@ -3,17 +3,20 @@ use std::{cell::RefCell, mem, rc::Rc};
 use actix_http::Request;
 use actix_router::{Path, ResourceDef, Router, Url};
 use actix_service::{boxed, fn_service, Service, ServiceFactory};
+use ahash::AHashMap;
 use futures_core::future::LocalBoxFuture;
 use futures_util::future::join_all;
 
 use crate::{
-    body::BoxBody,
+    body::{BodySize, BoxBody, MessageBody as _},
     config::{AppConfig, AppService},
     data::FnDataFactory,
     dev::Extensions,
     guard::Guard,
+    http::{header, StatusCode},
     request::{HttpRequest, HttpRequestPool},
     rmap::ResourceMap,
+    scope::ErrorHandled,
     service::{
         AppServiceFactory, BoxedHttpService, BoxedHttpServiceFactory, ServiceRequest,
         ServiceResponse,
@@ -21,6 +24,12 @@ use crate::{
     Error, HttpResponse,
 };
 
+/// Builds the replacement for an error response with a given status code.
+pub(crate) type ErrorPage = dyn Fn(&HttpRequest) -> HttpResponse;
+
+/// Error pages registered with [`App::error_page`](crate::App::error_page), by status code.
+pub(crate) type ErrorPages = AHashMap<StatusCode, Box<ErrorPage>>;
+
 /// Service factory to convert [`Request`] to a [`ServiceRequest<S>`].
 ///
 /// It also executes data factories.
@@ -41,6 +50,7 @@ where
     pub(crate) default: Option<Rc<BoxedHttpServiceFactory>>,
     pub(crate) factory_ref: Rc<RefCell<Option<AppRoutingFactory>>>,
     pub(crate) external: RefCell<Vec<ResourceDef>>,
+    pub(crate) error_pages: Rc<ErrorPages>,
 }
 
 impl<T, B> ServiceFactory<Request> for AppInit<T, B>
@@ -80,11 +90,13 @@ where
 
         let mut rmap = ResourceMap::new(ResourceDef::prefix(""));
 
+        let schemas = config.schema_registry().clone();
         let (config, services) = config.into_services();
 
         // complete pipeline creation.
         *self.factory_ref.borrow_mut() = Some(AppRoutingFactory {
             default,
+            error_pages: Rc::clone(&self.error_pages),
             services: services
                 .into_iter()
                 .map(|(mut rdef, srv, guards, nested)| {
@@ -118,6 +130,8 @@ where
             .take()
             .unwrap_or_else(Extensions::new);
 
+        app_data.insert(schemas);
+
         Box::pin(async move {
             // async data factories
             let async_data_factories = factory_futs
@@ -250,6 +264,7 @@ pub struct AppRoutingFactory {
         )],
     >,
     default: Rc<BoxedHttpServiceFactory>,
+    error_pages: Rc<ErrorPages>,
 }
 
 impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
@@ -274,6 +289,7 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
 
         // construct default service factory future
         let default_fut = self.default.new_service(());
+        let error_pages = Rc::clone(&self.error_pages);
 
         Box::pin(async move {
             let default = default_fut.await?;
@@ -290,7 +306,11 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
                 })
                 .finish();
 
-            Ok(AppRouting { router, default })
+            Ok(AppRouting {
+                router,
+                default,
+                error_pages,
+            })
         })
     }
 }
@@ -299,6 +319,7 @@ impl ServiceFactory<ServiceRequest> for AppRoutingFactory {
 pub struct AppRouting {
     router: Router<BoxedHttpService, Vec<Box<dyn Guard>>>,
     default: BoxedHttpService,
+    error_pages: Rc<ErrorPages>,
 }
 
 impl Service<ServiceRequest> for AppRouting {
@@ -314,12 +335,60 @@ impl Service<ServiceRequest> for AppRouting {
             guards.iter().all(|guard| guard.check(&guard_ctx))
         });
 
-        if let Some((srv, _info)) = res {
+        let fut = if let Some((srv, _info)) = res {
             srv.call(req)
         } else {
             self.default.call(req)
+        };
+
+        if self.error_pages.is_empty() {
+            return fut;
+        }
+
+        let error_pages = Rc::clone(&self.error_pages);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            Ok(apply_error_page(&error_pages, res))
+        })
+    }
+}
+
+/// Replaces the response with its error page, if there is one and the response carries an error
+/// that was not handled by a scope or has no body.
+fn apply_error_page(error_pages: &ErrorPages, res: ServiceResponse) -> ServiceResponse {
+    let page = match error_pages.get(&res.status()) {
+        Some(page) => page,
+        None => return res,
+    };
+
+    if res.response().extensions().contains::<ErrorHandled>() {
+        return res;
+    }
+
+    let is_bare = matches!(
+        res.response().body().size(),
+        BodySize::None | BodySize::Sized(0)
+    );
+
+    if res.response().error().is_none() && !is_bare {
+        return res;
+    }
+
+    let (req, mut original) = res.into_parts();
+    let mut page_res = page(&req);
+
+    for (name, value) in original.headers() {
+        if name != header::CONTENT_TYPE && !page_res.headers().contains_key(name) {
+            page_res.headers_mut().append(name.clone(), value.clone());
         }
     }
+
+    if page_res.error.is_none() {
+        page_res.error = original.error.take();
+    }
+
+    ServiceResponse::new(req, page_res)
 }
 
 /// Wrapper service for routing
@@ -10,6 +10,7 @@ use crate::{
     resource::Resource,
     rmap::ResourceMap,
     route::Route,
+    schema::SchemaRegistry,
     service::{
         AppServiceFactory, BoxedHttpServiceFactory, HttpServiceFactory, ServiceFactoryWrapper,
         ServiceRequest, ServiceResponse,
@@ -30,6 +31,9 @@ pub struct AppService {
         Option<Guards>,
         Option<Rc<ResourceMap>>,
     )>,
+    schemas: SchemaRegistry,
+    /// Path prefix of the scopes being configured.
+    path_prefix: String,
 }
 
 impl AppService {
@@ -40,6 +44,8 @@ impl AppService {
             default,
             root: true,
             services: Vec::new(),
+            schemas: SchemaRegistry::default(),
+            path_prefix: String::new(),
         }
     }
 
@@ -71,9 +77,26 @@ impl AppService {
             default: self.default.clone(),
             services: Vec::new(),
             root: false,
+            schemas: self.schemas.clone(),
+            path_prefix: self.path_prefix.clone(),
         }
     }
 
+    /// Returns the registry in which routes record their request body schemas.
+    pub(crate) fn schema_registry(&self) -> &SchemaRegistry {
+        &self.schemas
+    }
+
+    /// Returns the path prefix of the scopes being configured.
+    pub(crate) fn path_prefix(&self) -> &str {
+        &self.path_prefix
+    }
+
+    /// Appends the path prefix of a nested scope.
+    pub(crate) fn push_path_prefix(&mut self, prefix: &str) {
+        self.path_prefix.push_str(prefix);
+    }
+
     /// Returns reference to configuration.
     pub fn config(&self) -> &AppConfig {
         &self.config
@@ -1,8 +1,9 @@
-use std::{any::type_name, ops::Deref, sync::Arc};
+use std::{any::type_name, fmt, future::Future, ops::Deref, sync::Arc};
 
 use actix_http::Extensions;
 use actix_utils::future::{err, ok, Ready};
 use futures_core::future::LocalBoxFuture;
+use futures_util::future::{FutureExt as _, Shared};
 use serde::Serialize;
 
 use crate::{dev::Payload, error, Error, FromRequest, HttpRequest};
@@ -94,6 +95,22 @@ impl<T> Data<T> {
     pub fn new(state: T) -> Data<T> {
         Data(Arc::new(state))
     }
+
+    /// Create new [`LazyData`] instance that is initialized by `init` on first extraction.
+    ///
+    /// See [`LazyData`] for more details.
+    pub fn lazy<F, Fut>(init: F) -> LazyData<T>
+    where
+        F: FnOnce() -> Fut + 'static,
+        Fut: Future<Output = T> + 'static,
+        T: 'static,
+    {
+        LazyData {
+            fut: async move { Data::new(init().await) }
+                .boxed_local()
+                .shared(),
+        }
+    }
 }
 
 impl<T: ?Sized> Data<T> {
@@ -165,6 +182,109 @@ impl<T: ?Sized + 'static> FromRequest for Data<T> {
     }
 }
 
+/// Lazily initialized application data wrapper and extractor.
+///
+/// Created using [`Data::lazy`]. The initialization future is not run until a handler first
+/// extracts the `LazyData`; its output is then cached and shared with all later (and any
+/// concurrent) extractions. Initialization happens at most once for each `LazyData` instance, so
+/// construct it _inside_ the `HttpServer::new` closure to get one instance per worker. Workers
+/// that never handle a request which extracts it never pay the initialization cost.
+///
+/// Extracting `LazyData` waits for initialization to complete. If it is not set for a handler,
+/// using the `LazyData<T>` extractor will cause a `500 Internal Server Error` response.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web::{self, Data, LazyData}, App, HttpResponse, Responder};
+///
+/// async fn connect_to_db() -> String {
+///     "db connection".to_owned()
+/// }
+///
+/// async fn index(conn: LazyData<String>) -> impl Responder {
+///     HttpResponse::Ok().body(conn.as_str().to_owned())
+/// }
+///
+/// let app = App::new()
+///     .app_data(Data::lazy(connect_to_db))
+///     .route("/", web::get().to(index));
+/// ```
+#[doc(alias = "state")]
+pub struct LazyData<T> {
+    fut: Shared<LocalBoxFuture<'static, Data<T>>>,
+}
+
+impl<T> LazyData<T> {
+    /// Returns the initialized data, running the initialization future if needed.
+    pub async fn get(&self) -> Data<T> {
+        self.fut.clone().await
+    }
+}
+
+/// Dereferences to the initialized data.
+///
+/// # Panics
+/// Panics if the data is not yet initialized. This is never the case for a `LazyData` obtained
+/// through extraction.
+impl<T> Deref for LazyData<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.fut
+            .peek()
+            .expect("LazyData should be initialized before it is dereferenced")
+    }
+}
+
+impl<T> Clone for LazyData<T> {
+    fn clone(&self) -> Self {
+        Self {
+            fut: self.fut.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for LazyData<T>
+where
+    T: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LazyData").field(&self.fut.peek()).finish()
+    }
+}
+
+impl<T: 'static> FromRequest for LazyData<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        if let Some(lazy) = req.app_data::<LazyData<T>>() {
+            let lazy = lazy.clone();
+
+            Box::pin(async move {
+                lazy.get().await;
+                Ok(lazy)
+            })
+        } else {
+            log::debug!(
+                "Failed to extract `LazyData<{}>` for `{}` handler. For the LazyData extractor to \
+                work correctly, create it with `Data::lazy()` and pass it to `App::app_data()`. \
+                Ensure that types align in both the set and retrieve calls.",
+                type_name::<T>(),
+                req.match_name().unwrap_or_else(|| req.path())
+            );
+
+            Box::pin(async {
+                Err(error::ErrorInternalServerError(
+                    "Requested application data is not configured correctly. \
+                    View/enable debug logs for more details.",
+                ))
+            })
+        }
+    }
+}
+
 impl<T: ?Sized + 'static> DataFactory for Data<T> {
     fn create(&self, extensions: &mut Extensions) -> bool {
         extensions.insert(Data(self.0.clone()));
@@ -251,6 +371,65 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
     }
 
+    #[actix_rt::test]
+    async fn test_lazy_data_extractor() {
+        use std::{cell::Cell, rc::Rc, time::Duration};
+
+        use futures_util::future::join_all;
+
+        let inits = Rc::new(Cell::new(0));
+
+        let srv = init_service(
+            App::new()
+                .app_data(Data::lazy({
+                    let inits = Rc::clone(&inits);
+
+                    move || async move {
+                        inits.set(inits.get() + 1);
+                        actix_rt::time::sleep(Duration::from_millis(50)).await;
+                        10usize
+                    }
+                }))
+                .route(
+                    "/",
+                    web::get().to(|data: LazyData<usize>| {
+                        assert_eq!(*data, 10);
+                        HttpResponse::Ok()
+                    }),
+                )
+                .route("/other", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // initialization is deferred until first extraction
+        let req = TestRequest::with_uri("/other").to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(inits.get(), 0);
+
+        let resps =
+            join_all((0..4).map(|_| srv.call(TestRequest::default().to_request()))).await;
+
+        for resp in resps {
+            assert_eq!(resp.unwrap().status(), StatusCode::OK);
+        }
+
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(inits.get(), 1);
+
+        let srv = init_service(
+            App::new()
+                .app_data(Data::lazy(|| async { 10u32 }))
+                .route("/", web::get().to(|_: LazyData<usize>| HttpResponse::Ok())),
+        )
+        .await;
+        let req = TestRequest::default().to_request();
+        let resp = srv.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     // allow deprecated App::data
     #[allow(deprecated)]
     #[actix_rt::test]
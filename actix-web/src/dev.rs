@@ -7,7 +7,11 @@
 //! - [`ConnectionInfo`]: Connection information
 //! - [`PeerAddr`]: Connection information
 
-pub use actix_http::{Extensions, Payload, RequestHead, Response, ResponseHead};
+pub use actix_http::{
+    h1::{FramingStrictness, HeaderFolding, PayloadMetrics, PayloadMetricsSnapshot},
+    h2::PushPromise,
+    Extensions, Payload, RequestHead, Response, ResponseDisconnect, ResponseHead,
+};
 pub use actix_router::{Path, ResourceDef, ResourcePath, Url};
 pub use actix_server::{Server, ServerHandle};
 pub use actix_service::{
@@ -20,11 +24,15 @@ pub use actix_http::encoding::Decoder as Decompress;
 pub use crate::config::{AppConfig, AppService};
 #[doc(hidden)]
 pub use crate::handler::Handler;
-pub use crate::info::{ConnectionInfo, PeerAddr};
+#[cfg(unix)]
+pub use crate::info::PeerCredentials;
+pub use crate::info::{ConnectionInfo, ForwardingHeader, PeerAddr};
+pub use crate::response::RangedBody;
 pub use crate::rmap::ResourceMap;
+pub use crate::server::ExcessConnections;
 pub use crate::service::{HttpServiceFactory, ServiceRequest, ServiceResponse, WebService};
 
-pub use crate::types::{JsonBody, Readlines, UrlEncoded};
+pub use crate::types::{JsonBody, PayloadExtractFut, Readlines, UrlEncoded};
 
 use actix_router::Patterns;
 
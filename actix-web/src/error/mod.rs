@@ -9,13 +9,19 @@ pub use actix_http::error::{
     ContentTypeError, DispatchError, HttpError, ParseError, PayloadError,
 };
 
+use std::{borrow::Cow, fmt};
+
 use derive_more::{Display, Error, From};
 use serde_json::error::Error as JsonError;
 use serde_urlencoded::de::Error as FormDeError;
 use serde_urlencoded::ser::Error as FormError;
 use url::ParseError as UrlParseError;
 
-use crate::http::StatusCode;
+use crate::{
+    body::BoxBody,
+    http::{header, StatusCode},
+    HttpResponse,
+};
 
 #[allow(clippy::module_inception)]
 mod error;
@@ -227,6 +233,278 @@ impl ResponseError for ReadlinesError {
     }
 }
 
+/// Error type returned when reading body as UTF-8 text with
+/// [`Utf8Payload`](crate::web::Utf8Payload).
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum Utf8PayloadError {
+    /// Payload contains a byte sequence that is not valid UTF-8.
+    #[display(fmt = "Invalid UTF-8 sequence at byte offset {}", offset)]
+    #[from(ignore)]
+    InvalidUtf8 {
+        /// Offset of the invalid sequence from the start of the payload.
+        offset: usize,
+    },
+
+    /// Payload ended in the middle of a UTF-8 sequence.
+    #[display(fmt = "Payload ended with an incomplete UTF-8 sequence")]
+    Incomplete,
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl ResponseError for Utf8PayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            Utf8PayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Errors that can occur when decoding a payload stream with
+/// [`Base64Payload`](crate::web::Base64Payload).
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum Base64PayloadError {
+    /// Payload contains a byte that is not valid base64 at its position.
+    #[display(fmt = "Invalid base64 character at byte offset {}", offset)]
+    #[from(ignore)]
+    InvalidCharacter {
+        /// Offset of the invalid byte from the start of the payload.
+        offset: usize,
+    },
+
+    /// Payload ended in the middle of a base64 quantum.
+    #[display(fmt = "Payload ended with an incomplete base64 quantum")]
+    Incomplete,
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+impl ResponseError for Base64PayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            Base64PayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Errors that can occur when decrypting a payload stream with
+/// [`DecryptPayload`](crate::web::DecryptPayload).
+#[cfg(feature = "decrypt-payload")]
+#[derive(Debug, Display, Error, From)]
+#[non_exhaustive]
+pub enum DecryptPayloadError {
+    /// A segment failed authentication, e.g., because it was tampered with or encrypted with
+    /// another key or nonce.
+    #[display(fmt = "Payload segment {} failed authentication", segment)]
+    #[from(ignore)]
+    Authentication {
+        /// Index of the segment that failed authentication, starting at zero.
+        segment: u32,
+    },
+
+    /// Payload is not a valid sequence of segments; e.g., a segment is too short or too long,
+    /// or data follows the final segment.
+    #[display(fmt = "Payload is not a valid sequence of encrypted segments")]
+    InvalidFraming,
+
+    /// Payload ended before its final segment.
+    #[display(fmt = "Payload ended before the final encrypted segment")]
+    Truncated,
+
+    /// Payload error.
+    #[display(fmt = "Error that occur during reading payload: {}", _0)]
+    Payload(PayloadError),
+}
+
+#[cfg(feature = "decrypt-payload")]
+impl ResponseError for DecryptPayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            DecryptPayloadError::Payload(ref err) => err.status_code(),
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// Errors that can occur when extracting credentials with [`BearerAuth`](crate::web::BearerAuth)
+/// or [`BasicAuth`](crate::web::BasicAuth).
+///
+/// Responds with `401 Unauthorized` and a `WWW-Authenticate` header that challenges the client to
+/// authenticate with the expected scheme.
+#[derive(Debug, Clone, PartialEq, Eq, Display, Error)]
+#[non_exhaustive]
+pub enum AuthError {
+    /// `Authorization` header is missing.
+    #[display(fmt = "Authorization header is missing")]
+    Missing {
+        /// Authentication scheme that was expected.
+        scheme: &'static str,
+
+        /// Protection space sent in the challenge, if any.
+        realm: Option<Cow<'static, str>>,
+    },
+
+    /// `Authorization` header does not hold valid credentials of the expected scheme.
+    #[display(fmt = "Authorization header is not valid for the {} scheme", scheme)]
+    Malformed {
+        /// Authentication scheme that was expected.
+        scheme: &'static str,
+
+        /// Protection space sent in the challenge, if any.
+        realm: Option<Cow<'static, str>>,
+    },
+}
+
+impl AuthError {
+    /// Returns the `WWW-Authenticate` challenge sent with the error response.
+    fn challenge(&self) -> String {
+        let (scheme, realm, malformed) = match self {
+            AuthError::Missing { scheme, realm } => (*scheme, realm, false),
+            AuthError::Malformed { scheme, realm } => (*scheme, realm, true),
+        };
+
+        let mut params = Vec::new();
+
+        if let Some(realm) = realm {
+            let realm = realm.replace('\\', "\\\\").replace('"', "\\\"");
+            params.push(format!("realm=\"{}\"", realm));
+        }
+
+        match scheme {
+            "Bearer" if malformed => params.push(r#"error="invalid_request""#.to_owned()),
+            "Basic" => params.push(r#"charset="UTF-8""#.to_owned()),
+            _ => {}
+        }
+
+        if params.is_empty() {
+            scheme.to_owned()
+        } else {
+            format!("{} {}", scheme, params.join(", "))
+        }
+    }
+}
+
+impl ResponseError for AuthError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        HttpResponse::build(self.status_code())
+            .insert_header((header::WWW_AUTHENTICATE, self.challenge()))
+            .body(self.to_string())
+    }
+}
+
+/// A validation failure of a single field.
+///
+/// See [`ValidationErrors`].
+#[derive(Debug, Clone, PartialEq, Eq, Display)]
+#[display(fmt = "{}: {}", field, message)]
+pub struct FieldError {
+    field: String,
+    message: String,
+}
+
+impl FieldError {
+    /// Constructs a new field error.
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Returns the name of the field that failed validation.
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Returns a description of why the field failed validation.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Errors returned when an extracted value fails [validation](crate::web::Validate).
+///
+/// Responds with `400 Bad Request` and a JSON body listing each failing field, for example:
+/// `{"errors":[{"field":"age","message":"must be between 1 and 150"}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationErrors {
+    /// Constructs an empty set of validation errors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a failure of `field`.
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError::new(field, message));
+    }
+
+    /// Returns true if no failures were added.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Returns the field failures.
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// Returns `Ok(())` if no failures were added, otherwise returns `Err(self)`.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Validation failed")?;
+
+        for (idx, err) in self.errors.iter().enumerate() {
+            f.write_str(if idx == 0 { ": " } else { "; " })?;
+            fmt::Display::fmt(err, f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+impl ResponseError for ValidationErrors {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let errors = self
+            .errors
+            .iter()
+            .map(|err| serde_json::json!({ "field": err.field, "message": err.message }))
+            .collect::<Vec<_>>();
+
+        HttpResponse::build(self.status_code()).json(serde_json::json!({ "errors": errors }))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -124,6 +124,19 @@ impl ResponseError for actix_http::error::PayloadError {
             _ => StatusCode::BAD_REQUEST,
         }
     }
+
+    /// Creates a response with the canonical reason of the status as its body.
+    ///
+    /// The `Display` impl is not used since it may include details of underlying I/O errors.
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        let status = self.status_code();
+        let mut res = HttpResponse::new(status);
+
+        let mime = mime::TEXT_PLAIN_UTF_8.try_into_value().unwrap();
+        res.headers_mut().insert(header::CONTENT_TYPE, mime);
+
+        res.set_body(BoxBody::new(status.canonical_reason().unwrap_or_default()))
+    }
 }
 
 impl ResponseError for actix_http::ws::ProtocolError {}
@@ -157,4 +170,46 @@ mod tests {
         let not_err = resp_err.downcast_ref::<ContentTypeError>();
         assert!(not_err.is_none());
     }
+
+    #[actix_rt::test]
+    async fn test_payload_error_status() {
+        use actix_http::error::PayloadError;
+
+        use crate::{
+            test::{call_service, init_service, read_body, TestRequest},
+            web, App,
+        };
+
+        let srv = init_service(App::new().route(
+            "/{variant}",
+            web::get().to(|variant: web::Path<String>| async move {
+                let err = match variant.as_str() {
+                    "overflow" => PayloadError::Overflow,
+                    "incomplete" => PayloadError::Incomplete(Some(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "connection closed",
+                    ))),
+                    _ => PayloadError::Io(io::Error::new(io::ErrorKind::Other, "reset")),
+                };
+
+                Err::<HttpResponse, _>(err)
+            }),
+        ))
+        .await;
+
+        for (variant, status, body) in [
+            (
+                "overflow",
+                StatusCode::PAYLOAD_TOO_LARGE,
+                "Payload Too Large",
+            ),
+            ("incomplete", StatusCode::BAD_REQUEST, "Bad Request"),
+            ("io", StatusCode::BAD_REQUEST, "Bad Request"),
+        ] {
+            let req = TestRequest::with_uri(&format!("/{}", variant)).to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), status);
+            assert_eq!(read_body(res).await, body);
+        }
+    }
 }
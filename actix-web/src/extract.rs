@@ -94,6 +94,25 @@ pub trait FromRequest: Sized {
     fn extract(req: &HttpRequest) -> Self::Future {
         Self::from_request(req, &mut Payload::None)
     }
+
+    /// Returns whether this extractor can handle the request's `Content-Type`.
+    ///
+    /// Used by [`Either`](crate::Either) to choose an extractor before reading the payload.
+    /// Returns `None` for extractors that do not depend on the content type.
+    ///
+    /// The `Sealed` argument can not be named outside this crate, so this method can be neither
+    /// called nor overridden by other crates.
+    #[doc(hidden)]
+    fn accepts_content_type(_req: &HttpRequest, _: private::Sealed) -> Option<bool> {
+        None
+    }
+}
+
+pub(crate) mod private {
+    /// Token that restricts [`FromRequest::accepts_content_type`](super::FromRequest) to this
+    /// crate.
+    #[derive(Debug, Clone, Copy)]
+    pub struct Sealed(pub(crate) ());
 }
 
 /// Optionally extract from the request.
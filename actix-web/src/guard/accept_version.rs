@@ -0,0 +1,153 @@
+use super::{Guard, GuardContext};
+use crate::http::header::{Accept, Quality};
+
+/// Creates a guard that matches requests accepting a specific version of a vendor media type.
+///
+/// The version is read from the `Accept` header, which keeps it out of the URL. A media range
+/// matches if it is acceptable (i.e., its quality is not zero) and either:
+/// - its subtype, without any structured syntax suffix, is `vnd.{name}.v{version}`; e.g.,
+///   `application/vnd.api.v2+json` for `AcceptVersion("api", 2)`;
+/// - its subtype is `vnd.{name}` and it has a `version` parameter equal to `version`; e.g.,
+///   `application/vnd.api+json; version=2`.
+///
+/// Requests without a (valid) `Accept` header never match, so a fallback route can serve a
+/// default version. The request body is not read.
+///
+/// # Examples
+/// ```
+/// use actix_web::{guard, web, HttpResponse};
+///
+/// web::resource("/items")
+///     .route(
+///         web::get()
+///             .guard(guard::AcceptVersion("api", 2))
+///             .to(|| async { HttpResponse::Ok().body("items v2") }),
+///     )
+///     .route(web::get().to(|| async { HttpResponse::Ok().body("items v1") }));
+/// ```
+#[allow(non_snake_case)]
+pub fn AcceptVersion(name: impl AsRef<str>, version: u32) -> impl Guard {
+    AcceptVersionGuard {
+        vendor: format!("vnd.{}", name.as_ref()),
+        versioned: format!("vnd.{}.v{}", name.as_ref(), version),
+        version: version.to_string(),
+    }
+}
+
+struct AcceptVersionGuard {
+    /// Subtype that carries the version as a parameter.
+    vendor: String,
+    /// Subtype that carries the version itself.
+    versioned: String,
+    version: String,
+}
+
+impl Guard for AcceptVersionGuard {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        let accept = match ctx.header::<Accept>() {
+            Some(hdr) => hdr,
+            None => return false,
+        };
+
+        accept
+            .0
+            .iter()
+            .filter(|item| item.quality != Quality::ZERO)
+            .any(|item| {
+                let subtype = item.item.subtype();
+
+                subtype.as_str().eq_ignore_ascii_case(&self.versioned)
+                    || (subtype.as_str().eq_ignore_ascii_case(&self.vendor)
+                        && item
+                            .item
+                            .get_param("version")
+                            .map_or(false, |version| version == self.version.as_str()))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::header,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[test]
+    fn versions() {
+        let v2 = AcceptVersion("api", 2);
+
+        for accept in [
+            "application/vnd.api.v2+json",
+            "application/vnd.api.v2",
+            "text/html, application/vnd.API.v2+json;q=0.5",
+            "application/vnd.api+json; version=2",
+        ] {
+            let req = TestRequest::default()
+                .insert_header((header::ACCEPT, accept))
+                .to_srv_request();
+            assert!(v2.check(&req.guard_ctx()), "{}", accept);
+        }
+
+        for accept in [
+            "application/vnd.api.v1+json",
+            "application/vnd.api.v20+json",
+            "application/vnd.other.v2+json",
+            "application/vnd.api.v2+json;q=0",
+            "application/vnd.api+json; version=1",
+            "application/vnd.api+json",
+            "*/*",
+        ] {
+            let req = TestRequest::default()
+                .insert_header((header::ACCEPT, accept))
+                .to_srv_request();
+            assert!(!v2.check(&req.guard_ctx()), "{}", accept);
+        }
+
+        let req = TestRequest::default().to_srv_request();
+        assert!(!v2.check(&req.guard_ctx()));
+    }
+
+    #[actix_rt::test]
+    async fn dispatch_by_version() {
+        let srv = init_service(
+            App::new().service(
+                web::resource("/items")
+                    .route(
+                        web::post()
+                            .guard(AcceptVersion("api", 2))
+                            .to(|body: String| async move { format!("v2: {}", body) }),
+                    )
+                    .route(
+                        web::post()
+                            .guard(AcceptVersion("api", 1))
+                            .to(|body: String| async move { format!("v1: {}", body) }),
+                    )
+                    .default_service(web::to(HttpResponse::NotAcceptable)),
+            ),
+        )
+        .await;
+
+        for (accept, expected) in [
+            ("application/vnd.api.v1+json", &b"v1: item"[..]),
+            ("application/vnd.api.v2+json", b"v2: item"),
+        ] {
+            let req = TestRequest::post()
+                .uri("/items")
+                .insert_header((header::ACCEPT, accept))
+                .set_payload("item")
+                .to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(read_body(res).await, expected);
+        }
+
+        let req = TestRequest::post()
+            .uri("/items")
+            .insert_header((header::ACCEPT, "application/vnd.api.v3+json"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), 406);
+    }
+}
@@ -56,9 +56,11 @@ use actix_http::{header, Extensions, Method as HttpMethod, RequestHead};
 
 use crate::{http::header::Header, service::ServiceRequest, HttpMessage as _};
 
+mod accept_version;
 mod acceptable;
 mod host;
 
+pub use self::accept_version::AcceptVersion;
 pub use self::acceptable::Acceptable;
 pub use self::host::{Host, HostGuard};
 
@@ -185,6 +187,10 @@ pub fn Any<F: Guard + 'static>(guard: F) -> AnyGuard {
 ///
 /// That is, only one contained guard needs to match in order for the aggregate guard to match.
 ///
+/// Guards are checked in the order they were added and checking stops at the first one that
+/// matches, so guards added after it are never evaluated. Like all guards, they only have access
+/// to the request head and extensions; the request payload is never read.
+///
 /// Construct an `AnyGuard` using [`Any`].
 pub struct AnyGuard {
     guards: Vec<Box<dyn Guard>>,
@@ -237,6 +243,10 @@ pub fn All<F: Guard + 'static>(guard: F) -> AllGuard {
 ///
 /// That is, **all** contained guard needs to match in order for the aggregate guard to match.
 ///
+/// Guards are checked in the order they were added and checking stops at the first one that does
+/// not match, so guards added after it are never evaluated. Like all guards, they only have access
+/// to the request head and extensions; the request payload is never read.
+///
 /// Construct an `AllGuard` using [`All`].
 pub struct AllGuard {
     guards: Vec<Box<dyn Guard>>,
@@ -342,7 +352,11 @@ method_guard!(Connect, CONNECT);
 method_guard!(Patch, PATCH);
 method_guard!(Trace, TRACE);
 
-/// Creates a guard that matches if request contains given header name and value.
+/// Creates a guard that matches if request contains given header name and a matching value.
+///
+/// The value can either be a string, which must be equal to the (first) header value, or a
+/// predicate closure that is checked against each value of a multi-valued header. See
+/// [`HeaderMatcher`] for details. Requests without the header never match.
 ///
 /// # Examples
 /// The handler below will be called when the request contains an `x-guarded` header with value
@@ -354,23 +368,54 @@ method_guard!(Trace, TRACE);
 ///     .guard(guard::Header("x-guarded", "secret"))
 ///     .to(|| HttpResponse::Ok());
 /// ```
+///
+/// Predicates allow matching on parts of the header value, such as a version prefix.
+/// ```
+/// use actix_web::{guard, web, HttpResponse};
+///
+/// web::route()
+///     .guard(guard::Header("x-api-version", |v: &str| v.starts_with("2.")))
+///     .to(|| HttpResponse::Ok());
+/// ```
 #[allow(non_snake_case)]
-pub fn Header(name: &'static str, value: &'static str) -> impl Guard {
-    HeaderGuard(
-        header::HeaderName::try_from(name).unwrap(),
-        header::HeaderValue::from_static(value),
-    )
+pub fn Header<M: HeaderMatcher>(name: &'static str, value: M) -> impl Guard {
+    HeaderGuard(header::HeaderName::try_from(name).unwrap(), value)
 }
 
-struct HeaderGuard(header::HeaderName, header::HeaderValue);
+/// Header value matcher used by the [`Header`] guard.
+///
+/// Implemented for:
+/// - `&'static str`: matches if the first value of the header is equal to the string.
+/// - `Fn(&str) -> bool`: matches if any value of the header is a valid string that satisfies the
+///   predicate.
+pub trait HeaderMatcher: 'static {
+    /// Returns true if the values of header `name` in `headers` are a match.
+    fn matches(&self, name: &header::HeaderName, headers: &header::HeaderMap) -> bool;
+}
 
-impl Guard for HeaderGuard {
-    fn check(&self, ctx: &GuardContext<'_>) -> bool {
-        if let Some(val) = ctx.head().headers.get(&self.0) {
-            return val == self.1;
-        }
+impl HeaderMatcher for &'static str {
+    fn matches(&self, name: &header::HeaderName, headers: &header::HeaderMap) -> bool {
+        headers.get(name).map_or(false, |val| val == *self)
+    }
+}
 
-        false
+impl<F> HeaderMatcher for F
+where
+    F: Fn(&str) -> bool + 'static,
+{
+    fn matches(&self, name: &header::HeaderName, headers: &header::HeaderMap) -> bool {
+        headers
+            .get_all(name)
+            .filter_map(|val| val.to_str().ok())
+            .any(self)
+    }
+}
+
+struct HeaderGuard<M>(header::HeaderName, M);
+
+impl<M: HeaderMatcher> Guard for HeaderGuard<M> {
+    fn check(&self, ctx: &GuardContext<'_>) -> bool {
+        self.1.matches(&self.0, &ctx.head().headers)
     }
 }
 
@@ -400,6 +445,24 @@ mod tests {
         assert!(!hdr.check(&req.guard_ctx()));
     }
 
+    #[test]
+    fn header_predicate() {
+        let req = TestRequest::default()
+            .append_header(("x-api-version", "1.4"))
+            .append_header(("x-api-version", "2.1"))
+            .to_srv_request();
+
+        let hdr = Header("x-api-version", |v: &str| v.starts_with("2."));
+        assert!(hdr.check(&req.guard_ctx()));
+
+        let hdr = Header("x-api-version", |v: &str| v.starts_with("3."));
+        assert!(!hdr.check(&req.guard_ctx()));
+
+        let req = TestRequest::default().to_srv_request();
+        let hdr = Header("x-api-version", |_: &str| true);
+        assert!(!hdr.check(&req.guard_ctx()));
+    }
+
     #[test]
     fn method_guards() {
         let get_req = TestRequest::get().to_srv_request();
@@ -468,6 +531,58 @@ mod tests {
         assert!(!All(Trace()).and(Get()).check(&req.guard_ctx()));
     }
 
+    fn unreachable_guard() -> impl Guard {
+        fn_guard(|_| panic!("guard should not be evaluated"))
+    }
+
+    #[test]
+    fn aggregate_any_short_circuit() {
+        let req = TestRequest::default().to_srv_request();
+
+        assert!(Any(Get()).or(unreachable_guard()).check(&req.guard_ctx()));
+        assert!(Any(Post())
+            .or(Get())
+            .or(unreachable_guard())
+            .check(&req.guard_ctx()));
+    }
+
+    #[test]
+    fn aggregate_all_short_circuit() {
+        let req = TestRequest::default().to_srv_request();
+
+        assert!(!All(Post()).and(unreachable_guard()).check(&req.guard_ctx()));
+        assert!(!All(Get())
+            .and(Post())
+            .and(unreachable_guard())
+            .check(&req.guard_ctx()));
+    }
+
+    #[actix_rt::test]
+    async fn aggregate_payload_untouched() {
+        use crate::{
+            test::{call_service, init_service, read_body},
+            web, App,
+        };
+
+        let srv = init_service(
+            App::new().route(
+                "/",
+                web::post()
+                    .guard(Any(Get()).or(Header("content-type", "text/plain")))
+                    .guard(All(Post()).and(Not(Put())))
+                    .to(|body: String| async move { body }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .insert_header(("content-type", "text/plain"))
+            .set_payload("payload")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "payload");
+    }
+
     #[test]
     fn nested_not() {
         let req = TestRequest::default().to_srv_request();
@@ -3,6 +3,7 @@ use std::future::Future;
 use actix_service::{boxed, fn_service};
 
 use crate::{
+    response::sniff_content_type,
     service::{BoxedHttpServiceFactory, ServiceRequest, ServiceResponse},
     FromRequest, HttpResponse, Responder,
 };
@@ -108,6 +109,8 @@ where
                     .map_into_boxed_body(),
             };
 
+            let res = sniff_content_type(res).await;
+
             Ok(ServiceResponse::new(req, res))
         }
     }))
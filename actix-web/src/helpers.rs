@@ -1,5 +1,6 @@
 use std::io;
 
+use actix_http::header::{self, HeaderMap, HeaderName, HeaderValue};
 use bytes::BufMut;
 
 /// An `io::Write`r that only requires mutable reference and assumes that there is space available
@@ -23,3 +24,19 @@ where
         Ok(())
     }
 }
+
+/// Adds `name` to the `Vary` header of `headers` unless it is already listed, or `*` is.
+///
+/// Names are appended as separate header values so that values set before are kept as is.
+pub(crate) fn append_vary(headers: &mut HeaderMap, name: &HeaderName) {
+    let listed = headers
+        .get_all(header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .map(str::trim)
+        .any(|val| val == "*" || val.eq_ignore_ascii_case(name.as_str()));
+
+    if !listed {
+        headers.append(header::VARY, HeaderValue::from_str(name.as_str()).unwrap());
+    }
+}
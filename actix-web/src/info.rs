@@ -1,7 +1,11 @@
-use std::{convert::Infallible, net::SocketAddr};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+};
 
 use actix_utils::future::{err, ok, Ready};
 use derive_more::{Display, Error};
+use ipnet::IpNet;
 
 use crate::{
     dev::{AppConfig, Payload, RequestHead},
@@ -28,6 +32,21 @@ fn first_header_value<'a>(req: &'a RequestHead, name: &'_ HeaderName) -> Option<
     Some(val)
 }
 
+/// Parses the IP address of a forwarding hop, which may include a port.
+///
+/// IPv6 addresses may be enclosed in brackets, e.g. `[2001:db8::1]`, with or without a port.
+/// Returns `None` for obfuscated identifiers and `unknown` hops.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    // bracketed IPv6 address without a port, as in `for="[2001:db8::1]"`
+    if let Some(ip) = hop.strip_prefix('[').and_then(|hop| hop.strip_suffix(']')) {
+        return ip.parse::<Ipv6Addr>().ok().map(IpAddr::V6);
+    }
+
+    hop.parse::<IpAddr>()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
 /// HTTP connection information.
 ///
 /// `ConnectionInfo` implements `FromRequest` and can be extracted in handlers.
@@ -65,6 +84,12 @@ pub struct ConnectionInfo {
     scheme: String,
     peer_addr: Option<String>,
     realip_remote_addr: Option<String>,
+    /// Forwarding hops of the `Forwarded` header, from the client to the last proxy.
+    forwarded_for: Vec<String>,
+    /// Forwarding hops of the `X-Forwarded-For` header, from the client to the last proxy.
+    x_forwarded_for: Vec<String>,
+    #[cfg(unix)]
+    peer_credentials: Option<PeerCredentials>,
 }
 
 impl ConnectionInfo {
@@ -72,6 +97,7 @@ impl ConnectionInfo {
         let mut host = None;
         let mut scheme = None;
         let mut realip_remote_addr = None;
+        let mut forwarded_for = Vec::new();
 
         for (name, val) in req
             .headers
@@ -100,7 +126,10 @@ impl ConnectionInfo {
             // --- https://datatracker.ietf.org/doc/html/rfc7239#section-5.2
 
             match name.trim().to_lowercase().as_str() {
-                "for" => realip_remote_addr.get_or_insert_with(|| unquote(val)),
+                "for" => {
+                    forwarded_for.push(unquote(val).to_owned());
+                    realip_remote_addr.get_or_insert_with(|| unquote(val))
+                }
                 "proto" => scheme.get_or_insert_with(|| unquote(val)),
                 "host" => host.get_or_insert_with(|| unquote(val)),
                 "by" => {
@@ -129,6 +158,14 @@ impl ConnectionInfo {
             .or_else(|| first_header_value(req, &X_FORWARDED_FOR))
             .map(str::to_owned);
 
+        let x_forwarded_for = req
+            .headers
+            .get_all(&X_FORWARDED_FOR)
+            .filter_map(|hdr| hdr.to_str().ok())
+            .flat_map(|val| val.split(','))
+            .map(|hop| hop.trim().to_owned())
+            .collect();
+
         let peer_addr = req.peer_addr.map(|addr| addr.ip().to_string());
 
         ConnectionInfo {
@@ -136,7 +173,63 @@ impl ConnectionInfo {
             scheme,
             peer_addr,
             realip_remote_addr,
+            forwarded_for,
+            x_forwarded_for,
+            #[cfg(unix)]
+            peer_credentials: None,
+        }
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn with_peer_credentials(mut self, cred: Option<PeerCredentials>) -> Self {
+        self.peer_credentials = cred;
+        self
+    }
+
+    /// IP address of the client that initiated the request, as seen by the first untrusted hop.
+    ///
+    /// Starting with the peer address of the connection, the forwarding chain of the `header`
+    /// the trusted proxies append to is walked backwards for as long as the hops are within one
+    /// of the `trusted` networks, i.e., are known proxies. The first hop that is not trusted is
+    /// returned. If all hops are trusted, the one furthest from the server is returned.
+    ///
+    /// Unlike [`realip_remote_addr`](Self::realip_remote_addr), this cannot be spoofed by clients
+    /// as long as all trusted proxies append to `header`, since entries before the first
+    /// untrusted hop are never looked at. The other forwarding header is ignored, as it is passed
+    /// through from the client unchanged. Returns `None` if the peer address is unknown or a hop
+    /// that has to be looked at is not an IP address, e.g. because it is obfuscated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_web::{dev::{ConnectionInfo, ForwardingHeader}, HttpResponse};
+    /// async fn handler(conn: ConnectionInfo) -> HttpResponse {
+    ///     // proxies in this network append to `X-Forwarded-For`, like nginx does by default
+    ///     let proxies = ["10.0.0.0/8".parse().unwrap()];
+    ///
+    ///     match conn.client_ip(ForwardingHeader::XForwardedFor, &proxies) {
+    ///         Some(ip) => HttpResponse::Ok().body(ip.to_string()),
+    ///         None => HttpResponse::BadRequest().finish(),
+    ///     }
+    /// }
+    /// # let _svc = actix_web::web::to(handler);
+    /// ```
+    pub fn client_ip(&self, header: ForwardingHeader, trusted: &[IpNet]) -> Option<IpAddr> {
+        let mut ip = self.peer_addr.as_deref()?.parse::<IpAddr>().ok()?;
+
+        let hops = match header {
+            ForwardingHeader::Forwarded => &self.forwarded_for,
+            ForwardingHeader::XForwardedFor => &self.x_forwarded_for,
+        };
+
+        for hop in hops.iter().rev() {
+            if !trusted.iter().any(|net| net.contains(&ip)) {
+                break;
+            }
+
+            ip = parse_hop(hop)?;
         }
+
+        Some(ip)
     }
 
     /// Real IP (remote address) of client that initiated request.
@@ -189,6 +282,31 @@ impl ConnectionInfo {
         &self.scheme
     }
 
+    /// Returns the credentials of the peer process, for connections accepted on a Unix domain
+    /// socket.
+    ///
+    /// Credentials are obtained from the operating system when the connection is accepted, so,
+    /// unlike forwarding headers, they cannot be spoofed by the client and can be used for
+    /// authorizing local processes. Returns `None` for other connections.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{dev::ConnectionInfo, HttpResponse};
+    ///
+    /// async fn handler(conn: ConnectionInfo) -> HttpResponse {
+    ///     match conn.peer_credentials() {
+    ///         Some(cred) if cred.uid() == 0 => HttpResponse::Ok().body("hello, root"),
+    ///         _ => HttpResponse::Forbidden().finish(),
+    ///     }
+    /// }
+    /// # let _svc = actix_web::web::to(handler);
+    /// ```
+    #[cfg(unix)]
+    #[inline]
+    pub fn peer_credentials(&self) -> Option<PeerCredentials> {
+        self.peer_credentials
+    }
+
     #[doc(hidden)]
     #[deprecated(since = "4.0.0", note = "Renamed to `peer_addr`.")]
     pub fn remote_addr(&self) -> Option<&str> {
@@ -205,6 +323,51 @@ impl FromRequest for ConnectionInfo {
     }
 }
 
+/// Header that trusted proxies record forwarding hops in.
+///
+/// See [`ConnectionInfo::client_ip`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ForwardingHeader {
+    /// The `Forwarded` header of [RFC 7239](https://datatracker.ietf.org/doc/html/rfc7239).
+    Forwarded,
+
+    /// The `X-Forwarded-For` header.
+    XForwardedFor,
+}
+
+/// Credentials of the process on the other end of a Unix domain socket connection.
+///
+/// See [`ConnectionInfo::peer_credentials`].
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PeerCredentials {
+    uid: u32,
+    gid: u32,
+    pid: Option<i32>,
+}
+
+#[cfg(unix)]
+impl PeerCredentials {
+    pub(crate) fn new(uid: u32, gid: u32, pid: Option<i32>) -> Self {
+        Self { uid, gid, pid }
+    }
+
+    /// Returns the user ID of the peer process.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the group ID of the peer process.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the process ID of the peer process, if the platform provides it.
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+}
+
 /// Extractor for peer's socket address.
 ///
 /// Also see [`HttpRequest::peer_addr`] and [`ConnectionInfo::peer_addr`].
@@ -390,6 +553,124 @@ mod tests {
         assert_eq!(info.realip_remote_addr(), Some("192.0.2.60"));
     }
 
+    #[test]
+    fn client_ip_x_forwarded_for() {
+        use ForwardingHeader::XForwardedFor;
+
+        let proxies = [
+            "10.0.0.0/8".parse().unwrap(),
+            "192.0.2.1/32".parse().unwrap(),
+        ];
+
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:8080".parse().unwrap())
+            .insert_header((X_FORWARDED_FOR, "198.51.100.7, 203.0.113.9, 192.0.2.1"))
+            .append_header((X_FORWARDED_FOR, "10.1.2.3"))
+            .to_http_request();
+        let info = req.connection_info();
+
+        // walks the trusted proxies back to the first untrusted hop; earlier hops may be spoofed
+        let ip = info.client_ip(XForwardedFor, &proxies).unwrap();
+        assert_eq!(ip, "203.0.113.9".parse::<IpAddr>().unwrap());
+
+        // without trusted proxies, only the peer address is used
+        let ip = info.client_ip(XForwardedFor, &[]).unwrap();
+        assert_eq!(ip, "10.0.0.2".parse::<IpAddr>().unwrap());
+
+        // all hops are trusted
+        let ip = info
+            .client_ip(XForwardedFor, &["0.0.0.0/0".parse().unwrap()])
+            .unwrap();
+        assert_eq!(ip, "198.51.100.7".parse::<IpAddr>().unwrap());
+
+        // an unparsable hop cannot be trusted
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:8080".parse().unwrap())
+            .insert_header((X_FORWARDED_FOR, "198.51.100.7, unknown"))
+            .to_http_request();
+        assert_eq!(
+            req.connection_info().client_ip(XForwardedFor, &proxies),
+            None
+        );
+
+        assert_eq!(
+            TestRequest::default()
+                .to_http_request()
+                .connection_info()
+                .client_ip(XForwardedFor, &proxies),
+            None
+        );
+    }
+
+    #[test]
+    fn client_ip_ignores_client_sent_forwarded() {
+        let proxies = ["10.0.0.0/8".parse().unwrap()];
+
+        // the proxy appends to `X-Forwarded-For` and passes the client's `Forwarded` header on
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:8080".parse().unwrap())
+            .insert_header((header::FORWARDED, "for=6.6.6.6"))
+            .insert_header((X_FORWARDED_FOR, "203.0.113.9"))
+            .to_http_request();
+        let info = req.connection_info();
+
+        let ip = info
+            .client_ip(ForwardingHeader::XForwardedFor, &proxies)
+            .unwrap();
+        assert_eq!(ip, "203.0.113.9".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn client_ip_forwarded() {
+        let proxies = ["2001:db8::/32".parse().unwrap()];
+
+        let req = TestRequest::default()
+            .peer_addr("[2001:db8::1]:8080".parse().unwrap())
+            .insert_header((
+                header::FORWARDED,
+                r#"for=192.0.2.60;proto=https, for="[2001:db8:cafe::17]:4711""#,
+            ))
+            // passed through from the client
+            .insert_header((X_FORWARDED_FOR, "198.51.100.7"))
+            .to_http_request();
+        let info = req.connection_info();
+
+        let ip = info
+            .client_ip(ForwardingHeader::Forwarded, &proxies)
+            .unwrap();
+        assert_eq!(ip, "192.0.2.60".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn client_ip_forwarded_bracketed_ipv6() {
+        let proxies = ["2001:db8::/32".parse().unwrap()];
+
+        let req = TestRequest::default()
+            .peer_addr("[2001:db8::1]:8080".parse().unwrap())
+            .insert_header((header::FORWARDED, r#"for=192.0.2.60, for="[2001:db8::17]""#))
+            .to_http_request();
+        let info = req.connection_info();
+
+        let ip = info
+            .client_ip(ForwardingHeader::Forwarded, &proxies)
+            .unwrap();
+        assert_eq!(ip, "192.0.2.60".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn hops() {
+        let ip = |ip: &str| Some(ip.parse::<IpAddr>().unwrap());
+
+        assert_eq!(parse_hop("192.0.2.60"), ip("192.0.2.60"));
+        assert_eq!(parse_hop("192.0.2.60:4711"), ip("192.0.2.60"));
+        assert_eq!(parse_hop("2001:db8::1"), ip("2001:db8::1"));
+        assert_eq!(parse_hop("[2001:db8::1]"), ip("2001:db8::1"));
+        assert_eq!(parse_hop("[2001:db8::1]:4711"), ip("2001:db8::1"));
+        assert_eq!(parse_hop("[192.0.2.60]"), None);
+        assert_eq!(parse_hop("unknown"), None);
+        assert_eq!(parse_hop("_hidden"), None);
+    }
+
     #[test]
     fn scheme_from_uri() {
         let req = TestRequest::get()
@@ -95,6 +95,7 @@ mod response;
 mod rmap;
 mod route;
 pub mod rt;
+mod schema;
 mod scope;
 mod server;
 mod service;
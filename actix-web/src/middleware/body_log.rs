@@ -0,0 +1,355 @@
+//! For middleware documentation, see [`BodyLog`].
+
+use std::{
+    borrow::Cow,
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::{error::PayloadError, BoxedPayloadStream};
+use bytes::{Bytes, BytesMut};
+use futures_core::{ready, Stream};
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BodySize, MessageBody},
+    dev::{Payload, Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// Middleware for logging request and response bodies, for debugging.
+///
+/// Bodies are logged at the `debug` level once they have been read or sent completely, or when
+/// they are dropped before that. Only the first [`limit`](Self::limit) bytes of each body are
+/// kept for logging; the rest is passed through without being buffered, so streaming bodies keep
+/// streaming. Request bodies are logged as far as the application reads them.
+///
+/// Since bodies can contain sensitive data, this middleware is best wrapped around specific
+/// routes or resources rather than the whole app.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::BodyLog, web, App};
+///
+/// let app = App::new().service(
+///     web::resource("/webhook")
+///         .wrap(BodyLog::new().limit(4096))
+///         .route(web::post().to(|body: String| async move { body })),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct BodyLog(Rc<Inner>);
+
+#[derive(Debug)]
+struct Inner {
+    limit: usize,
+    log_target: Cow<'static, str>,
+}
+
+impl BodyLog {
+    /// Constructs a new `BodyLog` middleware that logs up to 1024 bytes of each body.
+    pub fn new() -> Self {
+        Self(Rc::new(Inner {
+            limit: 1024,
+            log_target: Cow::Borrowed(module_path!()),
+        }))
+    }
+
+    /// Sets the maximum number of bytes logged of each body.
+    pub fn limit(mut self, limit: usize) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().limit = limit;
+        self
+    }
+
+    /// Sets the logging target to `target`.
+    ///
+    /// By default, the log target is `module_path!()` of the log call location, i.e.,
+    /// `actix_web::middleware::body_log`.
+    pub fn log_target(mut self, target: impl Into<Cow<'static, str>>) -> Self {
+        Rc::get_mut(&mut self.0).unwrap().log_target = target.into();
+        self
+    }
+}
+
+impl Default for BodyLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BodyLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<LoggedBody<B>>;
+    type Error = Error;
+    type Transform = BodyLogMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyLogMiddleware {
+            service,
+            inner: Rc::clone(&self.0),
+        }))
+    }
+}
+
+pub struct BodyLogMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<LoggedBody<B>>;
+    type Error = Error;
+    type Future = BodyLogFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let request_line: Rc<str> = format!("{} {}", req.method(), req.path()).into();
+
+        if req.has_payload() {
+            let (_, payload) = req.parts_mut();
+
+            let stream = LoggedPayload {
+                payload: payload.take(),
+                log: BodyLogger::new("request", &request_line, &self.inner),
+            };
+
+            req.set_payload(Payload::from(Box::pin(stream) as BoxedPayloadStream));
+        }
+
+        BodyLogFuture {
+            fut: self.service.call(req),
+            request_line,
+            inner: Rc::clone(&self.inner),
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    pub struct BodyLogFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        request_line: Rc<str>,
+        inner: Rc<Inner>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for BodyLogFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<LoggedBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let log = BodyLogger::new("response", this.request_line, this.inner);
+
+        Poll::Ready(Ok(res.map_body(move |_, body| LoggedBody { body, log })))
+    }
+}
+
+/// Keeps the beginning of a body and logs it once the body ends or is dropped.
+struct BodyLogger {
+    kind: &'static str,
+    request_line: Rc<str>,
+    inner: Rc<Inner>,
+    buf: BytesMut,
+    size: u64,
+    logged: bool,
+}
+
+impl BodyLogger {
+    fn new(kind: &'static str, request_line: &Rc<str>, inner: &Rc<Inner>) -> Self {
+        Self {
+            kind,
+            request_line: Rc::clone(request_line),
+            inner: Rc::clone(inner),
+            buf: BytesMut::new(),
+            size: 0,
+            logged: false,
+        }
+    }
+
+    fn record(&mut self, chunk: &[u8]) {
+        let n = chunk.len().min(self.inner.limit - self.buf.len());
+        self.buf.extend_from_slice(&chunk[..n]);
+        self.size += chunk.len() as u64;
+    }
+
+    fn log(&mut self) {
+        if self.logged {
+            return;
+        }
+        self.logged = true;
+
+        let truncated = if self.buf.len() as u64 == self.size {
+            ""
+        } else {
+            ", truncated"
+        };
+
+        log::debug!(
+            target: self.inner.log_target.as_ref(),
+            "{} body of {} ({} bytes{}): {:?}",
+            self.kind,
+            self.request_line,
+            self.size,
+            truncated,
+            String::from_utf8_lossy(&self.buf)
+        );
+    }
+}
+
+impl Drop for BodyLogger {
+    fn drop(&mut self) {
+        self.log();
+    }
+}
+
+struct LoggedPayload {
+    payload: Payload,
+    log: BodyLogger,
+}
+
+impl Stream for LoggedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.payload).poll_next(cx));
+
+        match item {
+            Some(Ok(ref chunk)) => self.log.record(chunk),
+            None => self.log.log(),
+            Some(Err(_)) => {}
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+pin_project! {
+    /// Response body that is logged by the [`BodyLog`] middleware.
+    pub struct LoggedBody<B> {
+        #[pin]
+        body: B,
+        log: BodyLogger,
+    }
+}
+
+impl<B: MessageBody> MessageBody for LoggedBody<B> {
+    type Error = B::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        let item = ready!(this.body.poll_next(cx));
+
+        match item {
+            Some(Ok(ref chunk)) => this.log.record(chunk),
+            None => this.log.log(),
+            Some(Err(_)) => {}
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, sync::Once};
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+
+    const LOG_TARGET: &str = "body_log_test";
+
+    thread_local! {
+        static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Captures log records with the test target on the thread they are logged on.
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            metadata.target() == LOG_TARGET
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            if self.enabled(record.metadata()) {
+                LOGS.with(|logs| logs.borrow_mut().push(record.args().to_string()));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn init_logger() {
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            log::set_logger(&TestLogger).unwrap();
+            log::set_max_level(log::LevelFilter::Debug);
+        });
+    }
+
+    #[actix_rt::test]
+    async fn logs_truncated_bodies() {
+        init_logger();
+
+        let srv = init_service(
+            App::new()
+                .wrap(BodyLog::new().limit(5).log_target(LOG_TARGET))
+                .route(
+                    "/",
+                    web::post().to(|body: String| async move { body.to_uppercase() }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload("hello world")
+            .to_request();
+        let res = call_service(&srv, req).await;
+
+        // the handler and client see the full bodies
+        assert_eq!(read_body(res).await, "HELLO WORLD");
+
+        assert_eq!(
+            LOGS.with(|logs| logs.take()),
+            [
+                r#"request body of POST / (11 bytes, truncated): "hello""#,
+                r#"response body of POST / (11 bytes, truncated): "HELLO""#,
+            ]
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! For middleware documentation, see [`ByteCount`].
+
+use std::{
+    cell::Cell,
+    fmt,
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::{error::PayloadError, BoxedPayloadStream};
+use bytes::Bytes;
+use futures_core::{ready, Stream};
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BodySize, MessageBody},
+    dev::{Payload, Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _,
+};
+
+type OnComplete = Rc<dyn Fn(&ByteCounts)>;
+
+/// Middleware for counting the request and response body bytes of each request.
+///
+/// A [`ByteCounts`] handle is inserted into the request extensions, from where it can be cloned by
+/// handlers and other middleware. The counts are final once the response body has been dropped,
+/// at which point the [`on_complete`](Self::on_complete) callback, if any, is called.
+///
+/// Counts are taken at the position of this middleware in the service stack. Only request body
+/// bytes that are actually read by the application are counted. To measure compressed rather than
+/// uncompressed response sizes, register this middleware after (i.e., outside of) [`Compress`].
+///
+/// [`Compress`]: crate::middleware::Compress
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::ByteCount, App};
+///
+/// let app = App::new().wrap(ByteCount::new().on_complete(|counts| {
+///     if counts.is_complete() {
+///         log::info!("in: {}B, out: {}B", counts.ingress(), counts.egress());
+///     } else {
+///         log::warn!("response aborted after {}B", counts.egress());
+///     }
+/// }));
+/// ```
+#[derive(Clone, Default)]
+pub struct ByteCount {
+    on_complete: Option<OnComplete>,
+}
+
+impl ByteCount {
+    /// Constructs a new `ByteCount` middleware.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a callback that is called with the final counts once the response body is dropped.
+    ///
+    /// The callback is also called when sending the response body was aborted, for example because
+    /// the client disconnected or the body stream failed. Use [`ByteCounts::is_complete()`] to
+    /// tell the two cases apart.
+    pub fn on_complete<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&ByteCounts) + 'static,
+    {
+        self.on_complete = Some(Rc::new(f));
+        self
+    }
+}
+
+impl fmt::Debug for ByteCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteCount")
+            .field("on_complete", &self.on_complete.is_some())
+            .finish()
+    }
+}
+
+/// Body byte counts of a request, shared with the [`ByteCount`] middleware.
+#[derive(Debug, Clone, Default)]
+pub struct ByteCounts {
+    inner: Rc<Counts>,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    ingress: Cell<u64>,
+    egress: Cell<u64>,
+    complete: Cell<bool>,
+}
+
+impl ByteCounts {
+    /// Returns the number of request body bytes read so far.
+    pub fn ingress(&self) -> u64 {
+        self.inner.ingress.get()
+    }
+
+    /// Returns the number of response body bytes sent so far.
+    pub fn egress(&self) -> u64 {
+        self.inner.egress.get()
+    }
+
+    /// Returns true if the response body has been sent in full.
+    pub fn is_complete(&self) -> bool {
+        self.inner.complete.get()
+    }
+
+    fn add_ingress(&self, n: usize) {
+        self.inner.ingress.set(self.ingress() + n as u64);
+    }
+
+    fn add_egress(&self, n: usize) {
+        self.inner.egress.set(self.egress() + n as u64);
+    }
+
+    fn set_complete(&self) {
+        self.inner.complete.set(true);
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ByteCount
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<CountedBody<B>>;
+    type Error = Error;
+    type Transform = ByteCountMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ByteCountMiddleware {
+            service,
+            on_complete: self.on_complete.clone(),
+        }))
+    }
+}
+
+pub struct ByteCountMiddleware<S> {
+    service: S,
+    on_complete: Option<OnComplete>,
+}
+
+impl<S, B> Service<ServiceRequest> for ByteCountMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<CountedBody<B>>;
+    type Error = Error;
+    type Future = ByteCountFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let counts = ByteCounts::default();
+
+        if req.has_payload() {
+            let (_, payload) = req.parts_mut();
+
+            let stream = CountedPayload {
+                payload: payload.take(),
+                counts: counts.clone(),
+            };
+
+            req.set_payload(Payload::from(Box::pin(stream) as BoxedPayloadStream));
+        }
+
+        req.extensions_mut().insert(counts.clone());
+
+        ByteCountFuture {
+            fut: self.service.call(req),
+            counts: Some(counts),
+            on_complete: self.on_complete.clone(),
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    pub struct ByteCountFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        counts: Option<ByteCounts>,
+        on_complete: Option<OnComplete>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for ByteCountFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<CountedBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let counts = this.counts.take().unwrap();
+        let on_complete = this.on_complete.take();
+
+        Poll::Ready(Ok(res.map_body(move |_, body| {
+            // empty bodies are not polled by the dispatcher
+            if matches!(body.size(), BodySize::None | BodySize::Sized(0)) {
+                counts.set_complete();
+            }
+
+            CountedBody {
+                body,
+                counts,
+                on_complete,
+            }
+        })))
+    }
+}
+
+struct CountedPayload {
+    payload: Payload,
+    counts: ByteCounts,
+}
+
+impl Stream for CountedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = ready!(Pin::new(&mut self.payload).poll_next(cx));
+
+        if let Some(Ok(ref chunk)) = item {
+            self.counts.add_ingress(chunk.len());
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+pin_project! {
+    /// Response body that counts bytes sent for the [`ByteCount`] middleware.
+    pub struct CountedBody<B> {
+        #[pin]
+        body: B,
+        counts: ByteCounts,
+        on_complete: Option<OnComplete>,
+    }
+
+    impl<B> PinnedDrop for CountedBody<B> {
+        fn drop(this: Pin<&mut Self>) {
+            if let Some(ref on_complete) = this.on_complete {
+                on_complete(&this.counts);
+            }
+        }
+    }
+}
+
+impl<B: MessageBody> MessageBody for CountedBody<B> {
+    type Error = B::Error;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        let item = ready!(this.body.poll_next(cx));
+
+        match item {
+            Some(Ok(ref chunk)) => this.counts.add_egress(chunk.len()),
+            None => this.counts.set_complete(),
+            Some(Err(_)) => {}
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn counts_request_and_response_bytes() {
+        let completed = Rc::new(RefCell::new(Vec::new()));
+
+        let srv = init_service(
+            App::new()
+                .wrap(ByteCount::new().on_complete({
+                    let completed = Rc::clone(&completed);
+                    move |counts| {
+                        completed.borrow_mut().push((
+                            counts.ingress(),
+                            counts.egress(),
+                            counts.is_complete(),
+                        ))
+                    }
+                }))
+                .route(
+                    "/",
+                    web::post().to(|body: web::Bytes| async move { body.repeat(2) }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload("hello")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        let counts = res
+            .request()
+            .extensions()
+            .get::<ByteCounts>()
+            .cloned()
+            .unwrap();
+        assert_eq!(counts.ingress(), 5);
+        assert_eq!(counts.egress(), 0);
+
+        assert!(!counts.is_complete());
+
+        let body = read_body(res).await;
+        assert_eq!(body, "hellohello");
+        assert_eq!(counts.egress(), 10);
+        assert_eq!(*completed.borrow(), [(5, 10, true)]);
+
+        // response body dropped before being sent
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload("hello")
+            .to_request();
+        drop(call_service(&srv, req).await);
+        assert_eq!(completed.borrow()[1], (5, 0, false));
+    }
+
+    #[actix_rt::test]
+    async fn request_without_payload() {
+        let srv = init_service(
+            App::new()
+                .wrap(ByteCount::new())
+                .default_service(web::to(|| async { "hi" })),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        let counts = res
+            .request()
+            .extensions()
+            .get::<ByteCounts>()
+            .cloned()
+            .unwrap();
+        read_body(res).await;
+
+        assert_eq!(counts.ingress(), 0);
+        assert_eq!(counts.egress(), 2);
+        assert!(counts.is_complete());
+    }
+
+    #[actix_rt::test]
+    async fn empty_response_is_complete() {
+        let srv = init_service(
+            App::new()
+                .wrap(ByteCount::new())
+                .default_service(web::to(HttpResponse::NoContent)),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        let counts = res
+            .request()
+            .extensions()
+            .get::<ByteCounts>()
+            .cloned()
+            .unwrap();
+
+        assert_eq!(counts.egress(), 0);
+        assert!(counts.is_complete());
+    }
+}
@@ -0,0 +1,608 @@
+//! For middleware documentation, see [`ResponseCache`].
+
+use std::{
+    cell::RefCell,
+    cmp,
+    collections::HashMap,
+    convert::TryFrom as _,
+    future::{ready, Ready},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use actix_http::{
+    body::BodySize,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method, StatusCode,
+};
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::{self, EitherBody, MessageBody},
+    dev::{Service, Transform},
+    error::ErrorInternalServerError,
+    http::header::{self, CacheDirective},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+/// Configuration for the [`ResponseCache`] middleware.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    capacity: usize,
+    ttl: Duration,
+    max_body_size: usize,
+}
+
+impl CacheConfig {
+    /// Sets the maximum number of responses held by the cache.
+    ///
+    /// When the cache is full, the least recently used response is evicted to make room for a new
+    /// one. By default, up to 256 responses are cached.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets how long a response is served from the cache after it was stored.
+    ///
+    /// A shorter lifetime given by the response's `s-maxage` or `max-age` cache directive takes
+    /// precedence. By default, responses are cached for 60 seconds.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the largest response body size, in bytes, that will be cached.
+    ///
+    /// Responses with larger or streaming bodies are passed through untouched. By default this
+    /// limit is 64kB.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            ttl: Duration::from_secs(60),
+            max_body_size: 65_536,
+        }
+    }
+}
+
+/// Middleware for caching responses to `GET` requests in memory.
+///
+/// Responses are keyed by request method, path and query, and the values of any request headers
+/// named by the response's `Vary` header, so that each variant is cached separately. Cached
+/// responses are served with an `Age` header indicating how many seconds ago they were stored,
+/// alongside the headers of the original response, including `Cache-Control`.
+///
+/// Requests with a `Cookie` header are passed through without using the cache, since their
+/// responses are likely personalized. A response is only cached if all of the following hold:
+/// - its request has no `Authorization` header, unless the response's `Cache-Control` header
+///   contains the `public` or `s-maxage` directives, as required of shared caches;
+/// - its status is `200 OK`;
+/// - its body has a known size no larger than [`CacheConfig::max_body_size`];
+/// - it has no `Set-Cookie` header and no `Vary: *` header;
+/// - its `Cache-Control` header, if any, does not contain the `no-store`, `no-cache` or `private`
+///   directives, nor a zero `max-age` or `s-maxage`.
+///
+/// Each worker thread has its own cache, so the same response may be stored once per worker.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{middleware::{CacheConfig, ResponseCache}, App};
+///
+/// let app = App::new().wrap(ResponseCache::new(
+///     CacheConfig::default()
+///         .capacity(1024)
+///         .ttl(Duration::from_secs(300)),
+/// ));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ResponseCache {
+    config: Rc<CacheConfig>,
+    store: Rc<RefCell<CacheStore>>,
+}
+
+impl ResponseCache {
+    /// Constructs a new `ResponseCache` middleware using the given configuration.
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+            store: Rc::default(),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseCache
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = ResponseCacheMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseCacheMiddleware {
+            service,
+            config: Rc::clone(&self.config),
+            store: Rc::clone(&self.store),
+        }))
+    }
+}
+
+pub struct ResponseCacheMiddleware<S> {
+    service: S,
+    config: Rc<CacheConfig>,
+    store: Rc<RefCell<CacheStore>>,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseCacheMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if *req.method() != Method::GET || req.headers().contains_key(header::COOKIE) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+        }
+
+        let key = CacheKey {
+            method: req.method().clone(),
+            path: req
+                .uri()
+                .path_and_query()
+                .map_or_else(|| req.path().to_owned(), |pq| pq.as_str().to_owned()),
+        };
+
+        let hit = self
+            .store
+            .borrow_mut()
+            .get(&key, req.headers(), Instant::now());
+
+        if let Some((mut res, age)) = hit {
+            res.headers_mut()
+                .insert(header::AGE, HeaderValue::from(age.as_secs()));
+
+            let (req, _) = req.into_parts();
+            return Box::pin(ready(Ok(
+                ServiceResponse::new(req, res).map_into_right_body()
+            )));
+        }
+
+        let config = Rc::clone(&self.config);
+        let store = Rc::clone(&self.store);
+        let authorized = req.headers().contains_key(header::AUTHORIZATION);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let ttl = match cache_ttl(&res, &config, authorized) {
+                Some(ttl) => ttl,
+                None => return Ok(res.map_into_left_body()),
+            };
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+
+            let body = body::to_bytes(body)
+                .await
+                .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+            let vary = res
+                .headers()
+                .get_all(header::VARY)
+                .filter_map(|val| val.to_str().ok())
+                .flat_map(|val| val.split(','))
+                .filter_map(|name| HeaderName::try_from(name.trim()).ok())
+                .map(|name| {
+                    let val = req.headers().get(&name).cloned();
+                    (name, val)
+                })
+                .collect();
+
+            let cached = CachedResponse {
+                status: res.status(),
+                headers: res.headers().clone(),
+                body: body.clone(),
+                vary,
+                stored_at: Instant::now(),
+                ttl,
+                last_used: 0,
+            };
+            store.borrow_mut().insert(key, cached, config.capacity);
+
+            let res = res.set_body(body);
+            Ok(ServiceResponse::new(req, res).map_into_right_body())
+        })
+    }
+}
+
+/// Returns how long `res` may be cached for, or `None` if it must not be cached.
+///
+/// `authorized` is set when the request carried an `Authorization` header.
+fn cache_ttl<B: MessageBody>(
+    res: &ServiceResponse<B>,
+    config: &CacheConfig,
+    authorized: bool,
+) -> Option<Duration> {
+    let size_ok = matches!(
+        res.response().body().size(),
+        BodySize::Sized(len) if len <= config.max_body_size as u64
+    );
+
+    if res.status() != StatusCode::OK
+        || !size_ok
+        || res.headers().contains_key(header::SET_COOKIE)
+    {
+        return None;
+    }
+
+    let vary_any = res
+        .headers()
+        .get_all(header::VARY)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .any(|name| name.trim() == "*");
+
+    if vary_any {
+        return None;
+    }
+
+    let mut ttl = config.ttl;
+    let mut shared_max_age = None;
+    let mut public = false;
+
+    let directives = res
+        .headers()
+        .get_all(header::CACHE_CONTROL)
+        .filter_map(|val| val.to_str().ok())
+        .flat_map(|val| val.split(','))
+        .filter_map(|dir| dir.trim().parse::<CacheDirective>().ok());
+
+    for dir in directives {
+        match dir {
+            CacheDirective::NoStore | CacheDirective::NoCache | CacheDirective::Private => {
+                return None
+            }
+            CacheDirective::MaxAge(secs) => {
+                ttl = cmp::min(ttl, Duration::from_secs(secs.into()));
+            }
+            CacheDirective::SMaxAge(secs) => {
+                shared_max_age = Some(Duration::from_secs(secs.into()));
+            }
+            CacheDirective::Public => public = true,
+            _ => {}
+        }
+    }
+
+    // responses to authorized requests must be explicitly marked as shareable
+    if authorized && !public && shared_max_age.is_none() {
+        return None;
+    }
+
+    // `s-maxage` overrides `max-age` for shared caches
+    if let Some(max_age) = shared_max_age {
+        ttl = cmp::min(config.ttl, max_age);
+    }
+
+    if ttl.is_zero() {
+        None
+    } else {
+        Some(ttl)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    method: Method,
+    path: String,
+}
+
+#[derive(Debug)]
+struct CachedResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    /// Request header values that were named by the response's `Vary` header.
+    vary: Vec<(HeaderName, Option<HeaderValue>)>,
+    stored_at: Instant,
+    ttl: Duration,
+    /// Value of [`CacheStore::tick`] when this entry was last used.
+    last_used: u64,
+}
+
+impl CachedResponse {
+    fn matches(&self, headers: &HeaderMap) -> bool {
+        self.vary
+            .iter()
+            .all(|(name, val)| headers.get(name) == val.as_ref())
+    }
+
+    fn to_response(&self) -> HttpResponse<Bytes> {
+        let mut res = HttpResponse::with_body(self.status, self.body.clone());
+        *res.headers_mut() = self.headers.clone();
+        res
+    }
+}
+
+/// Least recently used cache of responses.
+#[derive(Debug, Default)]
+struct CacheStore {
+    /// Variants of the response to each key, differing in their `Vary` header values.
+    entries: HashMap<CacheKey, Vec<CachedResponse>>,
+    /// Number of variants stored.
+    len: usize,
+    tick: u64,
+}
+
+impl CacheStore {
+    /// Returns the fresh response stored for `key` that matches `headers`, along with its age.
+    fn get(
+        &mut self,
+        key: &CacheKey,
+        headers: &HeaderMap,
+        now: Instant,
+    ) -> Option<(HttpResponse<Bytes>, Duration)> {
+        let variants = self.entries.get_mut(key)?;
+
+        let before = variants.len();
+        variants.retain(|entry| now.saturating_duration_since(entry.stored_at) < entry.ttl);
+        self.len -= before - variants.len();
+
+        if variants.is_empty() {
+            self.entries.remove(key);
+            return None;
+        }
+
+        let entry = variants.iter_mut().find(|entry| entry.matches(headers))?;
+        let age = now.saturating_duration_since(entry.stored_at);
+
+        self.tick += 1;
+        entry.last_used = self.tick;
+
+        Some((entry.to_response(), age))
+    }
+
+    fn insert(&mut self, key: CacheKey, mut entry: CachedResponse, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+
+        self.tick += 1;
+        entry.last_used = self.tick;
+
+        let variants = self.entries.entry(key.clone()).or_default();
+
+        if let Some(variant) = variants
+            .iter_mut()
+            .find(|variant| variant.vary == entry.vary)
+        {
+            *variant = entry;
+            return;
+        }
+
+        variants.push(entry);
+        self.len += 1;
+
+        if self.len > capacity {
+            self.evict_lru();
+        }
+    }
+
+    /// Removes the least recently used variant.
+    fn evict_lru(&mut self) {
+        let lru = self
+            .entries
+            .iter()
+            .flat_map(|(key, variants)| {
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(move |(idx, entry)| (entry.last_used, key, idx))
+            })
+            .min_by_key(|(last_used, _, _)| *last_used)
+            .map(|(_, key, idx)| (key.clone(), idx));
+
+        if let Some((key, idx)) = lru {
+            let variants = self.entries.get_mut(&key).unwrap();
+            variants.remove(idx);
+            self.len -= 1;
+
+            if variants.is_empty() {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    #[actix_rt::test]
+    async fn serves_cached_responses() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(
+            App::new()
+                .wrap(ResponseCache::default())
+                .route(
+                    "/",
+                    web::get().to({
+                        let calls = Rc::clone(&calls);
+                        move || {
+                            calls.set(calls.get() + 1);
+                            HttpResponse::Ok()
+                                .insert_header((header::CACHE_CONTROL, "max-age=120"))
+                                .body(format!("call {}", calls.get()))
+                        }
+                    }),
+                )
+                .route(
+                    "/no-store",
+                    web::get().to({
+                        let calls = Rc::clone(&calls);
+                        move || {
+                            calls.set(calls.get() + 1);
+                            HttpResponse::Ok()
+                                .insert_header((header::CACHE_CONTROL, "no-store"))
+                                .finish()
+                        }
+                    }),
+                ),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::get().uri("/").to_request()).await;
+        assert!(!res.headers().contains_key(header::AGE));
+        assert_eq!(read_body(res).await, "call 1");
+
+        let res = call_service(&srv, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::AGE).unwrap(), "0");
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=120"
+        );
+        assert_eq!(read_body(res).await, "call 1");
+        assert_eq!(calls.get(), 1);
+
+        // different query is a different key
+        let res = call_service(&srv, TestRequest::get().uri("/?q=1").to_request()).await;
+        assert_eq!(read_body(res).await, "call 2");
+
+        call_service(&srv, TestRequest::get().uri("/no-store").to_request()).await;
+        call_service(&srv, TestRequest::get().uri("/no-store").to_request()).await;
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[actix_rt::test]
+    async fn varies_and_evicts() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(
+            App::new()
+                .wrap(ResponseCache::new(CacheConfig::default().capacity(2)))
+                .default_service(web::to({
+                    let calls = Rc::clone(&calls);
+                    move || {
+                        calls.set(calls.get() + 1);
+                        HttpResponse::Ok()
+                            .insert_header((header::VARY, "Accept-Language"))
+                            .body(calls.get().to_string())
+                    }
+                })),
+        )
+        .await;
+
+        let en = || {
+            TestRequest::get()
+                .uri("/a")
+                .insert_header((header::ACCEPT_LANGUAGE, "en"))
+                .to_request()
+        };
+
+        assert_eq!(read_body(call_service(&srv, en()).await).await, "1");
+        assert_eq!(read_body(call_service(&srv, en()).await).await, "1");
+
+        let req = TestRequest::get()
+            .uri("/a")
+            .insert_header((header::ACCEPT_LANGUAGE, "de"))
+            .to_request();
+        assert_eq!(read_body(call_service(&srv, req).await).await, "2");
+
+        // each variant is cached separately
+        assert_eq!(read_body(call_service(&srv, en()).await).await, "1");
+        assert_eq!(calls.get(), 2);
+
+        // the "de" variant is now the least recently used; evict it
+        call_service(&srv, TestRequest::get().uri("/b").to_request()).await;
+        assert_eq!(calls.get(), 3);
+        assert_eq!(read_body(call_service(&srv, en()).await).await, "1");
+
+        let req = TestRequest::get()
+            .uri("/a")
+            .insert_header((header::ACCEPT_LANGUAGE, "de"))
+            .to_request();
+        assert_eq!(read_body(call_service(&srv, req).await).await, "4");
+    }
+
+    #[actix_rt::test]
+    async fn skips_credentialed_requests() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(App::new().wrap(ResponseCache::default()).default_service(
+            web::to({
+                let calls = Rc::clone(&calls);
+                move |req: HttpRequest| {
+                    calls.set(calls.get() + 1);
+                    let cache_control = if req.path() == "/public" {
+                        "public, max-age=120"
+                    } else {
+                        "max-age=120"
+                    };
+
+                    HttpResponse::Ok()
+                        .insert_header((header::CACHE_CONTROL, cache_control))
+                        .body(calls.get().to_string())
+                }
+            }),
+        ))
+        .await;
+
+        let req = |path: &str, header: (HeaderName, &'static str)| {
+            TestRequest::get()
+                .uri(path)
+                .insert_header(header)
+                .to_request()
+        };
+        let auth = || (header::AUTHORIZATION, "Bearer secret");
+        let cookie = || (header::COOKIE, "session=secret");
+
+        // not stored unless marked as shareable
+        let res = call_service(&srv, req("/private", auth())).await;
+        assert_eq!(read_body(res).await, "1");
+        let res = call_service(&srv, req("/private", auth())).await;
+        assert_eq!(read_body(res).await, "2");
+
+        let res = call_service(&srv, req("/public", auth())).await;
+        assert_eq!(read_body(res).await, "3");
+        let res = call_service(&srv, req("/public", auth())).await;
+        assert_eq!(read_body(res).await, "3");
+
+        // requests with cookies bypass the cache
+        let res = call_service(&srv, req("/public", cookie())).await;
+        assert_eq!(read_body(res).await, "4");
+        let res = call_service(&srv, req("/cookie", cookie())).await;
+        assert_eq!(read_body(res).await, "5");
+        let res = call_service(&srv, req("/cookie", cookie())).await;
+        assert_eq!(read_body(res).await, "6");
+    }
+}
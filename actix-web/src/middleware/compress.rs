@@ -16,6 +16,7 @@ use pin_project_lite::pin_project;
 
 use crate::{
     body::{EitherBody, MessageBody},
+    helpers::append_vary,
     http::{
         header::{self, AcceptEncoding, Encoding, HeaderValue},
         StatusCode,
@@ -183,6 +184,9 @@ where
                 };
 
                 Poll::Ready(Ok(resp.map_body(move |head, body| {
+                    // response depends on accept-encoding even if it is not compressed
+                    append_vary(head.headers_mut(), &header::ACCEPT_ENCODING);
+
                     EitherBody::left(Encoder::response(enc, head, body))
                 })))
             }
@@ -324,4 +328,41 @@ mod tests {
         assert!(vary_headers.contains(&HeaderValue::from_static("x-test")));
         assert!(vary_headers.contains(&HeaderValue::from_static("accept-encoding")));
     }
+
+    #[actix_rt::test]
+    async fn vary_accept_encoding_once() {
+        const D: &str = "hello world ";
+        const DATA: &str = const_str::repeat!(D, 100);
+
+        let app = test::init_service({
+            App::new()
+                .wrap(Compress::default())
+                .default_service(web::to(move || {
+                    HttpResponse::Ok().vary(header::ACCEPT_ENCODING).body(DATA)
+                }))
+        })
+        .await;
+
+        // compressed and uncompressed responses both vary on accept-encoding, exactly once
+        for accept_encoding in ["gzip", "identity"] {
+            let req = test::TestRequest::default()
+                .insert_header((header::ACCEPT_ENCODING, accept_encoding))
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            let vary = res.headers().get_all(header::VARY).collect::<Vec<_>>();
+            assert_eq!(vary, ["accept-encoding"], "{}", accept_encoding);
+        }
+
+        let app = test::init_service({
+            App::new()
+                .wrap(Compress::default())
+                .default_service(web::to(move || HttpResponse::Ok().body(DATA)))
+        })
+        .await;
+
+        let req = test::TestRequest::default().to_request();
+        let res = test::call_service(&app, req).await;
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+    }
 }
@@ -0,0 +1,260 @@
+//! For middleware documentation, see [`AutoETag`].
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_http::{body::BodySize, Method, StatusCode};
+use bytes::Bytes;
+use futures_core::future::LocalBoxFuture;
+
+use crate::{
+    body::{self, EitherBody, MessageBody},
+    dev::{Service, Transform},
+    error::ErrorInternalServerError,
+    http::header::{self, EntityTag, IfNoneMatch, TryIntoHeaderValue as _},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpResponse,
+};
+
+/// Response headers that are carried over to `304 Not Modified` responses.
+///
+/// See <https://datatracker.ietf.org/doc/html/rfc7232#section-4.1>.
+const NOT_MODIFIED_HEADERS: [header::HeaderName; 5] = [
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::DATE,
+    header::EXPIRES,
+    header::VARY,
+];
+
+/// Configuration for the [`AutoETag`] middleware.
+#[derive(Debug, Clone)]
+pub struct ETagConfig {
+    max_buffer: usize,
+}
+
+impl ETagConfig {
+    /// Sets the largest response body size, in bytes, that will be buffered to compute an ETag.
+    ///
+    /// Responses with larger or streaming bodies are passed through untouched. By default this
+    /// limit is 64kB.
+    pub fn max_buffer(mut self, max_buffer: usize) -> Self {
+        self.max_buffer = max_buffer;
+        self
+    }
+}
+
+impl Default for ETagConfig {
+    fn default() -> Self {
+        Self { max_buffer: 65_536 }
+    }
+}
+
+/// Middleware for generating strong `ETag`s for dynamic responses.
+///
+/// Successful (`200 OK`) responses to `GET` and `HEAD` requests that do not already have an `ETag`
+/// header and whose body has a known size no larger than [`ETagConfig::max_buffer`] are buffered
+/// and hashed. The resulting tag is set as the response's `ETag` header and, if the request's
+/// `If-None-Match` header matches it, the response is replaced with `304 Not Modified`.
+///
+/// The tag is the 64-bit FNV-1a hash of the body, so it is stable across processes, builds, and
+/// versions of Rust, and all instances of a server generate the same tag for the same body. The
+/// hash is not cryptographic and is only intended to detect changes in the body.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::{AutoETag, ETagConfig}, App};
+///
+/// let app = App::new().wrap(AutoETag::new(ETagConfig::default().max_buffer(16 * 1024)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AutoETag {
+    config: Rc<ETagConfig>,
+}
+
+impl AutoETag {
+    /// Constructs a new `AutoETag` middleware using the given configuration.
+    pub fn new(config: ETagConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AutoETag
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = AutoETagMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AutoETagMiddleware {
+            service,
+            config: Rc::clone(&self.config),
+        }))
+    }
+}
+
+pub struct AutoETagMiddleware<S> {
+    service: S,
+    config: Rc<ETagConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for AutoETagMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let cacheable_method = matches!(*req.method(), Method::GET | Method::HEAD);
+        let config = Rc::clone(&self.config);
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+
+            let eligible = cacheable_method
+                && res.status() == StatusCode::OK
+                && !res.headers().contains_key(header::ETAG)
+                && matches!(
+                    res.response().body().size(),
+                    BodySize::Sized(len) if len <= config.max_buffer as u64
+                );
+
+            if !eligible {
+                return Ok(res.map_into_left_body::<Bytes>());
+            }
+
+            let (req, res) = res.into_parts();
+            let (res, body) = res.into_parts();
+
+            let body = body::to_bytes(body)
+                .await
+                .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+            let etag = EntityTag::new_strong(format!("{:016x}", fnv1a(&body)));
+
+            let not_modified = match req.get_header::<IfNoneMatch>() {
+                Some(IfNoneMatch::Any) => true,
+                Some(IfNoneMatch::Items(ref items)) => {
+                    items.iter().any(|tag| tag.weak_eq(&etag))
+                }
+                None => false,
+            };
+
+            if not_modified {
+                let mut not_modified = HttpResponse::NotModified();
+                not_modified.insert_header(header::ETag(etag));
+
+                for name in &NOT_MODIFIED_HEADERS {
+                    for value in res.headers().get_all(name) {
+                        not_modified.append_header((name.clone(), value.clone()));
+                    }
+                }
+
+                let res = not_modified.finish().set_body(Bytes::new());
+                return Ok(ServiceResponse::new(req, res).map_into_right_body());
+            }
+
+            let mut res = res.set_body(body);
+            res.headers_mut()
+                .insert(header::ETAG, header::ETag(etag).try_into_value().unwrap());
+
+            Ok(ServiceResponse::new(req, res).map_into_right_body())
+        })
+    }
+}
+
+/// Computes the 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+
+    async fn streaming() -> HttpResponse {
+        HttpResponse::Ok().streaming(stream::once(async {
+            Ok::<_, Error>(Bytes::from_static(b"stream"))
+        }))
+    }
+
+    #[actix_rt::test]
+    async fn sets_etag_and_handles_conditional_requests() {
+        let srv = init_service(
+            App::new()
+                .wrap(AutoETag::default())
+                .route("/", web::get().to(|| async { "hello world" }))
+                .route("/stream", web::get().to(streaming)),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let etag = res.headers().get(header::ETAG).cloned().unwrap();
+        assert_eq!(etag, "\"779a65e7023cd2e7\"");
+        assert_eq!(read_body(res).await, "hello world");
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG), Some(&etag));
+        assert!(read_body(res).await.is_empty());
+
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((header::IF_NONE_MATCH, "\"other\""))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = call_service(&srv, TestRequest::get().uri("/stream").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+        assert_eq!(read_body(res).await, "stream");
+    }
+
+    #[actix_rt::test]
+    async fn bypasses_large_bodies() {
+        let srv = init_service(
+            App::new()
+                .wrap(AutoETag::new(ETagConfig::default().max_buffer(4)))
+                .route("/", web::get().to(|| async { "hello world" })),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::get().uri("/").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+    }
+}
@@ -0,0 +1,695 @@
+//! For middleware documentation, see [`Idempotency`].
+
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap},
+    future::{ready, Future, Ready},
+    hash::{Hash as _, Hasher as _},
+    mem,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use actix_http::{
+    body::BodySize,
+    h1,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    StatusCode,
+};
+use bytes::{Bytes, BytesMut};
+use futures_core::future::LocalBoxFuture;
+use futures_util::StreamExt as _;
+
+use crate::{
+    body::{self, EitherBody, MessageBody},
+    dev::{Payload, Service, Transform},
+    error::{ErrorInternalServerError, PayloadError},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpMessage as _, HttpResponse,
+};
+
+/// Request header carrying the client chosen idempotency key.
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// Response header added to responses that are replayed from the store.
+const IDEMPOTENT_REPLAYED: &str = "idempotent-replayed";
+
+/// Configuration for the [`Idempotency`] middleware.
+#[derive(Debug, Clone)]
+pub struct IdempotencyConfig {
+    capacity: usize,
+    ttl: Duration,
+    max_body_size: usize,
+}
+
+impl IdempotencyConfig {
+    /// Sets the maximum number of responses held by the store.
+    ///
+    /// When the store is full, the oldest response is evicted to make room for a new one. By
+    /// default, up to 1024 responses are stored.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets how long a response is replayed for after it was stored.
+    ///
+    /// Once it has expired, a new request with the same key is processed again. By default,
+    /// responses are stored for 24 hours.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the largest request and response body size, in bytes.
+    ///
+    /// Request bodies are buffered to fingerprint the request; requests with larger bodies are
+    /// rejected with `413 Payload Too Large`. Responses with larger or streaming bodies are passed
+    /// through without being stored. By default this limit is 64kB.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            ttl: Duration::from_secs(24 * 60 * 60),
+            max_body_size: 65_536,
+        }
+    }
+}
+
+/// Middleware for deduplicating requests that carry an `Idempotency-Key` header.
+///
+/// The first request with a given key is processed as usual and its response is stored, along
+/// with a fingerprint of the request made of its method, path, query, and body. Subsequent
+/// requests with the same key and fingerprint are answered with the stored response, along with
+/// an `Idempotent-Replayed: true` header, without calling the wrapped service again. Requests
+/// that arrive while the first one is still being processed wait for it to complete. Requests
+/// that reuse a key for a different fingerprint are rejected with `422 Unprocessable Entity`.
+///
+/// A response is only stored if its status is not a server error (`5xx`) and its body has a known
+/// size no larger than [`IdempotencyConfig::max_body_size`]. Otherwise, and if the wrapped service
+/// fails, the next request with the same key is processed as if it was the first, and any other
+/// waiting requests wait for it instead. `Set-Cookie` headers are not stored, so replayed
+/// responses never hand out the cookies, such as session IDs, given to the first request.
+///
+/// Requests without an `Idempotency-Key` header are passed through untouched. Keys are not
+/// scoped to clients, so they should be hard to guess, e.g., random UUIDs. Each worker thread has
+/// its own store, so this middleware only deduplicates requests handled by the same worker.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{middleware::{Idempotency, IdempotencyConfig}, web, App};
+///
+/// let app = App::new()
+///     .wrap(Idempotency::new(
+///         IdempotencyConfig::default().ttl(Duration::from_secs(60 * 60)),
+///     ))
+///     .route("/payments", web::post().to(|| async { "payment created" }));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Idempotency {
+    config: Rc<IdempotencyConfig>,
+    store: Rc<RefCell<IdempotencyStore>>,
+}
+
+impl Idempotency {
+    /// Constructs a new `Idempotency` middleware using the given configuration.
+    pub fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            config: Rc::new(config),
+            store: Rc::default(),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Transform = IdempotencyMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddleware {
+            service: Rc::new(service),
+            config: Rc::clone(&self.config),
+            store: Rc::clone(&self.store),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<S>,
+    config: Rc<IdempotencyConfig>,
+    store: Rc<RefCell<IdempotencyStore>>,
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B, Bytes>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let key = match req.headers().get(IDEMPOTENCY_KEY) {
+            Some(key) => key.clone(),
+            None => {
+                let fut = self.service.call(req);
+                return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+            }
+        };
+
+        let service = Rc::clone(&self.service);
+        let config = Rc::clone(&self.config);
+        let store = Rc::clone(&self.store);
+
+        Box::pin(async move {
+            let body = read_body(req.take_payload(), config.max_body_size).await?;
+            let fingerprint = Fingerprint::new(&req, &body);
+
+            let (_, mut payload) = h1::Payload::create(true);
+            payload.unread_data(body);
+            req.set_payload(Payload::from(payload));
+
+            let stored = loop {
+                let claim =
+                    store
+                        .borrow_mut()
+                        .claim(&key, fingerprint, Instant::now(), config.ttl);
+
+                match claim {
+                    Claim::Stored(stored) => break stored,
+
+                    // once the request being waited for has completed, its stored response is
+                    // replayed or, if it did not leave one behind, one of the waiting requests
+                    // takes its place
+                    Claim::InFlight => {
+                        WaitForResponse {
+                            store: Rc::clone(&store),
+                            key: &key,
+                        }
+                        .await
+                    }
+
+                    Claim::Mismatch => {
+                        let res = HttpResponse::with_body(
+                            StatusCode::UNPROCESSABLE_ENTITY,
+                            Bytes::from_static(
+                                b"Idempotency-Key was already used for a different request",
+                            ),
+                        );
+                        let (req, _) = req.into_parts();
+                        return Ok(ServiceResponse::new(req, res).map_into_right_body());
+                    }
+
+                    Claim::Owner => {
+                        return process(&service, req, key, fingerprint, &config, store).await;
+                    }
+                }
+            };
+
+            let (req, _) = req.into_parts();
+            let res = ServiceResponse::new(req, stored.to_response());
+            Ok(res.map_into_right_body())
+        })
+    }
+}
+
+/// Reads `payload` into memory, failing if it is larger than `limit` bytes.
+async fn read_body(mut payload: Payload, limit: usize) -> Result<Bytes, Error> {
+    let mut body = BytesMut::new();
+
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+
+        if body.len() + chunk.len() > limit {
+            return Err(PayloadError::Overflow.into());
+        }
+
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(body.freeze())
+}
+
+/// Processes the first request with `key`, storing its response if possible.
+async fn process<S, B>(
+    service: &S,
+    req: ServiceRequest,
+    key: HeaderValue,
+    fingerprint: Fingerprint,
+    config: &IdempotencyConfig,
+    store: Rc<RefCell<IdempotencyStore>>,
+) -> Result<ServiceResponse<EitherBody<B, Bytes>>, Error>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    // releases waiting requests if processing fails or is cancelled
+    let mut guard = InFlightGuard {
+        store,
+        key: Some(key),
+    };
+
+    let res = service.call(req).await?;
+
+    let storable = !res.status().is_server_error()
+        && matches!(
+            res.response().body().size(),
+            BodySize::Sized(len) if len <= config.max_body_size as u64
+        );
+
+    if !storable {
+        return Ok(res.map_into_left_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+
+    let body = body::to_bytes(body)
+        .await
+        .map_err(|err| ErrorInternalServerError(err.into()))?;
+
+    let mut headers = res.headers().clone();
+    headers.remove(header::SET_COOKIE);
+
+    let stored = StoredResponse {
+        fingerprint,
+        status: res.status(),
+        headers,
+        body: body.clone(),
+        stored_at: Instant::now(),
+    };
+
+    if let Some(key) = guard.key.take() {
+        let wakers = guard
+            .store
+            .borrow_mut()
+            .complete(key, stored, config.capacity);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    let res = res.set_body(body);
+    Ok(ServiceResponse::new(req, res).map_into_right_body())
+}
+
+/// Hash of the method, path, query, and body of a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new(req: &ServiceRequest, body: &[u8]) -> Self {
+        let mut hasher = DefaultHasher::new();
+        req.method().hash(&mut hasher);
+        req.path().hash(&mut hasher);
+        req.query_string().hash(&mut hasher);
+        body.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    fingerprint: Fingerprint,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    stored_at: Instant,
+}
+
+impl StoredResponse {
+    fn to_response(&self) -> HttpResponse<Bytes> {
+        let mut res = HttpResponse::with_body(self.status, self.body.clone());
+        *res.headers_mut() = self.headers.clone();
+        res.headers_mut().insert(
+            HeaderName::from_static(IDEMPOTENT_REPLAYED),
+            HeaderValue::from_static("true"),
+        );
+        res
+    }
+}
+
+#[derive(Debug)]
+enum Entry {
+    /// The first request with the key is being processed; holds the wakers of waiting requests.
+    InFlight {
+        fingerprint: Fingerprint,
+        wakers: Vec<Waker>,
+    },
+    Stored(StoredResponse),
+}
+
+/// Outcome of looking up an idempotency key.
+enum Claim {
+    /// The caller has been registered as the first request with the key.
+    Owner,
+    /// Another request with the key is being processed.
+    InFlight,
+    /// The key was used for a request with another fingerprint.
+    Mismatch,
+    Stored(StoredResponse),
+}
+
+#[derive(Debug, Default)]
+struct IdempotencyStore {
+    entries: HashMap<HeaderValue, Entry>,
+}
+
+impl IdempotencyStore {
+    fn claim(
+        &mut self,
+        key: &HeaderValue,
+        fingerprint: Fingerprint,
+        now: Instant,
+        ttl: Duration,
+    ) -> Claim {
+        let (other, claim) = match self.entries.get(key) {
+            Some(Entry::InFlight {
+                fingerprint: other, ..
+            }) => (*other, Claim::InFlight),
+            Some(Entry::Stored(stored))
+                if now.saturating_duration_since(stored.stored_at) < ttl =>
+            {
+                (stored.fingerprint, Claim::Stored(stored.clone()))
+            }
+            _ => (fingerprint, Claim::Owner),
+        };
+
+        if other != fingerprint {
+            return Claim::Mismatch;
+        }
+
+        if !matches!(claim, Claim::Owner) {
+            return claim;
+        }
+
+        self.entries.insert(
+            key.clone(),
+            Entry::InFlight {
+                fingerprint,
+                wakers: Vec::new(),
+            },
+        );
+        Claim::Owner
+    }
+
+    /// Stores the response for `key`, returning the wakers of requests waiting for it.
+    fn complete(
+        &mut self,
+        key: HeaderValue,
+        stored: StoredResponse,
+        capacity: usize,
+    ) -> Vec<Waker> {
+        let wakers = self.abandon(&key);
+
+        if capacity == 0 {
+            return wakers;
+        }
+
+        if self.stored_len() >= capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .filter_map(|(key, entry)| match entry {
+                    Entry::Stored(stored) => Some((key, stored.stored_at)),
+                    Entry::InFlight { .. } => None,
+                })
+                .min_by_key(|(_, stored_at)| *stored_at)
+                .map(|(key, _)| key.clone());
+
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+
+        self.entries.insert(key, Entry::Stored(stored));
+        wakers
+    }
+
+    /// Removes the in-flight entry for `key`, returning the wakers of requests waiting for it.
+    fn abandon(&mut self, key: &HeaderValue) -> Vec<Waker> {
+        match self.entries.get_mut(key) {
+            Some(Entry::InFlight { wakers, .. }) => {
+                let wakers = mem::take(wakers);
+                self.entries.remove(key);
+                wakers
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn stored_len(&self) -> usize {
+        self.entries
+            .values()
+            .filter(|entry| matches!(entry, Entry::Stored(_)))
+            .count()
+    }
+}
+
+/// Removes the in-flight entry of a request that did not store a response.
+struct InFlightGuard {
+    store: Rc<RefCell<IdempotencyStore>>,
+    key: Option<HeaderValue>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            let wakers = self.store.borrow_mut().abandon(&key);
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+/// Resolves once the in-flight request with `key` has completed.
+struct WaitForResponse<'a> {
+    store: Rc<RefCell<IdempotencyStore>>,
+    key: &'a HeaderValue,
+}
+
+impl Future for WaitForResponse<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut store = self.store.borrow_mut();
+
+        match store.entries.get_mut(self.key) {
+            Some(Entry::InFlight { wakers, .. }) => {
+                if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                    wakers.push(cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+            _ => Poll::Ready(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use actix_rt::time::sleep;
+    use futures_util::future::join;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, try_call_service, TestRequest},
+        web, App,
+    };
+
+    fn payment(key: &'static str) -> TestRequest {
+        TestRequest::post()
+            .uri("/payments")
+            .insert_header((IDEMPOTENCY_KEY, key))
+    }
+
+    #[actix_rt::test]
+    async fn concurrent_duplicates_run_once() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(App::new().wrap(Idempotency::default()).route(
+            "/payments",
+            web::post().to({
+                let calls = Rc::clone(&calls);
+                move || {
+                    let calls = Rc::clone(&calls);
+                    async move {
+                        calls.set(calls.get() + 1);
+                        sleep(Duration::from_millis(50)).await;
+                        format!("payment {}", calls.get())
+                    }
+                }
+            }),
+        ))
+        .await;
+
+        let (first, second) = join(
+            call_service(&srv, payment("abc").to_request()),
+            call_service(&srv, payment("abc").to_request()),
+        )
+        .await;
+        assert_eq!(calls.get(), 1);
+
+        assert!(!first.headers().contains_key(IDEMPOTENT_REPLAYED));
+        assert_eq!(second.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+        assert_eq!(read_body(first).await, "payment 1");
+        assert_eq!(read_body(second).await, "payment 1");
+
+        // later duplicates are replayed too
+        let res = call_service(&srv, payment("abc").to_request()).await;
+        assert_eq!(read_body(res).await, "payment 1");
+        assert_eq!(calls.get(), 1);
+
+        // other keys, and requests without a key, are processed
+        let res = call_service(&srv, payment("def").to_request()).await;
+        assert_eq!(read_body(res).await, "payment 2");
+
+        let req = TestRequest::post().uri("/payments").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "payment 3");
+    }
+
+    #[actix_rt::test]
+    async fn rejects_reused_keys_and_strips_cookies() {
+        let srv = init_service(
+            App::new()
+                .wrap(Idempotency::new(
+                    IdempotencyConfig::default().max_body_size(16),
+                ))
+                .route(
+                    "/payments",
+                    web::post().to(|body: Bytes| {
+                        HttpResponse::Ok()
+                            .insert_header((header::SET_COOKIE, "session=secret"))
+                            .body(body)
+                    }),
+                ),
+        )
+        .await;
+
+        let res = call_service(&srv, payment("abc").set_payload("10 EUR").to_request()).await;
+        assert!(res.headers().contains_key(header::SET_COOKIE));
+        assert_eq!(read_body(res).await, "10 EUR");
+
+        let res = call_service(&srv, payment("abc").set_payload("10 EUR").to_request()).await;
+        assert_eq!(res.headers().get(IDEMPOTENT_REPLAYED).unwrap(), "true");
+        assert!(!res.headers().contains_key(header::SET_COOKIE));
+        assert_eq!(read_body(res).await, "10 EUR");
+
+        // same key with a different body, path, or query
+        let res = call_service(&srv, payment("abc").set_payload("99 EUR").to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = payment("abc")
+            .uri("/payments?amount=99")
+            .set_payload("10 EUR")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // request bodies are only buffered up to the limit
+        let req = payment("def")
+            .set_payload("a body that is too large")
+            .to_request();
+        let err = try_call_service(&srv, req).await.unwrap_err();
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::PAYLOAD_TOO_LARGE
+        );
+    }
+
+    #[actix_rt::test]
+    async fn server_errors_are_not_stored() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(App::new().wrap(Idempotency::default()).route(
+            "/payments",
+            web::post().to({
+                let calls = Rc::clone(&calls);
+                move || {
+                    calls.set(calls.get() + 1);
+                    HttpResponse::ServiceUnavailable().finish()
+                }
+            }),
+        ))
+        .await;
+
+        let (first, second) = join(
+            call_service(&srv, payment("abc").to_request()),
+            call_service(&srv, payment("abc").to_request()),
+        )
+        .await;
+        assert_eq!(first.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(second.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!second.headers().contains_key(IDEMPOTENT_REPLAYED));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn waiters_of_failed_request_run_once() {
+        let calls = Rc::new(Cell::new(0));
+
+        let srv = init_service(App::new().wrap(Idempotency::default()).route(
+            "/payments",
+            web::post().to({
+                let calls = Rc::clone(&calls);
+                move || {
+                    let calls = Rc::clone(&calls);
+                    async move {
+                        calls.set(calls.get() + 1);
+                        sleep(Duration::from_millis(50)).await;
+
+                        let status = match calls.get() {
+                            1 => StatusCode::SERVICE_UNAVAILABLE,
+                            _ => StatusCode::OK,
+                        };
+
+                        (format!("payment {}", calls.get()), status)
+                    }
+                }
+            }),
+        ))
+        .await;
+
+        let (first, (second, third)) = join(
+            call_service(&srv, payment("abc").to_request()),
+            join(
+                call_service(&srv, payment("abc").to_request()),
+                call_service(&srv, payment("abc").to_request()),
+            ),
+        )
+        .await;
+        assert_eq!(first.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // only one of the waiting requests is processed, the other one is replayed its response
+        assert_eq!(calls.get(), 2);
+        let replayed = [&second, &third]
+            .iter()
+            .filter(|res| res.headers().contains_key(IDEMPOTENT_REPLAYED))
+            .count();
+        assert_eq!(replayed, 1);
+
+        assert_eq!(read_body(second).await, "payment 2");
+        assert_eq!(read_body(third).await, "payment 2");
+    }
+}
@@ -0,0 +1,958 @@
+//! For middleware documentation, see [`JsonSchema`].
+
+use std::{
+    cell::RefCell,
+    fmt,
+    future::{ready, Future, Ready},
+    io,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use actix_http::{error::PayloadError, BoxedPayloadStream};
+use futures_core::{ready, Stream};
+use pin_project_lite::pin_project;
+use serde_json::Value;
+
+use crate::{
+    dev::{Payload, Service, Transform},
+    http::StatusCode,
+    service::{ServiceRequest, ServiceResponse},
+    web::Bytes,
+    Error, HttpMessage as _, ResponseError,
+};
+
+/// Middleware for validating JSON request bodies against a JSON Schema while they are read.
+///
+/// The body of each request with a JSON content type (`application/json` or `+json`) is checked
+/// chunk by chunk as the application reads it, without buffering the document. As soon as a
+/// violation is found, reading the payload fails and the request is rejected with a
+/// [`SchemaViolation`] error, responding with `422 Unprocessable Entity`. Memory use is bounded
+/// by the nesting depth of the document rather than its size: documents nested deeper than
+/// [`max_depth`](Self::max_depth) and property names longer than 1KiB are reported as violations
+/// rather than buffered.
+///
+/// Only bodies that are actually read by the application are validated. Syntax errors are left
+/// for the extractor that parses the body to report; validation of a malformed body stops at
+/// the first syntax error.
+///
+/// # Supported Keywords
+/// The schema is built from a subset of JSON Schema that can be checked incrementally:
+/// - `type`, as a single type name or an array of them;
+/// - `properties`, `required` and `additionalProperties` of objects;
+/// - `items` of arrays, as a single schema applying to every item;
+/// - the boolean schemas `true` and `false`.
+///
+/// Other keywords are ignored.
+///
+/// # Examples
+/// ```
+/// use actix_web::{middleware::JsonSchema, web, App};
+///
+/// let schema = JsonSchema::new(serde_json::json!({
+///     "type": "object",
+///     "required": ["name"],
+///     "properties": {
+///         "name": { "type": "string" },
+///         "tags": { "type": "array", "items": { "type": "string" } },
+///     },
+///     "additionalProperties": false,
+/// }));
+///
+/// let app = App::new().service(
+///     web::resource("/users")
+///         .wrap(schema)
+///         .route(web::post().to(|user: web::Json<serde_json::Value>| async move {
+///             user["name"].to_string()
+///         })),
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    schema: Rc<Schema>,
+    max_depth: usize,
+}
+
+impl JsonSchema {
+    /// Constructs a new `JsonSchema` middleware from a JSON Schema document.
+    ///
+    /// # Panics
+    /// Panics if a supported keyword has a value of the wrong type, or if `type` names an
+    /// unknown type.
+    pub fn new(schema: Value) -> Self {
+        let mut nodes = Vec::new();
+        compile(&mut nodes, &schema);
+
+        Self {
+            schema: Rc::new(Schema { nodes }),
+            max_depth: 128,
+        }
+    }
+
+    /// Sets the maximum number of objects and arrays a value may be nested in.
+    ///
+    /// Documents nested deeper are rejected with a [`SchemaViolation`]. By default, values may be
+    /// nested up to 128 levels deep.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// Error returned when a request body violates the schema of the [`JsonSchema`] middleware.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pointer: String,
+    message: String,
+}
+
+impl SchemaViolation {
+    /// Returns the location of the offending value, as a JSON Pointer.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// Returns a description of the violation.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.pointer.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.pointer, self.message)
+        }
+    }
+}
+
+impl std::error::Error for SchemaViolation {}
+
+impl ResponseError for SchemaViolation {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for JsonSchema
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = JsonSchemaMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(JsonSchemaMiddleware {
+            service,
+            schema: Rc::clone(&self.schema),
+            max_depth: self.max_depth,
+        }))
+    }
+}
+
+pub struct JsonSchemaMiddleware<S> {
+    service: S,
+    schema: Rc<Schema>,
+    max_depth: usize,
+}
+
+impl<S, B> Service<ServiceRequest> for JsonSchemaMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = JsonSchemaFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let violation = Rc::new(RefCell::new(None));
+
+        let is_json = matches!(
+            req.mime_type(),
+            Ok(Some(mime)) if mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON)
+        );
+
+        if is_json && req.has_payload() {
+            let (_, payload) = req.parts_mut();
+
+            let stream = ValidatedPayload {
+                payload: payload.take(),
+                validator: Some(Validator::new(Rc::clone(&self.schema), self.max_depth)),
+                violation: Rc::clone(&violation),
+            };
+
+            req.set_payload(Payload::from(Box::pin(stream) as BoxedPayloadStream));
+        }
+
+        JsonSchemaFuture {
+            fut: self.service.call(req),
+            violation,
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    pub struct JsonSchemaFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        violation: Rc<RefCell<Option<SchemaViolation>>>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for JsonSchemaFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<B>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx));
+
+        // the violation replaces whatever error the application made of the failed payload
+        match this.violation.borrow_mut().take() {
+            Some(violation) => Poll::Ready(Err(violation.into())),
+            None => Poll::Ready(res),
+        }
+    }
+}
+
+/// Request payload that feeds chunks through a [`Validator`] as they are read.
+struct ValidatedPayload {
+    payload: Payload,
+    // `None` once the document has been found to be invalid JSON
+    validator: Option<Validator>,
+    violation: Rc<RefCell<Option<SchemaViolation>>>,
+}
+
+impl ValidatedPayload {
+    fn check(&mut self, res: Result<(), Stop>) -> Result<(), PayloadError> {
+        match res {
+            Ok(()) => Ok(()),
+
+            Err(Stop::Invalid) => {
+                self.validator = None;
+                Ok(())
+            }
+
+            Err(Stop::Violation(violation)) => {
+                self.validator = None;
+                self.payload = Payload::None;
+
+                let err = io::Error::new(io::ErrorKind::InvalidData, violation.to_string());
+                *self.violation.borrow_mut() = Some(violation);
+
+                Err(PayloadError::Io(err))
+            }
+        }
+    }
+}
+
+impl Stream for ValidatedPayload {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        match ready!(Pin::new(&mut this.payload).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                if let Some(validator) = this.validator.as_mut() {
+                    let res = validator.feed(&chunk);
+                    this.check(res)?;
+                }
+
+                Poll::Ready(Some(Ok(chunk)))
+            }
+
+            None => {
+                if let Some(validator) = this.validator.as_mut() {
+                    let res = validator.finish();
+                    this.check(res)?;
+                }
+
+                Poll::Ready(None)
+            }
+
+            item => Poll::Ready(item),
+        }
+    }
+}
+
+/// JSON value types, as named by the `type` keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Null,
+    Boolean,
+    Object,
+    Array,
+    Number,
+    Integer,
+    String,
+}
+
+impl Kind {
+    fn parse(name: &str) -> Self {
+        match name {
+            "null" => Kind::Null,
+            "boolean" => Kind::Boolean,
+            "object" => Kind::Object,
+            "array" => Kind::Array,
+            "number" => Kind::Number,
+            "integer" => Kind::Integer,
+            "string" => Kind::String,
+            _ => panic!("unknown JSON Schema type `{}`", name),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Null => "null",
+            Kind::Boolean => "boolean",
+            Kind::Object => "object",
+            Kind::Array => "array",
+            Kind::Number => "number",
+            Kind::Integer => "integer",
+            Kind::String => "string",
+        }
+    }
+}
+
+/// Compiled schema, as a flat list of nodes of which the first is the root.
+#[derive(Debug)]
+struct Schema {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    // `false` schema; no value is valid
+    never: bool,
+    types: Option<Vec<Kind>>,
+    properties: Vec<(String, usize)>,
+    required: Vec<String>,
+    additional_properties: Option<usize>,
+    items: Option<usize>,
+}
+
+impl Node {
+    fn allows(&self, kind: Kind) -> bool {
+        match self.types {
+            None => true,
+            Some(ref types) => {
+                types.contains(&kind)
+                    || (kind == Kind::Integer && types.contains(&Kind::Number))
+            }
+        }
+    }
+}
+
+/// Compiles `schema` and its subschemas into `nodes`, returning the index of its node.
+fn compile(nodes: &mut Vec<Node>, schema: &Value) -> usize {
+    let idx = nodes.len();
+    nodes.push(Node::default());
+
+    let mut node = Node::default();
+
+    let map = match schema {
+        Value::Bool(valid) => {
+            node.never = !valid;
+            nodes[idx] = node;
+            return idx;
+        }
+        Value::Object(map) => map,
+        _ => panic!("JSON Schema must be an object or a boolean"),
+    };
+
+    node.types = map.get("type").map(|types| match types {
+        Value::String(name) => vec![Kind::parse(name)],
+        Value::Array(names) => names
+            .iter()
+            .map(|name| Kind::parse(name.as_str().expect("`type` must name types as strings")))
+            .collect(),
+        _ => panic!("`type` must be a string or an array of strings"),
+    });
+
+    if let Some(properties) = map.get("properties") {
+        let properties = properties
+            .as_object()
+            .expect("`properties` must be an object");
+
+        for (name, schema) in properties {
+            let sub = compile(nodes, schema);
+            node.properties.push((name.clone(), sub));
+        }
+    }
+
+    if let Some(required) = map.get("required") {
+        node.required = required
+            .as_array()
+            .expect("`required` must be an array")
+            .iter()
+            .map(|name| {
+                name.as_str()
+                    .expect("`required` must list property names as strings")
+                    .to_owned()
+            })
+            .collect();
+    }
+
+    if let Some(schema) = map.get("additionalProperties") {
+        node.additional_properties = Some(compile(nodes, schema));
+    }
+
+    if let Some(schema) = map.get("items") {
+        node.items = Some(compile(nodes, schema));
+    }
+
+    nodes[idx] = node;
+    idx
+}
+
+/// Longest property name that is buffered, in bytes as written in the document.
+const MAX_KEY_LEN: usize = 1024;
+
+/// Reason for validation to stop.
+#[derive(Debug)]
+enum Stop {
+    Violation(SchemaViolation),
+    // not valid JSON; left for the body's parser to report
+    Invalid,
+}
+
+/// Open object or array.
+#[derive(Debug)]
+enum Frame {
+    Object {
+        node: Option<usize>,
+        // whether each of the node's required properties has been seen
+        seen: Vec<bool>,
+    },
+    Array {
+        node: Option<usize>,
+        len: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Value,
+    // after `{`
+    FirstKey,
+    // after `,` in an object
+    Key,
+    Colon,
+    // after `[`
+    FirstItem,
+    // after a value inside an object or array
+    Comma,
+    Str { key: bool, escaped: bool },
+    Number { node: Option<usize>, integer: bool },
+    Literal,
+    End,
+}
+
+/// Incremental JSON tokenizer that checks values against a [`Schema`] as they start and end.
+///
+/// Only object keys are buffered; string values and numbers are checked without being stored.
+struct Validator {
+    schema: Rc<Schema>,
+    max_depth: usize,
+    stack: Vec<Frame>,
+    state: State,
+    // JSON Pointer segments of the current value
+    path: Vec<String>,
+    key: Vec<u8>,
+    // schema node of the value that comes next
+    next: Option<usize>,
+}
+
+impl Validator {
+    fn new(schema: Rc<Schema>, max_depth: usize) -> Self {
+        Self {
+            schema,
+            max_depth,
+            stack: Vec::new(),
+            state: State::Value,
+            path: Vec::new(),
+            key: Vec::new(),
+            next: Some(0),
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) -> Result<(), Stop> {
+        let mut idx = 0;
+
+        while idx < chunk.len() {
+            if self.step(chunk[idx])? {
+                idx += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Stop> {
+        match self.state {
+            State::Number { node, integer } => self.end_number(node, integer),
+            State::Literal => self.end_value(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Processes one byte, returning whether it was consumed.
+    ///
+    /// Bytes that end a number or literal are processed again in the following state.
+    fn step(&mut self, byte: u8) -> Result<bool, Stop> {
+        match self.state {
+            State::Str { key, escaped: true } => {
+                if key {
+                    self.push_key(byte)?;
+                }
+
+                self.state = State::Str {
+                    key,
+                    escaped: false,
+                };
+            }
+
+            State::Str {
+                key,
+                escaped: false,
+            } => match byte {
+                b'"' if key => self.end_key()?,
+                b'"' => self.end_value()?,
+                _ => {
+                    if key {
+                        self.push_key(byte)?;
+                    }
+
+                    if byte == b'\\' {
+                        self.state = State::Str { key, escaped: true };
+                    }
+                }
+            },
+
+            State::Number { node, integer } => match byte {
+                b'0'..=b'9' | b'-' | b'+' => {}
+                b'.' | b'e' | b'E' => {
+                    self.state = State::Number {
+                        node,
+                        integer: false,
+                    }
+                }
+                _ => {
+                    self.end_number(node, integer)?;
+                    return Ok(false);
+                }
+            },
+
+            State::Literal => {
+                if !byte.is_ascii_alphabetic() {
+                    self.end_value()?;
+                    return Ok(false);
+                }
+            }
+
+            _ if matches!(byte, b' ' | b'\t' | b'\n' | b'\r') => {}
+
+            State::Value => self.start_value(byte)?,
+
+            State::FirstItem if byte == b']' => self.end_array()?,
+            State::FirstItem => {
+                self.start_item();
+                self.start_value(byte)?;
+            }
+
+            State::FirstKey if byte == b'}' => self.end_object()?,
+            State::FirstKey | State::Key if byte == b'"' => {
+                self.key.clear();
+                self.state = State::Str {
+                    key: true,
+                    escaped: false,
+                };
+            }
+
+            State::Colon if byte == b':' => self.state = State::Value,
+
+            State::Comma => match (self.stack.last(), byte) {
+                (Some(Frame::Object { .. }), b',') => self.state = State::Key,
+                (Some(Frame::Object { .. }), b'}') => self.end_object()?,
+                (Some(Frame::Array { .. }), b',') => {
+                    self.start_item();
+                    self.state = State::Value;
+                }
+                (Some(Frame::Array { .. }), b']') => self.end_array()?,
+                _ => return Err(Stop::Invalid),
+            },
+
+            _ => return Err(Stop::Invalid),
+        }
+
+        Ok(true)
+    }
+
+    fn violation(&self, message: String) -> Stop {
+        let pointer = self
+            .path
+            .iter()
+            .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+            .collect();
+
+        Stop::Violation(SchemaViolation { pointer, message })
+    }
+
+    fn node(&self, idx: Option<usize>) -> Option<&Node> {
+        idx.map(|idx| &self.schema.nodes[idx])
+    }
+
+    fn check_kind(&self, node: Option<usize>, kind: Kind) -> Result<(), Stop> {
+        match self.node(node) {
+            Some(node) if !node.allows(kind) => {
+                let expected = node
+                    .types
+                    .iter()
+                    .flatten()
+                    .map(|kind| kind.name())
+                    .collect::<Vec<_>>()
+                    .join(" or ");
+
+                Err(self.violation(format!("expected {}, found {}", expected, kind.name())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn start_value(&mut self, byte: u8) -> Result<(), Stop> {
+        let node = self.next.take();
+
+        if matches!(self.node(node), Some(node) if node.never) {
+            return Err(self.violation("no value is allowed here".to_owned()));
+        }
+
+        let kind = match byte {
+            b'{' => Kind::Object,
+            b'[' => Kind::Array,
+            b'"' => Kind::String,
+            b't' | b'f' => Kind::Boolean,
+            b'n' => Kind::Null,
+            b'-' | b'0'..=b'9' => {
+                // integer checks must wait until the number is complete
+                if self
+                    .node(node)
+                    .map_or(true, |node| node.allows(Kind::Integer))
+                {
+                    self.state = State::Number {
+                        node,
+                        integer: true,
+                    };
+                    return Ok(());
+                }
+
+                Kind::Number
+            }
+            _ => return Err(Stop::Invalid),
+        };
+
+        self.check_kind(node, kind)?;
+
+        if matches!(kind, Kind::Object | Kind::Array) && self.stack.len() == self.max_depth {
+            return Err(self.violation(format!(
+                "value is nested deeper than {} levels",
+                self.max_depth
+            )));
+        }
+
+        self.state = match kind {
+            Kind::Object => {
+                let required = self.node(node).map_or(0, |node| node.required.len());
+
+                self.stack.push(Frame::Object {
+                    node,
+                    seen: vec![false; required],
+                });
+
+                State::FirstKey
+            }
+
+            Kind::Array => {
+                self.stack.push(Frame::Array { node, len: 0 });
+                State::FirstItem
+            }
+
+            Kind::String => State::Str {
+                key: false,
+                escaped: false,
+            },
+
+            _ => State::Literal,
+        };
+
+        Ok(())
+    }
+
+    fn start_item(&mut self) {
+        let nodes = &self.schema.nodes;
+
+        if let Some(Frame::Array { node, len }) = self.stack.last_mut() {
+            self.next = node.and_then(|node| nodes[node].items);
+            self.path.push(len.to_string());
+            *len += 1;
+        }
+    }
+
+    fn push_key(&mut self, byte: u8) -> Result<(), Stop> {
+        if self.key.len() == MAX_KEY_LEN {
+            return Err(self.violation(format!(
+                "property name is longer than {} bytes",
+                MAX_KEY_LEN
+            )));
+        }
+
+        self.key.push(byte);
+        Ok(())
+    }
+
+    fn end_key(&mut self) -> Result<(), Stop> {
+        // let serde_json deal with escape sequences
+        let mut quoted = Vec::with_capacity(self.key.len() + 2);
+        quoted.push(b'"');
+        quoted.extend_from_slice(&self.key);
+        quoted.push(b'"');
+
+        let key = serde_json::from_slice::<String>(&quoted).map_err(|_| Stop::Invalid)?;
+
+        let schema = Rc::clone(&self.schema);
+        let mut next = None;
+
+        if let Some(Frame::Object {
+            node: Some(node),
+            seen,
+        }) = self.stack.last_mut()
+        {
+            let node = &schema.nodes[*node];
+
+            next = match node.properties.iter().find(|(name, _)| *name == key) {
+                Some(&(_, sub)) => Some(sub),
+                None => node.additional_properties,
+            };
+
+            if let Some(idx) = node.required.iter().position(|name| *name == key) {
+                seen[idx] = true;
+            }
+        }
+
+        self.path.push(key);
+
+        if matches!(self.node(next), Some(node) if node.never) {
+            return Err(self.violation("property is not allowed".to_owned()));
+        }
+
+        self.next = next;
+        self.state = State::Colon;
+        Ok(())
+    }
+
+    fn end_number(&mut self, node: Option<usize>, integer: bool) -> Result<(), Stop> {
+        let kind = if integer { Kind::Integer } else { Kind::Number };
+        self.check_kind(node, kind)?;
+        self.end_value()
+    }
+
+    fn end_object(&mut self) -> Result<(), Stop> {
+        if let Some(Frame::Object {
+            node: Some(node),
+            seen,
+        }) = self.stack.pop()
+        {
+            let node = &self.schema.nodes[node];
+
+            if let Some((name, _)) = node.required.iter().zip(seen).find(|(_, seen)| !seen) {
+                return Err(self.violation(format!("missing required property `{}`", name)));
+            }
+        }
+
+        self.end_value()
+    }
+
+    fn end_array(&mut self) -> Result<(), Stop> {
+        self.stack.pop();
+        self.end_value()
+    }
+
+    fn end_value(&mut self) -> Result<(), Stop> {
+        if self.stack.is_empty() {
+            self.state = State::End;
+        } else {
+            self.path.pop();
+            self.state = State::Comma;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::{stream, StreamExt as _};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        http::header::ContentType,
+        test::{call_service, init_service, read_body, try_call_service, TestRequest},
+        web, App,
+    };
+
+    fn validate(schema: Value, parts: &[&str]) -> Result<(), String> {
+        let schema = JsonSchema::new(schema).max_depth(4);
+        let mut validator = Validator::new(schema.schema, schema.max_depth);
+
+        let res = parts
+            .iter()
+            .try_for_each(|part| validator.feed(part.as_bytes()))
+            .and_then(|_| validator.finish());
+
+        match res {
+            Ok(()) => Ok(()),
+            Err(Stop::Violation(violation)) => Err(violation.to_string()),
+            Err(Stop::Invalid) => Err("invalid".to_owned()),
+        }
+    }
+
+    #[test]
+    fn validator() {
+        let schema = json!({
+            "type": "object",
+            "required": ["a/b", "n"],
+            "properties": {
+                "a/b": { "type": ["string", "null"] },
+                "n": { "type": "integer" },
+                "list": { "type": "array", "items": { "type": "number" } },
+                "nested": { "additionalProperties": false },
+            },
+        });
+
+        assert_eq!(
+            validate(
+                schema.clone(),
+                &[r#"{"a\/b": "x\"}", "n": 10, "list": [1.5, -2e3]}"#]
+            ),
+            Ok(())
+        );
+
+        // same document split at every byte
+        let doc = r#" { "a/b" : null , "n" : 7 , "nested" : { } } "#;
+        let parts = (0..doc.len()).map(|i| &doc[i..i + 1]).collect::<Vec<_>>();
+        assert_eq!(validate(schema.clone(), &parts), Ok(()));
+
+        assert_eq!(
+            validate(schema.clone(), &[r#"{"a/b": 1"#]),
+            Err("/a~1b: expected string or null, found number".to_owned())
+        );
+        assert_eq!(
+            validate(schema.clone(), &[r#"{"a/b": "", "n": 1.5}"#]),
+            Err("/n: expected integer, found number".to_owned())
+        );
+        assert_eq!(
+            validate(schema.clone(), &[r#"{"list": [1, "#, r#"true"#]),
+            Err("/list/1: expected number, found boolean".to_owned())
+        );
+        assert_eq!(
+            validate(schema.clone(), &[r#"{"nested": {"x""#]),
+            Err("/nested/x: property is not allowed".to_owned())
+        );
+        assert_eq!(
+            validate(schema.clone(), &[r#"{"n": 1}"#]),
+            Err("missing required property `a/b`".to_owned())
+        );
+        assert_eq!(
+            validate(schema.clone(), &["[]"]),
+            Err("expected object, found array".to_owned())
+        );
+
+        // long property names are not buffered
+        let key = "k".repeat(MAX_KEY_LEN + 1);
+        assert_eq!(
+            validate(schema.clone(), &["{\"", &key]),
+            Err("property name is longer than 1024 bytes".to_owned())
+        );
+
+        // deeply nested values are not tracked
+        assert_eq!(validate(json!(true), &["[[[[1]]]]"]), Ok(()));
+        assert_eq!(
+            validate(json!(true), &["[[[[", "[1"]),
+            Err("/0/0/0/0: value is nested deeper than 4 levels".to_owned())
+        );
+
+        // syntax errors stop validation
+        assert_eq!(validate(schema, &["{1"]), Err("invalid".to_owned()));
+    }
+
+    #[actix_rt::test]
+    async fn rejects_invalid_bodies_early() {
+        let srv = init_service(
+            App::new()
+                .wrap(JsonSchema::new(json!({
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": { "name": { "type": "string" } },
+                })))
+                .route(
+                    "/",
+                    web::post().to(|body: web::Json<Value>| async move {
+                        body["name"].as_str().unwrap().to_owned()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_json(json!({ "name": "ferris" }))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "ferris");
+
+        // the rest of the body never arrives, so the request is only answered if the missing
+        // field is detected from the first chunk
+        let body =
+            stream::iter([Ok(Bytes::from_static(b"{\"tags\": []}"))]).chain(stream::pending());
+        let req = TestRequest::post()
+            .uri("/")
+            .insert_header(ContentType::json())
+            .to_request();
+        let (req, _) = req.replace_payload(Payload::from(Box::pin(body) as BoxedPayloadStream));
+
+        let err = actix_rt::time::timeout(Duration::from_secs(5), try_call_service(&srv, req))
+            .await
+            .expect("validation should not wait for the end of the body")
+            .unwrap_err();
+        assert_eq!(
+            err.as_error::<SchemaViolation>().unwrap().message(),
+            "missing required property `name`"
+        );
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        // other content types are not validated
+        let req = TestRequest::post().uri("/").set_payload("{}").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
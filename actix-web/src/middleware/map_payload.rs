@@ -0,0 +1,190 @@
+//! For middleware documentation, see [`MapPayload`].
+
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use crate::{
+    dev::{Payload, Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// Transformation of a request payload, applied by the [`MapPayload`] middleware.
+///
+/// Implemented for closures taking and returning a [`Payload`].
+pub trait TransformPayload {
+    /// Returns the payload that replaces `payload` for the rest of the service stack.
+    fn transform(&self, payload: Payload) -> Payload;
+}
+
+impl<F> TransformPayload for F
+where
+    F: Fn(Payload) -> Payload,
+{
+    fn transform(&self, payload: Payload) -> Payload {
+        (self)(payload)
+    }
+}
+
+/// Middleware for transforming request payloads before extractors read them.
+///
+/// The [`TransformPayload`] implementation is handed the payload of each request that has one and
+/// returns the payload that inner services and extractors see in its place. This generalizes
+/// body decoding, such as decompression or decryption, and observing the body as it is read.
+/// Transforms that work chunk by chunk do not buffer the body.
+///
+/// To wrap the payload of individual requests, e.g., based on their headers, from within
+/// [`App::wrap_fn`](crate::App::wrap_fn), see [`ServiceRequest::map_payload`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{dev::Payload, middleware::MapPayload, web, App};
+/// use futures_util::StreamExt as _;
+///
+/// // normalizes CRLF line endings to LF, assuming they are not split across chunks
+/// let normalize = |payload: Payload| -> Payload {
+///     let stream = payload.map(|chunk| {
+///         chunk.map(|chunk| {
+///             let text = String::from_utf8_lossy(&chunk).replace("\r\n", "\n");
+///             web::Bytes::from(text)
+///         })
+///     });
+///
+///     Payload::Stream {
+///         payload: Box::pin(stream),
+///     }
+/// };
+///
+/// let app = App::new()
+///     .wrap(MapPayload::new(normalize))
+///     .route("/", web::post().to(|body: String| async move { body }));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MapPayload<T> {
+    transform: Rc<T>,
+}
+
+impl<T: TransformPayload> MapPayload<T> {
+    /// Constructs a new `MapPayload` middleware that applies `transform` to request payloads.
+    pub fn new(transform: T) -> Self {
+        Self {
+            transform: Rc::new(transform),
+        }
+    }
+}
+
+impl<S, B, T> Transform<S, ServiceRequest> for MapPayload<T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    T: TransformPayload + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = MapPayloadMiddleware<S, T>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(MapPayloadMiddleware {
+            service,
+            transform: Rc::clone(&self.transform),
+        }))
+    }
+}
+
+pub struct MapPayloadMiddleware<S, T> {
+    service: S,
+    transform: Rc<T>,
+}
+
+impl<S, B, T> Service<ServiceRequest> for MapPayloadMiddleware<S, T>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    T: TransformPayload,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if req.has_payload() {
+            let (_, payload) = req.parts_mut();
+            let payload = self.transform.transform(payload.take());
+            req.set_payload(payload);
+        }
+
+        self.service.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_http::BoxedPayloadStream;
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web::{self, Bytes},
+        App,
+    };
+
+    /// "Decrypts" payloads by XOR-ing each byte with a key.
+    struct Xor(u8);
+
+    impl TransformPayload for Xor {
+        fn transform(&self, payload: Payload) -> Payload {
+            let key = self.0;
+
+            let stream = payload.map(move |chunk| {
+                chunk.map(|chunk| chunk.iter().map(|byte| byte ^ key).collect::<Bytes>())
+            });
+
+            Payload::from(Box::pin(stream) as BoxedPayloadStream)
+        }
+    }
+
+    #[actix_rt::test]
+    async fn transforms_payload() {
+        let srv = init_service(
+            App::new()
+                .wrap(MapPayload::new(Xor(0x2a)))
+                .route("/", web::post().to(|body: Bytes| async move { body })),
+        )
+        .await;
+
+        let encrypted = b"secret message"
+            .iter()
+            .map(|byte| byte ^ 0x2a)
+            .collect::<Vec<_>>();
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_payload(encrypted)
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "secret message");
+    }
+
+    #[actix_rt::test]
+    async fn closure_transform() {
+        let srv = init_service(
+            App::new()
+                .wrap(MapPayload::new(|payload: Payload| {
+                    let stream = payload
+                        .map(|chunk| chunk.map(|chunk| chunk.to_ascii_uppercase().into()));
+                    Payload::from(Box::pin(stream) as BoxedPayloadStream)
+                }))
+                .route("/", web::post().to(|body: String| async move { body })),
+        )
+        .await;
+
+        let req = TestRequest::post().uri("/").set_payload("abc").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "ABC");
+    }
+}
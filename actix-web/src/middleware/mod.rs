@@ -1,22 +1,42 @@
 //! A collection of common middleware.
 
+mod body_log;
+mod byte_count;
+mod cache;
 mod compat;
 mod condition;
 mod default_headers;
 mod err_handlers;
+mod etag;
+mod idempotency;
+mod json_schema;
 mod logger;
+mod map_payload;
 #[cfg(test)]
 mod noop;
 mod normalize;
+mod rate_limit;
+mod response_size_limit;
 
+pub use self::body_log::BodyLog;
+pub use self::byte_count::{ByteCount, ByteCounts};
+pub use self::cache::{CacheConfig, ResponseCache};
 pub use self::compat::Compat;
 pub use self::condition::Condition;
 pub use self::default_headers::DefaultHeaders;
 pub use self::err_handlers::{ErrorHandlerResponse, ErrorHandlers};
+pub use self::etag::{AutoETag, ETagConfig};
+pub use self::idempotency::{Idempotency, IdempotencyConfig};
+pub use self::json_schema::{JsonSchema, SchemaViolation};
 pub use self::logger::Logger;
+pub use self::map_payload::{MapPayload, TransformPayload};
 #[cfg(test)]
 pub(crate) use self::noop::Noop;
-pub use self::normalize::{NormalizePath, TrailingSlash};
+pub use self::normalize::{NormalizePath, NormalizePathRedirect, TrailingSlash};
+pub use self::rate_limit::{
+    InMemoryRateLimitStore, Quota, RateLimit, RateLimitStore, TokenBucket,
+};
+pub use self::response_size_limit::{OnExceed, ResponseSizeExceeded, ResponseSizeLimit};
 
 #[cfg(feature = "__compress")]
 mod compress;
@@ -42,6 +62,15 @@ mod tests {
                 Ok(ErrorHandlerResponse::Response(res.map_into_left_body()))
             }))
             .wrap(Logger::default())
+            .wrap(ByteCount::new())
+            .wrap(BodyLog::new())
+            .wrap(AutoETag::default())
+            .wrap(ResponseCache::default())
+            .wrap(Idempotency::default())
+            .wrap(JsonSchema::new(serde_json::Value::Bool(true)))
+            .wrap(MapPayload::new(|payload| payload))
+            .wrap(RateLimit::new(Quota::per_second(10)))
+            .wrap(ResponseSizeLimit::new(1024, OnExceed::Truncate))
             .wrap(NormalizePath::new(TrailingSlash::Trim));
 
         let _ = App::new()
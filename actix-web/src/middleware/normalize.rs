@@ -1,14 +1,18 @@
 //! For middleware documentation, see [`NormalizePath`].
 
+use std::future::{ready, Ready};
+
 use actix_http::uri::{PathAndQuery, Uri};
 use actix_service::{Service, Transform};
-use actix_utils::future::{ready, Ready};
 use bytes::Bytes;
+use futures_util::future::{Either, MapOk, TryFutureExt as _};
 use regex::Regex;
 
 use crate::{
+    body::EitherBody,
+    http::{header, Method, StatusCode},
     service::{ServiceRequest, ServiceResponse},
-    Error,
+    Error, HttpResponse,
 };
 
 /// Determines the behavior of the [`NormalizePath`] middleware.
@@ -47,6 +51,9 @@ impl Default for TrailingSlash {
 ///   slashes as-is, depending on which [`TrailingSlash`] variant is supplied
 ///   to [`new`](NormalizePath::new()).
 ///
+/// The normalized path replaces the request's path before it is routed. To redirect clients to
+/// the normalized path instead, use [`NormalizePathRedirect`].
+///
 /// # Default Behavior
 /// The default constructor chooses to strip trailing slashes from the end of paths with them
 /// ([`TrailingSlash::Trim`]). The implication is that route definitions should be defined without
@@ -111,6 +118,31 @@ impl NormalizePath {
     pub fn trim() -> Self {
         Self::new(TrailingSlash::Trim)
     }
+
+    /// Turns this into a [`NormalizePathRedirect`] middleware, which redirects requests whose path
+    /// is not normalized to the normalized path instead of rewriting it.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::header, middleware, test, web, App};
+    ///
+    /// # actix_web::rt::System::new().block_on(async {
+    /// let app = test::init_service(
+    ///     App::new()
+    ///         .wrap(middleware::NormalizePath::trim().use_redirects())
+    ///         .route("/items", web::get().to(|| async { "items" })),
+    /// )
+    /// .await;
+    ///
+    /// let req = test::TestRequest::with_uri("/items/?page=2").to_request();
+    /// let res = test::call_service(&app, req).await;
+    /// assert_eq!(res.status(), 301);
+    /// assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/items?page=2");
+    /// # })
+    /// ```
+    pub fn use_redirects(self) -> NormalizePathRedirect {
+        NormalizePathRedirect(self.0)
+    }
 }
 
 impl<S, B> Transform<S, ServiceRequest> for NormalizePath
@@ -153,56 +185,177 @@ where
     fn call(&self, mut req: ServiceRequest) -> Self::Future {
         let head = req.head_mut();
 
-        let original_path = head.uri.path();
-
-        // An empty path here means that the URI has no valid path. We skip normalization in this
-        // case, because adding a path can make the URI invalid
-        if !original_path.is_empty() {
-            // Either adds a string to the end (duplicates will be removed anyways) or trims all
-            // slashes from the end
-            let path = match self.trailing_slash_behavior {
-                TrailingSlash::Always => format!("{}/", original_path),
-                TrailingSlash::MergeOnly => original_path.to_string(),
-                TrailingSlash::Trim => original_path.trim_end_matches('/').to_string(),
-            };
-
-            // normalize multiple /'s to one /
-            let path = self.merge_slash.replace_all(&path, "/");
-
-            // Ensure root paths are still resolvable. If resulting path is blank after previous
-            // step it means the path was one or more slashes. Reduce to single slash.
-            let path = if path.is_empty() { "/" } else { path.as_ref() };
-
-            // Check whether the path has been changed
-            //
-            // This check was previously implemented as string length comparison
-            //
-            // That approach fails when a trailing slash is added,
-            // and a duplicate slash is removed,
-            // since the length of the strings remains the same
-            //
-            // For example, the path "/v1//s" will be normalized to "/v1/s/"
-            // Both of the paths have the same length,
-            // so the change can not be deduced from the length comparison
-            if path != original_path {
-                let mut parts = head.uri.clone().into_parts();
-                let query = parts.path_and_query.as_ref().and_then(|pq| pq.query());
-
-                let path = match query {
-                    Some(q) => Bytes::from(format!("{}?{}", path, q)),
-                    None => Bytes::copy_from_slice(path.as_bytes()),
-                };
-                parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
-
-                let uri = Uri::from_parts(parts).unwrap();
-                req.match_info_mut().get_mut().update(&uri);
-                req.head_mut().uri = uri;
-            }
+        if let Some(path) =
+            normalize(&head.uri, &self.merge_slash, self.trailing_slash_behavior)
+        {
+            let mut parts = head.uri.clone().into_parts();
+            parts.path_and_query = Some(PathAndQuery::from_maybe_shared(path).unwrap());
+
+            let uri = Uri::from_parts(parts).unwrap();
+            req.match_info_mut().get_mut().update(&uri);
+            req.head_mut().uri = uri;
         }
+
         self.service.call(req)
     }
 }
 
+/// Middleware for redirecting requests whose path is not normalized to the normalized path.
+///
+/// Paths are normalized in the same way as by [`NormalizePath`], which this middleware is
+/// constructed from with [`NormalizePath::use_redirects`]. Instead of rewriting the request's
+/// path, clients are redirected to the normalized path, so that every resource is only reachable
+/// under its canonical URL.
+///
+/// `GET` and `HEAD` requests are redirected with `301 Moved Permanently`. Requests with other
+/// methods are redirected with `308 Permanent Redirect`, which makes clients repeat them with the
+/// same method and body. The query string is kept. Requests whose path is already normalized are
+/// passed on unchanged.
+///
+/// Backslashes in the path are percent-encoded in the `Location` header, since browsers treat
+/// them like slashes and would otherwise resolve a path such as `/\example.com` to another host.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizePathRedirect(TrailingSlash);
+
+impl<S, B> Transform<S, ServiceRequest> for NormalizePathRedirect
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = NormalizePathRedirection<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(NormalizePathRedirection {
+            service,
+            merge_slash: Regex::new("//+").unwrap(),
+            trailing_slash_behavior: self.0,
+        }))
+    }
+}
+
+pub struct NormalizePathRedirection<S> {
+    service: S,
+    merge_slash: Regex,
+    trailing_slash_behavior: TrailingSlash,
+}
+
+impl<S, B> Service<ServiceRequest> for NormalizePathRedirection<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Either<
+        MapOk<S::Future, fn(ServiceResponse<B>) -> Self::Response>,
+        Ready<Result<Self::Response, Self::Error>>,
+    >;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let path = normalize(
+            &req.head().uri,
+            &self.merge_slash,
+            self.trailing_slash_behavior,
+        );
+
+        let path = match path {
+            Some(path) => path,
+            None => {
+                return Either::Left(
+                    self.service
+                        .call(req)
+                        .map_ok(ServiceResponse::map_into_left_body as fn(_) -> _),
+                )
+            }
+        };
+
+        let status = match *req.method() {
+            Method::GET | Method::HEAD => StatusCode::MOVED_PERMANENTLY,
+            _ => StatusCode::PERMANENT_REDIRECT,
+        };
+
+        let res = HttpResponse::build(status)
+            .insert_header((header::LOCATION, redirect_location(&path)))
+            .finish();
+
+        Either::Right(ready(Ok(req.into_response(res).map_into_right_body())))
+    }
+}
+
+/// Returns the normalized path and query of `uri`, or `None` if its path is already normalized.
+fn normalize(uri: &Uri, merge_slash: &Regex, trailing_slash: TrailingSlash) -> Option<Bytes> {
+    let original_path = uri.path();
+
+    // An empty path here means that the URI has no valid path. We skip normalization in this
+    // case, because adding a path can make the URI invalid
+    if original_path.is_empty() {
+        return None;
+    }
+
+    // Either adds a string to the end (duplicates will be removed anyways) or trims all
+    // slashes from the end
+    let path = match trailing_slash {
+        TrailingSlash::Always => format!("{}/", original_path),
+        TrailingSlash::MergeOnly => original_path.to_string(),
+        TrailingSlash::Trim => original_path.trim_end_matches('/').to_string(),
+    };
+
+    // normalize multiple /'s to one /
+    let path = merge_slash.replace_all(&path, "/");
+
+    // Ensure root paths are still resolvable. If resulting path is blank after previous
+    // step it means the path was one or more slashes. Reduce to single slash.
+    let path = if path.is_empty() { "/" } else { path.as_ref() };
+
+    // Check whether the path has been changed
+    //
+    // This check was previously implemented as string length comparison
+    //
+    // That approach fails when a trailing slash is added,
+    // and a duplicate slash is removed,
+    // since the length of the strings remains the same
+    //
+    // For example, the path "/v1//s" will be normalized to "/v1/s/"
+    // Both of the paths have the same length,
+    // so the change can not be deduced from the length comparison
+    if path == original_path {
+        return None;
+    }
+
+    Some(match uri.query() {
+        Some(q) => Bytes::from(format!("{}?{}", path, q)),
+        None => Bytes::copy_from_slice(path.as_bytes()),
+    })
+}
+
+/// Returns the `Location` of a redirect to the normalized path and query `path`.
+///
+/// Backslashes in the path are percent-encoded, so that clients cannot resolve the location to
+/// another host.
+fn redirect_location(path: &[u8]) -> Bytes {
+    let query_start = path.iter().position(|&b| b == b'?').unwrap_or(path.len());
+    let (path, query) = path.split_at(query_start);
+
+    let mut location = Vec::with_capacity(path.len() + query.len());
+
+    for &b in path {
+        match b {
+            b'\\' => location.extend_from_slice(b"%5C"),
+            _ => location.push(b),
+        }
+    }
+
+    location.extend_from_slice(query);
+    Bytes::from(location)
+}
+
 #[cfg(test)]
 mod tests {
     use actix_http::StatusCode;
@@ -486,4 +639,71 @@ mod tests {
         let res = normalize.call(req).await.unwrap();
         assert!(res.status().is_success());
     }
+
+    #[actix_rt::test]
+    async fn redirects() {
+        let tests = [
+            (
+                TrailingSlash::Trim,
+                "/v1//something/?query=test",
+                "/v1/something?query=test",
+            ),
+            (TrailingSlash::Trim, "//", "/"),
+            (
+                TrailingSlash::Always,
+                "/v1/something?query=test",
+                "/v1/something/?query=test",
+            ),
+            (TrailingSlash::Always, "//v1//something//", "/v1/something/"),
+            (
+                TrailingSlash::MergeOnly,
+                "//v1//something/?a=1&b=2",
+                "/v1/something/?a=1&b=2",
+            ),
+        ];
+
+        for (trailing_slash, uri, location) in tests {
+            let app = init_service(
+                App::new()
+                    .wrap(NormalizePath::new(trailing_slash).use_redirects())
+                    .default_service(web::to(HttpResponse::Ok)),
+            )
+            .await;
+
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY, "{}", uri);
+            assert_eq!(res.headers().get(header::LOCATION).unwrap(), location);
+
+            // method and body must be kept by the client
+            let req = TestRequest::post().uri(uri).to_request();
+            let res = call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT, "{}", uri);
+            assert_eq!(res.headers().get(header::LOCATION).unwrap(), location);
+
+            // normalized paths are not redirected
+            let req = TestRequest::post().uri(location).to_request();
+            let res = call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", location);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn redirect_encodes_backslashes() {
+        let app = init_service(
+            App::new()
+                .wrap(NormalizePath::trim().use_redirects())
+                .default_service(web::to(HttpResponse::Ok)),
+        )
+        .await;
+
+        // browsers would resolve `/\evil.com` to `//evil.com`, i.e., another host
+        let req = TestRequest::with_uri("/\\evil.com/?q=\\").to_request();
+        let res = call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "/%5Cevil.com?q=\\"
+        );
+    }
 }
@@ -0,0 +1,478 @@
+//! For middleware documentation, see [`RateLimit`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::{ready, Ready},
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::{Either, MapOk, TryFutureExt as _};
+
+use crate::{
+    body::EitherBody,
+    dev::{Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error, HttpResponse,
+};
+
+type KeyFn = dyn Fn(&ServiceRequest) -> Option<String>;
+
+/// Number of requests allowed by the [`RateLimit`] middleware for each key.
+///
+/// Each key has a bucket of tokens that holds up to `burst` tokens and is refilled at a steady
+/// rate. Every request takes a token from the bucket of its key; requests for which no token is
+/// left are throttled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    interval: Duration,
+    burst: u32,
+}
+
+impl Quota {
+    /// Constructs a quota that allows `requests` requests per `period`.
+    ///
+    /// The burst size defaults to `requests`, so that all requests of a period can be made at once.
+    ///
+    /// # Panics
+    /// Panics if `requests` is zero.
+    pub fn new(requests: u32, period: Duration) -> Self {
+        assert!(requests > 0, "quota must allow at least one request");
+
+        Self {
+            interval: period / requests,
+            burst: requests,
+        }
+    }
+
+    /// Constructs a quota that allows `requests` requests per second.
+    ///
+    /// # Panics
+    /// Panics if `requests` is zero.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Constructs a quota that allows `requests` requests per minute.
+    ///
+    /// # Panics
+    /// Panics if `requests` is zero.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(60))
+    }
+
+    /// Sets the number of requests that can be made at once, after a key has been idle.
+    ///
+    /// # Panics
+    /// Panics if `burst` is zero.
+    pub fn burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "burst size must be at least one request");
+        self.burst = burst;
+        self
+    }
+
+    /// Returns the time it takes to refill one token.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the burst size.
+    pub fn burst_size(&self) -> u32 {
+        self.burst
+    }
+}
+
+/// A token bucket of a single key, usable by [`RateLimitStore`] implementations.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Constructs a full bucket for `quota`.
+    pub fn new(quota: Quota, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(quota.burst),
+            updated: now,
+        }
+    }
+
+    /// Takes a token from the bucket, refilling it for the time passed since the last call.
+    ///
+    /// Returns how long it takes until a token is available if the bucket is empty.
+    pub fn take(&mut self, quota: Quota, now: Instant) -> Result<(), Duration> {
+        self.refill(quota, now);
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(quota.interval.mul_f64(1.0 - self.tokens))
+        }
+    }
+
+    /// Returns true if the bucket is full at `now`, meaning it is equal to a new bucket.
+    pub fn is_full(&self, quota: Quota, now: Instant) -> bool {
+        let mut bucket = *self;
+        bucket.refill(quota, now);
+        bucket.tokens >= f64::from(quota.burst)
+    }
+
+    fn refill(&mut self, quota: Quota, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        let refilled = elapsed.as_secs_f64() / quota.interval.as_secs_f64();
+
+        self.tokens = (self.tokens + refilled).min(f64::from(quota.burst));
+        self.updated = now;
+    }
+}
+
+/// Storage of the token buckets used by the [`RateLimit`] middleware.
+///
+/// Implemented for [`InMemoryRateLimitStore`], the default, and for `Arc`s of stores, which allows
+/// sharing a store between the workers of a server.
+pub trait RateLimitStore {
+    /// Takes a token from the bucket of `key` for a request received at `now`.
+    ///
+    /// Returns how long it takes until a token is available if the bucket is empty. Keys without a
+    /// bucket are treated as having a full one, see [`TokenBucket`].
+    fn take(&self, key: &str, quota: Quota, now: Instant) -> Result<(), Duration>;
+}
+
+impl<T: RateLimitStore + ?Sized> RateLimitStore for Arc<T> {
+    fn take(&self, key: &str, quota: Quota, now: Instant) -> Result<(), Duration> {
+        (**self).take(key, quota, now)
+    }
+}
+
+/// A [`RateLimitStore`] that holds token buckets in memory.
+///
+/// Buckets that have refilled completely are removed as the store grows, so that memory usage is
+/// proportional to the number of recently active keys.
+#[derive(Debug)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<Buckets>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    map: HashMap<String, TokenBucket>,
+
+    /// Number of buckets at which full buckets are removed next.
+    purge_at: usize,
+}
+
+impl InMemoryRateLimitStore {
+    const MIN_PURGE_AT: usize = 1024;
+
+    /// Constructs a new, empty store.
+    pub fn new() -> Self {
+        Self {
+            buckets: Mutex::new(Buckets {
+                map: HashMap::new(),
+                purge_at: Self::MIN_PURGE_AT,
+            }),
+        }
+    }
+}
+
+impl Default for InMemoryRateLimitStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn take(&self, key: &str, quota: Quota, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if let Some(bucket) = buckets.map.get_mut(key) {
+            return bucket.take(quota, now);
+        }
+
+        if buckets.map.len() >= buckets.purge_at {
+            buckets.map.retain(|_, bucket| !bucket.is_full(quota, now));
+            buckets.purge_at = (buckets.map.len() * 2).max(Self::MIN_PURGE_AT);
+        }
+
+        let mut bucket = TokenBucket::new(quota, now);
+        let res = bucket.take(quota, now);
+        buckets.map.insert(key.to_owned(), bucket);
+
+        res
+    }
+}
+
+/// Middleware for limiting the rate of requests per client.
+///
+/// Requests are grouped by a key, by default the IP address of the peer. Each key is allowed the
+/// number of requests given by its [`Quota`]. Requests beyond it are responded to with `429 Too
+/// Many Requests` and a `Retry-After` header telling when the next request is allowed, without
+/// calling the wrapped service.
+///
+/// The key function can be replaced with [`key`](Self::key), e.g. to limit requests by API key
+/// or user ID. Requests for which it returns `None` are not limited.
+///
+/// Token buckets are held in an [`InMemoryRateLimitStore`] by default. Since the middleware is
+/// constructed for each worker, each worker then limits requests separately. Pass the same
+/// `Arc`'d store to [`store`](Self::store) in every worker to share limits between them.
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{Quota, RateLimit},
+///     web, App,
+/// };
+///
+/// let app = App::new()
+///     .wrap(
+///         RateLimit::new(Quota::per_minute(60).burst(10)).key(|req| {
+///             req.headers()
+///                 .get("x-api-key")
+///                 .and_then(|key| key.to_str().ok())
+///                 .map(ToOwned::to_owned)
+///         }),
+///     )
+///     .route("/", web::get().to(|| async { "Hello, World!" }));
+/// ```
+#[derive(Clone)]
+pub struct RateLimit {
+    quota: Quota,
+    key: Rc<KeyFn>,
+    store: Rc<dyn RateLimitStore>,
+}
+
+impl RateLimit {
+    /// Constructs a new `RateLimit` middleware that allows `quota` requests per peer IP address.
+    pub fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            key: Rc::new(|req| req.peer_addr().map(|addr| addr.ip().to_string())),
+            store: Rc::new(InMemoryRateLimitStore::new()),
+        }
+    }
+
+    /// Sets the function that returns the key requests are limited by.
+    pub fn key<F>(mut self, key: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<String> + 'static,
+    {
+        self.key = Rc::new(key);
+        self
+    }
+
+    /// Sets the store that holds the token buckets.
+    pub fn store<T: RateLimitStore + 'static>(mut self, store: T) -> Self {
+        self.store = Rc::new(store);
+        self
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("quota", &self.quota)
+            .finish()
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            quota: self.quota,
+            key: Rc::clone(&self.key),
+            store: Rc::clone(&self.store),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    quota: Quota,
+    key: Rc<KeyFn>,
+    store: Rc<dyn RateLimitStore>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    #[allow(clippy::type_complexity)]
+    type Future = Either<
+        MapOk<S::Future, fn(ServiceResponse<B>) -> Self::Response>,
+        Ready<Result<Self::Response, Self::Error>>,
+    >;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(key) = (self.key)(&req) {
+            if let Err(wait) = self.store.take(&key, self.quota, Instant::now()) {
+                let res = HttpResponse::TooManyRequests().retry_after(wait).finish();
+                return Either::Right(ready(Ok(req.into_response(res).map_into_right_body())));
+            }
+        }
+
+        Either::Left(
+            self.service
+                .call(req)
+                .map_ok(ServiceResponse::map_into_left_body as fn(_) -> _),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{call_service, init_service, TestRequest},
+        web, App,
+    };
+
+    #[test]
+    fn token_bucket() {
+        let quota = Quota::new(1, Duration::from_secs(10)).burst(2);
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(quota, start);
+
+        assert!(bucket.take(quota, start).is_ok());
+        assert!(bucket.take(quota, start).is_ok());
+        assert_eq!(bucket.take(quota, start), Err(Duration::from_secs(10)));
+
+        let later = start + Duration::from_secs(4);
+        assert_eq!(bucket.take(quota, later), Err(Duration::from_secs(6)));
+
+        let later = start + Duration::from_secs(10);
+        assert!(bucket.take(quota, later).is_ok());
+        assert!(bucket.take(quota, later).is_err());
+
+        // refills up to the burst size only
+        let later = start + Duration::from_secs(100);
+        assert!(bucket.is_full(quota, later));
+        assert!(bucket.take(quota, later).is_ok());
+        assert!(bucket.take(quota, later).is_ok());
+        assert!(bucket.take(quota, later).is_err());
+    }
+
+    #[test]
+    fn in_memory_store() {
+        let quota = Quota::per_minute(1);
+        let store = InMemoryRateLimitStore::new();
+        let now = Instant::now();
+
+        assert!(store.take("a", quota, now).is_ok());
+        assert!(store.take("a", quota, now).is_err());
+        assert!(store.take("b", quota, now).is_ok());
+
+        // full buckets are removed once the store grows
+        let later = now + Duration::from_secs(60);
+        for i in 0..InMemoryRateLimitStore::MIN_PURGE_AT - 2 {
+            assert!(store.take(&i.to_string(), quota, later).is_ok());
+        }
+        assert!(store.buckets.lock().unwrap().map.contains_key("a"));
+        assert!(store.take("new", quota, later).is_ok());
+        assert!(!store.buckets.lock().unwrap().map.contains_key("a"));
+        assert!(store.take("0", quota, later).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn throttles_beyond_burst() {
+        let srv = init_service(
+            App::new()
+                .wrap(RateLimit::new(
+                    Quota::new(1, Duration::from_millis(200)).burst(2),
+                ))
+                .route("/", web::get().to(|| async { "ok" })),
+        )
+        .await;
+
+        let req = || {
+            TestRequest::default()
+                .peer_addr("127.0.0.1:8080".parse().unwrap())
+                .to_request()
+        };
+
+        for _ in 0..2 {
+            let res = call_service(&srv, req()).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        let res = call_service(&srv, req()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "1");
+
+        // other clients have their own quota
+        let other = TestRequest::default()
+            .peer_addr("127.0.0.2:8080".parse().unwrap())
+            .to_request();
+        let res = call_service(&srv, other).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // recovers after a token is refilled
+        actix_rt::time::sleep(Duration::from_millis(250)).await;
+        let res = call_service(&srv, req()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = call_service(&srv, req()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[actix_rt::test]
+    async fn custom_key_and_shared_store() {
+        let store = Arc::new(InMemoryRateLimitStore::new());
+
+        let srv = init_service(
+            App::new()
+                .wrap(
+                    RateLimit::new(Quota::per_minute(1))
+                        .key(|req| {
+                            req.headers()
+                                .get("x-api-key")
+                                .map(|key| key.to_str().unwrap().to_owned())
+                        })
+                        .store(Arc::clone(&store)),
+                )
+                .route("/", web::get().to(|| async { "ok" })),
+        )
+        .await;
+
+        let req = |key: &str| TestRequest::default().insert_header(("x-api-key", key));
+
+        let res = call_service(&srv, req("a").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let res = call_service(&srv, req("a").to_request()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "60");
+
+        // requests without a key are not limited
+        for _ in 0..3 {
+            let res = call_service(&srv, TestRequest::default().to_request()).await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        // the store is shared
+        assert!(store
+            .take("a", Quota::per_minute(1), Instant::now())
+            .is_err());
+        assert!(store
+            .take("b", Quota::per_minute(1), Instant::now())
+            .is_ok());
+    }
+}
@@ -0,0 +1,300 @@
+//! For middleware documentation, see [`ResponseSizeLimit`].
+
+use std::{
+    error::Error as StdError,
+    future::{ready, Future, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use derive_more::{Display, Error};
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BodySize, MessageBody},
+    dev::{Service, Transform},
+    service::{ServiceRequest, ServiceResponse},
+    Error,
+};
+
+/// What the [`ResponseSizeLimit`] middleware does with a response body that exceeds its cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnExceed {
+    /// End the body after the first `cap` bytes and log a warning.
+    ///
+    /// The body ends cleanly, so clients cannot tell that it was truncated unless they know its
+    /// expected length by other means.
+    Truncate,
+
+    /// Fail the body with a [`ResponseSizeExceeded`] error, which closes the connection.
+    ///
+    /// Bytes up to the chunk that would exceed the cap are sent before the connection is closed.
+    CloseConnection,
+}
+
+/// Error that fails a response body that exceeds the cap of the [`ResponseSizeLimit`] middleware.
+#[derive(Debug, Display, Error)]
+#[display(fmt = "Response body exceeded the limit of {} bytes", cap)]
+#[non_exhaustive]
+pub struct ResponseSizeExceeded {
+    /// Maximum number of bytes allowed in the response body.
+    pub cap: usize,
+}
+
+/// Middleware for enforcing a maximum response body size.
+///
+/// Bytes of the response body are counted as they are sent, so streaming bodies keep streaming
+/// and are only cut short once they produce more than the cap. Bodies of a known size larger than
+/// the cap are announced with the truncated size under [`OnExceed::Truncate`].
+///
+/// Sizes are measured at the position of this middleware in the service stack. To limit
+/// compressed rather than uncompressed response sizes, register this middleware after (i.e.,
+/// outside of) [`Compress`].
+///
+/// [`Compress`]: crate::middleware::Compress
+///
+/// # Examples
+/// ```
+/// use actix_web::{
+///     middleware::{OnExceed, ResponseSizeLimit},
+///     App,
+/// };
+///
+/// // no response may be larger than 10 MiB
+/// let app = App::new().wrap(ResponseSizeLimit::new(10 * 1024 * 1024, OnExceed::CloseConnection));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ResponseSizeLimit {
+    cap: usize,
+    on_exceed: OnExceed,
+}
+
+impl ResponseSizeLimit {
+    /// Constructs a new `ResponseSizeLimit` middleware that allows at most `cap` bytes of each
+    /// response body.
+    pub fn new(cap: usize, on_exceed: OnExceed) -> Self {
+        Self { cap, on_exceed }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ResponseSizeLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<LimitedBody<B>>;
+    type Error = Error;
+    type Transform = ResponseSizeLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ResponseSizeLimitMiddleware {
+            service,
+            limit: *self,
+        }))
+    }
+}
+
+pub struct ResponseSizeLimitMiddleware<S> {
+    service: S,
+    limit: ResponseSizeLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for ResponseSizeLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<LimitedBody<B>>;
+    type Error = Error;
+    type Future = ResponseSizeLimitFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        ResponseSizeLimitFuture {
+            fut: self.service.call(req),
+            limit: self.limit,
+            _body: PhantomData,
+        }
+    }
+}
+
+pin_project! {
+    pub struct ResponseSizeLimitFuture<S: Service<ServiceRequest>, B> {
+        #[pin]
+        fut: S::Future,
+        limit: ResponseSizeLimit,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<S, B> Future for ResponseSizeLimitFuture<S, B>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Output = Result<ServiceResponse<LimitedBody<B>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let res = ready!(this.fut.poll(cx))?;
+
+        let limit = *this.limit;
+
+        Poll::Ready(Ok(res.map_body(move |_, body| LimitedBody {
+            body,
+            remaining: limit.cap,
+            limit,
+            done: false,
+        })))
+    }
+}
+
+pin_project! {
+    /// Response body that is capped by the [`ResponseSizeLimit`] middleware.
+    pub struct LimitedBody<B> {
+        #[pin]
+        body: B,
+        remaining: usize,
+        limit: ResponseSizeLimit,
+        done: bool,
+    }
+}
+
+impl<B: MessageBody> MessageBody for LimitedBody<B> {
+    type Error = Box<dyn StdError>;
+
+    fn size(&self) -> BodySize {
+        match self.body.size() {
+            BodySize::Sized(size)
+                if size > self.limit.cap as u64
+                    && self.limit.on_exceed == OnExceed::Truncate =>
+            {
+                BodySize::Sized(self.limit.cap as u64)
+            }
+            size => size,
+        }
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let mut chunk = match ready!(this.body.poll_next(cx)) {
+            Some(Ok(chunk)) => chunk,
+            Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+            None => return Poll::Ready(None),
+        };
+
+        if chunk.len() <= *this.remaining {
+            *this.remaining -= chunk.len();
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+
+        *this.done = true;
+        let cap = this.limit.cap;
+
+        match this.limit.on_exceed {
+            OnExceed::Truncate => {
+                log::warn!(
+                    "response body exceeded the limit of {} bytes and was truncated",
+                    cap
+                );
+
+                chunk.truncate(*this.remaining);
+                *this.remaining = 0;
+
+                if chunk.is_empty() {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+
+            OnExceed::CloseConnection => {
+                Poll::Ready(Some(Err(Box::new(ResponseSizeExceeded { cap }))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+
+    use super::*;
+    use crate::{
+        body,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    /// Streams "0123456789" in chunks of two bytes.
+    async fn streaming() -> HttpResponse {
+        let chunks = vec!["01", "23", "45", "67", "89"];
+
+        HttpResponse::Ok().streaming(stream::iter(
+            chunks
+                .into_iter()
+                .map(|chunk| Ok::<_, Error>(Bytes::from_static(chunk.as_bytes()))),
+        ))
+    }
+
+    #[actix_rt::test]
+    async fn truncate() {
+        let srv = init_service(
+            App::new()
+                .wrap(ResponseSizeLimit::new(5, OnExceed::Truncate))
+                .route("/stream", web::get().to(streaming))
+                .route("/sized", web::get().to(|| async { "0123456789" }))
+                .route("/small", web::get().to(|| async { "01234" })),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/stream").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.response().body().size(), BodySize::Stream);
+        assert_eq!(read_body(res).await, "01234");
+
+        let req = TestRequest::get().uri("/sized").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.response().body().size(), BodySize::Sized(5));
+        assert_eq!(read_body(res).await, "01234");
+
+        let req = TestRequest::get().uri("/small").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "01234");
+    }
+
+    #[actix_rt::test]
+    async fn close_connection() {
+        let srv = init_service(
+            App::new()
+                .wrap(ResponseSizeLimit::new(5, OnExceed::CloseConnection))
+                .route("/stream", web::get().to(streaming))
+                .route("/small", web::get().to(|| async { "01234" })),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/stream").to_request();
+        let res = call_service(&srv, req).await;
+
+        let err = body::to_bytes(res.into_body()).await.unwrap_err();
+        let err = err.downcast_ref::<ResponseSizeExceeded>().unwrap();
+        assert_eq!(err.cap, 5);
+
+        let req = TestRequest::get().uri("/small").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "01234");
+    }
+}
@@ -5,7 +5,10 @@ use std::{
     str,
 };
 
-use actix_http::{Message, RequestHead};
+use actix_http::{
+    h2::{PushPromise, ServerPush},
+    Message, RequestHead, ResponseDisconnect,
+};
 use actix_router::{Path, Url};
 use actix_utils::future::{ok, Ready};
 #[cfg(feature = "cookies")]
@@ -181,6 +184,62 @@ impl HttpRequest {
             .and_then(|container| container.get::<T>())
     }
 
+    /// Initiates an HTTP/2 server push of a resource related to this request.
+    ///
+    /// Returns a [`PushPromise`] through which the response to the promised request is supplied.
+    /// The promise is sent to the client just before the response to this request, so the handler
+    /// must supply the pushed response before returning.
+    ///
+    /// Returns `None` when the connection does not support server push (i.e., HTTP/1.x) or if
+    /// `path` is not a valid URI path. Pushes refused by an HTTP/2 client are silently dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_web::{http::{header::HeaderMap, Method}, HttpRequest, HttpResponse};
+    /// async fn index(req: HttpRequest) -> HttpResponse {
+    ///     if let Some(promise) = req.push(Method::GET, "/style.css", HeaderMap::new()) {
+    ///         promise.respond(HttpResponse::Ok().content_type("text/css").body("body {}"));
+    ///     }
+    ///
+    ///     HttpResponse::Ok().body(r#"<link rel="stylesheet" href="/style.css">"#)
+    /// }
+    /// ```
+    ///
+    /// [`PushPromise`]: crate::dev::PushPromise
+    pub fn push(&self, method: Method, path: &str, headers: HeaderMap) -> Option<PushPromise> {
+        self.extensions()
+            .get::<ServerPush>()
+            .and_then(|push| push.promise(method, path, headers).ok())
+    }
+
+    /// Returns a future that resolves if the client disconnects before the response to this
+    /// request has been fully sent.
+    ///
+    /// This allows producers of long-running streaming responses to stop early. The future never
+    /// resolves when the connection does not support disconnect detection (i.e., HTTP/2) or in
+    /// unit tests.
+    ///
+    /// # Examples
+    /// ```
+    /// # use actix_web::{HttpRequest, HttpResponse};
+    /// async fn export(req: HttpRequest) -> HttpResponse {
+    ///     let disconnect = req.on_response_disconnect();
+    ///
+    ///     actix_web::rt::spawn(async move {
+    ///         disconnect.await;
+    ///         log::info!("client went away; cancelling export");
+    ///     });
+    ///
+    ///     HttpResponse::Ok().body("id,name\n")
+    /// }
+    /// ```
+    pub fn on_response_disconnect(&self) -> ResponseDisconnect {
+        self.extensions()
+            .get::<ResponseDisconnect>()
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Generates URL for a named resource.
     ///
     /// This substitutes in sequence all URL parameters that appear in the resource itself and in
@@ -254,6 +313,10 @@ impl HttpRequest {
     pub fn connection_info(&self) -> Ref<'_, ConnectionInfo> {
         if !self.extensions().contains::<ConnectionInfo>() {
             let info = ConnectionInfo::new(self.head(), self.app_config());
+
+            #[cfg(unix)]
+            let info = info.with_peer_credentials(self.conn_data().copied());
+
             self.extensions_mut().insert(info);
         }
 
@@ -332,6 +395,20 @@ impl HttpRequest {
         }))
     }
 
+    /// Load request cookies for modification.
+    ///
+    /// Changes are visible to later calls to [`cookies`](Self::cookies) and
+    /// [`cookie`](Self::cookie) on this request, but are not sent to the client.
+    #[cfg(feature = "cookies")]
+    pub fn cookies_mut(&self) -> Result<RefMut<'_, Vec<Cookie<'static>>>, CookieParseError> {
+        // ensure cookies are parsed and cached
+        drop(self.cookies()?);
+
+        Ok(RefMut::map(self.extensions_mut(), |ext| {
+            &mut ext.get_mut::<Cookies>().unwrap().0
+        }))
+    }
+
     /// Return request cookie.
     #[cfg(feature = "cookies")]
     pub fn cookie(&self, name: &str) -> Option<Cookie<'static>> {
@@ -344,6 +421,30 @@ impl HttpRequest {
         }
         None
     }
+
+    /// Return request cookie after verifying its signature with `key`.
+    ///
+    /// Returns `None` if the cookie is missing or its value has been tampered with. The returned
+    /// cookie contains the original, unsigned value. See
+    /// [`HttpResponseBuilder::signed_cookie`](crate::HttpResponseBuilder::signed_cookie).
+    #[cfg(feature = "secure-cookies")]
+    pub fn signed_cookie(&self, name: &str, key: &cookie::Key) -> Option<Cookie<'static>> {
+        let mut jar = cookie::CookieJar::new();
+        jar.add_original(self.cookie(name)?);
+        jar.signed(key).get(name)
+    }
+
+    /// Return request cookie after decrypting and authenticating its value with `key`.
+    ///
+    /// Returns `None` if the cookie is missing or its value has been tampered with. The returned
+    /// cookie contains the original, decrypted value. See
+    /// [`HttpResponseBuilder::private_cookie`](crate::HttpResponseBuilder::private_cookie).
+    #[cfg(feature = "secure-cookies")]
+    pub fn private_cookie(&self, name: &str, key: &cookie::Key) -> Option<Cookie<'static>> {
+        let mut jar = cookie::CookieJar::new();
+        jar.add_original(self.cookie(name)?);
+        jar.private(key).get(name)
+    }
 }
 
 impl HttpMessage for HttpRequest {
@@ -557,6 +658,55 @@ mod tests {
 
         let cookie = req.cookie("cookie-unknown");
         assert!(cookie.is_none());
+
+        req.cookies_mut()
+            .unwrap()
+            .push(cookie::Cookie::new("cookie3", "value3"));
+        assert_eq!(req.cookies().unwrap().len(), 3);
+        assert_eq!(req.cookie("cookie3").unwrap().value(), "value3");
+    }
+
+    #[test]
+    #[cfg(feature = "secure-cookies")]
+    fn test_signed_cookies() {
+        use cookie::{Cookie, Key};
+
+        let key = Key::generate();
+
+        let res = HttpResponse::Ok()
+            .signed_cookie(Cookie::new("signed", "value"), &key)
+            .private_cookie(Cookie::new("private", "secret"), &key)
+            .finish();
+
+        let mut cookies = res.cookies().collect::<Vec<_>>();
+        cookies.sort_by(|a, b| a.name().cmp(b.name()));
+        assert_eq!(cookies.len(), 2);
+        assert_ne!(cookies[0].value(), "secret");
+        assert_ne!(cookies[1].value(), "value");
+
+        let header = cookies
+            .iter()
+            .map(|c| c.stripped().to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        let req = TestRequest::default()
+            .insert_header((header::COOKIE, header))
+            .to_http_request();
+
+        assert_eq!(req.signed_cookie("signed", &key).unwrap().value(), "value");
+        assert_eq!(
+            req.private_cookie("private", &key).unwrap().value(),
+            "secret"
+        );
+        assert!(req.signed_cookie("signed", &Key::generate()).is_none());
+        assert!(req.signed_cookie("missing", &key).is_none());
+
+        // tamper with the signed value
+        let tampered = format!("signed={}x", cookies[1].value());
+        let req = TestRequest::default()
+            .insert_header((header::COOKIE, tampered))
+            .to_http_request();
+        assert!(req.signed_cookie("signed", &key).is_none());
     }
 
     #[test]
@@ -651,6 +801,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_push_unsupported() {
+        let req = TestRequest::default().to_http_request();
+        assert!(req
+            .push(Method::GET, "/style.css", HeaderMap::new())
+            .is_none());
+    }
+
     #[actix_rt::test]
     async fn test_drop_http_request_pool() {
         let srv = init_service(App::new().service(web::resource("/").to(
@@ -13,9 +13,9 @@ use crate::{
     body::MessageBody,
     data::Data,
     dev::{ensure_leading_slash, AppService, ResourceDef},
-    guard::{self, Guard},
+    guard::Guard,
     handler::Handler,
-    http::header,
+    http::{header, Method},
     route::{Route, RouteService},
     service::{
         BoxedHttpService, BoxedHttpServiceFactory, HttpServiceFactory, ServiceRequest,
@@ -54,6 +54,7 @@ pub struct Resource<T = ResourceEndpoint> {
     app_data: Option<Extensions>,
     guards: Vec<Box<dyn Guard>>,
     default: BoxedHttpServiceFactory,
+    auto_head: bool,
     factory_ref: Rc<RefCell<Option<ResourceFactory>>>,
 }
 
@@ -69,15 +70,12 @@ impl Resource {
             factory_ref: fref,
             guards: Vec::new(),
             app_data: None,
+            auto_head: false,
             default: boxed::factory(fn_service(|req: ServiceRequest| async {
-                use crate::HttpMessage as _;
-
-                let allowed = req.extensions().get::<guard::RegisteredMethods>().cloned();
-
-                if let Some(methods) = allowed {
+                if let Some(methods) = req.allowed_methods() {
                     Ok(req.into_response(
                         HttpResponse::MethodNotAllowed()
-                            .insert_header(header::Allow(methods.0))
+                            .insert_header(header::Allow(methods))
                             .finish(),
                     ))
                 } else {
@@ -245,6 +243,30 @@ where
         self
     }
 
+    /// Sets whether `HEAD` requests are handled by this resource's `GET` routes.
+    ///
+    /// When enabled, a `HEAD` request that does not match any route is retried against the routes
+    /// as if it were a `GET` request. The matched handler still sees the `HEAD` method. The
+    /// response headers, including `Content-Length`, are preserved and its body is discarded by
+    /// the server without being sent.
+    ///
+    /// Disabled by default.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// let app = App::new().service(
+    ///     web::resource("/")
+    ///         .auto_head(true)
+    ///         .route(web::get().to(|| async { HttpResponse::Ok().body("not sent for HEAD") })),
+    /// );
+    /// ```
+    pub fn auto_head(mut self, enabled: bool) -> Self {
+        self.auto_head = enabled;
+        self
+    }
+
     /// Registers a resource middleware.
     ///
     /// `mw` is a middleware component (type), that can modify the request and response across all
@@ -283,6 +305,7 @@ where
             routes: self.routes,
             default: self.default,
             app_data: self.app_data,
+            auto_head: self.auto_head,
             factory_ref: self.factory_ref,
         }
     }
@@ -321,6 +344,7 @@ where
             routes: self.routes,
             default: self.default,
             app_data: self.app_data,
+            auto_head: self.auto_head,
             factory_ref: self.factory_ref,
         }
     }
@@ -396,9 +420,20 @@ where
             rdef.set_name(name);
         }
 
+        for (methods, schema) in self.routes.iter().filter_map(Route::body_schema_info) {
+            for pattern in rdef.pattern_iter() {
+                config.schema_registry().record(
+                    format!("{}{}", config.path_prefix(), pattern),
+                    methods.to_vec(),
+                    schema.clone(),
+                );
+            }
+        }
+
         *self.factory_ref.borrow_mut() = Some(ResourceFactory {
             routes: self.routes,
             default: self.default,
+            auto_head: self.auto_head,
         });
 
         let resource_data = self.app_data.map(Rc::new);
@@ -421,6 +456,7 @@ where
 pub struct ResourceFactory {
     routes: Vec<Route>,
     default: BoxedHttpServiceFactory,
+    auto_head: bool,
 }
 
 impl ServiceFactory<ServiceRequest> for ResourceFactory {
@@ -438,6 +474,8 @@ impl ServiceFactory<ServiceRequest> for ResourceFactory {
         // construct route service factory futures
         let factory_fut = join_all(self.routes.iter().map(|route| route.new_service(())));
 
+        let auto_head = self.auto_head;
+
         Box::pin(async move {
             let default = default_fut.await?;
             let routes = factory_fut
@@ -445,7 +483,11 @@ impl ServiceFactory<ServiceRequest> for ResourceFactory {
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()?;
 
-            Ok(ResourceService { routes, default })
+            Ok(ResourceService {
+                routes,
+                default,
+                auto_head,
+            })
         })
     }
 }
@@ -453,6 +495,7 @@ impl ServiceFactory<ServiceRequest> for ResourceFactory {
 pub struct ResourceService {
     routes: Vec<RouteService>,
     default: BoxedHttpService,
+    auto_head: bool,
 }
 
 impl Service<ServiceRequest> for ResourceService {
@@ -469,6 +512,17 @@ impl Service<ServiceRequest> for ResourceService {
             }
         }
 
+        if self.auto_head && req.method() == Method::HEAD {
+            // match routes as a GET request but let handler see the original method
+            req.head_mut().method = Method::GET;
+            let route = self.routes.iter().find(|route| route.check(&mut req));
+            req.head_mut().method = Method::HEAD;
+
+            if let Some(route) = route {
+                return route.call(req);
+            }
+        }
+
         self.default.call(req)
     }
 }
@@ -515,7 +569,7 @@ mod tests {
         middleware::DefaultHeaders,
         service::{ServiceRequest, ServiceResponse},
         test::{call_service, init_service, TestRequest},
-        web, App, Error, HttpMessage, HttpResponse,
+        web, App, Error, HttpMessage, HttpRequest, HttpResponse,
     };
 
     #[test]
@@ -683,6 +737,48 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[actix_rt::test]
+    async fn test_default_service_allowed_methods() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/test")
+                        .route(web::get().to(HttpResponse::Ok))
+                        .default_service(fn_service(|mut req: ServiceRequest| async move {
+                            assert!(req.has_payload());
+                            req.drain_payload(1024).await?;
+                            assert!(!req.has_payload());
+
+                            let res = match req.allowed_methods() {
+                                Some(methods) => HttpResponse::MethodNotAllowed()
+                                    .insert_header(header::Allow(methods))
+                                    .finish(),
+                                None => HttpResponse::NotFound().finish(),
+                            };
+
+                            Ok::<_, Error>(req.into_response(res))
+                        })),
+                )
+                .default_service(fn_service(|req: ServiceRequest| async move {
+                    assert!(req.allowed_methods().is_none());
+                    Ok::<_, Error>(req.into_response(HttpResponse::NotFound()))
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/test")
+            .method(Method::POST)
+            .set_payload("body")
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(resp.headers().get(header::ALLOW).unwrap(), "GET");
+
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_resource_guards() {
         let srv = init_service(
@@ -724,6 +820,60 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::NO_CONTENT);
     }
 
+    #[actix_rt::test]
+    async fn test_auto_head() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::resource("/auto")
+                        .auto_head(true)
+                        .route(web::get().to(|req: HttpRequest| {
+                            HttpResponse::Ok()
+                                .insert_header(("x-method", req.method().as_str()))
+                                .body("get")
+                        }))
+                        .route(web::head().to(HttpResponse::NoContent)),
+                )
+                .service(web::resource("/manual").route(web::get().to(HttpResponse::Ok)))
+                .service(
+                    web::resource("/auto-get")
+                        .auto_head(true)
+                        .route(web::get().to(|req: HttpRequest| {
+                            HttpResponse::Ok()
+                                .insert_header(("x-method", req.method().as_str()))
+                                .body("get")
+                        })),
+                ),
+        )
+        .await;
+
+        // explicit HEAD routes take precedence
+        let req = TestRequest::with_uri("/auto")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+        let req = TestRequest::with_uri("/manual")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = TestRequest::with_uri("/auto-get")
+            .method(Method::HEAD)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("x-method").unwrap(), "HEAD");
+
+        let req = TestRequest::with_uri("/auto-get")
+            .method(Method::POST)
+            .to_request();
+        let resp = call_service(&srv, req).await;
+        assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    }
+
     // allow deprecated `{App, Resource}::data`
     #[allow(deprecated)]
     #[actix_rt::test]
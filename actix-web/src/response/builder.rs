@@ -4,28 +4,36 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use actix_http::{error::HttpError, Response, ResponseHead};
 use bytes::Bytes;
-use futures_core::Stream;
+use futures_core::{ready, Stream};
+use futures_util::StreamExt as _;
+use pin_project_lite::pin_project;
 use serde::Serialize;
 
 use crate::{
     body::{BodyStream, BoxBody, MessageBody},
     dev::Extensions,
     error::{Error, JsonPayloadError},
-    http::header::{self, HeaderName, TryIntoHeaderPair, TryIntoHeaderValue},
+    helpers::append_vary,
+    http::header::{self, HeaderName, HttpDate, TryIntoHeaderPair, TryIntoHeaderValue},
     http::{ConnectionType, StatusCode},
     BoxError, HttpRequest, HttpResponse, Responder,
 };
 
+use super::{paced::PacedBody, sniff::SniffContentType};
+
 /// An HTTP response builder.
 ///
 /// This type can be used to construct an instance of `Response` through a builder-like pattern.
 pub struct HttpResponseBuilder {
     res: Option<Response<BoxBody>>,
     error: Option<HttpError>,
+    max_rate: Option<u64>,
+    sniff_content_type: bool,
 }
 
 impl HttpResponseBuilder {
@@ -35,6 +43,8 @@ impl HttpResponseBuilder {
         Self {
             res: Some(Response::with_body(status, BoxBody::new(()))),
             error: None,
+            max_rate: None,
+            sniff_content_type: false,
         }
     }
 
@@ -215,6 +225,234 @@ impl HttpResponseBuilder {
         self
     }
 
+    /// Set the `Retry-After` header to a delay after which the request can be retried.
+    ///
+    /// The delay is sent in seconds, rounding up fractions of a second so that clients do not
+    /// retry early. Typically used with `429 Too Many Requests` and `503 Service Unavailable`
+    /// responses.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix_web::{http::header, HttpResponse};
+    ///
+    /// let res = HttpResponse::TooManyRequests()
+    ///     .retry_after(Duration::from_secs(120))
+    ///     .finish();
+    ///
+    /// assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "120");
+    /// ```
+    pub fn retry_after(&mut self, delay: Duration) -> &mut Self {
+        let secs = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+
+        let mut buf = itoa::Buffer::new();
+        self.insert_header((header::RETRY_AFTER, buf.format(secs)))
+    }
+
+    /// Set the `Retry-After` header to a date after which the request can be retried.
+    ///
+    /// See [`retry_after`](Self::retry_after) for setting a delay instead.
+    pub fn retry_after_date(&mut self, date: HttpDate) -> &mut Self {
+        self.insert_header((header::RETRY_AFTER, date))
+    }
+
+    /// Add a request header name to the `Vary` header.
+    ///
+    /// The name is only added if it is not listed already, so responses that depend on several
+    /// request headers can call this once per header, from different places, without sending
+    /// duplicate entries. Names are compared ignoring ASCII case, and nothing is added if the
+    /// response already varies on `*`. Values set before, e.g., with
+    /// [`insert_header`](Self::insert_header), are kept.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::header, HttpResponse};
+    ///
+    /// let res = HttpResponse::Ok()
+    ///     .vary(header::ACCEPT_LANGUAGE)
+    ///     .vary(header::ACCEPT)
+    ///     .vary(header::ACCEPT_LANGUAGE)
+    ///     .finish();
+    ///
+    /// let vary = res.headers().get_all(header::VARY).collect::<Vec<_>>();
+    /// assert_eq!(vary, ["accept-language", "accept"]);
+    /// ```
+    pub fn vary(&mut self, name: HeaderName) -> &mut Self {
+        if let Some(parts) = self.inner() {
+            append_vary(&mut parts.headers, &name);
+        }
+
+        self
+    }
+
+    /// Append a link to the `Link` header, as defined in RFC 8288.
+    ///
+    /// Each call adds a `<uri>; rel="rel"` entry to the same comma-separated header, which also
+    /// absorbs any `Link` headers that were appended before. This is useful for pointing clients
+    /// to the next and previous pages of a paginated collection. Characters that are not allowed
+    /// in a URI reference are percent-encoded and `rel` is sent as a quoted string; it may contain
+    /// several space-separated relation types.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::header, HttpResponse};
+    ///
+    /// let res = HttpResponse::Ok()
+    ///     .link("/items?page=3", "next")
+    ///     .link("/items?page=1", "prev")
+    ///     .finish();
+    ///
+    /// assert_eq!(
+    ///     res.headers().get(header::LINK).unwrap(),
+    ///     r#"</items?page=3>; rel="next", </items?page=1>; rel="prev""#,
+    /// );
+    /// ```
+    pub fn link(&mut self, uri: &str, rel: &str) -> &mut Self {
+        let parts = match self.inner() {
+            Some(parts) => parts,
+            None => return self,
+        };
+
+        // fold any existing `Link` headers into the single header value
+        let mut value = Vec::new();
+        for prev in parts.headers.get_all(header::LINK) {
+            value.extend_from_slice(prev.as_bytes());
+            value.extend_from_slice(b", ");
+        }
+
+        value.push(b'<');
+        for &byte in uri.as_bytes() {
+            match byte {
+                b'<' | b'>' | b'"' | b'\\' | b'^' | b'`' | b'{' | b'|' | b'}' => {
+                    value.extend_from_slice(format!("%{:02X}", byte).as_bytes())
+                }
+                0x21..=0x7E => value.push(byte),
+                _ => value.extend_from_slice(format!("%{:02X}", byte).as_bytes()),
+            }
+        }
+        value.extend_from_slice(b">; rel=\"");
+        for &byte in rel.as_bytes() {
+            if byte == b'"' || byte == b'\\' {
+                value.push(b'\\');
+            }
+            value.push(byte);
+        }
+        value.push(b'"');
+
+        self.insert_header((header::LINK, value))
+    }
+
+    /// Set the `Content-Disposition` header to offer the response as a download named `filename`.
+    ///
+    /// The header carries a `filename` parameter in which non-ASCII and control characters are
+    /// replaced with `_`, for clients that do not support RFC 6266. If any characters had to be
+    /// replaced, the exact name is also sent, percent-encoded as UTF-8, in a `filename*` parameter
+    /// as defined in RFC 5987. Characters that could end the header or the parameter are never
+    /// sent as is, so untrusted file names can be passed safely.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{http::header, HttpResponse};
+    ///
+    /// let res = HttpResponse::Ok().attachment("résumé.pdf").body("...");
+    ///
+    /// assert_eq!(
+    ///     res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+    ///     r#"attachment; filename="r_sum_.pdf"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"#,
+    /// );
+    /// ```
+    pub fn attachment(&mut self, filename: impl AsRef<str>) -> &mut Self {
+        let filename = filename.as_ref();
+
+        let fallback = filename
+            .chars()
+            .map(|c| match c {
+                ' '..='~' => c,
+                _ => '_',
+            })
+            .collect::<String>();
+
+        let mut parameters = Vec::with_capacity(2);
+
+        if fallback != filename {
+            parameters.push(header::DispositionParam::FilenameExt(
+                header::ExtendedValue {
+                    charset: header::Charset::Ext(String::from("UTF-8")),
+                    language_tag: None,
+                    value: filename.as_bytes().to_vec(),
+                },
+            ));
+        }
+
+        parameters.insert(0, header::DispositionParam::Filename(fallback));
+
+        self.insert_header(header::ContentDisposition {
+            disposition: header::DispositionType::Attachment,
+            parameters,
+        })
+    }
+
+    /// Limit the rate at which the response body is sent to `bytes_per_sec` bytes per second.
+    ///
+    /// The body is yielded in pieces that are paced with the runtime's timer, so a body that is
+    /// produced faster than the rate is not polled again until the pending bytes have been sent.
+    /// This keeps a single large response from saturating the link at the cost of others. The
+    /// limit applies to the body set with [`body`](Self::body) and the methods built on top of it,
+    /// but not to [`message_body`](Self::message_body), whose body type is kept as is.
+    ///
+    /// # Panics
+    /// Panics if `bytes_per_sec` is 0.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::HttpResponse;
+    ///
+    /// // send at most 1 MiB per second
+    /// let res = HttpResponse::Ok()
+    ///     .max_rate(1024 * 1024)
+    ///     .body(vec![0; 4 * 1024 * 1024]);
+    /// ```
+    pub fn max_rate(&mut self, bytes_per_sec: u64) -> &mut Self {
+        assert!(bytes_per_sec > 0, "max rate must be greater than 0");
+        self.max_rate = Some(bytes_per_sec);
+        self
+    }
+
+    /// Sets whether the `Content-Type` header is detected from the start of the body.
+    ///
+    /// When enabled and no content type has been set, the first chunks of the body are read and
+    /// held back before the response is sent, until they are enough to recognize common formats:
+    /// PNG, JPEG, GIF and PDF signatures, JSON documents and UTF-8 text. Signatures are recognized
+    /// from their first bytes and binary data from its first non-text byte; text is only assumed
+    /// once 512 bytes have been seen or the body has ended. Anything else is sent as
+    /// `application/octet-stream`.
+    ///
+    /// Detection applies to the body set with [`body`](Self::body) and the methods built on top of
+    /// it, such as [`streaming`](Self::streaming), and happens when the response is returned from a
+    /// handler.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{web::Bytes, Error, HttpResponse};
+    /// use futures_util::stream;
+    ///
+    /// async fn image() -> HttpResponse {
+    ///     let chunks = vec![
+    ///         Ok::<_, Error>(Bytes::from_static(b"\x89PNG\r\n\x1a\n")),
+    ///         Ok(Bytes::from_static(b"...")),
+    ///     ];
+    ///
+    ///     // sent as `image/png`
+    ///     HttpResponse::Ok()
+    ///         .sniff_content_type(true)
+    ///         .streaming(stream::iter(chunks))
+    /// }
+    /// ```
+    pub fn sniff_content_type(&mut self, enabled: bool) -> &mut Self {
+        self.sniff_content_type = enabled;
+        self
+    }
+
     /// Add a cookie to the response.
     ///
     /// To send a "removal" cookie, call [`.make_removal()`](cookie::Cookie::make_removal) on the
@@ -263,6 +501,64 @@ impl HttpResponseBuilder {
         }
     }
 
+    /// Add all changed cookies of a cookie jar to the response.
+    ///
+    /// A `Set-Cookie` header is appended for every cookie in the jar's
+    /// [delta](cookie::CookieJar::delta), including removal cookies.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{HttpResponse, cookie::{Cookie, CookieJar}};
+    ///
+    /// let mut jar = CookieJar::new();
+    /// jar.add(Cookie::new("name", "value"));
+    /// jar.add(Cookie::new("theme", "dark"));
+    ///
+    /// let res = HttpResponse::Ok().cookie_jar(&jar).finish();
+    /// assert_eq!(res.cookies().count(), 2);
+    /// ```
+    #[cfg(feature = "cookies")]
+    pub fn cookie_jar(&mut self, jar: &cookie::CookieJar) -> &mut Self {
+        for cookie in jar.delta() {
+            self.cookie(cookie.clone());
+        }
+        self
+    }
+
+    /// Add a cookie to the response, signed with `key`.
+    ///
+    /// The cookie value remains readable by the client but cannot be modified without
+    /// invalidating the signature. Use [`HttpRequest::signed_cookie`] to read it back.
+    ///
+    /// [`HttpRequest::signed_cookie`]: crate::HttpRequest::signed_cookie
+    #[cfg(feature = "secure-cookies")]
+    pub fn signed_cookie(
+        &mut self,
+        cookie: cookie::Cookie<'_>,
+        key: &cookie::Key,
+    ) -> &mut Self {
+        let mut jar = cookie::CookieJar::new();
+        jar.signed_mut(key).add(cookie.into_owned());
+        self.cookie_jar(&jar)
+    }
+
+    /// Add a cookie to the response, encrypted and authenticated with `key`.
+    ///
+    /// The cookie value can neither be read nor modified by the client. Use
+    /// [`HttpRequest::private_cookie`] to read it back.
+    ///
+    /// [`HttpRequest::private_cookie`]: crate::HttpRequest::private_cookie
+    #[cfg(feature = "secure-cookies")]
+    pub fn private_cookie(
+        &mut self,
+        cookie: cookie::Cookie<'_>,
+        key: &cookie::Key,
+    ) -> &mut Self {
+        let mut jar = cookie::CookieJar::new();
+        jar.private_mut(key).add(cookie.into_owned());
+        self.cookie_jar(&jar)
+    }
+
     /// Returns a reference to the response-local data/extensions container.
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -291,10 +587,24 @@ impl HttpResponseBuilder {
     where
         B: MessageBody + 'static,
     {
-        match self.message_body(body) {
-            Ok(res) => res.map_into_boxed_body(),
-            Err(err) => HttpResponse::from_error(err),
+        let max_rate = self.max_rate.take();
+        let sniff_content_type = std::mem::take(&mut self.sniff_content_type);
+
+        let mut res = match self.message_body(body) {
+            Ok(res) => match max_rate {
+                Some(rate) => res
+                    .map_body(|_, body| PacedBody::new(body, rate))
+                    .map_into_boxed_body(),
+                None => res.map_into_boxed_body(),
+            },
+            Err(err) => return HttpResponse::from_error(err),
+        };
+
+        if sniff_content_type && !res.headers().contains_key(header::CONTENT_TYPE) {
+            res.extensions_mut().insert(SniffContentType);
         }
+
+        res
     }
 
     /// Set a body and build the `HttpResponse`.
@@ -326,6 +636,42 @@ impl HttpResponseBuilder {
         self.body(BodyStream::new(stream))
     }
 
+    /// Set a streaming body from a stream of items and build the `HttpResponse`.
+    ///
+    /// Each item yielded by `stream` is passed to `formatter`, which returns the framed bytes to
+    /// write for that item. This is useful for line-delimited or server-sent event (SSE) outputs.
+    ///
+    /// If the stream yields an error, the response body is terminated and the connection is closed
+    /// since the client has no reliable way to detect a partially sent body.
+    ///
+    /// Unlike a plain `Stream<Item = T>`, items are `Result`s so that the stream has a way to
+    /// report that error. Items of an infallible source can be wrapped in `Ok::<_, Infallible>`,
+    /// as in the example below.
+    ///
+    /// `HttpResponseBuilder` can not be used after this call.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::convert::Infallible;
+    /// use actix_web::{http::header::ContentType, HttpResponse};
+    /// use futures_util::stream;
+    ///
+    /// let events = stream::iter([1, 2, 3].map(Ok::<_, Infallible>));
+    ///
+    /// let res = HttpResponse::Ok()
+    ///     .content_type(ContentType(mime::TEXT_EVENT_STREAM))
+    ///     .stream_items(events, |n| format!("data: {}\n\n", n));
+    /// ```
+    pub fn stream_items<S, T, E, F, B>(&mut self, stream: S, mut formatter: F) -> HttpResponse
+    where
+        S: Stream<Item = Result<T, E>> + 'static,
+        E: Into<BoxError> + 'static,
+        F: FnMut(T) -> B + 'static,
+        B: Into<Bytes>,
+    {
+        self.streaming(stream.map(move |item| item.map(|item| formatter(item).into())))
+    }
+
     /// Set a JSON body and build the `HttpResponse`.
     ///
     /// `HttpResponseBuilder` can not be used after this call.
@@ -348,6 +694,47 @@ impl HttpResponseBuilder {
         }
     }
 
+    /// Set a streaming JSON array body from a stream of items and build the `HttpResponse`.
+    ///
+    /// Items are serialized as they are yielded by `stream` and written as the elements of a
+    /// single JSON array, so the collection never has to be buffered in full. The `Content-Type`
+    /// header is set to `application/json` unless already present.
+    ///
+    /// If an item fails to serialize, the response body is terminated and the connection is
+    /// closed since the partially sent JSON can not be completed.
+    ///
+    /// `HttpResponseBuilder` can not be used after this call.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::HttpResponse;
+    /// use futures_util::stream;
+    ///
+    /// let res = HttpResponse::Ok().json_stream(stream::iter(["v1", "v2", "v3"]));
+    /// ```
+    pub fn json_stream<S, T>(&mut self, stream: S) -> HttpResponse
+    where
+        S: Stream<Item = T> + 'static,
+        T: Serialize,
+    {
+        let contains = if let Some(parts) = self.inner() {
+            parts.headers.contains_key(header::CONTENT_TYPE)
+        } else {
+            true
+        };
+
+        if !contains {
+            self.insert_header((header::CONTENT_TYPE, mime::APPLICATION_JSON));
+        }
+
+        self.streaming(JsonArrayStream {
+            stream,
+            started: false,
+            first: true,
+            done: false,
+        })
+    }
+
     /// Set an empty body and build the `HttpResponse`.
     ///
     /// `HttpResponseBuilder` can not be used after this call.
@@ -361,6 +748,8 @@ impl HttpResponseBuilder {
         Self {
             res: self.res.take(),
             error: self.error.take(),
+            max_rate: self.max_rate.take(),
+            sniff_content_type: std::mem::take(&mut self.sniff_content_type),
         }
     }
 
@@ -373,6 +762,58 @@ impl HttpResponseBuilder {
     }
 }
 
+pin_project! {
+    /// Serializes the items of a stream as the elements of a JSON array.
+    struct JsonArrayStream<S> {
+        #[pin]
+        stream: S,
+        started: bool,
+        first: bool,
+        done: bool,
+    }
+}
+
+impl<S, T> Stream for JsonArrayStream<S>
+where
+    S: Stream<Item = T>,
+    T: Serialize,
+{
+    type Item = Result<Bytes, JsonPayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        if !*this.started {
+            *this.started = true;
+            return Poll::Ready(Some(Ok(Bytes::from_static(b"["))));
+        }
+
+        match ready!(this.stream.poll_next(cx)) {
+            Some(item) => {
+                let mut buf = if *this.first { Vec::new() } else { vec![b','] };
+                *this.first = false;
+
+                match serde_json::to_writer(&mut buf, &item) {
+                    Ok(()) => Poll::Ready(Some(Ok(Bytes::from(buf)))),
+                    Err(err) => {
+                        *this.done = true;
+                        Poll::Ready(Some(Err(JsonPayloadError::Serialize(err))))
+                    }
+                }
+            }
+
+            None => {
+                *this.done = true;
+                Poll::Ready(Some(Ok(Bytes::from_static(b"]"))))
+            }
+        }
+    }
+}
+
 impl From<HttpResponseBuilder> for HttpResponse {
     fn from(mut builder: HttpResponseBuilder) -> Self {
         builder.finish()
@@ -450,6 +891,138 @@ mod tests {
         assert_eq!(resp.headers().get(CONTENT_TYPE).unwrap(), "text/plain")
     }
 
+    #[test]
+    fn test_retry_after() {
+        let res = HttpResponse::ServiceUnavailable()
+            .retry_after(Duration::from_secs(30))
+            .finish();
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "30");
+
+        let res = HttpResponse::TooManyRequests()
+            .retry_after(Duration::from_millis(1500))
+            .finish();
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "2");
+
+        let date = std::time::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        let res = HttpResponse::ServiceUnavailable()
+            .retry_after_date(date.into())
+            .finish();
+        assert_eq!(
+            res.headers().get(header::RETRY_AFTER).unwrap(),
+            "Sun, 09 Sep 2001 01:46:40 GMT"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_max_rate() {
+        let start = std::time::Instant::now();
+
+        let res = HttpResponse::Ok()
+            .max_rate(10_000)
+            .streaming(futures_util::stream::iter(
+                (0..4).map(|_| Ok::<_, Error>(Bytes::from(vec![b'a'; 500]))),
+            ));
+        assert_eq!(res.body().size(), body::BodySize::Stream);
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, vec![b'a'; 2_000]);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_link() {
+        let res = HttpResponse::Ok()
+            .link("https://example.com/items?page=3", "next")
+            .link("/items?page=1", "prev")
+            .finish();
+
+        let mut links = res.headers().get_all(header::LINK);
+        assert_eq!(
+            links.next().unwrap(),
+            r#"<https://example.com/items?page=3>; rel="next", </items?page=1>; rel="prev""#
+        );
+        assert!(links.next().is_none());
+
+        let res = HttpResponse::Ok()
+            .append_header((header::LINK, "</style.css>; rel=\"preload\""))
+            .append_header((header::LINK, "</app.js>; rel=\"preload\""))
+            .link("/items?page=2", "next")
+            .finish();
+
+        let mut links = res.headers().get_all(header::LINK);
+        assert_eq!(
+            links.next().unwrap(),
+            r#"</style.css>; rel="preload", </app.js>; rel="preload", </items?page=2>; rel="next""#
+        );
+        assert!(links.next().is_none());
+
+        let res = HttpResponse::Ok().link("/a b/<ü>", r#"x "y" \z"#).finish();
+        assert_eq!(
+            res.headers().get(header::LINK).unwrap(),
+            r#"</a%20b/%3C%C3%BC%3E>; rel="x \"y\" \\z""#
+        );
+
+        let res = HttpResponse::Ok().link("/", "next\r\n").finish();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_vary() {
+        let res = HttpResponse::Ok()
+            .insert_header((header::VARY, "Accept-Language, Origin"))
+            .vary(header::ACCEPT)
+            .vary(header::ORIGIN)
+            .vary(header::ACCEPT)
+            .vary(header::ACCEPT_ENCODING)
+            .finish();
+
+        let vary = res.headers().get_all(header::VARY).collect::<Vec<_>>();
+        assert_eq!(
+            vary,
+            ["Accept-Language, Origin", "accept", "accept-encoding"]
+        );
+
+        let res = HttpResponse::Ok()
+            .insert_header((header::VARY, "*"))
+            .vary(header::ACCEPT)
+            .finish();
+
+        let vary = res.headers().get_all(header::VARY).collect::<Vec<_>>();
+        assert_eq!(vary, ["*"]);
+    }
+
+    #[test]
+    fn test_attachment() {
+        let res = HttpResponse::Ok().attachment("report 2023.csv").finish();
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            r#"attachment; filename="report 2023.csv""#
+        );
+
+        let res = HttpResponse::Ok().attachment("návrh \"v2\".pdf").finish();
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            r#"attachment; filename="n_vrh \"v2\".pdf"; filename*=UTF-8''n%C3%A1vrh%20%22v2%22.pdf"#
+        );
+
+        let res = HttpResponse::Ok().attachment("日本語.txt").finish();
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"___.txt\"; filename*=UTF-8''%E6%97%A5%E6%9C%AC%E8%AA%9E.txt"
+        );
+
+        let res = HttpResponse::Ok()
+            .attachment("a.txt\r\nSet-Cookie: id=1")
+            .finish();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::SET_COOKIE).is_none());
+        assert_eq!(
+            res.headers().get(header::CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"a.txt__Set-Cookie: id=1\"; \
+             filename*=UTF-8''a.txt%0D%0ASet%2DCookie%3A%20id=1"
+        );
+    }
+
     #[actix_rt::test]
     async fn test_json() {
         let res = HttpResponse::Ok().json(vec!["v1", "v2", "v3"]);
@@ -471,6 +1044,42 @@ mod tests {
         assert_body_eq!(res, br#"["v1","v2","v3"]"#);
     }
 
+    #[actix_rt::test]
+    async fn test_stream_items() {
+        let stream = futures_util::stream::iter((1..=3).map(Ok::<_, Error>));
+        let res = HttpResponse::Ok().stream_items(stream, |n| format!("data: {}\n\n", n));
+        assert_body_eq!(res, b"data: 1\n\ndata: 2\n\ndata: 3\n\n");
+
+        let stream = futures_util::stream::iter([
+            Ok(1),
+            Err(crate::error::ErrorInternalServerError("stream failed")),
+            Ok(3),
+        ]);
+        let res = HttpResponse::Ok().stream_items(stream, |n| format!("data: {}\n\n", n));
+        body::to_bytes(res.into_body()).await.unwrap_err();
+    }
+
+    #[actix_rt::test]
+    async fn test_json_stream() {
+        let stream = futures_util::stream::iter(0..1000);
+        let res = HttpResponse::Ok().json_stream(stream);
+        let ct = res.headers().get(CONTENT_TYPE).unwrap();
+        assert_eq!(ct, HeaderValue::from_static("application/json"));
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        let items: Vec<u32> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(items, (0..1000).collect::<Vec<_>>());
+
+        let res = HttpResponse::Ok().json_stream(futures_util::stream::empty::<u32>());
+        assert_body_eq!(res, b"[]");
+
+        // maps with non-string keys can not be serialized to JSON
+        let invalid = std::collections::BTreeMap::from([((1, 2), 3)]);
+        let stream = futures_util::stream::iter([Default::default(), invalid]);
+        let res = HttpResponse::Ok().json_stream(stream);
+        body::to_bytes(res.into_body()).await.unwrap_err();
+    }
+
     #[actix_rt::test]
     async fn test_serde_json_in_body() {
         let resp = HttpResponse::Ok().body(
@@ -1,14 +1,22 @@
 mod builder;
 mod customize_responder;
 mod http_codes;
+mod negotiate;
+mod paced;
+mod ranged;
 mod responder;
 #[allow(clippy::module_inception)]
 mod response;
+mod sniff;
 
 pub use self::builder::HttpResponseBuilder;
 pub use self::customize_responder::CustomizeResponder;
+pub use self::negotiate::Negotiate;
+pub use self::ranged::{RangeSource, Ranged, RangedBody};
 pub use self::responder::Responder;
 pub use self::response::HttpResponse;
 
+pub(crate) use self::sniff::sniff_content_type;
+
 #[cfg(feature = "cookies")]
 pub use self::response::CookieIter;
@@ -0,0 +1,269 @@
+//! See [`Negotiate`] for responder documentation.
+
+use std::fmt;
+
+use actix_http::header::Quality;
+use bytes::Bytes;
+use mime::Mime;
+use serde::Serialize;
+
+use crate::{
+    body::EitherBody,
+    error::{Error, JsonPayloadError},
+    http::header::{self, Accept, Header as _},
+    HttpRequest, HttpResponse, Responder,
+};
+
+type Serializer<T> = Box<dyn FnOnce(&T) -> Result<Bytes, Error>>;
+
+/// A responder that serializes a value in the format preferred by the request's `Accept` header.
+///
+/// Formats are registered with [`format`](Self::format), or [`json`](Self::json) for JSON, each
+/// with the media type it produces. The format is chosen as follows:
+/// - Each format is weighted by the quality value of the most specific `Accept` media range that
+///   matches it, so that `application/json; q=0.1, application/*` prefers other `application`
+///   formats over JSON.
+/// - The format with the highest quality value is used. Ties go to the format registered first.
+/// - Without a (valid) `Accept` header, the first registered format is used.
+/// - If no format is acceptable, the response is `406 Not Acceptable`.
+///
+/// Responses carry a `Content-Type` header with the media type of the chosen format and a `Vary:
+/// Accept` header. If the serializer fails, its error is responded with instead.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web::Negotiate, Responder};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// async fn handler() -> impl Responder {
+///     let user = User { name: "ferris".to_owned() };
+///
+///     Negotiate::new(user)
+///         .json()
+///         .format(mime::TEXT_XML, |user| {
+///             Ok::<_, actix_web::Error>(format!("<user><name>{}</name></user>", user.name))
+///         })
+/// }
+/// # actix_web::web::to(handler);
+/// ```
+pub struct Negotiate<T> {
+    value: T,
+    formats: Vec<(Mime, Serializer<T>)>,
+}
+
+impl<T> Negotiate<T> {
+    /// Constructs a new `Negotiate` responder for a value, without any formats.
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            formats: Vec::new(),
+        }
+    }
+
+    /// Registers a format with the media type it produces and a function serializing the value.
+    pub fn format<F, R, E>(mut self, mime: Mime, serialize: F) -> Self
+    where
+        F: FnOnce(&T) -> Result<R, E> + 'static,
+        R: Into<Bytes>,
+        E: Into<Error>,
+    {
+        let serialize = move |value: &T| serialize(value).map(Into::into).map_err(Into::into);
+        self.formats.push((mime, Box::new(serialize)));
+        self
+    }
+
+    /// Registers JSON as a format, serialized with `serde_json`.
+    pub fn json(self) -> Self
+    where
+        T: Serialize,
+    {
+        self.format(mime::APPLICATION_JSON, |value| {
+            serde_json::to_vec(value).map_err(JsonPayloadError::Serialize)
+        })
+    }
+}
+
+impl<T> fmt::Debug for Negotiate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Negotiate")
+            .field(
+                "formats",
+                &self
+                    .formats
+                    .iter()
+                    .map(|(mime, _)| mime)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<T> Responder for Negotiate<T> {
+    type Body = EitherBody<Bytes>;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let chosen = match Accept::parse(req) {
+            Ok(accept) if !accept.is_empty() => {
+                let mut chosen = None;
+                let mut max_quality = Quality::ZERO;
+
+                for (idx, (mime, _)) in self.formats.iter().enumerate() {
+                    // only change if strictly greater so that ties go to the earlier format
+                    match quality(&accept, mime) {
+                        Some(quality) if quality > max_quality => {
+                            max_quality = quality;
+                            chosen = Some(idx);
+                        }
+                        _ => {}
+                    }
+                }
+
+                chosen
+            }
+
+            // no preference
+            _ if self.formats.is_empty() => None,
+            _ => Some(0),
+        };
+
+        let (mime, serialize) = match chosen {
+            Some(idx) => self.formats.into_iter().nth(idx).unwrap(),
+
+            None => {
+                return HttpResponse::NotAcceptable()
+                    .insert_header((header::VARY, "accept"))
+                    .finish()
+                    .map_into_right_body();
+            }
+        };
+
+        match serialize(&self.value) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(mime)
+                .insert_header((header::VARY, "accept"))
+                .message_body(body)
+                .unwrap()
+                .map_into_left_body(),
+
+            Err(err) => HttpResponse::from_error(err).map_into_right_body(),
+        }
+    }
+}
+
+/// Returns the quality value of the most specific media range in `accept` that matches `mime`.
+fn quality(accept: &Accept, mime: &Mime) -> Option<Quality> {
+    accept
+        .iter()
+        .filter_map(|range| {
+            let specificity = match (range.item.type_(), range.item.subtype()) {
+                (mime::STAR, _) => 0,
+                (ty, mime::STAR) if ty == mime.type_() => 1,
+                (ty, subtype) if ty == mime.type_() && subtype == mime.subtype() => 2,
+                _ => return None,
+            };
+
+            Some((specificity, range.quality))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, quality)| quality)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+    use crate::{body::to_bytes, error, http::StatusCode, test::TestRequest};
+
+    #[derive(Serialize)]
+    struct User {
+        name: &'static str,
+    }
+
+    fn negotiate() -> Negotiate<User> {
+        Negotiate::new(User { name: "ferris" })
+            .json()
+            .format("application/xml".parse().unwrap(), |user| {
+                Ok::<_, Error>(format!("<user><name>{}</name></user>", user.name))
+            })
+    }
+
+    async fn respond(accept: Option<&str>) -> (StatusCode, Option<String>, Bytes) {
+        let mut req = TestRequest::default();
+        if let Some(accept) = accept {
+            req = req.insert_header((header::ACCEPT, accept));
+        }
+
+        let res = negotiate().respond_to(&req.to_http_request());
+        let status = res.status();
+        let content_type = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|val| val.to_str().unwrap().to_owned());
+
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept");
+
+        (
+            status,
+            content_type,
+            to_bytes(res.into_body()).await.unwrap(),
+        )
+    }
+
+    #[actix_rt::test]
+    async fn xml() {
+        let (status, content_type, body) = respond(Some("application/xml")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.unwrap(), "application/xml");
+        assert_eq!(body, "<user><name>ferris</name></user>");
+
+        // a more specific range overrides a wildcard one
+        let (_, content_type, _) =
+            respond(Some("application/json; q=0.1, application/*; q=0.9")).await;
+        assert_eq!(content_type.unwrap(), "application/xml");
+    }
+
+    #[actix_rt::test]
+    async fn any_format() {
+        for accept in [
+            Some("*/*"),
+            None,
+            Some("application/*"),
+            Some("not a media type"),
+        ] {
+            let (status, content_type, body) = respond(accept).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(content_type.unwrap(), "application/json");
+            assert_eq!(body, r#"{"name":"ferris"}"#);
+        }
+
+        let (_, content_type, _) =
+            respond(Some("application/xml; q=0.5, application/json")).await;
+        assert_eq!(content_type.unwrap(), "application/json");
+    }
+
+    #[actix_rt::test]
+    async fn not_acceptable() {
+        for accept in ["text/html", "application/json; q=0, application/xml; q=0"] {
+            let (status, _, body) = respond(Some(accept)).await;
+            assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+            assert!(body.is_empty());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn serializer_error() {
+        let req = TestRequest::default().to_http_request();
+        let res = Negotiate::new(())
+            .format(mime::TEXT_PLAIN, |_| {
+                Err::<Bytes, _>(error::ErrorInternalServerError("failed"))
+            })
+            .respond_to(&req);
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
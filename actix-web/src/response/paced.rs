@@ -0,0 +1,103 @@
+//! For pacing documentation, see [`HttpResponseBuilder::max_rate`].
+//!
+//! [`HttpResponseBuilder::max_rate`]: crate::HttpResponseBuilder::max_rate
+
+use std::{
+    convert::TryFrom as _,
+    error::Error as StdError,
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep_until, Instant, Sleep};
+use bytes::Bytes;
+use futures_core::ready;
+use pin_project_lite::pin_project;
+
+use crate::body::{BodySize, MessageBody};
+
+pin_project! {
+    /// Body that yields the chunks of another body at no more than a fixed number of bytes per
+    /// second.
+    ///
+    /// Chunks are split into pieces of a tenth of the rate so that output is spread evenly over
+    /// time. Each piece is held back until the time it takes to send it at the configured rate has
+    /// passed since the previous one, which also delays polling of the inner body.
+    pub(crate) struct PacedBody<B> {
+        #[pin]
+        body: B,
+        rate: u64,
+        pending: Bytes,
+        // bytes yielded since `start`, including the piece being waited for
+        sent: u64,
+        start: Option<Instant>,
+        delay: Option<Pin<Box<Sleep>>>,
+    }
+}
+
+impl<B> PacedBody<B> {
+    pub(crate) fn new(body: B, rate: u64) -> Self {
+        Self {
+            body,
+            rate,
+            pending: Bytes::new(),
+            sent: 0,
+            start: None,
+            delay: None,
+        }
+    }
+}
+
+impl<B: MessageBody> MessageBody for PacedBody<B> {
+    type Error = Box<dyn StdError>;
+
+    #[inline]
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(delay) = this.delay {
+                ready!(delay.as_mut().poll(cx));
+
+                *this.delay = None;
+                let len = piece_len(*this.rate, this.pending);
+                return Poll::Ready(Some(Ok(this.pending.split_to(len))));
+            }
+
+            if this.pending.is_empty() {
+                match ready!(this.body.as_mut().poll_next(cx)) {
+                    Some(Ok(chunk)) => *this.pending = chunk,
+                    Some(Err(err)) => return Poll::Ready(Some(Err(err.into()))),
+                    None => return Poll::Ready(None),
+                }
+
+                if this.pending.is_empty() {
+                    continue;
+                }
+            }
+
+            *this.sent += piece_len(*this.rate, this.pending) as u64;
+
+            let start = *this.start.get_or_insert_with(Instant::now);
+            let nanos = u128::from(*this.sent) * 1_000_000_000 / u128::from(*this.rate);
+            let deadline = start + Duration::from_nanos(nanos as u64);
+
+            *this.delay = Some(Box::pin(sleep_until(deadline)));
+        }
+    }
+}
+
+/// Returns the length of the next piece of `pending` to yield at `rate` bytes per second.
+fn piece_len(rate: u64, pending: &Bytes) -> usize {
+    let max = usize::try_from(rate / 10).unwrap_or(usize::MAX).max(1);
+    pending.len().min(max)
+}
@@ -0,0 +1,613 @@
+//! See [`Ranged`] for responder documentation.
+
+use std::{
+    cmp,
+    collections::{hash_map::RandomState, VecDeque},
+    error::Error as StdError,
+    hash::{BuildHasher as _, Hasher as _},
+    io, mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::ready;
+use mime::Mime;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::{BodySize, EitherBody, MessageBody},
+    http::header::{self, ContentRange, ContentRangeSpec, ContentType, Header as _, Range},
+    HttpRequest, HttpResponse, Responder,
+};
+
+/// Maximum number of bytes requested from a [`RangeSource`] at once.
+const CHUNK_SIZE: usize = 65_536;
+
+/// Maximum number of distinct ranges served in one `multipart/byteranges` response.
+///
+/// Requests for more ranges, after overlapping ones are merged, get the full content instead.
+const MAX_RANGES: usize = 32;
+
+/// A seekable source of bytes with a known size, from which [`Ranged`] serves byte ranges.
+pub trait RangeSource {
+    /// The error type produced when seeking or reading fails.
+    type Error: Into<Box<dyn StdError>>;
+
+    /// Returns the total size of the content, in bytes.
+    fn size(&self) -> u64;
+
+    /// Attempts to move the read position to `pos` bytes from the start of the content.
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: u64,
+    ) -> Poll<Result<(), Self::Error>>;
+
+    /// Attempts to read up to `max` bytes from the current read position.
+    ///
+    /// Returning an empty chunk signals that the end of the content was reached.
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        max: usize,
+    ) -> Poll<Result<Bytes, Self::Error>>;
+}
+
+/// A responder that serves the byte range requested by the `Range` header from a [`RangeSource`].
+///
+/// - Without a (valid) `Range` header, the full content is sent with a `200 OK` status.
+/// - A single satisfiable byte range is sent with a `206 Partial Content` status and a matching
+///   `Content-Range` header.
+/// - Multiple satisfiable byte ranges are sent with a `206 Partial Content` status as a
+///   `multipart/byteranges` body, with one part per range carrying its own `Content-Range`
+///   header. Ranges are sorted and those that overlap or are adjacent are merged first, so a
+///   request whose ranges merge into one is answered with a single range.
+/// - Requests in which no range is satisfiable are answered with `416 Range Not Satisfiable`.
+///
+/// Unsatisfiable ranges in a request that also has satisfiable ones are ignored. All responses
+/// include an `Accept-Ranges: bytes` header. Use [`content_type`](Self::content_type) rather
+/// than customizing the response to set the content type, so that multi-range responses keep
+/// their `multipart/byteranges` type.
+///
+/// # Examples
+/// ```
+/// use std::{convert::Infallible, pin::Pin, task::{Context, Poll}};
+///
+/// use actix_web::{web::{RangeSource, Ranged}, Responder};
+/// use bytes::Bytes;
+///
+/// struct Zeroes {
+///     size: u64,
+///     pos: u64,
+/// }
+///
+/// impl RangeSource for Zeroes {
+///     type Error = Infallible;
+///
+///     fn size(&self) -> u64 {
+///         self.size
+///     }
+///
+///     fn poll_seek(
+///         mut self: Pin<&mut Self>,
+///         _: &mut Context<'_>,
+///         pos: u64,
+///     ) -> Poll<Result<(), Infallible>> {
+///         self.pos = pos;
+///         Poll::Ready(Ok(()))
+///     }
+///
+///     fn poll_read(
+///         mut self: Pin<&mut Self>,
+///         _: &mut Context<'_>,
+///         max: usize,
+///     ) -> Poll<Result<Bytes, Infallible>> {
+///         let n = std::cmp::min(max as u64, self.size - self.pos);
+///         self.pos += n;
+///         Poll::Ready(Ok(Bytes::from(vec![0; n as usize])))
+///     }
+/// }
+///
+/// async fn handler() -> impl Responder {
+///     Ranged::new(Zeroes { size: 1 << 30, pos: 0 })
+/// }
+/// # actix_web::web::to(handler);
+/// ```
+#[derive(Debug)]
+pub struct Ranged<S> {
+    source: S,
+    content_type: Option<Mime>,
+}
+
+impl<S: RangeSource> Ranged<S> {
+    /// Constructs a new `Ranged` responder from a source.
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            content_type: None,
+        }
+    }
+
+    /// Sets the content type of the source.
+    ///
+    /// It is sent as the `Content-Type` header of full and single range responses and as the
+    /// `Content-Type` header of each part of `multipart/byteranges` responses.
+    pub fn content_type(mut self, content_type: Mime) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+}
+
+impl<S> Responder for Ranged<S>
+where
+    S: RangeSource + 'static,
+{
+    type Body = EitherBody<RangedBody<S>>;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let size = self.source.size();
+
+        let ranges = match Range::parse(req) {
+            Ok(Range::Bytes(specs)) => coalesce(
+                specs
+                    .iter()
+                    .filter_map(|spec| spec.to_satisfiable_range(size))
+                    .collect(),
+            ),
+
+            // no (valid) `Range` header
+            _ => return self.respond_full(size),
+        };
+
+        match ranges.as_slice() {
+            [] => HttpResponse::RangeNotSatisfiable()
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                    range: None,
+                    instance_length: Some(size),
+                }))
+                .finish()
+                .map_into_right_body(),
+
+            &[(start, end)] => {
+                let mut res = HttpResponse::PartialContent();
+                res.insert_header((header::ACCEPT_RANGES, "bytes"))
+                    .insert_header(ContentRange(ContentRangeSpec::Bytes {
+                        range: Some((start, end)),
+                        instance_length: Some(size),
+                    }));
+
+                if let Some(content_type) = self.content_type {
+                    res.insert_header(ContentType(content_type));
+                }
+
+                res.message_body(RangedBody::new(self.source, start, end - start + 1))
+                    .unwrap()
+                    .map_into_left_body()
+            }
+
+            // serving many small ranges separately costs more than sending the full content
+            ranges if ranges.len() > MAX_RANGES => self.respond_full(size),
+
+            ranges => {
+                let boundary = boundary();
+                let body = RangedBody::multipart(
+                    self.source,
+                    ranges,
+                    size,
+                    &boundary,
+                    self.content_type.as_ref(),
+                );
+
+                HttpResponse::PartialContent()
+                    .insert_header((header::ACCEPT_RANGES, "bytes"))
+                    .insert_header((
+                        header::CONTENT_TYPE,
+                        format!("multipart/byteranges; boundary={}", boundary),
+                    ))
+                    .message_body(body)
+                    .unwrap()
+                    .map_into_left_body()
+            }
+        }
+    }
+}
+
+impl<S: RangeSource> Ranged<S> {
+    fn respond_full(self, size: u64) -> HttpResponse<EitherBody<RangedBody<S>>> {
+        let mut res = HttpResponse::Ok();
+        res.insert_header((header::ACCEPT_RANGES, "bytes"));
+
+        if let Some(content_type) = self.content_type {
+            res.insert_header(ContentType(content_type));
+        }
+
+        res.message_body(RangedBody::new(self.source, 0, size))
+            .unwrap()
+            .map_into_left_body()
+    }
+}
+
+/// Sorts inclusive byte ranges and merges those that overlap or are adjacent.
+fn coalesce(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = cmp::max(last.1, end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+/// Generates a random `multipart/byteranges` boundary.
+fn boundary() -> String {
+    // std's hasher keys are randomly seeded for each `RandomState`
+    let random = |n: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(n);
+        hasher.finish()
+    };
+
+    format!("{:016x}{:016x}", random(0), random(1))
+}
+
+pin_project! {
+    /// Body that streams byte ranges from a [`RangeSource`].
+    ///
+    /// Each range is preceded by a (possibly empty) header chunk and the body ends with a
+    /// trailer chunk, which are used for the part headers and closing delimiter of
+    /// `multipart/byteranges` bodies.
+    pub struct RangedBody<S> {
+        #[pin]
+        source: S,
+        // part headers, offsets and lengths of the ranges not yet started
+        parts: VecDeque<(Bytes, u64, u64)>,
+        seek_to: Option<u64>,
+        remaining: u64,
+        trailer: Bytes,
+        size: u64,
+    }
+}
+
+impl<S> RangedBody<S> {
+    fn new(source: S, offset: u64, len: u64) -> Self {
+        Self {
+            source,
+            parts: VecDeque::new(),
+            seek_to: Some(offset),
+            remaining: len,
+            trailer: Bytes::new(),
+            size: len,
+        }
+    }
+
+    fn multipart(
+        source: S,
+        ranges: &[(u64, u64)],
+        instance_length: u64,
+        boundary: &str,
+        content_type: Option<&Mime>,
+    ) -> Self {
+        let parts = ranges
+            .iter()
+            .enumerate()
+            .map(|(idx, &(start, end))| {
+                // the delimiter's leading CRLF is optional before the first part
+                let mut header = String::from(if idx == 0 { "--" } else { "\r\n--" });
+                header.push_str(boundary);
+                header.push_str("\r\n");
+
+                if let Some(content_type) = content_type {
+                    header.push_str(&format!("{}: {}\r\n", header::CONTENT_TYPE, content_type));
+                }
+
+                header.push_str(&format!(
+                    "{}: bytes {}-{}/{}\r\n\r\n",
+                    header::CONTENT_RANGE,
+                    start,
+                    end,
+                    instance_length
+                ));
+
+                (Bytes::from(header), start, end - start + 1)
+            })
+            .collect::<VecDeque<_>>();
+
+        let trailer = Bytes::from(format!("\r\n--{}--\r\n", boundary));
+
+        let size = parts
+            .iter()
+            .map(|(header, _, len)| header.len() as u64 + len)
+            .sum::<u64>()
+            + trailer.len() as u64;
+
+        Self {
+            source,
+            parts,
+            seek_to: None,
+            remaining: 0,
+            trailer,
+            size,
+        }
+    }
+}
+
+impl<S: RangeSource> MessageBody for RangedBody<S> {
+    type Error = Box<dyn StdError>;
+
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.size)
+    }
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let mut this = self.project();
+
+        while *this.remaining == 0 {
+            match this.parts.pop_front() {
+                Some((header, offset, len)) => {
+                    *this.seek_to = Some(offset);
+                    *this.remaining = len;
+
+                    if !header.is_empty() {
+                        return Poll::Ready(Some(Ok(header)));
+                    }
+                }
+
+                None if this.trailer.is_empty() => return Poll::Ready(None),
+                None => return Poll::Ready(Some(Ok(mem::take(this.trailer)))),
+            }
+        }
+
+        if let Some(pos) = *this.seek_to {
+            ready!(this.source.as_mut().poll_seek(cx, pos)).map_err(Into::into)?;
+            *this.seek_to = None;
+        }
+
+        let max = cmp::min(*this.remaining, CHUNK_SIZE as u64) as usize;
+        let mut chunk = ready!(this.source.poll_read(cx, max)).map_err(Into::into)?;
+
+        if chunk.is_empty() {
+            return Poll::Ready(Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "range source ended before the end of the requested range",
+            )
+            .into())));
+        }
+
+        chunk.truncate(max);
+        *this.remaining -= chunk.len() as u64;
+
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+    use crate::{body::to_bytes, http::StatusCode, test::TestRequest};
+
+    /// Source yielding the bytes `0..size` cast to `u8`, in small chunks.
+    struct Counting {
+        size: u64,
+        pos: u64,
+    }
+
+    impl RangeSource for Counting {
+        type Error = Infallible;
+
+        fn size(&self) -> u64 {
+            self.size
+        }
+
+        fn poll_seek(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            pos: u64,
+        ) -> Poll<Result<(), Self::Error>> {
+            self.pos = pos;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            max: usize,
+        ) -> Poll<Result<Bytes, Self::Error>> {
+            let end = cmp::min(self.pos + cmp::min(max, 3) as u64, self.size);
+            let chunk = (self.pos..end).map(|n| n as u8).collect::<Vec<_>>();
+            self.pos = end;
+            Poll::Ready(Ok(Bytes::from(chunk)))
+        }
+    }
+
+    fn ranged(size: u64) -> Ranged<Counting> {
+        Ranged::new(Counting { size, pos: 0 })
+    }
+
+    #[actix_rt::test]
+    async fn full_content_without_range() {
+        let req = TestRequest::default().to_http_request();
+        let res = ranged(10).respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert!(res.headers().get(header::CONTENT_RANGE).is_none());
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, (0..10).collect::<Vec<u8>>());
+    }
+
+    #[actix_rt::test]
+    async fn partial_content() {
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=2-7"))
+            .to_http_request();
+        let res = ranged(10).respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-7/10"
+        );
+        assert_eq!(res.body().size(), BodySize::Sized(6));
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, vec![2, 3, 4, 5, 6, 7]);
+
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=-3"))
+            .to_http_request();
+        let res = ranged(10).respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 7-9/10"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn range_not_satisfiable() {
+        for range in ["bytes=10-", "bytes=10-12,20-"] {
+            let req = TestRequest::default()
+                .insert_header((header::RANGE, range))
+                .to_http_request();
+            let res = ranged(10).respond_to(&req);
+
+            assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+            assert_eq!(
+                res.headers().get(header::CONTENT_RANGE).unwrap(),
+                "bytes */10"
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn multiple_ranges() {
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=6-7,0-1,1-2,20-"))
+            .to_http_request();
+        let res = ranged(10)
+            .content_type(mime::APPLICATION_OCTET_STREAM)
+            .respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(res.headers().get(header::CONTENT_RANGE).is_none());
+
+        let content_type = res.headers().get(header::CONTENT_TYPE).unwrap();
+        let boundary = content_type
+            .to_str()
+            .unwrap()
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_owned();
+
+        let size = res.body().size();
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(size, BodySize::Sized(body.len() as u64));
+
+        let body = body.to_vec();
+        let delimiter = format!("--{}", boundary).into_bytes();
+        let mut parts = split(&body, &delimiter);
+
+        // nothing before the first delimiter and only the closing marker after the last
+        assert_eq!(parts.remove(0), b"");
+        assert_eq!(parts.pop().unwrap(), b"--\r\n");
+
+        let parts = parts
+            .into_iter()
+            .map(|part| {
+                let part = part.strip_prefix(b"\r\n").unwrap();
+                let part = part.strip_suffix(b"\r\n").unwrap_or(part);
+                let (headers, data) = part.split_at(find(part, b"\r\n\r\n").unwrap());
+                (
+                    String::from_utf8(headers.to_vec()).unwrap(),
+                    data[4..].to_vec(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // overlapping ranges are merged, ranges are sorted and unsatisfiable ones are dropped
+        assert_eq!(
+            parts,
+            vec![
+                (
+                    "content-type: application/octet-stream\r\ncontent-range: bytes 0-2/10"
+                        .to_owned(),
+                    vec![0, 1, 2]
+                ),
+                (
+                    "content-type: application/octet-stream\r\ncontent-range: bytes 6-7/10"
+                        .to_owned(),
+                    vec![6, 7]
+                ),
+            ]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn multiple_ranges_coalesced() {
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, "bytes=4-5,0-1,2-3"))
+            .to_http_request();
+        let res = ranged(10).respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 0-5/10"
+        );
+
+        let body = to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, vec![0, 1, 2, 3, 4, 5]);
+
+        // too many distinct ranges result in the full content being sent
+        let range = (0..=MAX_RANGES)
+            .map(|n| format!("{0}-{0}", n * 2))
+            .collect::<Vec<_>>()
+            .join(",");
+        let req = TestRequest::default()
+            .insert_header((header::RANGE, format!("bytes={}", range)))
+            .to_http_request();
+        let res = ranged(100).respond_to(&req);
+
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().size(), BodySize::Sized(100));
+    }
+
+    fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    fn split<'a>(mut haystack: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+        let mut parts = Vec::new();
+
+        while let Some(idx) = find(haystack, delimiter) {
+            parts.push(&haystack[..idx]);
+            haystack = &haystack[idx + delimiter.len()..];
+        }
+
+        parts.push(haystack);
+        parts
+    }
+
+    #[actix_rt::test]
+    async fn truncated_source() {
+        // source is shorter than the range being streamed from it
+        let body = RangedBody::new(Counting { size: 4, pos: 0 }, 2, 5);
+        to_bytes(body).await.unwrap_err();
+    }
+}
@@ -0,0 +1,228 @@
+//! For content type sniffing documentation, see [`HttpResponseBuilder::sniff_content_type`].
+//!
+//! [`HttpResponseBuilder::sniff_content_type`]: crate::HttpResponseBuilder::sniff_content_type
+
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use futures_util::future::poll_fn;
+
+use crate::{
+    body::{BodySize, BoxBody, MessageBody},
+    http::header::{self, HeaderValue},
+    HttpResponse,
+};
+
+/// Longest prefix inspected before a body is assumed to be text.
+const MAX_PREFIX_LEN: usize = 512;
+
+/// Signatures of binary formats, checked in order.
+const SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+];
+
+/// Marks a response whose content type should be sniffed from the start of its body.
+pub(crate) struct SniffContentType;
+
+/// Sets the content type of a response marked with [`SniffContentType`] from the start of its
+/// body.
+///
+/// Chunks are only read from the body until the content type can be decided; they are yielded
+/// again before the rest of the body.
+pub(crate) async fn sniff_content_type(mut res: HttpResponse) -> HttpResponse {
+    if res.extensions_mut().remove::<SniffContentType>().is_none()
+        || res.headers().contains_key(header::CONTENT_TYPE)
+    {
+        return res;
+    }
+
+    let (res, mut body) = res.into_parts();
+
+    let size = body.size();
+    if matches!(size, BodySize::None | BodySize::Sized(0)) {
+        return res.set_body(body);
+    }
+
+    let mut prefix = BytesMut::new();
+    let mut chunks = VecDeque::new();
+    let mut error = None;
+    let mut done = false;
+
+    let mime = loop {
+        if let Some(mime) = detect(&prefix, done) {
+            break mime;
+        }
+
+        match poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            Some(Ok(chunk)) => {
+                prefix.extend_from_slice(&chunk[..chunk.len().min(MAX_PREFIX_LEN)]);
+                chunks.push_back(chunk);
+            }
+
+            // the error is yielded after the buffered chunks instead
+            Some(Err(err)) => {
+                error = Some(err);
+                break "application/octet-stream";
+            }
+
+            None => done = true,
+        }
+    };
+
+    let mut res = res.set_body(BoxBody::new(PrefixedBody {
+        chunks,
+        error,
+        body: if done { None } else { Some(body) },
+        size,
+    }));
+
+    res.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(mime));
+
+    res
+}
+
+/// Returns the content type of a body starting with `prefix`, or `None` if more of it is needed.
+///
+/// `complete` is set when `prefix` holds the whole body.
+fn detect(prefix: &[u8], complete: bool) -> Option<&'static str> {
+    let mut undecided = false;
+
+    for (signature, mime) in SIGNATURES {
+        if prefix.starts_with(signature) {
+            return Some(mime);
+        }
+
+        undecided |= signature.starts_with(prefix);
+    }
+
+    if undecided && !complete {
+        return None;
+    }
+
+    // binary data is recognized early, text only once enough of it has been seen
+    let text = match std::str::from_utf8(prefix) {
+        Ok(text) => text,
+        // a character may be split at the end of the prefix
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&prefix[..err.valid_up_to()]).unwrap()
+        }
+        Err(_) => return Some("application/octet-stream"),
+    };
+
+    if text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0C'))
+    {
+        return Some("application/octet-stream");
+    }
+
+    if matches!(text.trim_start().chars().next(), Some('{') | Some('[')) {
+        Some("application/json")
+    } else if complete || prefix.len() >= MAX_PREFIX_LEN {
+        Some("text/plain; charset=utf-8")
+    } else {
+        None
+    }
+}
+
+/// Body that yields the chunks read while sniffing before the rest of the body.
+struct PrefixedBody {
+    chunks: VecDeque<Bytes>,
+    error: Option<Box<dyn StdError>>,
+    body: Option<BoxBody>,
+    // size of the body before any of it was read
+    size: BodySize,
+}
+
+impl MessageBody for PrefixedBody {
+    type Error = Box<dyn StdError>;
+
+    fn size(&self) -> BodySize {
+        self.size
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        if let Some(chunk) = self.chunks.pop_front() {
+            return Poll::Ready(Some(Ok(chunk)));
+        }
+
+        if let Some(err) = self.error.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        match self.body.as_mut() {
+            Some(body) => Pin::new(body).poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+
+    use super::*;
+    use crate::{
+        body,
+        test::{call_service, init_service, TestRequest},
+        web, App, Error,
+    };
+
+    #[test]
+    fn detects_minimal_prefix() {
+        assert_eq!(detect(b"", false), None);
+        assert_eq!(detect(b"\x89PN", false), None);
+        assert_eq!(detect(b"\x89PNG\r\n\x1a\n", false), Some("image/png"));
+        assert_eq!(detect(b"\xFF\xD8\xFF\xE0", false), Some("image/jpeg"));
+        assert_eq!(detect(b"GIF89a", false), Some("image/gif"));
+        assert_eq!(detect(b"%PDF-1.7", false), Some("application/pdf"));
+        assert_eq!(detect(b"  {\"a\"", false), Some("application/json"));
+        assert_eq!(detect(b"\x00\x01", false), Some("application/octet-stream"));
+        assert_eq!(detect(b"hello", false), None);
+        assert_eq!(detect(b"hello", true), Some("text/plain; charset=utf-8"));
+        assert_eq!(
+            detect(&[b'a'; MAX_PREFIX_LEN], false),
+            Some("text/plain; charset=utf-8")
+        );
+        assert_eq!(detect(b"\x89PN", true), Some("application/octet-stream"));
+        assert_eq!(detect(b"GIF", true), Some("text/plain; charset=utf-8"));
+    }
+
+    #[actix_rt::test]
+    async fn sniffs_streamed_png() {
+        async fn png() -> HttpResponse {
+            let chunks = [&b"\x89PN"[..], b"G\r\n\x1a\n\0\0\0\rIHDR", b"rest"];
+
+            HttpResponse::Ok()
+                .sniff_content_type(true)
+                .streaming(stream::iter(
+                    chunks.map(|chunk| Ok::<_, Error>(Bytes::from_static(chunk))),
+                ))
+        }
+
+        let srv = init_service(App::new().route("/", web::get().to(png))).await;
+
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "image/png"
+        );
+        assert_eq!(res.response().body().size(), BodySize::Stream);
+
+        let body = body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(body, &b"\x89PNG\r\n\x1a\n\0\0\0\rIHDRrest"[..]);
+    }
+}
@@ -7,11 +7,13 @@ use actix_service::{
     fn_service, Service, ServiceFactory, ServiceFactoryExt, Transform,
 };
 use futures_core::future::LocalBoxFuture;
+use serde_json::Value;
 
 use crate::{
     guard::{self, Guard},
     handler::{handler_service, Handler},
     middleware::Compat,
+    schema::BodySchema,
     service::{BoxedHttpServiceFactory, ServiceRequest, ServiceResponse},
     Error, FromRequest, HttpResponse, Responder,
 };
@@ -23,6 +25,8 @@ use crate::{
 pub struct Route {
     service: BoxedHttpServiceFactory,
     guards: Rc<Vec<Box<dyn Guard>>>,
+    methods: Vec<Method>,
+    body_schema: Option<Value>,
 }
 
 impl Route {
@@ -34,6 +38,8 @@ impl Route {
                 Ok(req.into_response(HttpResponse::NotFound()))
             })),
             guards: Rc::new(Vec::new()),
+            methods: Vec::new(),
+            body_schema: None,
         }
     }
 
@@ -59,12 +65,22 @@ impl Route {
         Route {
             service: boxed::factory(apply(Compat::new(mw), self.service)),
             guards: self.guards,
+            methods: self.methods,
+            body_schema: self.body_schema,
         }
     }
 
     pub(crate) fn take_guards(&mut self) -> Vec<Box<dyn Guard>> {
         mem::take(Rc::get_mut(&mut self.guards).unwrap())
     }
+
+    /// Returns the methods of the route's method guards and its request body schema, if it has
+    /// one.
+    pub(crate) fn body_schema_info(&self) -> Option<(&[Method], &Value)> {
+        self.body_schema
+            .as_ref()
+            .map(|schema| (self.methods.as_slice(), schema))
+    }
 }
 
 impl ServiceFactory<ServiceRequest> for Route {
@@ -133,12 +149,48 @@ impl Route {
     /// # }
     /// ```
     pub fn method(mut self, method: Method) -> Self {
+        self.methods.push(method.clone());
         Rc::get_mut(&mut self.guards)
             .unwrap()
             .push(Box::new(guard::Method(method)));
         self
     }
 
+    /// Declares the request body the route expects, as described by the body extractor `E`.
+    ///
+    /// The schema is recorded in the app's [`SchemaRegistry`](crate::web::SchemaRegistry) when
+    /// the app is built, along with the route's path and methods, so that it can be enumerated by
+    /// documentation endpoints. The body is not validated against it.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{web, App};
+    /// use serde::Deserialize;
+    /// use serde_json::{json, Value};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// impl web::BodySchema for User {
+    ///     fn body_schema() -> Value {
+    ///         json!({ "type": "object", "properties": { "name": { "type": "string" } } })
+    ///     }
+    /// }
+    ///
+    /// let app = App::new().route(
+    ///     "/users",
+    ///     web::post()
+    ///         .body_schema::<web::Json<User>>()
+    ///         .to(|user: web::Json<User>| async move { user.into_inner().name }),
+    /// );
+    /// ```
+    pub fn body_schema<E: BodySchema>(mut self) -> Self {
+        self.body_schema = Some(E::body_schema());
+        self
+    }
+
     /// Add guard to the route.
     ///
     /// # Examples
@@ -0,0 +1,208 @@
+//! For request body schema documentation, see [`SchemaRegistry`].
+
+use std::{cell::RefCell, rc::Rc};
+
+use serde_json::Value;
+
+use crate::{
+    http::Method,
+    types::{Form, Json},
+};
+
+/// Types that describe the request body they are extracted from with a JSON Schema document.
+///
+/// This mirrors the `JsonSchema` trait of [schemars]; types deriving it can implement this trait
+/// by delegating to it, e.g. with `serde_json::to_value(schemars::schema_for!(Self)).unwrap()`.
+/// Body extractors implement it for the types they deserialize, so that routes can declare the
+/// body they expect with [`Route::body_schema`](crate::Route::body_schema).
+///
+/// Schemas are only used for introspection; bodies are not validated against them.
+///
+/// [schemars]: https://docs.rs/schemars
+pub trait BodySchema {
+    /// Returns the JSON Schema of the body.
+    fn body_schema() -> Value;
+}
+
+impl<T: BodySchema> BodySchema for Json<T> {
+    fn body_schema() -> Value {
+        T::body_schema()
+    }
+}
+
+impl<T: BodySchema> BodySchema for Form<T> {
+    fn body_schema() -> Value {
+        T::body_schema()
+    }
+}
+
+/// Request body schema of a route, as recorded in a [`SchemaRegistry`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteSchema {
+    path: String,
+    methods: Vec<Method>,
+    schema: Value,
+}
+
+impl RouteSchema {
+    /// Returns the path pattern of the route's resource, including the prefixes of its scopes.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the methods the route is restricted to, or an empty slice if it accepts any.
+    pub fn methods(&self) -> &[Method] {
+        &self.methods
+    }
+
+    /// Returns the JSON Schema of the request body the route expects.
+    pub fn schema(&self) -> &Value {
+        &self.schema
+    }
+}
+
+/// Request body schemas of the routes of an app.
+///
+/// The registry is populated when the app is built, from the routes declaring their request body
+/// with [`Route::body_schema`](crate::Route::body_schema), and is available as app data, so that a
+/// documentation endpoint can enumerate the bodies the app expects. Routes are listed in the
+/// order their resources are registered.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App, HttpRequest, HttpResponse};
+/// use serde::Deserialize;
+/// use serde_json::{json, Value};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// impl web::BodySchema for User {
+///     fn body_schema() -> Value {
+///         json!({ "type": "object", "properties": { "name": { "type": "string" } } })
+///     }
+/// }
+///
+/// async fn docs(req: HttpRequest) -> HttpResponse {
+///     let registry = req.app_data::<web::SchemaRegistry>().unwrap();
+///
+///     let routes = registry
+///         .routes()
+///         .iter()
+///         .map(|route| json!({ "path": route.path(), "body": route.schema() }))
+///         .collect::<Vec<_>>();
+///
+///     HttpResponse::Ok().json(routes)
+/// }
+///
+/// let app = App::new()
+///     .route(
+///         "/users",
+///         web::post()
+///             .body_schema::<web::Json<User>>()
+///             .to(|user: web::Json<User>| async move { user.into_inner().name }),
+///     )
+///     .route("/docs", web::get().to(docs));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SchemaRegistry {
+    routes: Rc<RefCell<Vec<RouteSchema>>>,
+}
+
+impl SchemaRegistry {
+    /// Returns the recorded routes.
+    pub fn routes(&self) -> Vec<RouteSchema> {
+        self.routes.borrow().clone()
+    }
+
+    /// Returns the request body schema of the route with the given path and method, if recorded.
+    pub fn get(&self, path: &str, method: &Method) -> Option<Value> {
+        self.routes
+            .borrow()
+            .iter()
+            .find(|route| {
+                route.path == path
+                    && (route.methods.is_empty() || route.methods.contains(method))
+            })
+            .map(|route| route.schema.clone())
+    }
+
+    pub(crate) fn record(&self, path: String, methods: Vec<Method>, schema: Value) {
+        self.routes.borrow_mut().push(RouteSchema {
+            path,
+            methods,
+            schema,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, HttpRequest,
+    };
+
+    #[derive(Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    impl BodySchema for User {
+        fn body_schema() -> Value {
+            json!({ "type": "object", "properties": { "name": { "type": "string" } } })
+        }
+    }
+
+    #[actix_rt::test]
+    async fn records_json_body_schema() {
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/api").route(
+                        "/users",
+                        web::post()
+                            .body_schema::<web::Json<User>>()
+                            .to(|user: web::Json<User>| async move { user.into_inner().name }),
+                    ),
+                )
+                .route(
+                    "/schema",
+                    web::get().to(|req: HttpRequest| async move {
+                        let registry = req.app_data::<SchemaRegistry>().unwrap();
+                        web::Json([
+                            registry.get("/api/users", &Method::POST),
+                            // routes that do not declare a body are not recorded
+                            registry.get("/schema", &Method::GET),
+                        ])
+                    }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::get().uri("/schema").to_request();
+        let res = call_service(&srv, req).await;
+        let schemas: Value = serde_json::from_slice(&read_body(res).await).unwrap();
+        assert_eq!(schemas, json!([User::body_schema(), null]));
+    }
+
+    #[actix_rt::test]
+    async fn lists_routes() {
+        let registry = SchemaRegistry::default();
+        registry.record("/users".to_owned(), vec![Method::POST], json!(true));
+
+        let routes = registry.routes();
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0].path(), "/users");
+        assert_eq!(routes[0].methods(), [Method::POST]);
+        assert_eq!(routes[0].schema(), &json!(true));
+
+        assert!(registry.get("/users", &Method::GET).is_none());
+    }
+}
@@ -19,10 +19,14 @@ use crate::{
         AppServiceFactory, BoxedHttpService, BoxedHttpServiceFactory, HttpServiceFactory,
         ServiceFactoryWrapper, ServiceRequest, ServiceResponse,
     },
-    Error, Resource, Route,
+    Error, HttpRequest, HttpResponse, Resource, Route,
 };
 
 type Guards = Vec<Box<dyn Guard>>;
+type ErrorHandler = dyn Fn(&Error, &HttpRequest) -> HttpResponse;
+
+/// Marks error responses that have already been transformed by a scope's error handler.
+pub(crate) struct ErrorHandled;
 
 /// A collection of [`Route`]s, [`Resource`]s, or other services that share a common path prefix.
 ///
@@ -61,6 +65,7 @@ pub struct Scope<T = ScopeEndpoint> {
     services: Vec<Box<dyn AppServiceFactory>>,
     guards: Vec<Box<dyn Guard>>,
     default: Option<Rc<BoxedHttpServiceFactory>>,
+    error_handler: Option<Rc<ErrorHandler>>,
     external: Vec<ResourceDef>,
     factory_ref: Rc<RefCell<Option<ScopeFactory>>>,
 }
@@ -77,6 +82,7 @@ impl Scope {
             guards: Vec::new(),
             services: Vec::new(),
             default: None,
+            error_handler: None,
             external: Vec::new(),
             factory_ref,
         }
@@ -289,6 +295,43 @@ where
         self
     }
 
+    /// Sets a handler that transforms error responses produced within this scope.
+    ///
+    /// The handler is called with the error and the request for each response of this scope that
+    /// was rendered from an error, e.g., because a handler returned an `Err` or an extractor
+    /// failed. Its response replaces the one rendered by
+    /// [`ResponseError::error_response`](crate::ResponseError::error_response), keeping the
+    /// [source error](HttpResponse::error) for middleware such as [`Logger`]. Errors returned by
+    /// scope middleware instead of a response are passed through as they are.
+    ///
+    /// Errors of scopes without a handler are rendered as usual. An error handled by a nested
+    /// scope is not transformed again by the handlers of enclosing scopes.
+    ///
+    /// [`Logger`]: crate::middleware::Logger
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{web, App, HttpResponse};
+    ///
+    /// let app = App::new().service(
+    ///     web::scope("/api")
+    ///         .error_handler(|err, _req| {
+    ///             HttpResponse::build(err.as_response_error().status_code())
+    ///                 .json(serde_json::json!({ "error": err.to_string() }))
+    ///         })
+    ///         .route("/user/{id}", web::get().to(|id: web::Path<u32>| async move {
+    ///             format!("User {}", id)
+    ///         })),
+    /// );
+    /// ```
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Error, &HttpRequest) -> HttpResponse + 'static,
+    {
+        self.error_handler = Some(Rc::new(f));
+        self
+    }
+
     /// Registers a scope-wide middleware.
     ///
     /// `mw` is a middleware component (type), that can modify the request and response across all
@@ -326,6 +369,7 @@ where
             guards: self.guards,
             services: self.services,
             default: self.default,
+            error_handler: self.error_handler,
             external: self.external,
             factory_ref: self.factory_ref,
         }
@@ -364,6 +408,7 @@ where
             guards: self.guards,
             services: self.services,
             default: self.default,
+            error_handler: self.error_handler,
             external: self.external,
             factory_ref: self.factory_ref,
         }
@@ -387,6 +432,7 @@ where
 
         // register nested services
         let mut cfg = config.clone_config();
+        cfg.push_path_prefix(&self.rdef);
         self.services
             .into_iter()
             .for_each(|mut srv| srv.register(&mut cfg));
@@ -422,16 +468,26 @@ where
         };
 
         let scope_data = self.app_data.map(Rc::new);
+        let error_handler = self.error_handler;
 
-        // wraps endpoint service (including middleware) call and injects app data for this scope
+        // wraps endpoint service (including middleware) call, injects app data for this scope and
+        // transforms error responses
         let endpoint = apply_fn_factory(self.endpoint, move |mut req: ServiceRequest, srv| {
             if let Some(ref data) = scope_data {
                 req.add_data_container(Rc::clone(data));
             }
 
+            let handler = error_handler.clone();
             let fut = srv.call(req);
 
-            async { Ok(fut.await?.map_into_boxed_body()) }
+            async move {
+                let res = fut.await?.map_into_boxed_body();
+
+                Ok(match handler {
+                    Some(handler) => handle_error(&*handler, res),
+                    None => res,
+                })
+            }
         });
 
         // register final service
@@ -444,6 +500,24 @@ where
     }
 }
 
+/// Transforms an unhandled error response with a scope's error handler.
+fn handle_error(handler: &ErrorHandler, res: ServiceResponse) -> ServiceResponse {
+    if res.response().error().is_none()
+        || res.response().extensions().contains::<ErrorHandled>()
+    {
+        return res;
+    }
+
+    let (req, mut res) = res.into_parts();
+    let err = res.error.take().unwrap();
+
+    let mut res = handler(&err, &req);
+    res.error = Some(err);
+    res.extensions_mut().insert(ErrorHandled);
+
+    ServiceResponse::new(req, res)
+}
+
 pub struct ScopeFactory {
     #[allow(clippy::type_complexity)]
     services: Rc<
@@ -932,6 +1006,76 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
 
+    #[actix_rt::test]
+    async fn test_error_handler() {
+        async fn fail() -> Result<HttpResponse, Error> {
+            Err(crate::error::ErrorForbidden("no access"))
+        }
+
+        let srv = init_service(
+            App::new()
+                .service(
+                    web::scope("/api")
+                        .error_handler(|err, _| {
+                            HttpResponse::build(err.as_response_error().status_code())
+                                .json(serde_json::json!({ "error": err.to_string() }))
+                        })
+                        .route("/fail", web::get().to(fail))
+                        .route("/ok", web::get().to(HttpResponse::Ok))
+                        // nested handlers take precedence
+                        .service(
+                            web::scope("/v2")
+                                .error_handler(|_, _| HttpResponse::Gone().finish())
+                                .route("/fail", web::get().to(fail)),
+                        ),
+                )
+                .service(
+                    web::scope("/web")
+                        .error_handler(|err, _| {
+                            HttpResponse::build(err.as_response_error().status_code())
+                                .content_type("text/html")
+                                .body(format!("<h1>{}</h1>", err))
+                        })
+                        .route("/fail", web::get().to(fail)),
+                )
+                .route("/fail", web::get().to(fail)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/api/fail").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        assert!(res.response().error().is_some());
+        assert_body_eq!(res, br#"{"error":"no access"}"#);
+
+        let req = TestRequest::with_uri("/web/fail").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/html"
+        );
+        assert_body_eq!(res, b"<h1>no access</h1>");
+
+        let req = TestRequest::with_uri("/api/v2/fail").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GONE);
+
+        // successful responses and errors outside of the scopes are left as they are
+        let req = TestRequest::with_uri("/api/ok").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/fail").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_body_eq!(res, b"no access");
+    }
+
     #[actix_rt::test]
     async fn test_middleware() {
         let srv = init_service(
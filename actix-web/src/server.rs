@@ -1,27 +1,63 @@
 use std::{
     any::Any,
-    cmp, fmt, io,
+    cell::{Cell, RefCell},
+    cmp,
+    collections::HashMap,
+    fmt,
+    future::Future,
+    io,
     marker::PhantomData,
     net,
+    pin::Pin,
+    rc::{Rc, Weak},
     sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
     time::Duration,
 };
 
-use actix_http::{body::MessageBody, Extensions, HttpService, KeepAlive, Request, Response};
+use actix_http::{
+    body::{BoxBody, EitherBody, MessageBody},
+    h1::{self, FramingStrictness, HeaderFolding, MemoryBudget, PayloadMetrics},
+    ConnectionType, Extensions, HttpService, KeepAlive, Method, Request, Response, StatusCode,
+};
+use actix_rt::time::{sleep, Sleep};
 use actix_server::{Server, ServerBuilder};
 use actix_service::{
     map_config, IntoServiceFactory, Service, ServiceFactory, ServiceFactoryExt as _,
 };
+use futures_core::{future::LocalBoxFuture, ready};
+use pin_project_lite::pin_project;
 
 #[cfg(feature = "openssl")]
 use actix_tls::accept::openssl::reexports::{AlpnError, SslAcceptor, SslAcceptorBuilder};
 #[cfg(feature = "rustls")]
-use actix_tls::accept::rustls::reexports::ServerConfig as RustlsServerConfig;
+use actix_tls::accept::rustls::{reexports::ServerConfig as RustlsServerConfig, TlsStream};
+#[cfg(feature = "rustls")]
+use tls_rustls::{
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
 
 #[cfg(any(feature = "openssl", feature = "rustls"))]
 use actix_http::TlsAcceptorConfig;
+#[cfg(feature = "rustls")]
+use actix_http::{header, uri::Authority};
+
+use crate::{config::AppConfig, Error, HttpResponse};
+
+#[cfg(unix)]
+use crate::info::PeerCredentials;
 
-use crate::{config::AppConfig, Error};
+type ServerOptionsHandler = Arc<dyn Fn() -> HttpResponse + Send + Sync>;
+type OnConnectFn = Arc<dyn Fn(&dyn Any, &mut Extensions) + Send + Sync>;
+#[cfg(feature = "rustls")]
+type VirtualHostFactory = Box<dyn Fn(net::SocketAddr) -> BoxedVirtualHostFactory + Send>;
+#[cfg(feature = "rustls")]
+type BoxedVirtualHostFactory =
+    actix_service::boxed::BoxServiceFactory<(), Request, Response<BoxBody>, HttpResponse, ()>;
+#[cfg(feature = "rustls")]
+type BoxedVirtualHostService =
+    actix_service::boxed::BoxService<Request, Response<BoxBody>, HttpResponse>;
 
 struct Socket {
     scheme: &'static str,
@@ -33,8 +69,28 @@ struct Config {
     keep_alive: KeepAlive,
     client_request_timeout: Duration,
     client_disconnect_timeout: Duration,
+    max_header_count: usize,
+    max_header_size: usize,
+    h1_framing_strictness: FramingStrictness,
+    h1_header_folding: HeaderFolding,
+    h1_payload_metrics: Option<PayloadMetrics>,
+    memory_budget: Option<MemoryBudget>,
+    proxy_protocol: bool,
+    max_connections_per_worker: Option<usize>,
+    excess_connections: ExcessConnections,
+    request_queue: Option<RequestQueue>,
     #[cfg(any(feature = "openssl", feature = "rustls"))]
     tls_handshake_timeout: Option<Duration>,
+    #[cfg(feature = "rustls")]
+    virtual_hosts: Vec<VirtualHost>,
+}
+
+/// Host served with its own certificate and app on Rustls listeners.
+#[cfg(feature = "rustls")]
+struct VirtualHost {
+    name: String,
+    cert: Arc<dyn ResolvesServerCert>,
+    factory: VirtualHostFactory,
 }
 
 /// An HTTP Server.
@@ -74,8 +130,8 @@ where
     backlog: u32,
     sockets: Vec<Socket>,
     builder: ServerBuilder,
-    #[allow(clippy::type_complexity)]
-    on_connect_fn: Option<Arc<dyn Fn(&dyn Any, &mut Extensions) + Send + Sync>>,
+    on_connect_fn: Option<OnConnectFn>,
+    server_options: Option<ServerOptionsHandler>,
     _phantom: PhantomData<(S, B)>,
 }
 
@@ -102,13 +158,26 @@ where
                 keep_alive: KeepAlive::default(),
                 client_request_timeout: Duration::from_secs(5),
                 client_disconnect_timeout: Duration::from_secs(1),
+                max_header_count: h1::DEFAULT_MAX_HEADER_COUNT,
+                max_header_size: h1::DEFAULT_MAX_HEADER_SIZE,
+                h1_framing_strictness: FramingStrictness::Strict,
+                h1_header_folding: HeaderFolding::Reject,
+                h1_payload_metrics: None,
+                memory_budget: None,
+                proxy_protocol: false,
+                max_connections_per_worker: None,
+                excess_connections: ExcessConnections::Reject,
+                request_queue: None,
                 #[cfg(any(feature = "rustls", feature = "openssl"))]
                 tls_handshake_timeout: None,
+                #[cfg(feature = "rustls")]
+                virtual_hosts: Vec::new(),
             })),
             backlog: 1024,
             sockets: Vec::new(),
             builder: ServerBuilder::default(),
             on_connect_fn: None,
+            server_options: None,
             _phantom: PhantomData,
         }
     }
@@ -155,6 +224,46 @@ where
         self
     }
 
+    /// Sets the maximum number of connections each worker serves at a time.
+    ///
+    /// The limit is shared by the connections of all of the server's listeners. Unlike [`max_connections`](Self::max_connections), connections beyond this limit are still
+    /// accepted. Requests on them are either answered with `503 Service Unavailable`, after which
+    /// HTTP/1 connections are closed, or held until one of the worker's connections closes and
+    /// frees up its slot. See [`excess_connections`](Self::excess_connections).
+    ///
+    /// By default, there is no limit.
+    pub fn max_connections_per_worker(self, num: usize) -> Self {
+        self.config.lock().unwrap().max_connections_per_worker = Some(num);
+        self
+    }
+
+    /// Sets how workers treat connections beyond the
+    /// [`max_connections_per_worker`](Self::max_connections_per_worker) limit.
+    ///
+    /// By default, requests on excess connections are rejected with `503 Service Unavailable`.
+    pub fn excess_connections(self, behavior: ExcessConnections) -> Self {
+        self.config.lock().unwrap().excess_connections = behavior;
+        self
+    }
+
+    /// Queues requests on connections beyond the
+    /// [`max_connections_per_worker`](Self::max_connections_per_worker) limit.
+    ///
+    /// Instead of being rejected or held indefinitely, up to `capacity` requests per worker wait
+    /// for one of the worker's connections to close and free up its slot. Requests that arrive
+    /// while the queue is full, or that do not get a slot within `max_wait`, are answered with
+    /// `503 Service Unavailable`, after which HTTP/1 connections are closed. This smooths over
+    /// transient spikes in load while still shedding sustained overload.
+    ///
+    /// Takes precedence over [`excess_connections`](Self::excess_connections). Has no effect
+    /// unless a connection limit is set.
+    ///
+    /// By default, there is no request queue.
+    pub fn request_queue(self, capacity: usize, max_wait: Duration) -> Self {
+        self.config.lock().unwrap().request_queue = Some(RequestQueue { capacity, max_wait });
+        self
+    }
+
     /// Sets the per-worker maximum concurrent TLS connection limit.
     ///
     /// All listeners will stop accepting connections when this limit is reached. It can be used to
@@ -178,11 +287,14 @@ where
         self
     }
 
-    /// Sets server client timeout for first request.
+    /// Sets server client timeout for reading request heads.
     ///
     /// Defines a timeout for reading client request head. If a client does not transmit the entire
     /// set headers within this time, the request is terminated with a 408 (Request Timeout) error.
     ///
+    /// The timeout is tracked separately from the keep-alive timeout and applies to every request
+    /// on a connection, not just the first. It does not limit the time taken to read request bodies.
+    ///
     /// To disable timeout set value to 0.
     ///
     /// By default client timeout is set to 5000 milliseconds.
@@ -210,6 +322,121 @@ where
         self
     }
 
+    /// Sets the maximum number of header fields accepted in an HTTP/1 request head.
+    ///
+    /// Requests with more header fields are rejected with a `431 Request Header Fields Too Large`
+    /// response.
+    ///
+    /// By default, up to 96 header fields are accepted.
+    pub fn max_header_count(self, count: usize) -> Self {
+        self.config.lock().unwrap().max_header_count = count;
+        self
+    }
+
+    /// Sets the maximum size, in bytes, of an HTTP/1 request head, including the request line.
+    ///
+    /// Requests with a larger head are rejected with a `431 Request Header Fields Too Large`
+    /// response.
+    ///
+    /// By default, the limit is 128KiB.
+    pub fn max_header_size(self, size: usize) -> Self {
+        self.config.lock().unwrap().max_header_size = size;
+        self
+    }
+
+    /// Sets how strictly the headers that determine the length of HTTP/1 request bodies are
+    /// checked.
+    ///
+    /// Requests with conflicting `Content-Length` and `Transfer-Encoding` headers are rejected
+    /// with a `400 Bad Request` response and the connection is closed, to prevent request
+    /// smuggling. See [`FramingStrictness`](crate::dev::FramingStrictness) for the available
+    /// levels.
+    ///
+    /// By default, `FramingStrictness::Strict` is used.
+    pub fn h1_framing_strictness(self, strictness: FramingStrictness) -> Self {
+        self.config.lock().unwrap().h1_framing_strictness = strictness;
+        self
+    }
+
+    /// Sets how obsolete line folding in HTTP/1 request headers is handled.
+    ///
+    /// Folded headers are a known source of request smuggling, so requests with them are rejected
+    /// with a `400 Bad Request` response by default. For legacy clients that still fold headers,
+    /// [`HeaderFolding::Normalize`](crate::dev::HeaderFolding::Normalize) accepts them instead and
+    /// replaces each line fold with spaces.
+    ///
+    /// By default, `HeaderFolding::Reject` is used.
+    pub fn h1_header_folding(self, folding: HeaderFolding) -> Self {
+        self.config.lock().unwrap().h1_header_folding = folding;
+        self
+    }
+
+    /// Sets counters to be updated by the payloads of HTTP/1 requests, across all workers.
+    ///
+    /// Keep a clone of `metrics` to read the totals, e.g. from a handler that serves them.
+    /// By default, no metrics are collected.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use actix_web::{dev::PayloadMetrics, web, App, HttpServer};
+    ///
+    /// # async fn run() -> std::io::Result<()> {
+    /// let metrics = PayloadMetrics::new();
+    /// let data = web::Data::new(metrics.clone());
+    ///
+    /// HttpServer::new(move || {
+    ///     App::new().app_data(data.clone()).route(
+    ///         "/metrics",
+    ///         web::get().to(|metrics: web::Data<PayloadMetrics>| async move {
+    ///             metrics.snapshot().to_string()
+    ///         }),
+    ///     )
+    /// })
+    /// .h1_payload_metrics(metrics)
+    /// .bind(("127.0.0.1", 8080))?
+    /// .run()
+    /// .await
+    /// # }
+    /// ```
+    pub fn h1_payload_metrics(self, metrics: PayloadMetrics) -> Self {
+        self.config.lock().unwrap().h1_payload_metrics = Some(metrics);
+        self
+    }
+
+    /// Sets a limit, in bytes, on the memory buffered by HTTP/1 connections, across all workers.
+    ///
+    /// Each connection accounts for the bytes it buffers: the unread part of its current request
+    /// payload, as well as its read and write buffers. When the total exceeds `limit`, the idle
+    /// connections (i.e., those not sending a response) that use the most memory are evicted:
+    /// their current request is dropped, its payload fails, and the connection is closed after a
+    /// `503 Service Unavailable` response.
+    ///
+    /// By default, memory is not limited.
+    pub fn memory_budget(self, limit: usize) -> Self {
+        self.config.lock().unwrap().memory_budget = Some(MemoryBudget::new(limit));
+        self
+    }
+
+    /// Sets whether plaintext TCP connections start with a PROXY protocol header.
+    ///
+    /// When enabled, a version 1 or version 2 PROXY protocol header, as sent by load balancers
+    /// such as HAProxy, is read from connections accepted by listeners added with
+    /// [`bind()`](Self::bind) and [`listen()`](Self::listen) before any HTTP data. The client
+    /// address in the header is then reported by [`HttpRequest::peer_addr()`] and, unless
+    /// overridden by `Forwarded` headers, [`ConnectionInfo::realip_remote_addr()`]. Connections
+    /// without a valid header are closed.
+    ///
+    /// Only enable this when the server is exclusively reachable through such a proxy, since
+    /// otherwise clients can spoof their address. TLS and Unix socket listeners are not affected.
+    /// By default, this is disabled.
+    ///
+    /// [`HttpRequest::peer_addr()`]: crate::HttpRequest::peer_addr
+    /// [`ConnectionInfo::realip_remote_addr()`]: crate::dev::ConnectionInfo::realip_remote_addr
+    pub fn proxy_protocol(self, enabled: bool) -> Self {
+        self.config.lock().unwrap().proxy_protocol = enabled;
+        self
+    }
+
     /// Sets TLS handshake timeout.
     ///
     /// Defines a timeout for TLS handshake. If the TLS handshake does not complete within this
@@ -255,10 +482,126 @@ where
             sockets: self.sockets,
             builder: self.builder,
             on_connect_fn: Some(Arc::new(f)),
+            server_options: self.server_options,
             _phantom: PhantomData,
         }
     }
 
+    /// Sets handler for server-wide `OPTIONS *` requests.
+    ///
+    /// Requests with the `OPTIONS` method and an asterisk (`*`) request target ask about the
+    /// capabilities of the server as a whole rather than of a specific resource. They are
+    /// answered by the server directly and never routed to the application. The handler is
+    /// typically used to advertise, using the `Allow` header, the methods supported globally.
+    ///
+    /// By default, these requests receive an empty `200 OK` response.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use actix_web::{http::{header, Method}, App, HttpResponse, HttpServer};
+    ///
+    /// # fn inner() -> std::io::Result<()> {
+    /// HttpServer::new(|| App::new())
+    ///     .server_options_handler(|| {
+    ///         HttpResponse::Ok()
+    ///             .insert_header(header::Allow(vec![Method::GET, Method::POST]))
+    ///             .finish()
+    ///     })
+    ///     .bind(("127.0.0.1", 8080))?
+    ///     .run();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn server_options_handler<H>(mut self, handler: H) -> Self
+    where
+        H: Fn() -> HttpResponse + Send + Sync + 'static,
+    {
+        self.server_options = Some(Arc::new(handler));
+        self
+    }
+
+    /// Adds a virtual host that is served with its own certificate and app on Rustls listeners.
+    ///
+    /// TLS connections whose SNI (Server Name Indication) host name matches `name`, ignoring
+    /// ASCII case, are presented the certificate of `config` and have all their requests routed to
+    /// the app created by `factory`. Only the certificate of `config` is used; all other TLS
+    /// settings are taken from the config passed to [`bind_rustls()`](Self::bind_rustls) or
+    /// [`listen_rustls()`](Self::listen_rustls). Connections that send no or an unknown host name
+    /// are served with the certificate of that config and the app of this server, which act as
+    /// the fallback.
+    ///
+    /// Requests whose `Host` header, or `:authority` for HTTP/2, names a different host than the
+    /// SNI host name of their connection are answered with `421 Misdirected Request`, since a
+    /// client may reuse a connection for any host covered by the presented certificate.
+    ///
+    /// Like the server's own app factory, `factory` is called once per worker. Virtual hosts only
+    /// apply to Rustls listeners bound after they are added.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate tls_rustls as rustls;
+    /// use actix_web::{App, HttpServer};
+    /// # use rustls::ServerConfig;
+    ///
+    /// # fn inner(fallback: ServerConfig, a: ServerConfig, b: ServerConfig) -> std::io::Result<()> {
+    /// HttpServer::new(|| App::new().default_service(actix_web::web::to(|| async { "default" })))
+    ///     .virtual_host("a.example.com", a, || {
+    ///         App::new().default_service(actix_web::web::to(|| async { "a" }))
+    ///     })
+    ///     .virtual_host("b.example.com", b, || {
+    ///         App::new().default_service(actix_web::web::to(|| async { "b" }))
+    ///     })
+    ///     .bind_rustls(("0.0.0.0", 443), fallback)?
+    ///     .run();
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "rustls")]
+    pub fn virtual_host<HF, HI, HS, HB>(
+        self,
+        name: impl Into<String>,
+        config: RustlsServerConfig,
+        factory: HF,
+    ) -> Self
+    where
+        HF: Fn() -> HI + Send + 'static,
+        HI: IntoServiceFactory<HS, Request>,
+        HS: ServiceFactory<Request, Config = AppConfig> + 'static,
+        HS::Error: Into<Error> + 'static,
+        HS::InitError: fmt::Debug,
+        HS::Response: Into<Response<HB>> + 'static,
+        HS::Future: 'static,
+        HS::Service: 'static,
+        <HS::Service as Service<Request>>::Future: 'static,
+        HB: MessageBody + 'static,
+    {
+        let name = name.into();
+        let host = name.clone();
+
+        let factory: VirtualHostFactory = Box::new(move |addr| {
+            let host = host.clone();
+            let fac = factory()
+                .into_factory()
+                .map_err(|err| err.into().error_response())
+                .map(|res| res.into().map_into_boxed_body())
+                .map_init_err(|err| {
+                    log::error!("Can not construct virtual host app: {:?}", err);
+                });
+
+            actix_service::boxed::factory(map_config(fac, move |_| {
+                AppConfig::new(true, host.clone(), addr)
+            }))
+        });
+
+        self.config.lock().unwrap().virtual_hosts.push(VirtualHost {
+            name,
+            cert: config.cert_resolver,
+            factory,
+        });
+
+        self
+    }
+
     /// Sets server host name.
     ///
     /// Host name is used by application router as a hostname for url generation. Check
@@ -289,6 +632,13 @@ where
     /// After receiving a stop signal, workers have this much time to finish serving requests.
     /// Workers still alive after the timeout are force dropped.
     ///
+    /// Requests that are in progress, including uploads whose bodies are still being received,
+    /// continue to be served during this time. When connections are force dropped, HTTP/1 request
+    /// payloads that are still being read fail with a [`PayloadError::Io`] error instead of
+    /// ending silently.
+    ///
+    /// [`PayloadError::Io`]: crate::error::PayloadError::Io
+    ///
     /// By default shutdown timeout sets to 30 seconds.
     pub fn shutdown_timeout(mut self, sec: u64) -> Self {
         self.builder = self.builder.shutdown_timeout(sec);
@@ -429,6 +779,7 @@ where
         });
 
         let on_connect_fn = self.on_connect_fn.clone();
+        let server_options = self.server_options.clone();
 
         self.builder =
             self.builder
@@ -440,21 +791,33 @@ where
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
                         .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .max_header_count(c.max_header_count)
+                        .max_header_size(c.max_header_size)
+                        .h1_framing_strictness(c.h1_framing_strictness)
+                        .h1_header_folding(c.h1_header_folding)
+                        .proxy_protocol(c.proxy_protocol)
                         .local_addr(addr);
 
-                    if let Some(handler) = on_connect_fn.clone() {
-                        svc = svc.on_connect_ext(move |io: &_, ext: _| {
-                            (handler)(io as &dyn Any, ext)
-                        })
-                    };
+                    if let Some(metrics) = c.h1_payload_metrics.clone() {
+                        svc = svc.h1_payload_metrics(metrics);
+                    }
+
+                    if let Some(budget) = c.memory_budget.clone() {
+                        svc = svc.h1_memory_budget(budget);
+                    }
+
+                    if let Some(on_connect) = on_connect_ext(on_connect_fn.clone(), &c) {
+                        svc = svc.on_connect_ext(on_connect);
+                    }
 
                     let fac = factory()
                         .into_factory()
                         .map_err(|err| err.into().error_response());
 
-                    svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(false, host.clone(), addr)
-                    }))
+                    svc.finish(ConnectionLimited::new(ServerOptions::new(
+                        map_config(fac, move |_| AppConfig::new(false, host.clone(), addr)),
+                        server_options.clone(),
+                    )))
                     .tcp()
                 })?;
         Ok(self)
@@ -478,17 +841,37 @@ where
     fn listen_rustls_inner(
         mut self,
         lst: net::TcpListener,
-        config: RustlsServerConfig,
+        mut config: RustlsServerConfig,
     ) -> io::Result<Self> {
         let factory = self.factory.clone();
         let cfg = self.config.clone();
         let addr = lst.local_addr().unwrap();
+
+        // virtual hosts added later are not served by this listener
+        let virtual_hosts = {
+            let c = cfg.lock().unwrap();
+
+            if !c.virtual_hosts.is_empty() {
+                config.cert_resolver = Arc::new(VirtualHostCerts {
+                    hosts: c
+                        .virtual_hosts
+                        .iter()
+                        .map(|host| (host.name.clone(), Arc::clone(&host.cert)))
+                        .collect(),
+                    fallback: Arc::clone(&config.cert_resolver),
+                });
+            }
+
+            c.virtual_hosts.len()
+        };
+
         self.sockets.push(Socket {
             addr,
             scheme: "https",
         });
 
         let on_connect_fn = self.on_connect_fn.clone();
+        let server_options = self.server_options.clone();
 
         self.builder =
             self.builder
@@ -499,26 +882,65 @@ where
                     let svc = HttpService::build()
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
-                        .client_disconnect_timeout(c.client_disconnect_timeout);
+                        .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .max_header_count(c.max_header_count)
+                        .max_header_size(c.max_header_size)
+                        .h1_framing_strictness(c.h1_framing_strictness)
+                        .h1_header_folding(c.h1_header_folding);
+
+                    let svc = match c.h1_payload_metrics.clone() {
+                        Some(metrics) => svc.h1_payload_metrics(metrics),
+                        None => svc,
+                    };
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
+                    let svc = match c.memory_budget.clone() {
+                        Some(budget) => svc.h1_memory_budget(budget),
+                        None => svc,
+                    };
+
+                    let on_connect = on_connect_ext(on_connect_fn.clone(), &c);
+
+                    let svc = if virtual_hosts > 0 {
+                        svc.on_connect_ext(
+                            move |io: &TlsStream<actix_rt::net::TcpStream>,
+                                  ext: &mut Extensions| {
+                                if let Some(name) = io.get_ref().1.sni_hostname() {
+                                    ext.insert(ServerName(name.to_owned()));
+                                }
+
+                                if let Some(ref on_connect) = on_connect {
+                                    on_connect(io, ext);
+                                }
+                            },
+                        )
                     } else {
-                        svc
+                        match on_connect {
+                            Some(on_connect) => svc.on_connect_ext(on_connect),
+                            None => svc,
+                        }
                     };
 
                     let fac = factory()
                         .into_factory()
                         .map_err(|err| err.into().error_response());
 
+                    let hosts = c.virtual_hosts[..virtual_hosts]
+                        .iter()
+                        .map(|host| (host.name.clone(), (host.factory)(addr)))
+                        .collect();
+
                     let acceptor_config = match c.tls_handshake_timeout {
                         Some(dur) => TlsAcceptorConfig::default().handshake_timeout(dur),
                         None => TlsAcceptorConfig::default(),
                     };
 
-                    svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(true, host.clone(), addr)
-                    }))
+                    svc.finish(ConnectionLimited::new(ServerOptions::new(
+                        VirtualHosts::new(
+                            map_config(fac, move |_| AppConfig::new(true, host.clone(), addr)),
+                            hosts,
+                        ),
+                        server_options.clone(),
+                    )))
                     .rustls_with_config(config.clone(), acceptor_config)
                 })?;
 
@@ -554,6 +976,7 @@ where
         });
 
         let on_connect_fn = self.on_connect_fn.clone();
+        let server_options = self.server_options.clone();
 
         self.builder =
             self.builder
@@ -565,12 +988,25 @@ where
                         .keep_alive(c.keep_alive)
                         .client_request_timeout(c.client_request_timeout)
                         .client_disconnect_timeout(c.client_disconnect_timeout)
+                        .max_header_count(c.max_header_count)
+                        .max_header_size(c.max_header_size)
+                        .h1_framing_strictness(c.h1_framing_strictness)
+                        .h1_header_folding(c.h1_header_folding)
                         .local_addr(addr);
 
-                    let svc = if let Some(handler) = on_connect_fn.clone() {
-                        svc.on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext))
-                    } else {
-                        svc
+                    let svc = match c.h1_payload_metrics.clone() {
+                        Some(metrics) => svc.h1_payload_metrics(metrics),
+                        None => svc,
+                    };
+
+                    let svc = match c.memory_budget.clone() {
+                        Some(budget) => svc.h1_memory_budget(budget),
+                        None => svc,
+                    };
+
+                    let svc = match on_connect_ext(on_connect_fn.clone(), &c) {
+                        Some(on_connect) => svc.on_connect_ext(on_connect),
+                        None => svc,
                     };
 
                     let fac = factory()
@@ -584,9 +1020,10 @@ where
                         None => TlsAcceptorConfig::default(),
                     };
 
-                    svc.finish(map_config(fac, move |_| {
-                        AppConfig::new(true, host.clone(), addr)
-                    }))
+                    svc.finish(ConnectionLimited::new(ServerOptions::new(
+                        map_config(fac, move |_| AppConfig::new(true, host.clone(), addr)),
+                        server_options.clone(),
+                    )))
                     .openssl_with_config(acceptor.clone(), acceptor_config)
                 })?;
 
@@ -594,6 +1031,9 @@ where
     }
 
     /// Opens Unix Domain Socket (UDS) from `uds` path and binds server to created listener.
+    ///
+    /// Credentials of connecting processes are available to handlers through
+    /// [`ConnectionInfo::peer_credentials`](crate::dev::ConnectionInfo::peer_credentials).
     #[cfg(unix)]
     pub fn bind_uds<A>(mut self, uds_path: A) -> io::Result<Self>
     where
@@ -605,6 +1045,7 @@ where
 
         let cfg = self.config.clone();
         let factory = self.factory.clone();
+        let server_options = self.server_options.clone();
         let socket_addr =
             net::SocketAddr::new(net::IpAddr::V4(net::Ipv4Addr::new(127, 0, 0, 1)), 8080);
 
@@ -628,12 +1069,30 @@ where
                     .into_factory()
                     .map_err(|err| err.into().error_response());
 
+                let mut svc = HttpService::build()
+                    .keep_alive(c.keep_alive)
+                    .client_request_timeout(c.client_request_timeout)
+                    .client_disconnect_timeout(c.client_disconnect_timeout)
+                    .max_header_count(c.max_header_count)
+                    .max_header_size(c.max_header_size)
+                    .h1_framing_strictness(c.h1_framing_strictness)
+                    .h1_header_folding(c.h1_header_folding);
+
+                if let Some(metrics) = c.h1_payload_metrics.clone() {
+                    svc = svc.h1_payload_metrics(metrics);
+                }
+
+                if let Some(budget) = c.memory_budget.clone() {
+                    svc = svc.h1_memory_budget(budget);
+                }
+
+                svc = svc.on_connect_ext(uds_on_connect(on_connect_ext(None, &c)));
+
                 fn_service(|io: UnixStream| async { Ok((io, Protocol::Http1, None)) }).and_then(
-                    HttpService::build()
-                        .keep_alive(c.keep_alive)
-                        .client_request_timeout(c.client_request_timeout)
-                        .client_disconnect_timeout(c.client_disconnect_timeout)
-                        .finish(map_config(fac, move |_| config.clone())),
+                    svc.finish(ConnectionLimited::new(ServerOptions::new(
+                        map_config(fac, move |_| config.clone()),
+                        server_options.clone(),
+                    ))),
                 )
             },
         )?;
@@ -642,6 +1101,9 @@ where
     }
 
     /// Binds to existing Unix Domain Socket (UDS) listener.
+    ///
+    /// Credentials of connecting processes are available to handlers through
+    /// [`ConnectionInfo::peer_credentials`](crate::dev::ConnectionInfo::peer_credentials).
     #[cfg(unix)]
     pub fn listen_uds(mut self, lst: std::os::unix::net::UnixListener) -> io::Result<Self> {
         use actix_http::Protocol;
@@ -660,6 +1122,7 @@ where
         let addr = lst.local_addr()?;
         let name = format!("actix-web-service-{:?}", addr);
         let on_connect_fn = self.on_connect_fn.clone();
+        let server_options = self.server_options.clone();
 
         self.builder = self.builder.listen_uds(name, lst, move || {
             let c = cfg.lock().unwrap();
@@ -673,18 +1136,31 @@ where
                 let mut svc = HttpService::build()
                     .keep_alive(c.keep_alive)
                     .client_request_timeout(c.client_request_timeout)
-                    .client_disconnect_timeout(c.client_disconnect_timeout);
+                    .client_disconnect_timeout(c.client_disconnect_timeout)
+                    .max_header_count(c.max_header_count)
+                    .max_header_size(c.max_header_size)
+                    .h1_framing_strictness(c.h1_framing_strictness)
+                    .h1_header_folding(c.h1_header_folding);
+
+                if let Some(metrics) = c.h1_payload_metrics.clone() {
+                    svc = svc.h1_payload_metrics(metrics);
+                }
 
-                if let Some(handler) = on_connect_fn.clone() {
-                    svc = svc
-                        .on_connect_ext(move |io: &_, ext: _| (handler)(io as &dyn Any, ext));
+                if let Some(budget) = c.memory_budget.clone() {
+                    svc = svc.h1_memory_budget(budget);
                 }
 
+                svc = svc
+                    .on_connect_ext(uds_on_connect(on_connect_ext(on_connect_fn.clone(), &c)));
+
                 let fac = factory()
                     .into_factory()
                     .map_err(|err| err.into().error_response());
 
-                svc.finish(map_config(fac, move |_| config.clone()))
+                svc.finish(ConnectionLimited::new(ServerOptions::new(
+                    map_config(fac, move |_| config.clone()),
+                    server_options.clone(),
+                )))
             })
         })?;
         Ok(self)
@@ -751,3 +1227,670 @@ fn openssl_acceptor(mut builder: SslAcceptorBuilder) -> io::Result<SslAcceptor>
 
     Ok(builder.build())
 }
+
+/// Behavior of workers for connections beyond the limit set with
+/// [`HttpServer::max_connections_per_worker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExcessConnections {
+    /// Requests are responded to with `503 Service Unavailable`, closing HTTP/1 connections.
+    Reject,
+
+    /// Requests are held until the connection gets a slot, when another connection closes.
+    Hold,
+}
+
+/// Extends the on-connect callback of Unix domain socket connections to store the
+/// [`PeerCredentials`] of the peer in the connection data.
+#[cfg(unix)]
+fn uds_on_connect(
+    on_connect: Option<impl Fn(&actix_rt::net::UnixStream, &mut Extensions) + 'static>,
+) -> impl Fn(&actix_rt::net::UnixStream, &mut Extensions) + 'static {
+    move |io: &actix_rt::net::UnixStream, ext: &mut Extensions| {
+        if let Ok(cred) = io.peer_cred() {
+            ext.insert(PeerCredentials::new(cred.uid(), cred.gid(), cred.pid()));
+        }
+
+        if let Some(ref on_connect) = on_connect {
+            on_connect(io, ext);
+        }
+    }
+}
+
+thread_local! {
+    /// Connection limits of the servers run by this worker thread, keyed by the address of the
+    /// server's configuration.
+    static CONNECTION_LIMITS: RefCell<HashMap<usize, Weak<ConnectionLimit>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Combines the on-connect callback with tracking of connections against the worker's limit.
+///
+/// The limit is shared by all listeners of the server on the calling worker thread.
+fn on_connect_ext<T: Any>(
+    handler: Option<OnConnectFn>,
+    config: &Config,
+) -> Option<impl Fn(&T, &mut Extensions) + 'static> {
+    let limit = config.max_connections_per_worker.map(|max| {
+        CONNECTION_LIMITS.with(|limits| {
+            let mut limits = limits.borrow_mut();
+            let key = config as *const Config as usize;
+
+            if let Some(limit) = limits.get(&key).and_then(Weak::upgrade) {
+                return limit;
+            }
+
+            let limit = Rc::new(ConnectionLimit {
+                max,
+                behavior: config.excess_connections,
+                queue: config.request_queue,
+                active: Cell::new(0),
+                queued: Cell::new(0),
+                waiters: RefCell::new(Vec::new()),
+            });
+
+            limits.retain(|_, limit| limit.strong_count() > 0);
+            limits.insert(key, Rc::downgrade(&limit));
+            limit
+        })
+    });
+
+    if handler.is_none() && limit.is_none() {
+        return None;
+    }
+
+    Some(move |io: &T, ext: &mut Extensions| {
+        if let Some(ref handler) = handler {
+            (handler)(io as &dyn Any, ext);
+        }
+
+        if let Some(ref limit) = limit {
+            let slot = ConnectionSlot {
+                limit: Rc::clone(limit),
+                acquired: Cell::new(false),
+            };
+            slot.try_acquire();
+
+            ext.insert(Rc::new(slot));
+        }
+    })
+}
+
+/// Bounds on the requests waiting for a slot, set with [`HttpServer::request_queue`].
+#[derive(Debug, Clone, Copy)]
+struct RequestQueue {
+    capacity: usize,
+    max_wait: Duration,
+}
+
+/// Count of a worker's connections that hold a slot.
+struct ConnectionLimit {
+    max: usize,
+    behavior: ExcessConnections,
+    queue: Option<RequestQueue>,
+    active: Cell<usize>,
+    /// Number of requests currently in the request queue.
+    queued: Cell<usize>,
+    waiters: RefCell<Vec<Waker>>,
+}
+
+/// Place of a request in its worker's request queue, which is given up on drop.
+struct QueuedRequest {
+    limit: Rc<ConnectionLimit>,
+}
+
+impl QueuedRequest {
+    /// Enters the request queue, unless it is full.
+    fn enter(limit: &Rc<ConnectionLimit>, queue: RequestQueue) -> Option<Self> {
+        let queued = limit.queued.get();
+
+        if queued < queue.capacity {
+            limit.queued.set(queued + 1);
+            Some(Self {
+                limit: Rc::clone(limit),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Drop for QueuedRequest {
+    fn drop(&mut self) {
+        self.limit.queued.set(self.limit.queued.get() - 1);
+    }
+}
+
+/// Connection data tracking whether the connection holds one of its worker's slots.
+struct ConnectionSlot {
+    limit: Rc<ConnectionLimit>,
+    acquired: Cell<bool>,
+}
+
+impl ConnectionSlot {
+    /// Returns true if the connection holds a slot, acquiring a free one if needed.
+    fn try_acquire(&self) -> bool {
+        if !self.acquired.get() {
+            let active = self.limit.active.get();
+
+            if active < self.limit.max {
+                self.limit.active.set(active + 1);
+                self.acquired.set(true);
+            }
+        }
+
+        self.acquired.get()
+    }
+
+    fn poll_acquire(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.try_acquire() {
+            Poll::Ready(())
+        } else {
+            let mut waiters = self.limit.waiters.borrow_mut();
+
+            // a request polled again is still registered until a slot is freed
+            if !waiters.iter().any(|waker| waker.will_wake(cx.waker())) {
+                waiters.push(cx.waker().clone());
+            }
+
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        if self.acquired.get() {
+            let limit = &self.limit;
+            limit.active.set(limit.active.get() - 1);
+
+            // wake all held requests; those that do not get the free slot register again
+            for waker in limit.waiters.borrow_mut().drain(..) {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Service factory that holds or rejects requests on connections without a slot.
+struct ConnectionLimited<T> {
+    factory: T,
+}
+
+impl<T> ConnectionLimited<T> {
+    fn new(factory: T) -> Self {
+        Self { factory }
+    }
+}
+
+impl<T, B> ServiceFactory<Request> for ConnectionLimited<T>
+where
+    T: ServiceFactory<Request, Config = (), Response = Response<EitherBody<B>>>,
+    T::Future: 'static,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = T::Error;
+    type Config = ();
+    type Service = ConnectionLimitedService<T::Service>;
+    type InitError = T::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fut = self.factory.new_service(());
+
+        Box::pin(async move {
+            Ok(ConnectionLimitedService {
+                service: Rc::new(fut.await?),
+            })
+        })
+    }
+}
+
+struct ConnectionLimitedService<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<Request> for ConnectionLimitedService<S>
+where
+    S: Service<Request, Response = Response<EitherBody<B>>>,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = S::Error;
+    type Future = ConnectionLimitedFuture<S, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: Request) -> Self::Future {
+        let slot = match req.conn_data::<Rc<ConnectionSlot>>() {
+            Some(slot) if !slot.try_acquire() => slot,
+            _ => {
+                return ConnectionLimitedFuture::Service {
+                    fut: self.service.call(req),
+                }
+            }
+        };
+
+        if let Some(queue) = slot.limit.queue {
+            return match QueuedRequest::enter(&slot.limit, queue) {
+                Some(place) => ConnectionLimitedFuture::Queued {
+                    slot: Rc::clone(slot),
+                    place,
+                    deadline: Box::pin(sleep(queue.max_wait)),
+                    service: Rc::clone(&self.service),
+                    req: Some(req),
+                },
+                None => ConnectionLimitedFuture::Rejected {
+                    res: Some(service_unavailable()),
+                },
+            };
+        }
+
+        match slot.limit.behavior {
+            ExcessConnections::Reject => ConnectionLimitedFuture::Rejected {
+                res: Some(service_unavailable()),
+            },
+
+            ExcessConnections::Hold => ConnectionLimitedFuture::Held {
+                slot: Rc::clone(slot),
+                service: Rc::clone(&self.service),
+                req: Some(req),
+            },
+        }
+    }
+}
+
+/// Response to requests without a slot, which closes HTTP/1 connections.
+fn service_unavailable<B>() -> Response<EitherBody<B>> {
+    let mut res = Response::with_body(
+        StatusCode::SERVICE_UNAVAILABLE,
+        EitherBody::right(BoxBody::new(())),
+    );
+    res.head_mut().set_connection_type(ConnectionType::Close);
+    res
+}
+
+pin_project! {
+    #[project = ConnectionLimitedProj]
+    enum ConnectionLimitedFuture<S, B>
+    where
+        S: Service<Request, Response = Response<EitherBody<B>>>,
+    {
+        Service {
+            #[pin]
+            fut: S::Future,
+        },
+        Rejected {
+            res: Option<Response<EitherBody<B>>>,
+        },
+        Held {
+            slot: Rc<ConnectionSlot>,
+            service: Rc<S>,
+            req: Option<Request>,
+        },
+        Queued {
+            slot: Rc<ConnectionSlot>,
+            place: QueuedRequest,
+            deadline: Pin<Box<Sleep>>,
+            service: Rc<S>,
+            req: Option<Request>,
+        },
+    }
+}
+
+impl<S, B> Future for ConnectionLimitedFuture<S, B>
+where
+    S: Service<Request, Response = Response<EitherBody<B>>>,
+{
+    type Output = Result<Response<EitherBody<B>>, S::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.as_mut().project() {
+            ConnectionLimitedProj::Service { fut } => fut.poll(cx),
+
+            ConnectionLimitedProj::Rejected { res } => Poll::Ready(Ok(res.take().unwrap())),
+
+            ConnectionLimitedProj::Held { slot, service, req } => {
+                ready!(slot.poll_acquire(cx));
+
+                let fut = service.call(req.take().unwrap());
+                self.set(ConnectionLimitedFuture::Service { fut });
+                self.poll(cx)
+            }
+
+            ConnectionLimitedProj::Queued {
+                slot,
+                deadline,
+                service,
+                req,
+                ..
+            } => {
+                if slot.poll_acquire(cx).is_ready() {
+                    // leaves the queue by dropping its place
+                    let fut = service.call(req.take().unwrap());
+                    self.set(ConnectionLimitedFuture::Service { fut });
+                    return self.poll(cx);
+                }
+
+                ready!(deadline.as_mut().poll(cx));
+
+                Poll::Ready(Ok(service_unavailable()))
+            }
+        }
+    }
+}
+
+/// Service factory that answers server-wide `OPTIONS *` requests before they reach the app.
+struct ServerOptions<T, B> {
+    factory: T,
+    handler: Option<ServerOptionsHandler>,
+    _body: PhantomData<B>,
+}
+
+impl<T, B> ServerOptions<T, B> {
+    fn new(factory: T, handler: Option<ServerOptionsHandler>) -> Self {
+        Self {
+            factory,
+            handler,
+            _body: PhantomData,
+        }
+    }
+}
+
+impl<T, B> ServiceFactory<Request> for ServerOptions<T, B>
+where
+    T: ServiceFactory<Request, Config = ()>,
+    T::Future: 'static,
+    T::Response: Into<Response<B>>,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = T::Error;
+    type Config = ();
+    type Service = ServerOptionsService<T::Service, B>;
+    type InitError = T::InitError;
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fut = self.factory.new_service(());
+        let handler = self.handler.clone();
+
+        Box::pin(async move {
+            Ok(ServerOptionsService {
+                service: fut.await?,
+                handler,
+                _body: PhantomData,
+            })
+        })
+    }
+}
+
+struct ServerOptionsService<S, B> {
+    service: S,
+    handler: Option<ServerOptionsHandler>,
+    _body: PhantomData<B>,
+}
+
+impl<S, B> Service<Request> for ServerOptionsService<S, B>
+where
+    S: Service<Request>,
+    S::Response: Into<Response<B>>,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = S::Error;
+    type Future = ServerOptionsFuture<S::Future, B>;
+
+    actix_service::forward_ready!(service);
+
+    fn call(&self, req: Request) -> Self::Future {
+        if req.method() == Method::OPTIONS && req.uri() == "*" {
+            let res = match self.handler {
+                Some(ref handler) => handler().into(),
+                None => Response::with_body(StatusCode::OK, BoxBody::new(())),
+            };
+
+            ServerOptionsFuture {
+                fut: None,
+                res: Some(res),
+                _body: PhantomData,
+            }
+        } else {
+            ServerOptionsFuture {
+                fut: Some(self.service.call(req)),
+                res: None,
+                _body: PhantomData,
+            }
+        }
+    }
+}
+
+pin_project! {
+    struct ServerOptionsFuture<F, B> {
+        #[pin]
+        fut: Option<F>,
+        res: Option<Response<BoxBody>>,
+        _body: PhantomData<B>,
+    }
+}
+
+impl<F, R, E, B> Future for ServerOptionsFuture<F, B>
+where
+    F: Future<Output = Result<R, E>>,
+    R: Into<Response<B>>,
+{
+    type Output = Result<Response<EitherBody<B>>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Some(res) = this.res.take() {
+            return Poll::Ready(Ok(res.map_body(|_, body| EitherBody::right(body))));
+        }
+
+        let res = ready!(this.fut.as_pin_mut().unwrap().poll(cx))?;
+        Poll::Ready(Ok(res.into().map_body(|_, body| EitherBody::left(body))))
+    }
+}
+
+/// Certificate resolver that selects the certificate of a virtual host by SNI host name.
+#[cfg(feature = "rustls")]
+struct VirtualHostCerts {
+    hosts: Vec<(String, Arc<dyn ResolvesServerCert>)>,
+    fallback: Arc<dyn ResolvesServerCert>,
+}
+
+#[cfg(feature = "rustls")]
+impl ResolvesServerCert for VirtualHostCerts {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let resolver = client_hello
+            .server_name()
+            .and_then(|name| {
+                self.hosts
+                    .iter()
+                    .find(|(host, _)| host.eq_ignore_ascii_case(name))
+            })
+            .map_or(&self.fallback, |(_, resolver)| resolver);
+
+        resolver.resolve(client_hello)
+    }
+}
+
+/// SNI host name sent by the client of a TLS connection.
+#[cfg(feature = "rustls")]
+struct ServerName(String);
+
+/// Service factory that routes requests to the app of the virtual host their connection was
+/// established for.
+#[cfg(feature = "rustls")]
+struct VirtualHosts<T, B> {
+    fallback: T,
+    hosts: Vec<(String, BoxedVirtualHostFactory)>,
+    _body: PhantomData<B>,
+}
+
+#[cfg(feature = "rustls")]
+impl<T, B> VirtualHosts<T, B> {
+    fn new(fallback: T, hosts: Vec<(String, BoxedVirtualHostFactory)>) -> Self {
+        Self {
+            fallback,
+            hosts,
+            _body: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+impl<T, B> ServiceFactory<Request> for VirtualHosts<T, B>
+where
+    T: ServiceFactory<Request, Config = (), Error = HttpResponse>,
+    T::Future: 'static,
+    T::InitError: fmt::Debug,
+    T::Response: Into<Response<B>>,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = HttpResponse;
+    type Config = ();
+    type Service = VirtualHostsService<T::Service, B>;
+    type InitError = ();
+    type Future = LocalBoxFuture<'static, Result<Self::Service, Self::InitError>>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        let fallback = self.fallback.new_service(());
+        let hosts = self
+            .hosts
+            .iter()
+            .map(|(name, factory)| {
+                let fut = factory.new_service(());
+                let name = name.clone();
+                async move { Ok::<_, ()>((name, fut.await?)) }
+            })
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            let fallback = fallback.await.map_err(|err| {
+                log::error!("Can not construct app: {:?}", err);
+            })?;
+
+            let hosts = futures_util::future::try_join_all(hosts).await?;
+
+            Ok(VirtualHostsService {
+                fallback,
+                hosts,
+                _body: PhantomData,
+            })
+        })
+    }
+}
+
+#[cfg(feature = "rustls")]
+struct VirtualHostsService<S, B> {
+    fallback: S,
+    hosts: Vec<(String, BoxedVirtualHostService)>,
+    _body: PhantomData<B>,
+}
+
+#[cfg(feature = "rustls")]
+impl<S, B> Service<Request> for VirtualHostsService<S, B>
+where
+    S: Service<Request, Error = HttpResponse>,
+    S::Response: Into<Response<B>>,
+{
+    type Response = Response<EitherBody<B>>;
+    type Error = HttpResponse;
+    type Future = VirtualHostsFuture<S::Future, B>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.fallback.poll_ready(cx))?;
+
+        for (_, service) in &self.hosts {
+            ready!(service.poll_ready(cx))?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&self, req: Request) -> Self::Future {
+        let name = req.conn_data::<ServerName>();
+
+        if let Some(name) = name {
+            match request_host(&req) {
+                Some(host) if !host.eq_ignore_ascii_case(&name.0) => {
+                    return VirtualHostsFuture::Misdirected { _body: PhantomData };
+                }
+                _ => {}
+            }
+        }
+
+        let host = name.and_then(|name| {
+            self.hosts
+                .iter()
+                .find(|(host, _)| host.eq_ignore_ascii_case(&name.0))
+        });
+
+        match host {
+            Some((_, service)) => VirtualHostsFuture::Host {
+                fut: service.call(req),
+            },
+            None => VirtualHostsFuture::Fallback {
+                fut: self.fallback.call(req),
+                _body: PhantomData,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "rustls")]
+pin_project! {
+    #[project = VirtualHostsProj]
+    enum VirtualHostsFuture<F, B> {
+        Fallback {
+            #[pin]
+            fut: F,
+            _body: PhantomData<B>,
+        },
+        Host {
+            fut: LocalBoxFuture<'static, Result<Response<BoxBody>, HttpResponse>>,
+        },
+        Misdirected {
+            _body: PhantomData<B>,
+        },
+    }
+}
+
+/// Returns the host name, without port, that a request is addressed to.
+#[cfg(feature = "rustls")]
+fn request_host(req: &Request) -> Option<String> {
+    if let Some(host) = req.uri().host() {
+        return Some(host.to_owned());
+    }
+
+    let host = req.head().headers().get(header::HOST)?.to_str().ok()?;
+    let authority = host.parse::<Authority>().ok()?;
+
+    Some(authority.host().to_owned())
+}
+
+#[cfg(feature = "rustls")]
+impl<F, R, B> Future for VirtualHostsFuture<F, B>
+where
+    F: Future<Output = Result<R, HttpResponse>>,
+    R: Into<Response<B>>,
+{
+    type Output = Result<Response<EitherBody<B>>, HttpResponse>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.project() {
+            VirtualHostsProj::Fallback { fut, .. } => {
+                let res = ready!(fut.poll(cx))?;
+                Poll::Ready(Ok(res.into().map_body(|_, body| EitherBody::left(body))))
+            }
+
+            VirtualHostsProj::Host { fut } => {
+                let res = ready!(fut.as_mut().poll(cx))?;
+                Poll::Ready(Ok(res.map_body(|_, body| EitherBody::right(body))))
+            }
+
+            VirtualHostsProj::Misdirected { .. } => {
+                let res = Response::new(StatusCode::MISDIRECTED_REQUEST);
+                Poll::Ready(Ok(res.map_body(|_, body| EitherBody::right(body))))
+            }
+        }
+    }
+}
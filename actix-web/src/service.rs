@@ -6,6 +6,7 @@ use std::{
 
 use actix_http::{
     body::{BoxBody, EitherBody, MessageBody},
+    error::PayloadError,
     header::HeaderMap,
     BoxedPayloadStream, Extensions, HttpMessage, Method, Payload, RequestHead, Response,
     ResponseHead, StatusCode, Uri, Version,
@@ -15,13 +16,15 @@ use actix_service::{
     boxed::{BoxService, BoxServiceFactory},
     IntoServiceFactory, ServiceFactory,
 };
+use bytes::Bytes;
 #[cfg(feature = "cookies")]
 use cookie::{Cookie, ParseError as CookieParseError};
+use futures_core::Stream;
 
 use crate::{
     config::{AppConfig, AppService},
     dev::ensure_leading_slash,
-    guard::{Guard, GuardContext},
+    guard::{Guard, GuardContext, RegisteredMethods},
     info::ConnectionInfo,
     rmap::ResourceMap,
     Error, FromRequest, HttpRequest, HttpResponse,
@@ -313,6 +316,83 @@ impl ServiceRequest {
         self.payload = payload;
     }
 
+    /// Replaces the request payload with a stream built from the current one.
+    ///
+    /// This is the simplest way for middleware, including closures passed to
+    /// [`App::wrap_fn`](crate::App::wrap_fn), to wrap the body stream before it reaches
+    /// extractors: `f` is given the current payload and returns any stream of body chunks to
+    /// use in its place.
+    ///
+    /// # Examples
+    /// Decompressing gzip request bodies in closure middleware:
+    /// ```
+    /// use actix_web::{
+    ///     dev::{Decompress, Service as _},
+    ///     http::header::{ContentEncoding, CONTENT_ENCODING, CONTENT_LENGTH},
+    ///     web, App,
+    /// };
+    ///
+    /// let app = App::new()
+    ///     .wrap_fn(|mut req, srv| {
+    ///         if req.headers().get(CONTENT_ENCODING).map_or(false, |enc| enc == "gzip") {
+    ///             req.map_payload(|pl| Decompress::new(pl, ContentEncoding::Gzip));
+    ///
+    ///             // the body is no longer encoded and its length has changed
+    ///             req.headers_mut().remove(CONTENT_ENCODING);
+    ///             req.headers_mut().remove(CONTENT_LENGTH);
+    ///         }
+    ///
+    ///         srv.call(req)
+    ///     })
+    ///     .route("/", web::post().to(|body: String| async move { body }));
+    /// ```
+    pub fn map_payload<F, S>(&mut self, f: F)
+    where
+        F: FnOnce(Payload) -> S,
+        S: Stream<Item = Result<Bytes, PayloadError>> + 'static,
+    {
+        let stream = f(self.payload.take());
+        self.payload = Payload::from(Box::pin(stream) as BoxedPayloadStream);
+    }
+
+    /// Returns `true` if the request has a payload that has not been taken yet.
+    #[inline]
+    pub fn has_payload(&self) -> bool {
+        !matches!(self.payload, Payload::None)
+    }
+
+    /// Reads and discards the rest of the request payload.
+    ///
+    /// Useful in default services and other handlers that ignore the request body, so that the
+    /// connection can be reused for the next request. Fails with [`PayloadError::Overflow`] once
+    /// more than `limit` bytes have been discarded.
+    pub async fn drain_payload(&mut self, limit: usize) -> Result<(), PayloadError> {
+        let mut payload = self.payload.take();
+        let mut drained = 0;
+
+        while let Some(chunk) = futures_util::StreamExt::next(&mut payload).await {
+            drained += chunk?.len();
+
+            if drained > limit {
+                return Err(PayloadError::Overflow);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the methods accepted by the routes of the resource that matched the request path.
+    ///
+    /// Only methods of routes that were checked against this request are included. Returns `None`
+    /// if no resource matched the path or its routes have no method guards. A default service
+    /// can use this to differentiate `405 Method Not Allowed` from `404 Not Found` responses and
+    /// to set the `Allow` header.
+    pub fn allowed_methods(&self) -> Option<Vec<Method>> {
+        self.extensions()
+            .get::<RegisteredMethods>()
+            .map(|methods| methods.0.clone())
+    }
+
     /// Add data container to request's resolution set.
     ///
     /// In middleware, prefer [`extensions_mut`](ServiceRequest::extensions_mut) for request-local
@@ -845,6 +925,35 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn map_payload() {
+        use futures_util::StreamExt as _;
+
+        let app = test::init_service(
+            App::new()
+                .wrap_fn(|mut req, svc| {
+                    req.map_payload(|pl| {
+                        pl.map(|chunk| chunk.map(|chunk| chunk.to_ascii_uppercase().into()))
+                    });
+                    svc.call(req)
+                })
+                .route("/", web::post().to(|body: String| async move { body })),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .set_payload("hello world")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(test::read_body(res).await, "HELLO WORLD");
+
+        // requests without a payload yield an empty stream
+        let mut req = TestRequest::default().to_srv_request();
+        req.map_payload(|pl| pl);
+        let (_, mut pl) = req.into_parts();
+        assert!(pl.next().await.is_none());
+    }
+
     #[actix_rt::test]
     #[should_panic(expected = "called `Option::unwrap()` on a `None` value")]
     async fn cloning_request_panics() {
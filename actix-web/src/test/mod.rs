@@ -20,6 +20,8 @@
 //! - [`try_read_body`]
 //! - [`read_body_json`]
 //! - [`try_read_body_json`]
+//! - [`read_body_json_limit`]
+//! - [`try_read_body_json_limit`]
 
 // TODO: more docs on generally how testing works with these parts
 
@@ -34,9 +36,10 @@ pub use self::test_request::TestRequest;
 pub use self::test_services::{default_service, ok_service, simple_service, status_service};
 #[allow(deprecated)]
 pub use self::test_utils::{
-    call_and_read_body, call_and_read_body_json, call_service, init_service, read_body,
-    read_body_json, read_response, read_response_json, try_call_and_read_body_json,
-    try_call_service, try_read_body, try_read_body_json,
+    call_and_read_body, call_and_read_body_json, call_and_read_body_limit, call_service,
+    init_service, read_body, read_body_json, read_body_json_limit, read_response,
+    read_response_json, try_call_and_read_body_json, try_call_and_read_body_limit,
+    try_call_service, try_read_body, try_read_body_json, try_read_body_json_limit,
 };
 
 #[cfg(test)]
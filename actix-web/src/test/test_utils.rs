@@ -149,6 +149,72 @@ where
     read_body(res).await
 }
 
+/// Helper function that calls a service and returns at most `limit` bytes of the response body.
+///
+/// Works with both buffered and streaming response bodies, which are polled to completion. The
+/// limit guards tests against handlers that produce an unexpectedly large or endless body.
+///
+/// # Examples
+/// ```
+/// use actix_web::{test, web, App, HttpResponse};
+/// use bytes::Bytes;
+/// use futures_util::stream;
+///
+/// #[actix_web::test]
+/// async fn test_stream() {
+///     let app = test::init_service(App::new().route(
+///         "/",
+///         web::get().to(|| async {
+///             let chunks = ["hello", " ", "world"];
+///             HttpResponse::Ok().streaming(stream::iter(
+///                 chunks.map(|chunk| Ok::<_, actix_web::Error>(Bytes::from(chunk))),
+///             ))
+///         }),
+///     ))
+///     .await;
+///
+///     let req = test::TestRequest::get().uri("/").to_request();
+///     let body = test::call_and_read_body_limit(&app, req, 1024).await;
+///     assert_eq!(body, "hello world");
+/// }
+/// ```
+///
+/// # Panics
+/// Panics if:
+/// - service call returns error;
+/// - body yields an error while it is being read;
+/// - body size exceeds `limit`.
+pub async fn call_and_read_body_limit<S, B>(app: &S, req: Request, limit: usize) -> Bytes
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    try_call_and_read_body_limit(app, req, limit)
+        .await
+        .expect("error reading test response body")
+}
+
+/// Fallible version of [`call_and_read_body_limit`] that allows testing service call, body
+/// reading and body size errors.
+pub async fn try_call_and_read_body_limit<S, B>(
+    app: &S,
+    req: Request,
+    limit: usize,
+) -> Result<Bytes, Box<dyn StdError>>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = Error>,
+    B: MessageBody,
+{
+    let res = try_call_service(app, req)
+        .await
+        .map_err(Into::<Box<dyn StdError>>::into)?;
+
+    body::to_bytes_limited(res.into_body(), limit)
+        .await
+        .map_err(Into::<Box<dyn StdError>>::into)?
+        .map_err(Into::<Box<dyn StdError>>::into)
+}
+
 #[doc(hidden)]
 #[deprecated(since = "4.0.0", note = "Renamed to `call_and_read_body`.")]
 pub async fn read_response<S, B>(app: &S, req: Request) -> Bytes
@@ -280,6 +346,75 @@ where
     serde_json::from_slice(&body).map_err(Into::<Box<dyn StdError>>::into)
 }
 
+/// Helper function that returns a deserialized response body of a ServiceResponse, reading at
+/// most `limit` bytes of the body.
+///
+/// Works with both buffered and streaming response bodies. Useful for making sure a handler does
+/// not produce an unexpectedly large payload.
+///
+/// # Examples
+/// ```
+/// use actix_web::{App, test, web, HttpResponse};
+/// use serde::{Serialize, Deserialize};
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct Person {
+///     id: String,
+///     name: String,
+/// }
+///
+/// #[actix_web::test]
+/// async fn test_get_person() {
+///     let app = test::init_service(
+///         App::new().service(web::resource("/people").to(|| async {
+///             HttpResponse::Ok().json(Person { id: "1".to_owned(), name: "User".to_owned() })
+///         }))
+///     ).await;
+///
+///     let res = test::TestRequest::get().uri("/people").send_request(&app).await;
+///     let person: Person = test::read_body_json_limit(res, 1024).await;
+///     assert_eq!(person.name, "User");
+/// }
+/// ```
+///
+/// # Panics
+/// Panics if:
+/// - body yields an error while it is being read;
+/// - body size exceeds `limit`;
+/// - received body is not a valid JSON representation of `T`.
+pub async fn read_body_json_limit<T, B>(res: ServiceResponse<B>, limit: usize) -> T
+where
+    B: MessageBody,
+    T: DeserializeOwned,
+{
+    try_read_body_json_limit(res, limit)
+        .await
+        .unwrap_or_else(|err| {
+            panic!(
+                "could not deserialize body into a {}\nerr: {}",
+                std::any::type_name::<T>(),
+                err,
+            )
+        })
+}
+
+/// Fallible version of [`read_body_json_limit`] that allows testing response deserialization and
+/// body size errors.
+pub async fn try_read_body_json_limit<T, B>(
+    res: ServiceResponse<B>,
+    limit: usize,
+) -> Result<T, Box<dyn StdError>>
+where
+    B: MessageBody,
+    T: DeserializeOwned,
+{
+    let body = body::to_bytes_limited(res.into_body(), limit)
+        .await
+        .map_err(Into::<Box<dyn StdError>>::into)?
+        .map_err(Into::<Box<dyn StdError>>::into)?;
+    serde_json::from_slice(&body).map_err(Into::<Box<dyn StdError>>::into)
+}
+
 /// Helper function that returns a deserialized response body of a TestRequest
 ///
 /// # Examples
@@ -487,6 +622,90 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[actix_rt::test]
+    async fn test_call_and_read_body_limit() {
+        let app = init_service(
+            App::new()
+                .service(web::resource("/stream").to(|| {
+                    let chunks = ["hello", " ", "world"];
+                    HttpResponse::Ok().streaming(futures_util::stream::iter(
+                        chunks
+                            .map(|chunk| Ok::<_, Error>(Bytes::from_static(chunk.as_bytes()))),
+                    ))
+                }))
+                .service(web::resource("/endless").to(|| {
+                    HttpResponse::Ok().streaming(futures_util::stream::repeat_with(|| {
+                        Ok::<_, Error>(Bytes::from_static(b"data"))
+                    }))
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/stream").to_request();
+        let body = call_and_read_body_limit(&app, req, 11).await;
+        assert_eq!(body, "hello world");
+
+        let req = TestRequest::with_uri("/stream").to_request();
+        let err = try_call_and_read_body_limit(&app, req, 10)
+            .await
+            .unwrap_err();
+        assert!(err.is::<body::BodyLimitExceeded>());
+
+        // an endless body is cut off at the limit
+        let req = TestRequest::with_uri("/endless").to_request();
+        let err = try_call_and_read_body_limit(&app, req, 1024)
+            .await
+            .unwrap_err();
+        assert!(err.is::<body::BodyLimitExceeded>());
+    }
+
+    #[actix_rt::test]
+    async fn test_body_json_limit() {
+        let app = init_service(
+            App::new()
+                .service(web::resource("/people").route(
+                    web::post().to(|person: web::Json<Person>| HttpResponse::Ok().json(person)),
+                ))
+                .service(web::resource("/stream").to(|| {
+                    let chunks = [r#"{"id":"12345","#, r#""name":"User name"}"#];
+                    HttpResponse::Ok().streaming(futures_util::stream::iter(
+                        chunks
+                            .map(|chunk| Ok::<_, Error>(Bytes::from_static(chunk.as_bytes()))),
+                    ))
+                })),
+        )
+        .await;
+
+        let payload = r#"{"id":"12345","name":"User name"}"#.as_bytes();
+
+        let res = TestRequest::post()
+            .uri("/people")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(payload)
+            .send_request(&app)
+            .await;
+        let result: Person = read_body_json_limit(res, payload.len()).await;
+        assert_eq!(&result.name, "User name");
+
+        let res = TestRequest::post()
+            .uri("/people")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(payload)
+            .send_request(&app)
+            .await;
+        let result: Result<Person, Box<dyn StdError>> =
+            try_read_body_json_limit(res, payload.len() - 1).await;
+        assert!(result.is_err());
+
+        let res = TestRequest::with_uri("/stream").send_request(&app).await;
+        let result: Person = read_body_json_limit(res, payload.len()).await;
+        assert_eq!(&result.id, "12345");
+
+        let res = TestRequest::with_uri("/stream").send_request(&app).await;
+        let result: Result<Person, Box<dyn StdError>> = try_read_body_json_limit(res, 20).await;
+        assert!(result.is_err());
+    }
+
     #[actix_rt::test]
     async fn test_request_response_form() {
         let app = init_service(App::new().service(web::resource("/people").route(
@@ -0,0 +1,155 @@
+//! For accepted languages extractor documentation, see [`AcceptLanguage`].
+
+use std::{
+    cmp::Reverse,
+    convert::Infallible,
+    future::{ready, Ready},
+    ops,
+};
+
+use crate::{
+    dev::Payload,
+    http::header::{self, LanguageTag, Preference, Quality, QualityItem},
+    FromRequest, HttpRequest,
+};
+
+/// Extractor for the languages accepted by the client, most preferred first.
+///
+/// Language ranges are read from all `Accept-Language` headers of the request and sorted by
+/// quality value, keeping the listed order of ranges with equal quality. Unlike extracting
+/// [`header::AcceptLanguage`] with [`Header`](crate::web::Header), malformed ranges are skipped
+/// instead of failing the extraction. Ranges with a quality value of zero, which mark languages
+/// as not acceptable, are skipped too. Without an `Accept-Language` header, the list is empty.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+///
+/// #[get("/")]
+/// async fn index(langs: web::AcceptLanguage) -> &'static str {
+///     match langs.preferred(&["en", "fr"]) {
+///         Some("fr") => "Bonjour !",
+///         _ => "Hello!",
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AcceptLanguage(pub Vec<Preference<LanguageTag>>);
+
+impl AcceptLanguage {
+    /// Unwrap into the inner list of language ranges.
+    pub fn into_inner(self) -> Vec<Preference<LanguageTag>> {
+        self.0
+    }
+
+    /// Returns the most preferred of the `supported` language tags.
+    ///
+    /// Ranges are matched against supported tags by prefix, case-insensitively, so that a range
+    /// of `en` matches `en-US`. The wildcard range matches the first supported tag. Returns `None`
+    /// if no supported tag matches any range, including when the list is empty.
+    pub fn preferred<'a>(&self, supported: &[&'a str]) -> Option<&'a str> {
+        self.0.iter().find_map(|range| {
+            supported.iter().copied().find(|tag| match range {
+                Preference::Any => true,
+                Preference::Specific(range) => matches_range(range.as_str(), tag),
+            })
+        })
+    }
+}
+
+impl ops::Deref for AcceptLanguage {
+    type Target = [Preference<LanguageTag>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Basic filtering as defined in RFC 4647 §3.3.1.
+fn matches_range(range: &str, tag: &str) -> bool {
+    let prefix = match tag.get(..range.len()) {
+        Some(prefix) => prefix,
+        None => return false,
+    };
+
+    prefix.eq_ignore_ascii_case(range)
+        && (tag.len() == range.len() || tag.as_bytes()[range.len()] == b'-')
+}
+
+impl FromRequest for AcceptLanguage {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let mut ranges = req
+            .headers()
+            .get_all(header::ACCEPT_LANGUAGE)
+            .filter_map(|val| val.to_str().ok())
+            .flat_map(|val| val.split(','))
+            .filter_map(|item| {
+                item.trim()
+                    .parse::<QualityItem<Preference<LanguageTag>>>()
+                    .ok()
+            })
+            .filter(|item| item.quality > Quality::ZERO)
+            .collect::<Vec<_>>();
+
+        // use stable sort so items with equal q-factor retain listed order
+        ranges.sort_by_key(|item| Reverse(item.quality));
+
+        ready(Ok(AcceptLanguage(
+            ranges.into_iter().map(|item| item.item).collect(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    async fn extract(values: &[&str]) -> AcceptLanguage {
+        let mut req = TestRequest::default();
+        for val in values {
+            req = req.append_header((header::ACCEPT_LANGUAGE, *val));
+        }
+
+        let (req, mut pl) = req.to_http_parts();
+        AcceptLanguage::from_request(&req, &mut pl).await.unwrap()
+    }
+
+    fn tags(tags: &[&str]) -> Vec<Preference<LanguageTag>> {
+        tags.iter().map(|tag| tag.parse().unwrap()).collect()
+    }
+
+    #[actix_rt::test]
+    async fn ordered_by_quality() {
+        let langs = extract(&["en-US,en;q=0.9,fr;q=0.8"]).await;
+        assert_eq!(langs.0, tags(&["en-US", "en", "fr"]));
+
+        // listed order is kept for equal quality values, across headers
+        let langs = extract(&["fr;q=0.8, de", "*;q=0.5, en"]).await;
+        assert_eq!(langs.0, tags(&["de", "en", "fr", "*"]));
+
+        assert!(extract(&[]).await.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn malformed_ranges_skipped() {
+        let langs = extract(&["en;q=2, !!, fr;q=0.5, , de;q=0, es;q=abc, it"]).await;
+        assert_eq!(langs.0, tags(&["it", "fr"]));
+    }
+
+    #[actix_rt::test]
+    async fn preferred() {
+        let langs = extract(&["de-CH, fr;q=0.9, EN;q=0.8"]).await;
+        assert_eq!(langs.preferred(&["en-GB", "fr-FR"]), Some("fr-FR"));
+        assert_eq!(langs.preferred(&["en-GB", "frr"]), Some("en-GB"));
+        assert_eq!(langs.preferred(&["de"]), None);
+
+        let langs = extract(&["es, *;q=0.1"]).await;
+        assert_eq!(langs.preferred(&["en", "fr"]), Some("en"));
+        assert_eq!(extract(&[]).await.preferred(&["en"]), None);
+    }
+}
@@ -0,0 +1,360 @@
+//! For authorization extractor documentation, see [`BearerAuth`] and [`BasicAuth`].
+
+use std::{
+    borrow::Cow,
+    fmt,
+    future::{ready, Ready},
+};
+
+use base64::{engine::general_purpose, Engine as _};
+
+use crate::{dev::Payload, error::AuthError, http::header, web, FromRequest, HttpRequest};
+
+/// Extractor for a bearer token from the `Authorization` header, as defined in RFC 6750.
+///
+/// Fails with [`AuthError`], which responds with `401 Unauthorized` and a `Bearer` challenge, if
+/// the header is missing or does not hold a bearer token. The payload is left untouched.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+///
+/// #[get("/")]
+/// async fn index(auth: web::BearerAuth) -> String {
+///     format!("Token: {}", auth.token())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BearerAuth {
+    token: String,
+}
+
+impl BearerAuth {
+    /// Returns the bearer token.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Unwrap into the bearer token.
+    pub fn into_token(self) -> String {
+        self.token
+    }
+
+    fn parse(req: &HttpRequest) -> Result<Self, AuthError> {
+        let scheme = "Bearer";
+        let token = credentials(req, scheme, None)?;
+
+        let is_token68 = token.bytes().all(|b| {
+            b.is_ascii_alphanumeric()
+                || matches!(b, b'-' | b'.' | b'_' | b'~' | b'+' | b'/' | b'=')
+        });
+
+        if token.is_empty() || !is_token68 {
+            return Err(AuthError::Malformed {
+                scheme,
+                realm: None,
+            });
+        }
+
+        Ok(BearerAuth {
+            token: token.to_owned(),
+        })
+    }
+}
+
+impl FromRequest for BearerAuth {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::parse(req))
+    }
+}
+
+/// Extractor for Basic credentials from the `Authorization` header, as defined in RFC 7617.
+///
+/// Credentials are decoded from base64 and must be valid UTF-8. Fails with [`AuthError`], which
+/// responds with `401 Unauthorized` and a `Basic` challenge for the realm of the
+/// [`BasicAuthConfig`], if the header is missing or does not hold valid Basic credentials. The
+/// payload is left untouched.
+///
+/// The password is redacted from the `Debug` output.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, web};
+///
+/// #[get("/")]
+/// async fn index(auth: web::BasicAuth) -> String {
+///     format!("Welcome, {}!", auth.user_id())
+/// }
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct BasicAuth {
+    user_id: String,
+    password: String,
+}
+
+impl BasicAuth {
+    /// Returns the user ID.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Returns the password, which may be empty.
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    fn parse(req: &HttpRequest) -> Result<Self, AuthError> {
+        let scheme = "Basic";
+        let realm = &BasicAuthConfig::from_req(req).realm;
+        let malformed = || AuthError::Malformed {
+            scheme,
+            realm: Some(realm.clone()),
+        };
+
+        let credentials = credentials(req, scheme, Some(realm))?;
+        let decoded = base64_decode(credentials).ok_or_else(malformed)?;
+        let decoded = String::from_utf8(decoded).map_err(|_| malformed())?;
+
+        // user IDs cannot contain a colon, but passwords can
+        let (user_id, password) = decoded.split_once(':').ok_or_else(malformed)?;
+
+        Ok(BasicAuth {
+            user_id: user_id.to_owned(),
+            password: password.to_owned(),
+        })
+    }
+}
+
+impl fmt::Debug for BasicAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BasicAuth")
+            .field("user_id", &self.user_id)
+            .field("password", &"[redacted]")
+            .finish()
+    }
+}
+
+impl FromRequest for BasicAuth {
+    type Error = AuthError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::parse(req))
+    }
+}
+
+/// [`BasicAuth`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// let app = App::new().app_data(web::BasicAuthConfig::default().realm("Admin area"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BasicAuthConfig {
+    realm: Cow<'static, str>,
+}
+
+impl BasicAuthConfig {
+    /// Sets the realm sent in the `Basic` challenge, which names the protection space the
+    /// credentials are requested for. By default, the realm is "Restricted".
+    pub fn realm(mut self, realm: impl Into<Cow<'static, str>>) -> Self {
+        self.realm = realm.into();
+        self
+    }
+
+    /// Extract config from app data. Check both `T` and `Data<T>`, in that order, and fall back to
+    /// the default config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: BasicAuthConfig = BasicAuthConfig {
+    realm: Cow::Borrowed("Restricted"),
+};
+
+impl Default for BasicAuthConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+/// Returns the credentials following `scheme` in the `Authorization` header.
+///
+/// The scheme is matched case-insensitively. Errors challenge the client for `realm`.
+fn credentials<'a>(
+    req: &'a HttpRequest,
+    scheme: &'static str,
+    realm: Option<&Cow<'static, str>>,
+) -> Result<&'a str, AuthError> {
+    let val = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .ok_or_else(|| AuthError::Missing {
+            scheme,
+            realm: realm.cloned(),
+        })?;
+
+    val.to_str()
+        .ok()
+        .and_then(|val| val.trim().split_once(' '))
+        .filter(|(name, _)| name.eq_ignore_ascii_case(scheme))
+        .map(|(_, credentials)| credentials.trim_start())
+        .ok_or_else(|| AuthError::Malformed {
+            scheme,
+            realm: realm.cloned(),
+        })
+}
+
+/// Decodes standard base64, with optional padding.
+fn base64_decode(data: &str) -> Option<Vec<u8>> {
+    // padding, when present, must complete the final quantum
+    let engine = if data.ends_with('=') {
+        &general_purpose::STANDARD
+    } else {
+        &general_purpose::STANDARD_NO_PAD
+    };
+
+    engine.decode(data).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{header::WWW_AUTHENTICATE, StatusCode},
+        test::TestRequest,
+        ResponseError as _,
+    };
+
+    async fn extract<T: FromRequest>(auth: Option<&str>) -> Result<T, T::Error> {
+        let mut req = TestRequest::default();
+        if let Some(auth) = auth {
+            req = req.insert_header((header::AUTHORIZATION, auth));
+        }
+
+        let (req, mut pl) = req.to_http_parts();
+        T::from_request(&req, &mut pl).await
+    }
+
+    fn challenge(err: AuthError) -> String {
+        let res = err.error_response();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        res.headers()
+            .get(WWW_AUTHENTICATE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    #[actix_rt::test]
+    async fn bearer() {
+        let auth = extract::<BearerAuth>(Some("Bearer mF_9.B5f-4.1JqM"))
+            .await
+            .unwrap();
+        assert_eq!(auth.token(), "mF_9.B5f-4.1JqM");
+
+        let auth = extract::<BearerAuth>(Some("bearer abc==")).await.unwrap();
+        assert_eq!(auth.into_token(), "abc==");
+
+        for auth in ["Bearer", "Bearer ", "Bearer a b", "Basic abc"] {
+            let err = extract::<BearerAuth>(Some(auth)).await.unwrap_err();
+            assert!(matches!(err, AuthError::Malformed { .. }), "{}", auth);
+            assert_eq!(challenge(err), r#"Bearer error="invalid_request""#);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn basic() {
+        // "Aladdin:open sesame"
+        let auth = extract::<BasicAuth>(Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="))
+            .await
+            .unwrap();
+        assert_eq!(auth.user_id(), "Aladdin");
+        assert_eq!(auth.password(), "open sesame");
+
+        // "user:pass:word", unpadded
+        let auth = extract::<BasicAuth>(Some("BASIC dXNlcjpwYXNzOndvcmQ"))
+            .await
+            .unwrap();
+        assert_eq!(auth.user_id(), "user");
+        assert_eq!(auth.password(), "pass:word");
+
+        // "user:"
+        let auth = extract::<BasicAuth>(Some("Basic dXNlcjo=")).await.unwrap();
+        assert_eq!(auth.password(), "");
+
+        // missing colon, invalid base64, invalid UTF-8 and wrong scheme
+        for auth in [
+            "Basic dXNlcg==",
+            "Basic dXNlcjo*",
+            "Basic /w==",
+            "Bearer abc",
+        ] {
+            let err = extract::<BasicAuth>(Some(auth)).await.unwrap_err();
+            assert!(matches!(err, AuthError::Malformed { .. }), "{}", auth);
+            assert_eq!(
+                challenge(err),
+                r#"Basic realm="Restricted", charset="UTF-8""#
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn missing_header() {
+        let err = extract::<BearerAuth>(None).await.unwrap_err();
+        assert!(matches!(err, AuthError::Missing { .. }));
+        assert_eq!(challenge(err), "Bearer");
+
+        let err = extract::<BasicAuth>(None).await.unwrap_err();
+        assert!(matches!(err, AuthError::Missing { .. }));
+        assert_eq!(
+            challenge(err),
+            r#"Basic realm="Restricted", charset="UTF-8""#
+        );
+    }
+
+    #[actix_rt::test]
+    async fn basic_realm() {
+        let (req, mut pl) = TestRequest::default()
+            .app_data(BasicAuthConfig::default().realm(r#"Admin "area""#))
+            .to_http_parts();
+        let err = BasicAuth::from_request(&req, &mut pl).await.unwrap_err();
+        assert_eq!(
+            challenge(err),
+            r#"Basic realm="Admin \"area\"", charset="UTF-8""#
+        );
+    }
+
+    #[actix_rt::test]
+    async fn basic_debug_redacts_password() {
+        let auth = extract::<BasicAuth>(Some("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="))
+            .await
+            .unwrap();
+        let debug = format!("{:?}", auth);
+        assert!(debug.contains("Aladdin"));
+        assert!(!debug.contains("open sesame"));
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(base64_decode("").unwrap(), b"");
+        assert_eq!(base64_decode("Zg==").unwrap(), b"f");
+        assert_eq!(base64_decode("Zm8").unwrap(), b"fo");
+        assert_eq!(base64_decode("Zm9vYmFy").unwrap(), b"foobar");
+        assert!(base64_decode("Zm9vY").is_none());
+        assert!(base64_decode("Zg=").is_none());
+        assert!(base64_decode("Z===").is_none());
+    }
+}
@@ -0,0 +1,254 @@
+//! For base64 payload stream documentation, see [`Base64Payload`].
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine as _,
+};
+use bytes::{BufMut as _, Bytes, BytesMut};
+use futures_core::stream::Stream;
+
+use super::decode_stream::{ChunkDecoder, DecodeStream};
+use crate::{
+    dev,
+    error::{Base64PayloadError, PayloadError},
+};
+
+/// Stream that decodes a base64 encoded payload while it is read.
+///
+/// Chunks are decoded as soon as they arrive, using the standard alphabet. A base64 quantum split
+/// across chunk boundaries is held back until its remaining characters arrive, so the encoded
+/// body is never buffered as a whole. ASCII whitespace, such as the line breaks inserted by MIME
+/// encoders, is ignored. Padding is optional, but when present it must complete the final
+/// quantum. Any other character fails the stream immediately with
+/// [`Base64PayloadError::InvalidCharacter`].
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, Error};
+/// use futures_util::StreamExt as _;
+///
+/// #[post("/")]
+/// async fn decoded_len(body: web::Payload) -> Result<String, Error> {
+///     let mut body = web::Base64Payload::new(body);
+///     let mut len = 0;
+///
+///     while let Some(chunk) = body.next().await {
+///         len += chunk?.len();
+///     }
+///
+///     Ok(len.to_string())
+/// }
+/// ```
+pub struct Base64Payload<S = dev::Payload> {
+    inner: DecodeStream<S, Base64Decoder>,
+}
+
+impl<S> Base64Payload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    /// Constructs a new `Base64Payload` stream that decodes `payload`'s chunks.
+    pub fn new(payload: S) -> Self {
+        let decoder = Base64Decoder {
+            pending: BytesMut::new(),
+            sextets: 0,
+            padding: 0,
+            offset: 0,
+        };
+
+        Self {
+            inner: DecodeStream::new(payload, decoder),
+        }
+    }
+
+    /// Unwrap into the inner payload stream.
+    ///
+    /// Characters of an incomplete quantum held back from the last chunk are lost.
+    pub fn into_inner(self) -> S {
+        self.inner.stream
+    }
+}
+
+impl<S> Stream for Base64Payload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, Base64PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// Standard alphabet engine that leaves padding to be checked by [`Base64Decoder`].
+const ENGINE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+struct Base64Decoder {
+    /// Characters not yet decoded, without whitespace.
+    pending: BytesMut,
+    /// Number of sextets in the current quantum.
+    sextets: usize,
+    /// Number of padding characters seen.
+    padding: usize,
+    /// Number of encoded bytes read so far.
+    offset: usize,
+}
+
+impl Base64Decoder {
+    /// Decodes the first `len` pending characters.
+    fn decode_pending(&mut self, len: usize) -> Bytes {
+        let mut out = Vec::with_capacity(len / 4 * 3 + 2);
+
+        // characters are checked against the alphabet as they are read
+        ENGINE
+            .decode_vec(self.pending.split_to(len), &mut out)
+            .unwrap();
+
+        Bytes::from(out)
+    }
+}
+
+impl ChunkDecoder for Base64Decoder {
+    type Error = Base64PayloadError;
+
+    /// Decodes `chunk`, continuing any held back quantum, returning its complete bytes.
+    fn decode(&mut self, chunk: Bytes) -> Result<Bytes, Base64PayloadError> {
+        for (i, &b) in chunk.iter().enumerate() {
+            let invalid = Base64PayloadError::InvalidCharacter {
+                offset: self.offset + i,
+            };
+
+            if b.is_ascii_whitespace() {
+                continue;
+            }
+
+            if b == b'=' {
+                // padding may only follow two or three sextets, up to a whole quantum
+                if self.sextets < 2 || self.sextets + self.padding == 4 {
+                    return Err(invalid);
+                }
+
+                self.padding += 1;
+                continue;
+            }
+
+            if self.padding > 0 || !(b.is_ascii_alphanumeric() || b == b'+' || b == b'/') {
+                return Err(invalid);
+            }
+
+            self.pending.put_u8(b);
+            self.sextets = (self.sextets + 1) % 4;
+        }
+
+        self.offset += chunk.len();
+
+        // the final quantum is complete once padding has started
+        let len = if self.padding > 0 {
+            self.pending.len()
+        } else {
+            self.pending.len() - self.sextets
+        };
+
+        Ok(self.decode_pending(len))
+    }
+
+    /// Completes the final quantum once the payload has ended.
+    fn finish(&mut self) -> Result<Bytes, Base64PayloadError> {
+        match (self.sextets, self.padding) {
+            (0, _) => Ok(Bytes::new()),
+            (n, 0) if n > 1 => Ok(self.decode_pending(self.pending.len())),
+            (n, padding) if n + padding == 4 => Ok(Bytes::new()),
+            _ => Err(Base64PayloadError::Incomplete),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::types::decode_stream::chunk_stream;
+
+    #[actix_rt::test]
+    async fn split_quantum() {
+        // "aGVsbG8gd29ybGQ=" is "hello world"
+        let mut body = Base64Payload::new(chunk_stream(vec![
+            b"aGV",
+            b"sbG8",
+            b"g",
+            b"d29y\r\n",
+            b"bGQ",
+            b"=",
+        ]));
+
+        assert_eq!(body.next().await.unwrap().unwrap(), "hel");
+        assert_eq!(body.next().await.unwrap().unwrap(), "lo ");
+        assert_eq!(body.next().await.unwrap().unwrap(), "wor");
+        assert_eq!(body.next().await.unwrap().unwrap(), "ld");
+        assert!(body.next().await.is_none());
+
+        // padding is optional
+        let mut body = Base64Payload::new(chunk_stream(vec![b"aGVsbG8gd29ybG", b"Q"]));
+        assert_eq!(body.next().await.unwrap().unwrap(), "hello wor");
+        assert_eq!(body.next().await.unwrap().unwrap(), "ld");
+        assert!(body.next().await.is_none());
+
+        let mut body = Base64Payload::new(chunk_stream(vec![b"YQ", b"=", b"=\n"]));
+        assert_eq!(body.next().await.unwrap().unwrap(), "a");
+        assert!(body.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn invalid_character() {
+        let mut body = Base64Payload::new(chunk_stream(vec![b"aGVs", b"bG8*", b"never read"]));
+
+        assert_eq!(body.next().await.unwrap().unwrap(), "hel");
+
+        match body.next().await.unwrap().unwrap_err() {
+            Base64PayloadError::InvalidCharacter { offset } => assert_eq!(offset, 7),
+            err => panic!("unexpected error: {}", err),
+        }
+
+        assert!(body.next().await.is_none());
+
+        for chunks in [
+            vec![&b"="[..]],
+            vec![b"YQ===="],
+            vec![b"YQ==", b"YQ=="],
+            vec![b"YWJj=", b"="],
+        ] {
+            let res = Base64Payload::new(chunk_stream(chunks))
+                .collect::<Vec<_>>()
+                .await;
+            assert!(matches!(
+                res.last().unwrap(),
+                Err(Base64PayloadError::InvalidCharacter { .. })
+            ));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn incomplete_quantum() {
+        for chunks in [vec![&b"aGVsb"[..]], vec![b"YQ="], vec![b"Y", b"\r\n"]] {
+            let res = Base64Payload::new(chunk_stream(chunks))
+                .collect::<Vec<_>>()
+                .await;
+            assert!(matches!(
+                res.last().unwrap(),
+                Err(Base64PayloadError::Incomplete)
+            ));
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! For replayable request body documentation, see [`BufferedBody`].
+
+use actix_http::h1;
+use bytes::{Bytes, BytesMut};
+use futures_core::stream::Stream;
+use futures_util::StreamExt as _;
+
+use crate::{dev, error::PayloadError};
+
+/// Request body that is buffered once, up to a limit, and can then be replayed any number of
+/// times.
+///
+/// This is useful when the same body has to be processed more than once, such as when a POST
+/// handler issues a `307 Temporary Redirect` or `308 Permanent Redirect` for some requests and
+/// processes the body itself for others, or hands a request on to another handler after
+/// inspecting its body. Each call to [`replay`](Self::replay) returns a new payload stream over
+/// the buffered bytes, which the receiver reads as if it was the original request payload.
+/// Buffered bytes are shared between replays, so replaying does not copy the body.
+///
+/// Unlike the payload stream itself, a buffered body always has an explicit limit, so that
+/// holding on to bodies for replays does not let clients exhaust memory.
+///
+/// # Examples
+/// ```
+/// use actix_web::{dev, post, web, Error, HttpResponse};
+/// use futures_util::StreamExt as _;
+///
+/// async fn store(mut payload: dev::Payload) -> Result<HttpResponse, Error> {
+///     let mut len = 0;
+///
+///     while let Some(chunk) = payload.next().await {
+///         len += chunk?.len();
+///     }
+///
+///     Ok(HttpResponse::Ok().body(format!("stored {} bytes", len)))
+/// }
+///
+/// #[post("/upload")]
+/// async fn upload(payload: web::Payload) -> Result<HttpResponse, Error> {
+///     let body = web::BufferedBody::buffer(payload, 64 * 1024).await?;
+///
+///     if body.bytes().starts_with(b"legacy:") {
+///         // clients re-send the body to the new location
+///         return Ok(HttpResponse::PermanentRedirect()
+///             .insert_header(("location", "/v2/upload"))
+///             .finish());
+///     }
+///
+///     // process the body as if it had just been received
+///     store(body.replay()).await
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferedBody {
+    body: Bytes,
+    limit: usize,
+}
+
+impl BufferedBody {
+    /// Reads `payload` to the end, buffering at most `limit` bytes.
+    ///
+    /// Fails with [`PayloadError::Overflow`] as soon as the payload exceeds `limit` bytes, without
+    /// reading the rest of it.
+    pub async fn buffer<S>(mut payload: S, limit: usize) -> Result<Self, PayloadError>
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    {
+        let mut body = BytesMut::new();
+
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk?;
+
+            if body.len() + chunk.len() > limit {
+                return Err(PayloadError::Overflow);
+            }
+
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(Self {
+            body: body.freeze(),
+            limit,
+        })
+    }
+
+    /// Returns a new payload stream that yields the buffered body.
+    pub fn replay(&self) -> dev::Payload {
+        let (mut sender, payload) = h1::Payload::create(true);
+
+        if !self.body.is_empty() {
+            sender.feed_data(self.body.clone());
+        }
+
+        dev::Payload::from(payload)
+    }
+
+    /// Returns the buffered body.
+    pub fn bytes(&self) -> &Bytes {
+        &self.body
+    }
+
+    /// Returns the limit the body was buffered with.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Unwraps into the buffered body.
+    pub fn into_bytes(self) -> Bytes {
+        self.body
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+
+    #[actix_rt::test]
+    async fn replays_to_second_invocation() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::post().to(|body: String| async move { body.to_uppercase() }),
+        ))
+        .await;
+
+        let (mut sender, payload) = h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"hello "));
+        sender.feed_data(Bytes::from_static(b"world"));
+        sender.feed_eof();
+
+        let body = BufferedBody::buffer(payload, 16).await.unwrap();
+        assert_eq!(body.bytes(), "hello world");
+
+        // the first invocation, e.g. before a redirect, and its follow-up see the same body
+        for _ in 0..2 {
+            let (req, _) = TestRequest::post()
+                .uri("/")
+                .to_request()
+                .replace_payload(body.replay());
+
+            let res = call_service(&srv, req).await;
+            assert_eq!(read_body(res).await, "HELLO WORLD");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rejects_body_over_limit() {
+        let (mut sender, payload) = h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"hello world"));
+
+        // fails before the payload ends
+        let res = BufferedBody::buffer(payload, 5).await;
+        assert!(matches!(res, Err(PayloadError::Overflow)));
+    }
+}
@@ -0,0 +1,170 @@
+//! For chaos payload stream documentation, see [`ChaosPayload`].
+
+use std::{
+    future::Future as _,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use actix_rt::time::{sleep, Sleep};
+use bytes::Bytes;
+use futures_core::{ready, stream::Stream};
+use rand::{distributions::Distribution, rngs::StdRng, SeedableRng as _};
+
+use crate::{dev, error::PayloadError};
+
+/// Stream that delays each chunk of a payload by a random latency, simulating a jittery network.
+///
+/// Every chunk read from the inner payload is held back for a duration sampled from
+/// `latency_dist` before it is yielded, which also delays polling for the next chunk. Errors and
+/// the end of the payload are passed through without delay. This is meant for resilience testing,
+/// e.g. to surface timeout and backpressure bugs in staging, and should not be used in production.
+///
+/// Latencies are sampled from an entropy-seeded RNG; use [`seed`](Self::seed) for reproducible
+/// delays.
+///
+/// A `ChaosPayload` is itself a payload stream, so it can be passed on to other payload adapters,
+/// such as [`Utf8Payload`](crate::web::Utf8Payload), to test how they cope with the delays.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+///
+/// use actix_web::{post, web, Error};
+/// use futures_util::StreamExt as _;
+/// use rand::distributions::Uniform;
+///
+/// #[post("/")]
+/// async fn upload(body: web::Payload) -> Result<String, Error> {
+///     let latency = Uniform::new(Duration::from_millis(10), Duration::from_millis(200));
+///     let mut body = web::ChaosPayload::new(body, latency);
+///     let mut len = 0;
+///
+///     while let Some(chunk) = body.next().await {
+///         len += chunk?.len();
+///     }
+///
+///     Ok(len.to_string())
+/// }
+/// ```
+pub struct ChaosPayload<D, S = dev::Payload> {
+    stream: S,
+    latency_dist: D,
+    rng: StdRng,
+    /// Chunk held back until its sampled latency has elapsed.
+    pending: Option<(Bytes, Pin<Box<Sleep>>)>,
+}
+
+impl<D, S> ChaosPayload<D, S>
+where
+    D: Distribution<Duration>,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    /// Constructs a new `ChaosPayload` stream that delays `payload`'s chunks by latencies sampled
+    /// from `latency_dist`.
+    pub fn new(payload: S, latency_dist: D) -> Self {
+        Self {
+            stream: payload,
+            latency_dist,
+            rng: StdRng::from_entropy(),
+            pending: None,
+        }
+    }
+
+    /// Seeds the latency RNG so that the same sequence of delays is used on every run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Unwrap into the inner payload stream.
+    ///
+    /// A chunk held back by a pending delay is lost.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<D, S> Stream for ChaosPayload<D, S>
+where
+    D: Distribution<Duration> + Unpin,
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.pending.is_none() {
+            match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    let latency = this.latency_dist.sample(&mut this.rng);
+                    this.pending = Some((chunk, Box::pin(sleep(latency))));
+                }
+                res => return Poll::Ready(res),
+            }
+        }
+
+        let (_, delay) = this.pending.as_mut().unwrap();
+        ready!(delay.as_mut().poll(cx));
+
+        let (chunk, _) = this.pending.take().unwrap();
+        Poll::Ready(Some(Ok(chunk)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_rt::time::Instant;
+    use futures_util::{stream, StreamExt as _};
+    use rand::distributions::Uniform;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn seeded_delays() {
+        tokio::time::pause();
+
+        let dist = Uniform::new(Duration::from_millis(1), Duration::from_secs(1));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let expected = (0..3).map(|_| dist.sample(&mut rng)).collect::<Vec<_>>();
+
+        let chunks = [&b"chaos"[..], b"", b"payload"];
+        let mut body = ChaosPayload::new(
+            stream::iter(chunks).map(|chunk| Ok(Bytes::from_static(chunk))),
+            dist,
+        )
+        .seed(42);
+
+        for (chunk, latency) in chunks.iter().zip(expected) {
+            let start = Instant::now();
+            assert_eq!(body.next().await.unwrap().unwrap(), chunk);
+
+            // timers have millisecond resolution
+            let elapsed = start.elapsed();
+            assert!(elapsed >= latency && elapsed - latency < Duration::from_millis(1));
+        }
+
+        let start = Instant::now();
+        assert!(body.next().await.is_none());
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[actix_rt::test]
+    async fn errors_are_not_delayed() {
+        tokio::time::pause();
+
+        let dist = Uniform::new(Duration::from_secs(1), Duration::from_secs(2));
+        let mut body =
+            ChaosPayload::new(stream::iter([Err(PayloadError::Incomplete(None))]), dist);
+
+        let start = Instant::now();
+        assert!(matches!(
+            body.next().await.unwrap(),
+            Err(PayloadError::Incomplete(None))
+        ));
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,85 @@
+//! Streaming verification of request payloads against their `Content-MD5` header.
+//!
+//! See [`PayloadConfig::verify_content_md5`](super::PayloadConfig::verify_content_md5).
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use actix_http::{error::PayloadError, BoxedPayloadStream};
+use base64::prelude::*;
+use bytes::Bytes;
+use futures_core::{ready, Stream};
+use md5::{Digest as _, Md5};
+
+use crate::{dev, HttpRequest};
+
+const CONTENT_MD5: &str = "content-md5";
+
+/// Wraps the request payload so that it is checked against the request's `Content-MD5` header.
+///
+/// Does nothing if the request has no `Content-MD5` header.
+pub(crate) fn verify(req: &HttpRequest, payload: &mut dev::Payload) {
+    let expected = match req.headers().get(CONTENT_MD5) {
+        // a malformed value never matches and so causes the payload to be rejected
+        Some(val) => val.to_str().unwrap_or_default().trim().to_owned(),
+        None => return,
+    };
+
+    let stream = Md5Verify {
+        inner: payload.take(),
+        expected,
+        md5: Md5::new(),
+        done: false,
+    };
+
+    *payload = dev::Payload::from(Box::pin(stream) as BoxedPayloadStream);
+}
+
+/// Payload stream that digests chunks as they pass through.
+///
+/// Once the inner stream ends, the digest is compared with the expected value and a mismatch is
+/// yielded as [`PayloadError::ChecksumMismatch`] in place of the end of the stream.
+struct Md5Verify {
+    inner: dev::Payload,
+    expected: String,
+    md5: Md5,
+    done: bool,
+}
+
+impl Stream for Md5Verify {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.done {
+            return Poll::Ready(None);
+        }
+
+        match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                this.md5.update(&chunk);
+                Poll::Ready(Some(Ok(chunk)))
+            }
+
+            Some(Err(err)) => {
+                this.done = true;
+                Poll::Ready(Some(Err(err)))
+            }
+
+            None => {
+                this.done = true;
+                let digest = std::mem::take(&mut this.md5).finalize();
+
+                // `Content-MD5` holds the digest in standard, padded base64
+                if BASE64_STANDARD.encode(digest) == this.expected {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Ready(Some(Err(PayloadError::ChecksumMismatch)))
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Shared driver for payload streams that decode chunks as they are read.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::{ready, stream::Stream};
+
+use crate::error::PayloadError;
+
+/// Decoder of payload chunks whose units, such as characters, may be split across chunks.
+pub(super) trait ChunkDecoder {
+    type Error: From<PayloadError>;
+
+    /// Decodes `chunk`, holding back an incomplete unit at its end until the next chunk.
+    ///
+    /// Returns an empty chunk if all of `chunk` was held back.
+    fn decode(&mut self, chunk: Bytes) -> Result<Bytes, Self::Error>;
+
+    /// Decodes any units held back once the payload has ended.
+    fn finish(&mut self) -> Result<Bytes, Self::Error>;
+}
+
+/// Stream that passes chunks of the inner stream through a [`ChunkDecoder`].
+///
+/// Empty decoded chunks are skipped and the stream ends after the first error.
+pub(super) struct DecodeStream<S, D> {
+    pub(super) stream: S,
+    pub(super) decoder: D,
+    done: bool,
+}
+
+impl<S, D> DecodeStream<S, D> {
+    pub(super) fn new(stream: S, decoder: D) -> Self {
+        Self {
+            stream,
+            decoder,
+            done: false,
+        }
+    }
+}
+
+impl<S, D> Stream for DecodeStream<S, D>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    D: ChunkDecoder + Unpin,
+{
+    type Item = Result<Bytes, D::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let res = match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(chunk)) => match this.decoder.decode(chunk) {
+                    // wait for the rest of a unit split across chunks
+                    Ok(chunk) if chunk.is_empty() => continue,
+                    res => res,
+                },
+
+                Some(Err(err)) => Err(err.into()),
+
+                None => {
+                    this.done = true;
+
+                    match this.decoder.finish() {
+                        Ok(chunk) if chunk.is_empty() => return Poll::Ready(None),
+                        res => res,
+                    }
+                }
+            };
+
+            if res.is_err() {
+                this.done = true;
+            }
+
+            return Poll::Ready(Some(res));
+        }
+    }
+}
+
+/// Returns a payload stream yielding `chunks`.
+#[cfg(test)]
+pub(super) fn chunk_stream(
+    chunks: Vec<&'static [u8]>,
+) -> impl Stream<Item = Result<Bytes, PayloadError>> + Unpin {
+    futures_util::stream::iter(
+        chunks
+            .into_iter()
+            .map(|chunk| Ok(Bytes::from_static(chunk))),
+    )
+}
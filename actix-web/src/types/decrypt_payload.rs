@@ -0,0 +1,344 @@
+//! For decrypting payload stream documentation, see [`DecryptPayload`].
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use aes_gcm::{
+    aead::{Aead as _, KeyInit as _, Payload as AeadPayload},
+    Aes256Gcm, Nonce,
+};
+use bytes::{Bytes, BytesMut};
+use futures_core::{ready, stream::Stream};
+
+use crate::{
+    dev,
+    error::{DecryptPayloadError, PayloadError},
+};
+
+/// Flag in a segment header that marks the final segment.
+const FINAL_FLAG: u32 = 1 << 31;
+
+/// Length of the authentication tag at the end of each segment.
+const TAG_LEN: usize = 16;
+
+/// Maximum length of a segment's plaintext.
+const MAX_SEGMENT_LEN: usize = 1 << 20;
+
+/// Stream that decrypts an AES-256-GCM encrypted payload while it is read.
+///
+/// The payload is a sequence of independently encrypted segments, so the ciphertext is never
+/// buffered as a whole. Each segment is yielded as soon as it has been received and its
+/// authentication tag verified. A segment that fails authentication fails the stream immediately
+/// with [`DecryptPayloadError::Authentication`], as does a payload that ends before its final
+/// segment, with [`DecryptPayloadError::Truncated`]; together, these detect tampering with
+/// segments as well as their reordering, removal and truncation.
+///
+/// # Format
+/// Each segment consists of:
+/// - a 4 byte, big-endian header, holding the length of the rest of the segment in its lower 31
+///   bits and, in its highest bit, whether this is the final segment;
+/// - the encrypted data, holding up to 1MiB of plaintext;
+/// - the 16 byte authentication tag.
+///
+/// The header is passed as associated data. Segment `n`, counting from zero, is encrypted with
+/// the given nonce with its last 4 bytes XORed with `n` in big-endian order. The final segment
+/// may be empty.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, Error};
+/// use futures_util::StreamExt as _;
+///
+/// const KEY: &[u8; 32] = b"an example very very secret key.";
+///
+/// #[post("/")]
+/// async fn upload(body: web::Payload) -> Result<String, Error> {
+///     let mut body = web::DecryptPayload::new(body, KEY, b"unique nonce");
+///     let mut len = 0;
+///
+///     while let Some(chunk) = body.next().await {
+///         len += chunk?.len();
+///     }
+///
+///     Ok(len.to_string())
+/// }
+/// ```
+pub struct DecryptPayload<S = dev::Payload> {
+    stream: S,
+    cipher: Aes256Gcm,
+    nonce: [u8; 12],
+    /// Received bytes of incomplete segments.
+    buf: BytesMut,
+    /// Index of the next segment.
+    segment: u32,
+    /// Whether the final segment has been decrypted.
+    finished: bool,
+    done: bool,
+}
+
+impl<S> DecryptPayload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    /// Constructs a new `DecryptPayload` stream that decrypts `payload` with the given AES-256 key
+    /// and base nonce.
+    ///
+    /// A nonce must never be used twice with the same key.
+    pub fn new(payload: S, key: &[u8; 32], nonce: &[u8; 12]) -> Self {
+        Self {
+            stream: payload,
+            cipher: Aes256Gcm::new(key.into()),
+            nonce: *nonce,
+            buf: BytesMut::new(),
+            segment: 0,
+            finished: false,
+            done: false,
+        }
+    }
+
+    /// Unwrap into the inner payload stream.
+    ///
+    /// Bytes of an incomplete segment held back from previous chunks are lost.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Decrypts the next segment, if it has been received completely.
+    fn decrypt_next(&mut self) -> Result<Option<Bytes>, DecryptPayloadError> {
+        if self.buf.len() < 4 {
+            return Ok(None);
+        }
+
+        let header = u32::from_be_bytes([self.buf[0], self.buf[1], self.buf[2], self.buf[3]]);
+        let len = (header & !FINAL_FLAG) as usize;
+
+        if !(TAG_LEN..=MAX_SEGMENT_LEN + TAG_LEN).contains(&len) {
+            return Err(DecryptPayloadError::InvalidFraming);
+        }
+
+        if self.buf.len() < 4 + len {
+            self.buf.reserve(4 + len - self.buf.len());
+            return Ok(None);
+        }
+
+        let header_bytes = self.buf.split_to(4);
+        let ciphertext = self.buf.split_to(len);
+
+        let mut nonce = self.nonce;
+        for (b, n) in nonce[8..].iter_mut().zip(self.segment.to_be_bytes()) {
+            *b ^= n;
+        }
+
+        let plaintext = self
+            .cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                AeadPayload {
+                    msg: &ciphertext,
+                    aad: &header_bytes,
+                },
+            )
+            .map_err(|_| DecryptPayloadError::Authentication {
+                segment: self.segment,
+            })?;
+
+        self.finished = header & FINAL_FLAG != 0;
+
+        // segment nonces must not repeat
+        self.segment = match self.segment.checked_add(1) {
+            Some(segment) => segment,
+            None if self.finished => self.segment,
+            None => return Err(DecryptPayloadError::InvalidFraming),
+        };
+
+        Ok(Some(Bytes::from(plaintext)))
+    }
+}
+
+impl<S> Stream for DecryptPayload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Bytes, DecryptPayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let res = if this.finished {
+                // nothing may follow the final segment
+                if this.buf.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(DecryptPayloadError::InvalidFraming)
+                }
+            } else {
+                this.decrypt_next()
+            };
+
+            match res {
+                Ok(Some(chunk)) if chunk.is_empty() => continue,
+                Ok(Some(chunk)) => return Poll::Ready(Some(Ok(chunk))),
+                Ok(None) => {}
+                Err(err) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+            }
+
+            match ready!(Pin::new(&mut this.stream).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+
+                Some(Err(err)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+
+                None => {
+                    this.done = true;
+
+                    if !this.finished {
+                        return Poll::Ready(Some(Err(DecryptPayloadError::Truncated)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{stream, StreamExt as _};
+
+    use super::*;
+
+    const KEY: &[u8; 32] = b"an example very very secret key.";
+    const NONCE: &[u8; 12] = b"unique nonce";
+
+    /// Encrypts `segments` in the format expected by `DecryptPayload`.
+    fn encrypt(segments: &[&[u8]]) -> Vec<u8> {
+        let cipher = Aes256Gcm::new(KEY.into());
+        let mut out = Vec::new();
+
+        for (i, segment) in segments.iter().enumerate() {
+            let mut header = (segment.len() + TAG_LEN) as u32;
+            if i == segments.len() - 1 {
+                header |= FINAL_FLAG;
+            }
+            let header = header.to_be_bytes();
+
+            let mut nonce = *NONCE;
+            for (b, n) in nonce[8..].iter_mut().zip((i as u32).to_be_bytes()) {
+                *b ^= n;
+            }
+
+            let ciphertext = cipher
+                .encrypt(
+                    Nonce::from_slice(&nonce),
+                    AeadPayload {
+                        msg: segment,
+                        aad: &header,
+                    },
+                )
+                .unwrap();
+
+            out.extend_from_slice(&header);
+            out.extend_from_slice(&ciphertext);
+        }
+
+        out
+    }
+
+    /// Decrypts `ciphertext`, delivered in chunks of `chunk_size` bytes.
+    fn decrypt_payload(
+        ciphertext: Vec<u8>,
+        chunk_size: usize,
+    ) -> DecryptPayload<impl Stream<Item = Result<Bytes, PayloadError>> + Unpin> {
+        let chunks = ciphertext
+            .chunks(chunk_size)
+            .map(|chunk| Ok(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+
+        DecryptPayload::new(stream::iter(chunks), KEY, NONCE)
+    }
+
+    #[actix_rt::test]
+    async fn round_trip() {
+        let ciphertext = encrypt(&[b"hello", b" encrypted", b" world"]);
+
+        for chunk_size in [1, 7, ciphertext.len()] {
+            let mut body = decrypt_payload(ciphertext.clone(), chunk_size);
+
+            assert_eq!(body.next().await.unwrap().unwrap(), "hello");
+            assert_eq!(body.next().await.unwrap().unwrap(), " encrypted");
+            assert_eq!(body.next().await.unwrap().unwrap(), " world");
+            assert!(body.next().await.is_none());
+        }
+
+        // empty final segment
+        let mut body = decrypt_payload(encrypt(&[b"data", b""]), 5);
+        assert_eq!(body.next().await.unwrap().unwrap(), "data");
+        assert!(body.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn tampering() {
+        let ciphertext = encrypt(&[b"hello", b" encrypted", b" world"]);
+
+        // flipped bit in the second segment's data
+        let mut tampered = ciphertext.clone();
+        tampered[4 + 5 + TAG_LEN + 4] ^= 1;
+        let mut body = decrypt_payload(tampered, 8);
+
+        assert_eq!(body.next().await.unwrap().unwrap(), "hello");
+        match body.next().await.unwrap().unwrap_err() {
+            DecryptPayloadError::Authentication { segment } => assert_eq!(segment, 1),
+            err => panic!("unexpected error: {}", err),
+        }
+        assert!(body.next().await.is_none());
+
+        // first segment marked as final
+        let mut tampered = ciphertext.clone();
+        tampered[0] |= 0x80;
+        let mut body = decrypt_payload(tampered, 8);
+        assert!(matches!(
+            body.next().await.unwrap(),
+            Err(DecryptPayloadError::Authentication { segment: 0 })
+        ));
+
+        // segments swapped
+        let mut tampered = ciphertext[4 + 5 + TAG_LEN..].to_vec();
+        tampered.extend_from_slice(&ciphertext[..4 + 5 + TAG_LEN]);
+        let mut body = decrypt_payload(tampered, 8);
+        assert!(matches!(
+            body.next().await.unwrap(),
+            Err(DecryptPayloadError::Authentication { segment: 0 })
+        ));
+
+        // final segment removed
+        let body = decrypt_payload(
+            encrypt(&[b"hello", b" world"])[..4 + 5 + TAG_LEN].to_vec(),
+            8,
+        );
+        let res = body.collect::<Vec<_>>().await;
+        assert!(matches!(
+            res.last().unwrap(),
+            Err(DecryptPayloadError::Truncated)
+        ));
+
+        // data after the final segment
+        let mut appended = ciphertext;
+        appended.extend_from_slice(b"more");
+        let res = decrypt_payload(appended, 8).collect::<Vec<_>>().await;
+        assert!(matches!(
+            res.last().unwrap(),
+            Err(DecryptPayloadError::InvalidFraming)
+        ));
+    }
+}
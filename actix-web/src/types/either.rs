@@ -2,7 +2,6 @@
 
 use std::{
     future::Future,
-    mem,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -14,6 +13,7 @@ use pin_project_lite::pin_project;
 use crate::{
     body::EitherBody,
     dev,
+    extract::private::Sealed,
     web::{Form, Json},
     Error, FromRequest, HttpRequest, HttpResponse, Responder,
 };
@@ -24,8 +24,13 @@ use crate::{
 /// Provides a mechanism for trying two extractors, a primary and a fallback. Useful for
 /// "polymorphic payloads" where, for example, a form might be JSON or URL encoded.
 ///
-/// It is important to note that this extractor, by necessity, buffers the entire request payload
-/// as part of its implementation. Though, it does respect any `PayloadConfig` maximum size limits.
+/// When both extractors depend on the request's `Content-Type` (e.g., [`Json`] and [`Form`]), the
+/// extractor is chosen based on the content type before reading the payload, which is then passed
+/// to the chosen extractor as-is. If neither accepts the content type, the payload is not read.
+///
+/// Otherwise, it is important to note that this extractor, by necessity, buffers the entire
+/// request payload as part of its implementation. Though, it does respect any `PayloadConfig`
+/// maximum size limits.
 ///
 /// ```
 /// use actix_web::{post, web, Either};
@@ -173,11 +178,41 @@ where
     type Future = EitherExtractFut<L, R>;
 
     fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        EitherExtractFut {
-            req: req.clone(),
-            state: EitherExtractState::Bytes {
+        let state = match (
+            L::accepts_content_type(req, Sealed(())),
+            R::accepts_content_type(req, Sealed(())),
+        ) {
+            // only the primary extractor accepts the content type
+            (Some(true), Some(false)) => EitherExtractState::Left {
+                left: L::from_request(req, payload),
+                fallback: dev::Payload::None,
+            },
+
+            // the primary extractor does not accept the content type and will fail without
+            // needing the payload; the fallback extractor may or may not accept it
+            (Some(false), Some(_)) => EitherExtractState::Left {
+                left: L::extract(req),
+                fallback: payload.take(),
+            },
+
+            _ => EitherExtractState::Bytes {
                 bytes: Bytes::from_request(req, payload),
             },
+        };
+
+        EitherExtractFut {
+            req: req.clone(),
+            state,
+        }
+    }
+
+    fn accepts_content_type(req: &HttpRequest, _: Sealed) -> Option<bool> {
+        match (
+            L::accepts_content_type(req, Sealed(())),
+            R::accepts_content_type(req, Sealed(())),
+        ) {
+            (Some(left), Some(right)) => Some(left || right),
+            _ => None,
         }
     }
 }
@@ -208,7 +243,7 @@ pin_project! {
         Left {
             #[pin]
             left: L::Future,
-            fallback: Bytes,
+            fallback: dev::Payload,
         },
         Right {
             #[pin]
@@ -237,7 +272,7 @@ where
                     let res = ready!(bytes.poll(cx));
                     match res {
                         Ok(bytes) => {
-                            let fallback = bytes.clone();
+                            let fallback = payload_from_bytes(bytes.clone());
                             let left =
                                 L::from_request(this.req, &mut payload_from_bytes(bytes));
                             EitherExtractState::Left { left, fallback }
@@ -250,10 +285,7 @@ where
                     match res {
                         Ok(extracted) => break Ok(Either::Left(extracted)),
                         Err(left_err) => {
-                            let right = R::from_request(
-                                this.req,
-                                &mut payload_from_bytes(mem::take(fallback)),
-                            );
+                            let right = R::from_request(this.req, fallback);
                             EitherExtractState::Right {
                                 left_err: Some(left_err),
                                 right,
@@ -292,8 +324,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        test::TestRequest,
-        web::{Form, Json},
+        http::{header, StatusCode},
+        test::{call_service, init_service, read_body, TestRequest},
+        web::{self, Form, Json},
+        App,
     };
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -366,4 +400,57 @@ mod tests {
         .into_inner();
         assert_eq!(&form.hello, "world");
     }
+
+    #[actix_rt::test]
+    async fn test_either_content_type_dispatch() {
+        let srv = init_service(App::new().route(
+            "/",
+            web::post().to(|form: Either<Json<TestForm>, Form<TestForm>>| async move {
+                match form {
+                    Either::Left(json) => format!("json: {}", json.hello),
+                    Either::Right(form) => format!("form: {}", form.hello),
+                }
+            }),
+        ))
+        .await;
+
+        let form = TestForm {
+            hello: "world".to_owned(),
+        };
+
+        let req = TestRequest::post().set_json(&form).to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "json: world");
+
+        let req = TestRequest::post().set_form(&form).to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "form: world");
+
+        let req = TestRequest::post()
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .set_payload("hello=world")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_either_content_type_dispatch_does_not_buffer() {
+        // buffering would fail on the payload size limit
+        let (req, mut pl) = TestRequest::default()
+            .app_data(web::PayloadConfig::new(8))
+            .set_json(&TestForm {
+                hello: "world".to_owned(),
+            })
+            .to_http_parts();
+
+        let form = Either::<Form<TestForm>, Json<TestForm>>::from_request(&req, &mut pl)
+            .await
+            .unwrap()
+            .unwrap_right()
+            .into_inner();
+        assert_eq!(&form.hello, "world");
+    }
 }
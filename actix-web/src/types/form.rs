@@ -20,9 +20,11 @@ use serde::{de::DeserializeOwned, Serialize};
 #[cfg(feature = "__compress")]
 use crate::dev::Decompress;
 use crate::{
-    body::EitherBody, error::UrlencodedError, extract::FromRequest,
-    http::header::CONTENT_LENGTH, web, Error, HttpMessage, HttpRequest, HttpResponse,
-    Responder,
+    body::EitherBody,
+    error::UrlencodedError,
+    extract::{private::Sealed, FromRequest},
+    http::header::CONTENT_LENGTH,
+    web, Error, HttpMessage, HttpRequest, HttpResponse, Responder,
 };
 
 /// URL encoded payload extractor and responder.
@@ -139,6 +141,10 @@ where
             err_handler,
         }
     }
+
+    fn accepts_content_type(req: &HttpRequest, _: Sealed) -> Option<bool> {
+        Some(is_urlencoded(req))
+    }
 }
 
 type FormErrHandler = Option<Rc<dyn Fn(UrlencodedError, &HttpRequest) -> Error>>;
@@ -285,12 +291,17 @@ pub struct UrlEncoded<T> {
     fut: Option<LocalBoxFuture<'static, Result<T, UrlencodedError>>>,
 }
 
+fn is_urlencoded(req: &HttpRequest) -> bool {
+    req.content_type()
+        .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+}
+
 #[allow(clippy::borrow_interior_mutable_const)]
 impl<T> UrlEncoded<T> {
     /// Create a new future to decode a URL encoded request payload.
     pub fn new(req: &HttpRequest, payload: &mut Payload) -> Self {
         // check content type
-        if req.content_type().to_lowercase() != "application/x-www-form-urlencoded" {
+        if !is_urlencoded(req) {
             return Self::err(UrlencodedError::ContentType);
         }
         let encoding = match req.encoding() {
@@ -21,7 +21,7 @@ use crate::dev::Decompress;
 use crate::{
     body::EitherBody,
     error::{Error, JsonPayloadError},
-    extract::FromRequest,
+    extract::{private::Sealed, FromRequest},
     http::header::CONTENT_LENGTH,
     request::HttpRequest,
     web, HttpMessage, HttpResponse, Responder,
@@ -146,21 +146,30 @@ impl<T: DeserializeOwned> FromRequest for Json<T> {
         let config = JsonConfig::from_req(req);
 
         let limit = config.limit;
-        let ctype_required = config.content_type_required;
-        let ctype_fn = config.content_type.as_deref();
         let err_handler = config.err_handler.clone();
 
+        let fut = match config.check_content_type(req) {
+            Ok(()) => JsonBody::from_payload(req, payload),
+            Err(err) => JsonBody::Error(Some(err)),
+        };
+
         JsonExtractFut {
             req: Some(req.clone()),
-            fut: JsonBody::new(req, payload, ctype_fn, ctype_required).limit(limit),
+            fut: fut.limit(limit),
             err_handler,
         }
     }
+
+    fn accepts_content_type(req: &HttpRequest, _: Sealed) -> Option<bool> {
+        Some(JsonConfig::from_req(req).check_content_type(req).is_ok())
+    }
 }
 
 type JsonErrorHandler =
     Option<Arc<dyn Fn(JsonPayloadError, &HttpRequest) -> Error + Send + Sync>>;
 
+type JsonContentTypePredicate = Option<Arc<dyn Fn(&mime::Mime) -> bool + Send + Sync>>;
+
 pub struct JsonExtractFut<T> {
     req: Option<HttpRequest>,
     fut: JsonBody<T>,
@@ -235,6 +244,7 @@ pub struct JsonConfig {
     limit: usize,
     err_handler: JsonErrorHandler,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+    content_type_predicate: JsonContentTypePredicate,
     content_type_required: bool,
 }
 
@@ -255,6 +265,9 @@ impl JsonConfig {
     }
 
     /// Set predicate for allowed content types.
+    ///
+    /// Content types accepted by the predicate are allowed in addition to JSON ones, i.e.
+    /// `application/json` and types with a `json` subtype or `+json` suffix.
     pub fn content_type<F>(mut self, predicate: F) -> Self
     where
         F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
@@ -263,6 +276,34 @@ impl JsonConfig {
         self
     }
 
+    /// Set predicate that decides on its own which content types are accepted.
+    ///
+    /// Unlike [`content_type`](Self::content_type), the predicate replaces the built-in check for
+    /// JSON types, so only the content types it accepts are parsed; requests with other content
+    /// types fail with [`JsonPayloadError::ContentType`]. Whether a request without a
+    /// `Content-Type` header is parsed is still decided by
+    /// [`content_type_required`](Self::content_type_required).
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{error::{self, JsonPayloadError}, web};
+    ///
+    /// // only accept JSON:API documents, rejecting others with `415 Unsupported Media Type`
+    /// let json_cfg = web::JsonConfig::default()
+    ///     .content_type_predicate(|mime| mime.essence_str() == "application/vnd.api+json")
+    ///     .error_handler(|err, _req| match err {
+    ///         JsonPayloadError::ContentType => error::ErrorUnsupportedMediaType(err),
+    ///         err => err.into(),
+    ///     });
+    /// ```
+    pub fn content_type_predicate<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type_predicate = Some(Arc::new(predicate));
+        self
+    }
+
     /// Sets whether or not the request must have a `Content-Type` header to be parsed.
     pub fn content_type_required(mut self, content_type_required: bool) -> Self {
         self.content_type_required = content_type_required;
@@ -276,6 +317,21 @@ impl JsonConfig {
             .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
             .unwrap_or(&DEFAULT_CONFIG)
     }
+
+    /// Checks that the content type of `req` is accepted by this config.
+    fn check_content_type(&self, req: &HttpRequest) -> Result<(), JsonPayloadError> {
+        let ctype_fn = self.content_type.as_deref();
+
+        match self.content_type_predicate {
+            Some(ref predicate) => match req.mime_type() {
+                Ok(Some(mime)) if predicate(&mime) => Ok(()),
+                Ok(None) if !self.content_type_required => Ok(()),
+                _ => Err(JsonPayloadError::ContentType),
+            },
+            None if can_parse_json(req, ctype_fn, self.content_type_required) => Ok(()),
+            None => Err(JsonPayloadError::ContentType),
+        }
+    }
 }
 
 const DEFAULT_LIMIT: usize = 2_097_152; // 2 mb
@@ -285,6 +341,7 @@ const DEFAULT_CONFIG: JsonConfig = JsonConfig {
     limit: DEFAULT_LIMIT,
     err_handler: None,
     content_type: None,
+    content_type_predicate: None,
     content_type_required: true,
 };
 
@@ -320,9 +377,24 @@ pub enum JsonBody<T> {
 
 impl<T> Unpin for JsonBody<T> {}
 
+fn can_parse_json(
+    req: &HttpRequest,
+    ctype_fn: Option<&(dyn Fn(mime::Mime) -> bool + Send + Sync)>,
+    ctype_required: bool,
+) -> bool {
+    if let Ok(Some(mime)) = req.mime_type() {
+        mime.subtype() == mime::JSON
+            || mime.suffix() == Some(mime::JSON)
+            || ctype_fn.map_or(false, |predicate| predicate(mime))
+    } else {
+        // if `ctype_required` is false, assume payload is
+        // json even when content-type header is missing
+        !ctype_required
+    }
+}
+
 impl<T: DeserializeOwned> JsonBody<T> {
     /// Create a new future to decode a JSON request payload.
-    #[allow(clippy::borrow_interior_mutable_const)]
     pub fn new(
         req: &HttpRequest,
         payload: &mut Payload,
@@ -330,20 +402,16 @@ impl<T: DeserializeOwned> JsonBody<T> {
         ctype_required: bool,
     ) -> Self {
         // check content-type
-        let can_parse_json = if let Ok(Some(mime)) = req.mime_type() {
-            mime.subtype() == mime::JSON
-                || mime.suffix() == Some(mime::JSON)
-                || ctype_fn.map_or(false, |predicate| predicate(mime))
-        } else {
-            // if `ctype_required` is false, assume payload is
-            // json even when content-type header is missing
-            !ctype_required
-        };
-
-        if !can_parse_json {
+        if !can_parse_json(req, ctype_fn, ctype_required) {
             return JsonBody::Error(Some(JsonPayloadError::ContentType));
         }
 
+        Self::from_payload(req, payload)
+    }
+
+    /// Create a new future to decode a JSON request payload without checking its content type.
+    #[allow(clippy::borrow_interior_mutable_const)]
+    fn from_payload(req: &HttpRequest, payload: &mut Payload) -> Self {
         let length = req
             .headers()
             .get(&CONTENT_LENGTH)
@@ -719,6 +787,40 @@ mod tests {
         assert!(s.is_err())
     }
 
+    #[actix_rt::test]
+    async fn test_with_json_and_content_type_predicate() {
+        let json_cfg = || {
+            JsonConfig::default()
+                .content_type_predicate(|mime| mime.essence_str() == "application/vnd.api+json")
+        };
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/vnd.api+json"))
+            .insert_header((header::CONTENT_LENGTH, "16"))
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(json_cfg())
+            .to_http_parts();
+
+        let s = Json::<MyObject>::from_request(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name, "test");
+
+        let (req, mut pl) = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .insert_header((header::CONTENT_LENGTH, "16"))
+            .set_payload(Bytes::from_static(b"{\"name\": \"test\"}"))
+            .app_data(json_cfg())
+            .to_http_parts();
+
+        assert!(!Json::<MyObject>::accepts_content_type(&req, Sealed(())).unwrap());
+        let err = Json::<MyObject>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.as_error::<JsonPayloadError>(),
+            Some(JsonPayloadError::ContentType)
+        ));
+    }
+
     #[actix_rt::test]
     async fn test_json_with_no_content_type() {
         let (req, mut pl) = TestRequest::default()
@@ -1,19 +1,48 @@
 //! Common extractors and responders.
 
+mod accept_language;
+mod auth;
+mod base64_payload;
+mod buffered_body;
+#[cfg(feature = "chaos")]
+mod chaos_payload;
+#[cfg(feature = "content-md5")]
+mod content_md5;
+mod decode_stream;
+#[cfg(feature = "decrypt-payload")]
+mod decrypt_payload;
 mod either;
 mod form;
 mod header;
 mod json;
+mod ndjson;
 mod path;
 mod payload;
 mod query;
+mod range;
 mod readlines;
+mod utf8_payload;
+mod validated;
 
+pub use self::accept_language::AcceptLanguage;
+pub use self::auth::{BasicAuth, BasicAuthConfig, BearerAuth};
+pub use self::base64_payload::Base64Payload;
+pub use self::buffered_body::BufferedBody;
+#[cfg(feature = "chaos")]
+pub use self::chaos_payload::ChaosPayload;
+#[cfg(feature = "decrypt-payload")]
+pub use self::decrypt_payload::DecryptPayload;
 pub use self::either::Either;
 pub use self::form::{Form, FormConfig, UrlEncoded};
 pub use self::header::Header;
 pub use self::json::{Json, JsonBody, JsonConfig};
+pub use self::ndjson::{NdJson, NdJsonConfig};
 pub use self::path::{Path, PathConfig};
-pub use self::payload::{Payload, PayloadConfig};
+pub use self::payload::{Payload, PayloadConfig, PayloadExtractFut};
 pub use self::query::{Query, QueryConfig};
+pub use self::range::RangeHeader;
 pub use self::readlines::Readlines;
+pub use self::utf8_payload::Utf8Payload;
+pub use self::validated::{
+    Validate, ValidatedExtractFut, ValidatedJson, ValidatedPath, ValidatedQuery,
+};
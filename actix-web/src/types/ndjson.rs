@@ -0,0 +1,385 @@
+//! For newline-delimited JSON helper documentation, see [`NdJson`].
+
+use std::{
+    fmt,
+    future::{ready, Ready},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::BytesMut;
+use futures_core::{ready, Stream};
+use serde::de::DeserializeOwned;
+
+use actix_http::Payload;
+
+#[cfg(feature = "__compress")]
+use crate::dev::Decompress;
+use crate::{
+    error::{Error, JsonPayloadError},
+    extract::FromRequest,
+    request::HttpRequest,
+    web, HttpMessage,
+};
+
+/// Streaming extractor for newline-delimited JSON (`application/x-ndjson`) payloads.
+///
+/// `NdJson` is a [`Stream`] that deserializes one JSON value of type `T` per line of the request
+/// payload. Lines may be split across payload chunks and are reassembled before parsing. Empty
+/// lines are ignored and a trailing `\r` is stripped from each line.
+///
+/// Use [`NdJsonConfig`] to configure extraction options.
+///
+/// # Errors
+/// - A line that is not valid JSON for `T` yields [`JsonPayloadError::Deserialize`]. By default
+///   the stream ends after this error; see [`NdJsonConfig::skip_invalid_lines`].
+/// - A line longer than the [configured limit](NdJsonConfig::limit) yields
+///   [`JsonPayloadError::Overflow`] and ends the stream.
+/// - Errors reading the payload yield [`JsonPayloadError::Payload`] and end the stream.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, Error};
+/// use futures_util::StreamExt as _;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Event {
+///     kind: String,
+/// }
+///
+/// #[post("/events")]
+/// async fn ingest(mut events: web::NdJson<Event>) -> Result<String, Error> {
+///     let mut count = 0;
+///
+///     while let Some(event) = events.next().await {
+///         let event = event?;
+///         log::info!("event: {}", event.kind);
+///         count += 1;
+///     }
+///
+///     Ok(format!("received {} events", count))
+/// }
+/// ```
+pub struct NdJson<T> {
+    #[cfg(feature = "__compress")]
+    payload: Decompress<Payload>,
+    #[cfg(not(feature = "__compress"))]
+    payload: Payload,
+    buf: BytesMut,
+    limit: usize,
+    skip_invalid: bool,
+    eof: bool,
+    done: bool,
+    _res: PhantomData<T>,
+}
+
+impl<T> Unpin for NdJson<T> {}
+
+impl<T> fmt::Debug for NdJson<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NdJson")
+            .field("limit", &self.limit)
+            .field("skip_invalid", &self.skip_invalid)
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+fn can_parse_ndjson(req: &HttpRequest, ctype_required: bool) -> bool {
+    if let Ok(Some(mime)) = req.mime_type() {
+        mime.type_() == mime::APPLICATION && mime.subtype() == "x-ndjson"
+    } else {
+        !ctype_required
+    }
+}
+
+impl<T: DeserializeOwned> NdJson<T> {
+    fn new(req: &HttpRequest, payload: &mut Payload, cfg: &NdJsonConfig) -> Self {
+        let payload = {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "__compress")] {
+                    Decompress::from_headers(payload.take(), req.headers())
+                } else {
+                    payload.take()
+                }
+            }
+        };
+
+        NdJson {
+            payload,
+            buf: BytesMut::with_capacity(8192),
+            limit: cfg.limit,
+            skip_invalid: cfg.skip_invalid,
+            eof: false,
+            done: false,
+            _res: PhantomData,
+        }
+    }
+
+    /// Parses a single line, returning `None` if it is blank.
+    fn parse_line(&mut self, line: &[u8]) -> Option<Result<T, JsonPayloadError>> {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if line.len() > self.limit {
+            self.done = true;
+            return Some(Err(JsonPayloadError::Overflow { limit: self.limit }));
+        }
+
+        if line.iter().all(u8::is_ascii_whitespace) {
+            return None;
+        }
+
+        match serde_json::from_slice(line) {
+            Ok(item) => Some(Ok(item)),
+            Err(err) => {
+                if !self.skip_invalid {
+                    self.done = true;
+                }
+
+                Some(Err(JsonPayloadError::Deserialize(err)))
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for NdJson<T> {
+    type Item = Result<T, JsonPayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            if let Some(idx) = this.buf.iter().position(|&b| b == b'\n') {
+                let line = this.buf.split_to(idx + 1);
+
+                match this.parse_line(&line) {
+                    Some(item) => return Poll::Ready(Some(item)),
+                    None => continue,
+                }
+            }
+
+            if this.eof {
+                this.done = true;
+
+                if this.buf.is_empty() {
+                    return Poll::Ready(None);
+                }
+
+                let line = this.buf.split();
+                return Poll::Ready(this.parse_line(&line));
+            }
+
+            // allow for a trailing `\r\n` that has not arrived yet
+            if this.buf.len() > this.limit + 2 {
+                this.done = true;
+                return Poll::Ready(Some(Err(JsonPayloadError::Overflow {
+                    limit: this.limit,
+                })));
+            }
+
+            match ready!(Pin::new(&mut this.payload).poll_next(cx)) {
+                Some(Ok(chunk)) => this.buf.extend_from_slice(&chunk),
+                Some(Err(err)) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(err.into())));
+                }
+                None => this.eof = true,
+            }
+        }
+    }
+}
+
+/// See [here](#examples) for example of usage as an extractor.
+impl<T: DeserializeOwned> FromRequest for NdJson<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cfg = NdJsonConfig::from_req(req);
+
+        if !can_parse_ndjson(req, cfg.content_type_required) {
+            log::debug!(
+                "Rejected NdJson payload with unexpected content type. Request path: {}",
+                req.path()
+            );
+
+            return ready(Err(JsonPayloadError::ContentType.into()));
+        }
+
+        ready(Ok(NdJson::new(req, payload, cfg)))
+    }
+}
+
+/// [`NdJson`] extractor configuration.
+///
+/// # Examples
+/// ```
+/// use actix_web::{web, App};
+///
+/// let ndjson_cfg = web::NdJsonConfig::default()
+///     // limit the size of each line to 4kB
+///     .limit(4096)
+///     // keep reading after a malformed line
+///     .skip_invalid_lines(true);
+///
+/// App::new().app_data(ndjson_cfg);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NdJsonConfig {
+    limit: usize,
+    skip_invalid: bool,
+    content_type_required: bool,
+}
+
+impl NdJsonConfig {
+    /// Sets maximum accepted size of a single line. By default this limit is 64kB.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Sets whether the stream continues after a line that fails to deserialize.
+    ///
+    /// The error for the malformed line is yielded either way. When enabled, the following lines
+    /// are still read and parsed; otherwise the stream ends after the error. Disabled by default.
+    pub fn skip_invalid_lines(mut self, skip_invalid: bool) -> Self {
+        self.skip_invalid = skip_invalid;
+        self
+    }
+
+    /// Sets whether or not the request must have a `Content-Type` header to be parsed.
+    pub fn content_type_required(mut self, content_type_required: bool) -> Self {
+        self.content_type_required = content_type_required;
+        self
+    }
+
+    /// Extract payload config from app data. Check both `T` and `Data<T>`, in that order, and fall
+    /// back to the default payload config.
+    fn from_req(req: &HttpRequest) -> &Self {
+        req.app_data::<Self>()
+            .or_else(|| req.app_data::<web::Data<Self>>().map(|d| d.as_ref()))
+            .unwrap_or(&DEFAULT_CONFIG)
+    }
+}
+
+const DEFAULT_LINE_LIMIT: usize = 65_536; // 64 kb
+
+/// Allow shared refs used as default.
+const DEFAULT_CONFIG: NdJsonConfig = NdJsonConfig {
+    limit: DEFAULT_LINE_LIMIT,
+    skip_invalid: false,
+    content_type_required: true,
+};
+
+impl Default for NdJsonConfig {
+    fn default() -> Self {
+        DEFAULT_CONFIG
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures_util::StreamExt as _;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{http::header, test::TestRequest};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Item {
+        id: u32,
+    }
+
+    fn ndjson_req() -> TestRequest {
+        TestRequest::default().insert_header((header::CONTENT_TYPE, "application/x-ndjson"))
+    }
+
+    fn chunked_payload(chunks: &[&'static str]) -> Payload {
+        let (mut sender, payload) = actix_http::h1::Payload::create(false);
+
+        for chunk in chunks {
+            sender.feed_data(Bytes::from_static(chunk.as_bytes()));
+        }
+        sender.feed_eof();
+
+        payload.into()
+    }
+
+    #[actix_rt::test]
+    async fn streams_lines_across_chunks() {
+        let req = ndjson_req().to_http_request();
+        let mut pl = chunked_payload(&["{\"id\":1}\n{\"i", "d\":2}\r\n\n{\"id\"", ":3}"]);
+
+        let stream = NdJson::<Item>::from_request(&req, &mut pl).await.unwrap();
+        let items = stream.map(Result::unwrap).collect::<Vec<_>>().await;
+
+        assert_eq!(items, [Item { id: 1 }, Item { id: 2 }, Item { id: 3 }]);
+    }
+
+    #[actix_rt::test]
+    async fn invalid_lines() {
+        let chunks = ["{\"id\":1}\nnope\n", "{\"id\":3}\n"];
+
+        let req = ndjson_req().to_http_request();
+        let mut pl = chunked_payload(&chunks);
+        let mut stream = NdJson::<Item>::from_request(&req, &mut pl).await.unwrap();
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), Item { id: 1 });
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(JsonPayloadError::Deserialize(_))
+        ));
+        assert!(stream.next().await.is_none());
+
+        let req = ndjson_req()
+            .app_data(NdJsonConfig::default().skip_invalid_lines(true))
+            .to_http_request();
+        let mut pl = chunked_payload(&chunks);
+        let mut stream = NdJson::<Item>::from_request(&req, &mut pl).await.unwrap();
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), Item { id: 1 });
+        assert!(stream.next().await.unwrap().is_err());
+        assert_eq!(stream.next().await.unwrap().unwrap(), Item { id: 3 });
+        assert!(stream.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn line_limit() {
+        let req = ndjson_req()
+            .app_data(NdJsonConfig::default().limit(10))
+            .to_http_request();
+        let mut pl = chunked_payload(&["{\"id\":1}\n{\"id\":", "     2}\n"]);
+        let mut stream = NdJson::<Item>::from_request(&req, &mut pl).await.unwrap();
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), Item { id: 1 });
+        assert!(matches!(
+            stream.next().await.unwrap(),
+            Err(JsonPayloadError::Overflow { limit: 10 })
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn content_type() {
+        let req = TestRequest::default()
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .to_http_request();
+        let res = NdJson::<Item>::from_request(&req, &mut Payload::None).await;
+        assert!(res.is_err());
+
+        let req = TestRequest::default()
+            .app_data(NdJsonConfig::default().content_type_required(false))
+            .to_http_request();
+        let res = NdJson::<Item>::from_request(&req, &mut Payload::None).await;
+        assert!(res.is_ok());
+    }
+}
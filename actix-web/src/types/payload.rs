@@ -2,19 +2,25 @@
 
 use std::{
     borrow::Cow,
-    future::Future,
+    future::{self, Future},
+    io,
     pin::Pin,
     str,
     task::{Context, Poll},
+    time::Duration,
 };
 
-use actix_http::error::PayloadError;
+use actix_http::{error::PayloadError, BoxedPayloadStream};
+use actix_rt::time::{sleep, Instant, Sleep};
 use actix_utils::future::{ready, Either, Ready};
 use bytes::{Bytes, BytesMut};
 use encoding_rs::{Encoding, UTF_8};
 use futures_core::{ready, stream::Stream};
+use futures_util::StreamExt as _;
 use mime::Mime;
 
+#[cfg(feature = "content-md5")]
+use crate::types::content_md5;
 use crate::{
     dev, error::ErrorBadRequest, http::header, web, Error, FromRequest, HttpMessage,
     HttpRequest,
@@ -43,13 +49,125 @@ use crate::{
 ///     Ok(format!("Request Body Bytes:\n{:?}", bytes))
 /// }
 /// ```
-pub struct Payload(dev::Payload);
+pub struct Payload {
+    prefetched: Option<Result<Bytes, PayloadError>>,
+    inner: dev::Payload,
+}
 
 impl Payload {
+    fn new(inner: dev::Payload) -> Self {
+        Self {
+            prefetched: None,
+            inner,
+        }
+    }
+
     /// Unwrap to inner Payload type.
     #[inline]
     pub fn into_inner(self) -> dev::Payload {
-        self.0
+        match self.prefetched {
+            None => self.inner,
+            Some(item) => {
+                let stream = futures_util::stream::once(async { item }).chain(self.inner);
+                dev::Payload::from(Box::pin(stream) as BoxedPayloadStream)
+            }
+        }
+    }
+
+    /// Converts into an opaque `Stream` of body chunks.
+    ///
+    /// `Payload` itself implements `Stream`; this is useful when only the stream interface should
+    /// be exposed, for example, when handing the body to code built on `TryStreamExt` combinators.
+    ///
+    /// # Examples
+    /// ```
+    /// use actix_web::{post, web};
+    /// use futures_util::TryStreamExt as _;
+    ///
+    /// #[post("/")]
+    /// async fn index(body: web::Payload) -> actix_web::Result<String> {
+    ///     let chunks: Vec<web::Bytes> = body.into_stream().try_collect().await?;
+    ///     Ok(format!("received {} chunks", chunks.len()))
+    /// }
+    /// ```
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes, PayloadError>> + Unpin {
+        self
+    }
+
+    /// Converts into a `Stream` of body chunks that fails if more than `every` elapses between
+    /// chunks.
+    ///
+    /// The gap is measured between chunks being yielded, starting from this call for the first
+    /// chunk. Unlike an idle timeout, which only runs while the reader is waiting for data, time
+    /// spent by the reader processing a chunk counts towards the gap, so this detects streams that
+    /// do not keep data flowing steadily. Chunks that are already available are always yielded;
+    /// once the payload has no chunk ready and the gap is exceeded, the stream yields a
+    /// [`PayloadError::Io`] error of kind [`TimedOut`](io::ErrorKind::TimedOut) and then ends.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use actix_web::{post, web};
+    /// use futures_util::TryStreamExt as _;
+    ///
+    /// #[post("/")]
+    /// async fn index(body: web::Payload) -> actix_web::Result<String> {
+    ///     let mut body = body.require_activity(Duration::from_secs(5));
+    ///     let mut len = 0;
+    ///     while let Some(chunk) = body.try_next().await? {
+    ///         len += chunk.len();
+    ///     }
+    ///     Ok(format!("received {} bytes", len))
+    /// }
+    /// ```
+    pub fn require_activity(
+        self,
+        every: Duration,
+    ) -> impl Stream<Item = Result<Bytes, PayloadError>> + Unpin {
+        RequireActivity {
+            payload: self,
+            every,
+            deadline: Box::pin(sleep(every)),
+            expired: false,
+        }
+    }
+}
+
+/// Stream returned by [`Payload::require_activity`].
+struct RequireActivity {
+    payload: Payload,
+    every: Duration,
+    deadline: Pin<Box<Sleep>>,
+    expired: bool,
+}
+
+impl Stream for RequireActivity {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        if this.expired {
+            return Poll::Ready(None);
+        }
+
+        if let Poll::Ready(item) = Pin::new(&mut this.payload).poll_next(cx) {
+            if item.is_some() {
+                let deadline = Instant::now() + this.every;
+                this.deadline.as_mut().reset(deadline);
+            }
+
+            return Poll::Ready(item);
+        }
+
+        // the payload has no chunk ready; only fail once the gap has been exceeded
+        ready!(this.deadline.as_mut().poll(cx));
+
+        this.expired = true;
+        Poll::Ready(Some(Err(PayloadError::Io(io::Error::new(
+            io::ErrorKind::TimedOut,
+            "no payload chunk was received within the required interval",
+        )))))
     }
 }
 
@@ -58,18 +176,147 @@ impl Stream for Payload {
 
     #[inline]
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.0).poll_next(cx)
+        if let Some(item) = self.prefetched.take() {
+            return Poll::Ready(Some(item));
+        }
+
+        Pin::new(&mut self.inner).poll_next(cx)
     }
 }
 
 /// See [here](#Examples) for example of usage as an extractor.
 impl FromRequest for Payload {
     type Error = Error;
-    type Future = Ready<Result<Payload, Error>>;
+    type Future = Either<PayloadExtractFut, future::Ready<Result<Payload, Error>>>;
 
     #[inline]
-    fn from_request(_: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
-        ready(Ok(Payload(payload.take())))
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        let cfg = PayloadConfig::from_req(req);
+
+        #[cfg(feature = "content-md5")]
+        if cfg.verify_content_md5 {
+            content_md5::verify(req, payload);
+        }
+
+        if let Some(max) = cfg.contiguous_up_to {
+            match content_length(req) {
+                Some(len) if len <= max => {
+                    *payload = dev::Payload::Stream {
+                        payload: Box::pin(Contiguous::new(payload.take(), len)),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        #[cfg(feature = "__compress")]
+        if cfg.decompress {
+            let stream = dev::Decompress::from_headers(payload.take(), req.headers());
+            *payload = dev::Payload::Stream {
+                payload: Box::pin(stream),
+            };
+        }
+
+        if cfg.prefetch {
+            Either::left(PayloadExtractFut {
+                payload: Some(payload.take()),
+            })
+        } else {
+            Either::right(future::ready(Ok(Payload::new(payload.take()))))
+        }
+    }
+}
+
+/// Returns the request's `Content-Length`, if it is set and valid.
+fn content_length(req: &HttpRequest) -> Option<usize> {
+    req.headers()
+        .get(&header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Stream that collects a payload of known length into a single chunk.
+///
+/// The buffer is allocated once, with the expected length. If the payload turns out to be longer,
+/// the collected bytes are yielded as soon as the length is exceeded and the rest is streamed.
+struct Contiguous {
+    payload: dev::Payload,
+    buf: BytesMut,
+    len: usize,
+    streaming: bool,
+}
+
+impl Contiguous {
+    fn new(payload: dev::Payload, len: usize) -> Self {
+        Self {
+            payload,
+            buf: BytesMut::with_capacity(len),
+            len,
+            streaming: false,
+        }
+    }
+}
+
+impl Stream for Contiguous {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.streaming {
+            return Pin::new(&mut this.payload).poll_next(cx);
+        }
+
+        loop {
+            match ready!(Pin::new(&mut this.payload).poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    this.buf.extend_from_slice(&chunk);
+
+                    if this.buf.len() > this.len {
+                        this.streaming = true;
+                        return Poll::Ready(Some(Ok(this.buf.split().freeze())));
+                    }
+                }
+
+                Some(Err(err)) => {
+                    this.streaming = true;
+                    return Poll::Ready(Some(Err(err)));
+                }
+
+                None => {
+                    this.streaming = true;
+
+                    if this.buf.is_empty() {
+                        return Poll::Ready(None);
+                    }
+
+                    return Poll::Ready(Some(Ok(this.buf.split().freeze())));
+                }
+            }
+        }
+    }
+}
+
+/// Future for `Payload` extractor that waits for the first chunk of the body to arrive.
+pub struct PayloadExtractFut {
+    payload: Option<dev::Payload>,
+}
+
+impl Future for PayloadExtractFut {
+    type Output = Result<Payload, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = self
+            .payload
+            .as_mut()
+            .expect("PayloadExtractFut polled after completion");
+
+        let prefetched = ready!(Pin::new(&mut *inner).poll_next(cx));
+        let inner = self.payload.take().unwrap();
+
+        Poll::Ready(Ok(Payload { prefetched, inner }))
     }
 }
 
@@ -102,6 +349,11 @@ impl FromRequest for Bytes {
             return Either::right(ready(Err(err)));
         }
 
+        #[cfg(feature = "content-md5")]
+        if cfg.verify_content_md5 {
+            content_md5::verify(req, payload);
+        }
+
         Either::left(BytesExtractFut {
             body_fut: HttpMessageBody::new(req, payload).limit(cfg.limit),
         })
@@ -154,6 +406,12 @@ impl FromRequest for String {
             Ok(enc) => enc,
             Err(err) => return Either::right(ready(Err(err.into()))),
         };
+
+        #[cfg(feature = "content-md5")]
+        if cfg.verify_content_md5 {
+            content_md5::verify(req, payload);
+        }
+
         let limit = cfg.limit;
         let body_fut = HttpMessageBody::new(req, payload).limit(limit);
 
@@ -208,6 +466,11 @@ fn bytes_to_string(body: Bytes, encoding: &'static Encoding) -> Result<String, E
 pub struct PayloadConfig {
     limit: usize,
     mimetype: Option<Mime>,
+    prefetch: bool,
+    #[cfg(feature = "content-md5")]
+    verify_content_md5: bool,
+    decompress: bool,
+    contiguous_up_to: Option<usize>,
 }
 
 impl PayloadConfig {
@@ -231,6 +494,58 @@ impl PayloadConfig {
         self
     }
 
+    /// Set whether the [`Payload`] extractor waits for the first chunk of the body to arrive
+    /// before the handler is called.
+    ///
+    /// With prefetching enabled, the first read from the payload in a handler completes
+    /// immediately. Reading ahead continues in the background up to the protocol's buffer
+    /// capacity. By default, prefetching is disabled.
+    pub fn prefetch(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Set whether request payloads are verified against their `Content-MD5` header.
+    ///
+    /// The digest is computed incrementally as the body is read, before any content decoding,
+    /// and compared once the payload ends. On mismatch, reading the payload fails with
+    /// [`PayloadError::ChecksumMismatch`], which results in a `400 Bad Request` response. Requests
+    /// without a `Content-MD5` header are not affected.
+    ///
+    /// Applies to the [`Payload`] extractor as well as [`Bytes`] and [`String`]. Disabled by
+    /// default.
+    #[cfg(feature = "content-md5")]
+    pub fn verify_content_md5(mut self, verify_content_md5: bool) -> Self {
+        self.verify_content_md5 = verify_content_md5;
+        self
+    }
+
+    /// Set whether the [`Payload`] extractor decodes payloads according to their
+    /// `Content-Encoding` header.
+    ///
+    /// Transfer codings, such as `chunked`, are always removed before the payload reaches
+    /// extractors. With this enabled, content codings such as `gzip` are removed afterwards, in
+    /// that order, so that handlers read the plain body from the stream like [`Bytes`] and
+    /// [`String`] do. Requires one of the `compress-*` crate features; without them, payloads are
+    /// left as they are. Disabled by default.
+    pub fn decompress(mut self, decompress: bool) -> Self {
+        self.decompress = decompress;
+        self
+    }
+
+    /// Set the largest `Content-Length`, in bytes, for which the [`Payload`] extractor yields the
+    /// whole body as a single chunk.
+    ///
+    /// For such payloads, one buffer of exactly the body's length is allocated and filled before
+    /// anything is yielded, which suits parsers that want the complete body in contiguous memory.
+    /// Larger payloads, and those of unknown length, are streamed in chunks as usual. Payloads
+    /// are collected before being decoded with [`decompress`](Self::decompress), so decoded
+    /// bodies may still arrive in multiple chunks. Disabled by default.
+    pub fn contiguous_up_to(mut self, max: usize) -> Self {
+        self.contiguous_up_to = Some(max);
+        self
+    }
+
     fn check_mimetype(&self, req: &HttpRequest) -> Result<(), Error> {
         // check content-type
         if let Some(ref mt) = self.mimetype {
@@ -267,6 +582,11 @@ const DEFAULT_CONFIG_LIMIT: usize = 262_144; // 2^18 bytes (~256kB)
 const DEFAULT_CONFIG: PayloadConfig = PayloadConfig {
     limit: DEFAULT_CONFIG_LIMIT,
     mimetype: None,
+    prefetch: false,
+    #[cfg(feature = "content-md5")]
+    verify_content_md5: false,
+    decompress: false,
+    contiguous_up_to: None,
 };
 
 impl Default for PayloadConfig {
@@ -398,6 +718,155 @@ mod tests {
         assert!(cfg.check_mimetype(&req).is_ok());
     }
 
+    #[actix_rt::test]
+    async fn test_payload_into_stream() {
+        use futures_util::TryStreamExt as _;
+
+        let (req, mut pl) = TestRequest::default()
+            .set_payload(Bytes::from_static(b"hello world"))
+            .to_http_parts();
+
+        let payload = Payload::from_request(&req, &mut pl).await.unwrap();
+        let chunks: Vec<Bytes> = payload.into_stream().try_collect().await.unwrap();
+        assert_eq!(chunks.concat(), b"hello world");
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_injected_error_reaches_handler() {
+        let srv = init_service(App::new().default_service(web::to(
+            |mut body: Payload| async move {
+                let mut received = BytesMut::new();
+
+                while let Some(chunk) = body.next().await {
+                    match chunk {
+                        Ok(chunk) => received.extend_from_slice(&chunk),
+                        Err(err) => return format!("{:?} then {:?}", received, err),
+                    }
+                }
+
+                format!("{:?}", received)
+            },
+        )))
+        .await;
+
+        let (mut sender, h1_payload) = actix_http::h1::Payload::create(false);
+        sender.fail_after(6, PayloadError::Incomplete(None));
+        sender.feed_data(Bytes::from_static(b"data"));
+        sender.feed_data(Bytes::from_static(b"more data"));
+
+        let (req, _) = TestRequest::post()
+            .to_request()
+            .replace_payload(dev::Payload::from(h1_payload));
+        let res = call_service(&srv, req).await;
+        assert_eq!(
+            crate::test::read_body(res).await,
+            r#"b"datamo" then Incomplete(None)"#
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_require_activity() {
+        use std::time::Duration;
+
+        use futures_util::FutureExt as _;
+
+        let (mut sender, h1_payload) = actix_http::h1::Payload::create(false);
+        let req = TestRequest::default().to_http_request();
+        let mut pl = dev::Payload::from(h1_payload);
+
+        let payload = Payload::from_request(&req, &mut pl).await.unwrap();
+        let mut payload = payload.require_activity(Duration::from_millis(100));
+
+        sender.feed_data(Bytes::from_static(b"data"));
+        assert_eq!(payload.next().await.unwrap().unwrap(), "data");
+
+        // gaps shorter than the interval are fine
+        actix_rt::time::sleep(Duration::from_millis(50)).await;
+        sender.feed_data(Bytes::from_static(b"more"));
+        assert_eq!(payload.next().await.unwrap().unwrap(), "more");
+
+        // reader is busy for longer than the interval but a chunk is already buffered
+        sender.feed_data(Bytes::from_static(b"buffered"));
+        actix_rt::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(payload.next().await.unwrap().unwrap(), "buffered");
+
+        // reader is busy for longer than the interval and no chunk has arrived
+        actix_rt::time::sleep(Duration::from_millis(150)).await;
+        match payload.next().now_or_never().unwrap().unwrap() {
+            Err(PayloadError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::TimedOut),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        assert!(payload.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_prefetch() {
+        use futures_util::FutureExt as _;
+
+        let (mut sender, h1_payload) = actix_http::h1::Payload::create(false);
+        let req = TestRequest::default().to_http_request();
+        let mut pl = dev::Payload::from(h1_payload);
+
+        // without prefetching, the body is only read once the handler polls the payload
+        let mut payload = Payload::from_request(&req, &mut pl).await.unwrap();
+        assert!(payload.next().now_or_never().is_none());
+
+        let (mut sender2, h1_payload) = actix_http::h1::Payload::create(false);
+        let (req, _) = TestRequest::default()
+            .app_data(PayloadConfig::default().prefetch(true))
+            .to_http_parts();
+        let mut pl = dev::Payload::from(h1_payload);
+
+        let mut fut = Payload::from_request(&req, &mut pl);
+        assert!((&mut fut).now_or_never().is_none());
+
+        sender2.feed_data(Bytes::from_static(b"data"));
+        let mut payload = fut.await.unwrap();
+
+        // first read completes immediately
+        let chunk = payload.next().now_or_never().unwrap().unwrap().unwrap();
+        assert_eq!(chunk, "data");
+
+        sender.feed_eof();
+        sender2.feed_eof();
+        assert!(payload.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_payload_contiguous() {
+        let chunks = || {
+            futures_util::stream::iter([&b"con"[..], b"tig", b"uous"])
+                .map(|chunk| Ok(Bytes::from_static(chunk)))
+        };
+        let payload = |req: TestRequest| {
+            let (req, _) = req.to_http_parts();
+            let mut pl = dev::Payload::from(Box::pin(chunks()) as BoxedPayloadStream);
+            Payload::from_request(&req, &mut pl)
+        };
+
+        let req = TestRequest::default()
+            .app_data(PayloadConfig::default().contiguous_up_to(10))
+            .insert_header((header::CONTENT_LENGTH, 10));
+        let mut body = payload(req).await.unwrap();
+
+        // small bodies are yielded as one chunk
+        assert_eq!(body.next().await.unwrap().unwrap(), "contiguous");
+        assert!(body.next().await.is_none());
+
+        // larger bodies are streamed
+        let req = TestRequest::default()
+            .app_data(PayloadConfig::default().contiguous_up_to(9))
+            .insert_header((header::CONTENT_LENGTH, 10));
+        let body = payload(req).await.unwrap();
+        assert_eq!(body.collect::<Vec<_>>().await.len(), 3);
+
+        // as are bodies of unknown length
+        let req =
+            TestRequest::default().app_data(PayloadConfig::default().contiguous_up_to(10));
+        let body = payload(req).await.unwrap();
+        assert_eq!(body.collect::<Vec<_>>().await.len(), 3);
+    }
+
     // allow deprecated App::data
     #[allow(deprecated)]
     #[actix_rt::test]
@@ -476,6 +945,61 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[cfg(feature = "content-md5")]
+    #[actix_rt::test]
+    async fn test_verify_content_md5() {
+        use futures_util::TryStreamExt as _;
+
+        // base64 encoded MD5 digest of "hello world"
+        const DIGEST: &str = "XrY7u+Ae7tCTyyK7j1rNww==";
+
+        let srv = init_service(
+            App::new()
+                .app_data(PayloadConfig::default().verify_content_md5(true))
+                .route("/", web::post().to(|body: Bytes| async move { body })),
+        )
+        .await;
+
+        let req = TestRequest::post()
+            .insert_header(("content-md5", DIGEST))
+            .set_payload("hello world")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(crate::test::read_body(res).await, "hello world");
+
+        let req = TestRequest::post()
+            .insert_header(("content-md5", DIGEST))
+            .set_payload("hello, world")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post().set_payload("hello, world").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // streamed payloads yield all chunks before the mismatch is reported
+        let (mut sender, h1_payload) = actix_http::h1::Payload::create(false);
+        sender.feed_data(Bytes::from_static(b"hello "));
+        sender.feed_data(Bytes::from_static(b"there"));
+        sender.feed_eof();
+
+        let req = TestRequest::default()
+            .insert_header(("content-md5", DIGEST))
+            .app_data(PayloadConfig::default().verify_content_md5(true))
+            .to_http_request();
+        let mut pl = dev::Payload::from(h1_payload);
+        let mut payload = Payload::from_request(&req, &mut pl).await.unwrap();
+
+        assert_eq!(payload.try_next().await.unwrap().unwrap(), "hello ");
+        assert_eq!(payload.try_next().await.unwrap().unwrap(), "there");
+        assert!(matches!(
+            payload.try_next().await,
+            Err(PayloadError::ChecksumMismatch)
+        ));
+    }
+
     #[actix_rt::test]
     async fn test_bytes() {
         let (req, mut pl) = TestRequest::default()
@@ -0,0 +1,140 @@
+//! For range header extractor documentation, see [`RangeHeader`].
+
+use std::future::{ready, Ready};
+
+use crate::{
+    dev::Payload,
+    error::ParseError,
+    http::header::{self, ByteRangeSpec},
+    FromRequest, HttpRequest,
+};
+
+/// Extractor for the byte ranges requested with the `Range` header, as defined in RFC 7233.
+///
+/// Holds `None` if the request has no `Range` header or if it uses a range unit other than
+/// `bytes`, either of which should be answered with the full representation. Unlike parsing
+/// [`header::Range`] with [`Header`](crate::web::Header), every range in the set must be valid;
+/// otherwise, extraction fails with a [`ParseError`], which responds with `400 Bad Request`.
+///
+/// # Examples
+/// ```
+/// use actix_web::{get, http::header::ByteRangeSpec, web};
+///
+/// #[get("/")]
+/// async fn index(range: web::RangeHeader) -> String {
+///     match range.ranges() {
+///         Some([ByteRangeSpec::FromTo(from, to)]) => format!("bytes {} to {}", from, to),
+///         Some(_) => "other ranges".to_owned(),
+///         None => "full content".to_owned(),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeHeader(pub Option<Vec<ByteRangeSpec>>);
+
+impl RangeHeader {
+    /// Returns the requested byte ranges, in the order they were listed, or `None` if no byte
+    /// ranges were requested.
+    pub fn ranges(&self) -> Option<&[ByteRangeSpec]> {
+        self.0.as_deref()
+    }
+
+    /// Unwrap into the inner list of byte ranges.
+    pub fn into_inner(self) -> Option<Vec<ByteRangeSpec>> {
+        self.0
+    }
+
+    fn parse(req: &HttpRequest) -> Result<Self, ParseError> {
+        let val = match req.headers().get(header::RANGE) {
+            Some(val) => val.to_str().map_err(|_| ParseError::Header)?,
+            None => return Ok(RangeHeader(None)),
+        };
+
+        let (unit, set) = val.trim().split_once('=').ok_or(ParseError::Header)?;
+
+        // other range units may be ignored, as allowed by RFC 7233 §3.1
+        if !unit.eq_ignore_ascii_case("bytes") {
+            return Ok(RangeHeader(None));
+        }
+
+        let ranges = set
+            .split(',')
+            .map(str::trim)
+            // list syntax allows empty elements, see RFC 7230 §7
+            .filter(|spec| !spec.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<ByteRangeSpec>, _>>()?;
+
+        if ranges.is_empty() {
+            return Err(ParseError::Header);
+        }
+
+        Ok(RangeHeader(Some(ranges)))
+    }
+}
+
+impl FromRequest for RangeHeader {
+    type Error = ParseError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Self::parse(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestRequest;
+
+    async fn extract(range: Option<&str>) -> Result<RangeHeader, ParseError> {
+        let mut req = TestRequest::default();
+        if let Some(range) = range {
+            req = req.insert_header((header::RANGE, range));
+        }
+
+        let (req, mut pl) = req.to_http_parts();
+        RangeHeader::from_request(&req, &mut pl).await
+    }
+
+    #[actix_rt::test]
+    async fn byte_ranges() {
+        let range = extract(Some("bytes=0-499")).await.unwrap();
+        assert_eq!(range.ranges(), Some(&[ByteRangeSpec::FromTo(0, 499)][..]));
+
+        let range = extract(Some("bytes=500-")).await.unwrap();
+        assert_eq!(range.ranges(), Some(&[ByteRangeSpec::From(500)][..]));
+
+        let range = extract(Some("bytes=-200")).await.unwrap();
+        assert_eq!(range.ranges(), Some(&[ByteRangeSpec::Last(200)][..]));
+
+        let range = extract(Some("bytes=0-0, -1,,")).await.unwrap();
+        assert_eq!(
+            range.into_inner().unwrap(),
+            vec![ByteRangeSpec::FromTo(0, 0), ByteRangeSpec::Last(1)]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn absent_or_ignored() {
+        assert_eq!(extract(None).await.unwrap(), RangeHeader(None));
+        assert_eq!(extract(Some("items=0-9")).await.unwrap(), RangeHeader(None));
+    }
+
+    #[actix_rt::test]
+    async fn malformed() {
+        for range in [
+            "bytes",
+            "bytes=",
+            "bytes=,",
+            "bytes=abc",
+            "bytes=500-499",
+            "bytes=0-499,x-",
+            "bytes=--1",
+        ] {
+            let err = extract(Some(range)).await.unwrap_err();
+            assert!(matches!(err, ParseError::Header), "{}", range);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! For UTF-8 payload stream documentation, see [`Utf8Payload`].
+
+use std::{
+    convert::TryFrom as _,
+    pin::Pin,
+    str,
+    task::{Context, Poll},
+};
+
+use bytes::{Bytes, BytesMut};
+use bytestring::ByteString;
+use futures_core::{ready, stream::Stream};
+
+use super::decode_stream::{ChunkDecoder, DecodeStream};
+use crate::{
+    dev,
+    error::{PayloadError, Utf8PayloadError},
+};
+
+/// Stream that validates a payload as UTF-8 text while it is read.
+///
+/// Chunks are yielded as [`ByteString`]s as soon as they arrive. A multi-byte character split
+/// across chunk boundaries is held back until its remaining bytes arrive and is then yielded as
+/// part of the next chunk. Invalid UTF-8 fails the stream immediately with
+/// [`Utf8PayloadError::InvalidUtf8`], without reading or buffering the rest of the payload.
+///
+/// # Examples
+/// ```
+/// use actix_web::{post, web, Error};
+/// use futures_util::StreamExt as _;
+///
+/// #[post("/")]
+/// async fn count_chars(body: web::Payload) -> Result<String, Error> {
+///     let mut text = web::Utf8Payload::new(body);
+///     let mut chars = 0;
+///
+///     while let Some(chunk) = text.next().await {
+///         chars += chunk?.chars().count();
+///     }
+///
+///     Ok(chars.to_string())
+/// }
+/// ```
+pub struct Utf8Payload<S = dev::Payload> {
+    inner: DecodeStream<S, Utf8Decoder>,
+}
+
+impl<S> Utf8Payload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    /// Constructs a new `Utf8Payload` stream that validates `payload`'s chunks.
+    pub fn new(payload: S) -> Self {
+        let decoder = Utf8Decoder {
+            tail: BytesMut::new(),
+            offset: 0,
+        };
+
+        Self {
+            inner: DecodeStream::new(payload, decoder),
+        }
+    }
+
+    /// Unwrap into the inner payload stream.
+    ///
+    /// Bytes of an incomplete character held back from the last chunk are lost.
+    pub fn into_inner(self) -> S {
+        self.inner.stream
+    }
+}
+
+impl<S> Stream for Utf8Payload<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<ByteString, Utf8PayloadError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = ready!(Pin::new(&mut self.inner).poll_next(cx));
+
+        // chunks are validated by the decoder
+        Poll::Ready(res.map(|res| res.map(|chunk| ByteString::try_from(chunk).unwrap())))
+    }
+}
+
+struct Utf8Decoder {
+    /// Bytes of an incomplete multi-byte character at the end of the last chunk.
+    tail: BytesMut,
+    /// Number of bytes yielded so far.
+    offset: usize,
+}
+
+impl ChunkDecoder for Utf8Decoder {
+    type Error = Utf8PayloadError;
+
+    /// Validates `chunk`, prefixed by any held back bytes, returning its complete characters.
+    fn decode(&mut self, chunk: Bytes) -> Result<Bytes, Utf8PayloadError> {
+        let mut chunk = if self.tail.is_empty() {
+            chunk
+        } else {
+            self.tail.extend_from_slice(&chunk);
+            self.tail.split().freeze()
+        };
+
+        let valid_up_to = match str::from_utf8(&chunk) {
+            Ok(_) => chunk.len(),
+
+            // an incomplete sequence at the end is completed by the next chunk
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+
+            Err(err) => {
+                return Err(Utf8PayloadError::InvalidUtf8 {
+                    offset: self.offset + err.valid_up_to(),
+                })
+            }
+        };
+
+        self.tail.extend_from_slice(&chunk.split_off(valid_up_to));
+        self.offset += chunk.len();
+
+        Ok(chunk)
+    }
+
+    fn finish(&mut self) -> Result<Bytes, Utf8PayloadError> {
+        if self.tail.is_empty() {
+            Ok(Bytes::new())
+        } else {
+            Err(Utf8PayloadError::Incomplete)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::StreamExt as _;
+
+    use super::*;
+    use crate::types::decode_stream::chunk_stream;
+
+    #[actix_rt::test]
+    async fn split_character() {
+        // "é" is encoded as 0xC3 0xA9, "€" as 0xE2 0x82 0xAC
+        let mut text = Utf8Payload::new(chunk_stream(vec![
+            b"caf\xC3", b"\xA9 ", b"\xE2", b"\x82", b"\xAC!",
+        ]));
+
+        assert_eq!(text.next().await.unwrap().unwrap(), "caf");
+        assert_eq!(text.next().await.unwrap().unwrap(), "é ");
+        assert_eq!(text.next().await.unwrap().unwrap(), "€!");
+        assert!(text.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn invalid_sequence() {
+        let mut text = Utf8Payload::new(chunk_stream(vec![
+            b"caf\xC3",
+            b"\xA9",
+            b"ok \xC3\x28",
+            b"never read",
+        ]));
+
+        assert_eq!(text.next().await.unwrap().unwrap(), "caf");
+        assert_eq!(text.next().await.unwrap().unwrap(), "é");
+
+        match text.next().await.unwrap().unwrap_err() {
+            Utf8PayloadError::InvalidUtf8 { offset } => assert_eq!(offset, 8),
+            err => panic!("unexpected error: {}", err),
+        }
+
+        assert!(text.next().await.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn incomplete_sequence() {
+        let mut text = Utf8Payload::new(chunk_stream(vec![b"caf\xC3"]));
+
+        assert_eq!(text.next().await.unwrap().unwrap(), "caf");
+        assert!(matches!(
+            text.next().await.unwrap(),
+            Err(Utf8PayloadError::Incomplete)
+        ));
+        assert!(text.next().await.is_none());
+    }
+}
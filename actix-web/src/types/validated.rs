@@ -0,0 +1,291 @@
+//! For validating extractor documentation, see [`Validate`].
+
+use std::{
+    future::Future,
+    ops,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::ready;
+use pin_project_lite::pin_project;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    dev::Payload,
+    error::ValidationErrors,
+    web::{Json, Path, Query},
+    Error, FromRequest, HttpRequest,
+};
+
+/// Validation of extracted values.
+///
+/// Types implementing `Validate` can be extracted with [`ValidatedQuery`], [`ValidatedPath`]
+/// and [`ValidatedJson`], which run [`validate`](Self::validate) after deserialization. Values
+/// that fail validation are rejected with a [`ValidationErrors`] error, responding with `400 Bad
+/// Request` and a body listing each failing field.
+///
+/// Implementations may check fields by hand or delegate to a validation library.
+///
+/// # Examples
+/// ```
+/// use actix_web::{error::ValidationErrors, get, web};
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Pagination {
+///     page: u32,
+///     per_page: u32,
+/// }
+///
+/// impl web::Validate for Pagination {
+///     fn validate(&self) -> Result<(), ValidationErrors> {
+///         let mut errors = ValidationErrors::new();
+///
+///         if self.page == 0 {
+///             errors.add("page", "must be at least 1");
+///         }
+///
+///         if !(1..=100).contains(&self.per_page) {
+///             errors.add("per_page", "must be between 1 and 100");
+///         }
+///
+///         errors.into_result()
+///     }
+/// }
+///
+/// // a request to `/?page=0&per_page=500` is rejected with both field errors
+/// #[get("/")]
+/// async fn index(page: web::ValidatedQuery<Pagination>) -> String {
+///     format!("page {} ({} per page)", page.page, page.per_page)
+/// }
+/// ```
+pub trait Validate {
+    /// Checks this value, returning the failures of all invalid fields.
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+macro_rules! validated_extractor {
+    ($(#[$meta:meta])* $name:ident, $inner:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+        pub struct $name<T>(pub T);
+
+        impl<T> $name<T> {
+            /// Unwrap into inner `T` value.
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+
+        impl<T> ops::Deref for $name<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> ops::DerefMut for $name<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.0
+            }
+        }
+
+        impl<T: DeserializeOwned + Validate> FromRequest for $name<T> {
+            type Error = Error;
+            type Future =
+                ValidatedExtractFut<<$inner<T> as FromRequest>::Future, $inner<T>, Self>;
+
+            #[inline]
+            fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+                ValidatedExtractFut {
+                    fut: $inner::<T>::from_request(req, payload),
+                    wrap: |val| $name(val.into_inner()),
+                    req: req.clone(),
+                }
+            }
+        }
+    };
+}
+
+validated_extractor!(
+    /// Query string extractor that [validates](Validate) the deserialized value.
+    ///
+    /// Behaves like [`Query`] otherwise, including its use of
+    /// [`QueryConfig`](crate::web::QueryConfig).
+    ValidatedQuery,
+    Query
+);
+
+validated_extractor!(
+    /// Path segment extractor that [validates](Validate) the deserialized value.
+    ///
+    /// Behaves like [`Path`] otherwise, including its use of
+    /// [`PathConfig`](crate::web::PathConfig).
+    ValidatedPath,
+    Path
+);
+
+validated_extractor!(
+    /// JSON body extractor that [validates](Validate) the deserialized value.
+    ///
+    /// Behaves like [`Json`] otherwise, including its use of
+    /// [`JsonConfig`](crate::web::JsonConfig).
+    ValidatedJson,
+    Json
+);
+
+pin_project! {
+    /// Future for the validating extractors; see [`Validate`].
+    pub struct ValidatedExtractFut<F, X, W> {
+        #[pin]
+        fut: F,
+        wrap: fn(X) -> W,
+        req: HttpRequest,
+    }
+}
+
+impl<F, X, W, T> Future for ValidatedExtractFut<F, X, W>
+where
+    F: Future<Output = Result<X, Error>>,
+    X: ops::Deref<Target = T>,
+    T: Validate,
+{
+    type Output = Result<W, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let val = ready!(this.fut.poll(cx))?;
+
+        if let Err(err) = val.validate() {
+            log::debug!(
+                "Failed during validation of extracted value. Request path: {:?}",
+                this.req.path()
+            );
+
+            return Poll::Ready(Err(err.into()));
+        }
+
+        Poll::Ready(Ok((this.wrap)(val)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::{
+        http::{header, StatusCode},
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App, ResponseError as _,
+    };
+
+    #[derive(Debug, Deserialize)]
+    struct Person {
+        name: String,
+        age: u32,
+    }
+
+    impl Validate for Person {
+        fn validate(&self) -> Result<(), ValidationErrors> {
+            let mut errors = ValidationErrors::new();
+
+            if self.name.is_empty() {
+                errors.add("name", "must not be empty");
+            }
+
+            if !(1..=150).contains(&self.age) {
+                errors.add("age", "must be between 1 and 150");
+            }
+
+            errors.into_result()
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_validated_query() {
+        let req = TestRequest::with_uri("/?name=ferris&age=7").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let person = ValidatedQuery::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap();
+        assert_eq!(person.name, "ferris");
+        assert_eq!(person.age, 7);
+
+        let req = TestRequest::with_uri("/?name=&age=200").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let err = ValidatedQuery::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        let errors = err.as_error::<ValidationErrors>().unwrap();
+        assert_eq!(errors.errors().len(), 2);
+        assert_eq!(errors.errors()[1].field(), "age");
+
+        let res = errors.error_response();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let body = crate::body::to_bytes(res.into_body()).await.unwrap();
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+            serde_json::json!({
+                "errors": [
+                    { "field": "name", "message": "must not be empty" },
+                    { "field": "age", "message": "must be between 1 and 150" },
+                ]
+            })
+        );
+
+        // deserialization errors are not affected
+        let req = TestRequest::with_uri("/?name=ferris").to_srv_request();
+        let (req, mut pl) = req.into_parts();
+        let err = ValidatedQuery::<Person>::from_request(&req, &mut pl)
+            .await
+            .unwrap_err();
+        assert!(err.as_error::<ValidationErrors>().is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_validated_path_and_json() {
+        let srv = init_service(
+            App::new()
+                .route(
+                    "/{name}/{age}",
+                    web::get().to(|person: ValidatedPath<Person>| async move {
+                        person.into_inner().name
+                    }),
+                )
+                .route(
+                    "/",
+                    web::post().to(|person: ValidatedJson<Person>| async move {
+                        person.age.to_string()
+                    }),
+                ),
+        )
+        .await;
+
+        let res = call_service(&srv, TestRequest::get().uri("/ferris/7").to_request()).await;
+        assert_eq!(read_body(res).await, "ferris");
+
+        let res = call_service(&srv, TestRequest::get().uri("/ferris/0").to_request()).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_json(serde_json::json!({ "name": "ferris", "age": 7 }))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, "7");
+
+        let req = TestRequest::post()
+            .uri("/")
+            .set_json(serde_json::json!({ "name": "ferris", "age": 151 }))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+    }
+}
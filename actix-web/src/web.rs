@@ -2,6 +2,7 @@
 //!
 //! # Request Extractors
 //! - [`Data`]: Application data item
+//! - [`LazyData`]: Lazily initialized application data item
 //! - [`ReqData`]: Request-local data item
 //! - [`Path`]: URL path parameters / dynamic segments
 //! - [`Query`]: URL query parameters
@@ -15,6 +16,8 @@
 //! - [`Form`]: URL-encoded response
 //! - [`Bytes`]: Raw bytes response
 //! - [`Redirect`](Redirect::to): Convenient redirect responses
+//! - [`Ranged`]: Byte range responses from seekable sources
+//! - [`Negotiate`]: Responses in the format preferred by the `Accept` header
 
 use std::{borrow::Cow, future::Future};
 
@@ -27,9 +30,11 @@ use crate::{
 };
 
 pub use crate::config::ServiceConfig;
-pub use crate::data::Data;
+pub use crate::data::{Data, LazyData};
 pub use crate::redirect::Redirect;
 pub use crate::request_data::ReqData;
+pub use crate::response::{Negotiate, RangeSource, Ranged};
+pub use crate::schema::{BodySchema, RouteSchema, SchemaRegistry};
 pub use crate::types::*;
 
 /// Creates a new resource for a specific path.
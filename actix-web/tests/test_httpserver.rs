@@ -2,6 +2,8 @@
 
 #[cfg(feature = "openssl")]
 extern crate tls_openssl as openssl;
+#[cfg(feature = "rustls")]
+extern crate tls_rustls as rustls;
 
 #[cfg(any(unix, feature = "openssl"))]
 use {
@@ -135,3 +137,705 @@ async fn test_start_ssl() {
 
     srv.stop(false).await;
 }
+
+#[cfg(feature = "rustls")]
+fn rustls_config(host: &str) -> (rustls::ServerConfig, rustls::Certificate) {
+    use rustls::{Certificate, PrivateKey, ServerConfig};
+
+    let cert = rcgen::generate_simple_self_signed(vec![host.to_owned()]).unwrap();
+    let cert_der = Certificate(cert.serialize_der().unwrap());
+    let key_der = PrivateKey(cert.serialize_private_key_der());
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(vec![cert_der.clone()], key_der)
+        .unwrap();
+
+    (config, cert_der)
+}
+
+#[actix_rt::test]
+#[cfg(feature = "rustls")]
+async fn test_virtual_hosts() {
+    use std::{
+        convert::TryFrom as _,
+        io::{Read as _, Write as _},
+        sync::Arc,
+    };
+
+    use actix_web::HttpRequest;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerName, StreamOwned};
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    let (fallback_config, fallback_cert) = rustls_config("other.localhost");
+    let (a_config, a_cert) = rustls_config("a.localhost");
+    let (b_config, b_cert) = rustls_config("b.localhost");
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().default_service(web::to(|| async { "fallback" }))
+                })
+                .virtual_host("a.localhost", a_config, || {
+                    App::new()
+                        .default_service(web::to(|req: HttpRequest| async move {
+                            format!("a {}", req.url_for_static("item").unwrap())
+                        }))
+                        .service(web::resource("/item").name("item"))
+                })
+                .virtual_host("B.localhost", b_config, || {
+                    App::new().default_service(web::to(|| async { "b" }))
+                })
+                .workers(1)
+                .disable_signals()
+                .bind_rustls(addr, fallback_config)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    // handshakes only succeed if the server presents the certificate of `host`
+    let get = |sni: &str, host: &str, cert: &rustls::Certificate| {
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+
+        let conn = ClientConnection::new(Arc::new(config), ServerName::try_from(sni).unwrap())
+            .unwrap();
+        let mut stream = StreamOwned::new(conn, std::net::TcpStream::connect(addr).unwrap());
+
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            host
+        )
+        .unwrap();
+
+        let mut res = String::new();
+        let _ = stream.read_to_string(&mut res);
+        res
+    };
+
+    let res = get("a.localhost", "a.localhost", &a_cert);
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+    assert!(res.ends_with("a https://a.localhost/item"), "{}", res);
+
+    let res = get("b.localhost", "b.localhost:443", &b_cert);
+    assert!(res.ends_with("\r\n\r\nb"), "{}", res);
+
+    let res = get("other.localhost", "other.localhost", &fallback_cert);
+    assert!(res.ends_with("\r\n\r\nfallback"), "{}", res);
+
+    // request for another host over the connection of `a.localhost`
+    let res = get("a.localhost", "b.localhost", &a_cert);
+    assert!(
+        res.starts_with("HTTP/1.1 421 Misdirected Request\r\n"),
+        "{}",
+        res
+    );
+
+    srv.stop(false).await;
+}
+
+#[actix_rt::test]
+async fn test_server_options() {
+    use std::io::{Read as _, Write as _};
+
+    use actix_web::http::{header, Method};
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = actix_web::HttpServer::new(|| {
+                    actix_web::App::new().default_service(actix_web::web::to(|| async {
+                        actix_web::HttpResponse::NotFound().body("routed")
+                    }))
+                })
+                .workers(1)
+                .disable_signals()
+                .server_options_handler(|| {
+                    actix_web::HttpResponse::Ok()
+                        .insert_header(header::Allow(vec![Method::GET, Method::POST]))
+                        .finish()
+                })
+                .bind(addr)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"OPTIONS * HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+    assert!(
+        res.to_lowercase().contains("allow: get, post\r\n"),
+        "{}",
+        res
+    );
+    assert!(!res.contains("routed"), "{}", res);
+
+    srv.stop(false).await;
+}
+
+#[actix_rt::test]
+async fn test_proxy_protocol() {
+    use std::io::{Read as _, Write as _};
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().default_service(web::to(
+                        |req: actix_web::HttpRequest| async move {
+                            let info = req.connection_info();
+                            format!("{:?} {:?}", info.realip_remote_addr(), req.peer_addr())
+                        },
+                    ))
+                })
+                .workers(1)
+                .disable_signals()
+                .proxy_protocol(true)
+                .bind(addr)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"PROXY TCP4 192.0.2.1 198.51.100.2 56324 80\r\n")
+        .unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+    assert!(
+        res.ends_with(r#"Some("192.0.2.1") Some(192.0.2.1:56324)"#),
+        "{}",
+        res
+    );
+
+    // connections without a header are closed before any request is handled
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut res = String::new();
+    let _ = stream.read_to_string(&mut res);
+    assert!(res.is_empty(), "{}", res);
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_graceful_shutdown_completes_upload() {
+    use std::{
+        io::{Read as _, Write as _},
+        time::Instant,
+    };
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().route(
+                        "/",
+                        web::post()
+                            .to(|body: web::Bytes| async move { body.len().to_string() }),
+                    )
+                })
+                .workers(1)
+                .shutdown_timeout(5)
+                .disable_signals()
+                .bind(addr)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(
+            b"POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10\r\n\
+            Connection: close\r\n\r\nhello",
+        )
+        .unwrap();
+    actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+    // stop the server while the upload is still in progress
+    let start = Instant::now();
+    let stopped = actix_rt::spawn(srv.stop(true));
+    actix_rt::time::sleep(Duration::from_millis(200)).await;
+
+    stream.write_all(b"world").unwrap();
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+    assert!(res.ends_with("\r\n\r\n10"), "{}", res);
+
+    stopped.await.unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(unix)]
+fn start_limited_server(
+    behavior: actix_web::dev::ExcessConnections,
+    queue: Option<(usize, Duration)>,
+) -> (std::net::SocketAddr, actix_web::dev::ServerHandle) {
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let mut srv = HttpServer::new(|| {
+                    App::new().route(
+                        "/",
+                        web::get().to(|| async { HttpResponse::Ok().body("ok") }),
+                    )
+                })
+                .workers(1)
+                .max_connections_per_worker(1)
+                .excess_connections(behavior)
+                .disable_signals();
+
+                if let Some((capacity, max_wait)) = queue {
+                    srv = srv.request_queue(capacity, max_wait);
+                }
+
+                let srv = srv.bind(addr).unwrap().run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    (addr, rx.recv().unwrap())
+}
+
+/// Sends a keep-alive request and reads its response, which has a body of "ok" when successful.
+#[cfg(unix)]
+fn get(stream: &mut std::net::TcpStream) -> std::io::Result<String> {
+    use std::io::Write as _;
+
+    stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    read_response(stream)
+}
+
+#[cfg(unix)]
+fn read_response(stream: &mut std::net::TcpStream) -> std::io::Result<String> {
+    use std::io::Read as _;
+
+    let mut res = Vec::new();
+    let mut buf = [0; 1024];
+
+    while !res.ends_with(b"ok") && !res.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut buf)? {
+            0 => break,
+            n => res.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    Ok(String::from_utf8(res).unwrap())
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_max_connections_per_worker_reject() {
+    use std::{io::Read as _, net::TcpStream};
+
+    let (addr, srv) = start_limited_server(actix_web::dev::ExcessConnections::Reject, None);
+
+    let mut first = TcpStream::connect(addr).unwrap();
+    let res = get(&mut first).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // connections beyond the limit are accepted, responded with 503 and closed
+    for _ in 0..3 {
+        let mut excess = TcpStream::connect(addr).unwrap();
+        let mut res = get(&mut excess).unwrap();
+        excess.read_to_string(&mut res).unwrap();
+        assert!(
+            res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{}",
+            res
+        );
+        assert!(res.contains("connection: close\r\n"), "{}", res);
+    }
+
+    // the connection holding the slot is still served
+    let res = get(&mut first).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // closing it frees up the slot
+    drop(first);
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+
+    let mut next = TcpStream::connect(addr).unwrap();
+    let res = get(&mut next).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_max_connections_per_worker_shared_by_listeners() {
+    use std::{io::Read as _, net::TcpStream};
+
+    let addrs = [actix_test::unused_addr(), actix_test::unused_addr()];
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().route(
+                        "/",
+                        web::get().to(|| async { HttpResponse::Ok().body("ok") }),
+                    )
+                })
+                .workers(1)
+                .max_connections_per_worker(1)
+                .disable_signals()
+                .bind(addrs[0])
+                .unwrap()
+                .bind(addrs[1])
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    let mut first = TcpStream::connect(addrs[0]).unwrap();
+    let res = get(&mut first).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // the slot is taken by a connection to the other listener
+    let mut excess = TcpStream::connect(addrs[1]).unwrap();
+    let mut res = get(&mut excess).unwrap();
+    excess.read_to_string(&mut res).unwrap();
+    assert!(
+        res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        res
+    );
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_max_connections_per_worker_hold() {
+    use std::{io, net::TcpStream};
+
+    let (addr, srv) = start_limited_server(actix_web::dev::ExcessConnections::Hold, None);
+
+    let mut first = TcpStream::connect(addr).unwrap();
+    let res = get(&mut first).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // requests on connections beyond the limit are held...
+    let mut held = TcpStream::connect(addr).unwrap();
+    held.set_read_timeout(Some(Duration::from_millis(300)))
+        .unwrap();
+    let err = get(&mut held).unwrap_err();
+    assert!(
+        matches!(
+            err.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ),
+        "{}",
+        err
+    );
+
+    // ...until the connection holding the slot closes
+    drop(first);
+
+    held.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let res = read_response(&mut held).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_request_queue() {
+    use std::{io, io::Read as _, net::TcpStream, time::Instant};
+
+    let (addr, srv) = start_limited_server(
+        actix_web::dev::ExcessConnections::Reject,
+        Some((1, Duration::from_secs(1))),
+    );
+
+    let mut first = TcpStream::connect(addr).unwrap();
+    let res = get(&mut first).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // a request beyond the limit is queued...
+    let mut queued = TcpStream::connect(addr).unwrap();
+    queued
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .unwrap();
+    let err = get(&mut queued).unwrap_err();
+    assert!(
+        matches!(
+            err.kind(),
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ),
+        "{}",
+        err
+    );
+
+    // ...while requests past the queue's capacity are rejected right away
+    let mut excess = TcpStream::connect(addr).unwrap();
+    let mut res = get(&mut excess).unwrap();
+    excess.read_to_string(&mut res).unwrap();
+    assert!(
+        res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        res
+    );
+
+    // the queued request is served once the connection holding the slot closes
+    drop(first);
+
+    queued
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let res = read_response(&mut queued).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+
+    // queued requests that do not get a slot in time are rejected
+    let start = Instant::now();
+    let mut waiting = TcpStream::connect(addr).unwrap();
+    let mut res = get(&mut waiting).unwrap();
+    waiting.read_to_string(&mut res).unwrap();
+    assert!(
+        res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        res
+    );
+    assert!(start.elapsed() >= Duration::from_millis(900));
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_memory_budget() {
+    use std::{
+        io::{self, Read as _, Write as _},
+        net::TcpStream,
+    };
+
+    let addr = actix_test::unused_addr();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        actix_rt::System::new()
+            .block_on(async {
+                let srv = HttpServer::new(|| {
+                    App::new().route(
+                        "/",
+                        // hold on to the payload without reading it
+                        web::post().to(|body: web::Payload| async move {
+                            actix_rt::time::sleep(Duration::from_secs(60)).await;
+                            drop(body);
+                            HttpResponse::Ok().finish()
+                        }),
+                    )
+                })
+                .workers(1)
+                .memory_budget(80 * 1024)
+                .disable_signals()
+                .bind(addr)
+                .unwrap()
+                .run();
+
+                tx.send(srv.handle()).unwrap();
+
+                srv.await
+            })
+            .unwrap();
+    });
+
+    let srv = rx.recv().unwrap();
+
+    // starts a request whose body is fully buffered by the server
+    let upload = |len: usize| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n",
+            len
+        )
+        .unwrap();
+        stream.write_all(&vec![b'x'; len]).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_millis(300)))
+            .unwrap();
+        stream
+    };
+
+    let mut large = upload(48 * 1024);
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+    let mut small = upload(8 * 1024);
+    actix_rt::time::sleep(Duration::from_millis(100)).await;
+
+    // exceeding the budget evicts the connection using the most memory
+    let mut medium = upload(32 * 1024);
+
+    large
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut res = String::new();
+    large.read_to_string(&mut res).unwrap();
+    assert!(
+        res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        res
+    );
+    assert!(res.contains("connection: close\r\n"), "{}", res);
+
+    // the other connections are still waiting on their responses
+    for stream in [&mut small, &mut medium] {
+        let err = stream.read(&mut [0; 64]).unwrap_err();
+        assert!(
+            matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            ),
+            "{}",
+            err
+        );
+    }
+
+    srv.stop(false).await;
+}
+
+#[cfg(unix)]
+#[actix_rt::test]
+async fn test_bind_uds_peer_credentials() {
+    use std::{
+        io::{Read as _, Write as _},
+        os::unix::{fs::MetadataExt as _, net::UnixStream},
+    };
+
+    use actix_web::dev::ConnectionInfo;
+
+    let path = std::env::temp_dir().join(format!("actix-web-test-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let (tx, rx) = mpsc::channel();
+
+    {
+        let path = path.clone();
+
+        thread::spawn(move || {
+            actix_rt::System::new()
+                .block_on(async {
+                    let srv = HttpServer::new(|| {
+                        App::new().route(
+                            "/",
+                            web::get().to(|conn: ConnectionInfo| async move {
+                                let cred = conn.peer_credentials().unwrap();
+                                HttpResponse::Ok().body(cred.uid().to_string())
+                            }),
+                        )
+                    })
+                    .workers(1)
+                    .disable_signals()
+                    .bind_uds(&path)
+                    .unwrap()
+                    .run();
+
+                    tx.send(srv.handle()).unwrap();
+
+                    srv.await
+                })
+                .unwrap();
+        });
+    }
+
+    let srv = rx.recv().unwrap();
+
+    // the socket is created by this process, so it is owned by the peer's user
+    let uid = std::fs::metadata(&path).unwrap().uid();
+
+    let mut stream = UnixStream::connect(&path).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+
+    let mut res = String::new();
+    stream.read_to_string(&mut res).unwrap();
+    assert!(res.starts_with("HTTP/1.1 200 OK"));
+    assert!(res.ends_with(&format!("\r\n\r\n{}", uid)));
+
+    srv.stop(true).await;
+    let _ = std::fs::remove_file(&path);
+}
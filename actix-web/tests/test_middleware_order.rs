@@ -0,0 +1,49 @@
+#![cfg(debug_assertions)]
+
+use std::cell::RefCell;
+
+use actix_web::{
+    middleware::{DefaultHeaders, Logger, NormalizePath},
+    test, web, App,
+};
+
+thread_local! {
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Captures log records of the `actix_web` crate on the thread they are logged on.
+struct TestLogger;
+
+impl log::Log for TestLogger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.target().starts_with("actix_web")
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if self.enabled(record.metadata()) {
+            LOGS.with(|logs| logs.borrow_mut().push(record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[actix_rt::test]
+async fn logs_middleware_order() {
+    log::set_logger(&TestLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let _srv = test::init_service(
+        App::new()
+            .wrap(DefaultHeaders::new())
+            .wrap(NormalizePath::trim())
+            .wrap(Logger::default())
+            .route("/", web::get().to(|| async { "hello" })),
+    )
+    .await;
+
+    let logs = LOGS.with(|logs| logs.take());
+    assert!(logs.iter().any(|line| line
+        == "middleware order, outermost first (requests flow forward, responses flow back): \
+            Logger -> NormalizePath -> DefaultHeaders -> app"));
+}
@@ -276,6 +276,42 @@ async fn test_body_br_streaming() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_body_stream_items() {
+    let srv = actix_test::start_with(actix_test::config().h1(), || {
+        App::new()
+            .service(web::resource("/ok").route(web::to(|| async {
+                let items = futures_util::stream::iter((1..=3).map(Ok::<_, Error>));
+                HttpResponse::Ok().stream_items(items, |n| format!("data: {}\n\n", n))
+            })))
+            .service(web::resource("/err").route(web::to(|| async {
+                use futures_util::{stream, StreamExt as _};
+
+                let items = stream::once(async { Ok(1) }).chain(stream::once(async {
+                    actix_rt::time::sleep(Duration::from_millis(50)).await;
+                    Err(actix_web::error::ErrorInternalServerError("stream failed"))
+                }));
+                HttpResponse::Ok().stream_items(items, |n| format!("data: {}\n\n", n))
+            })))
+    });
+
+    let mut res = srv.get("/ok").send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    let bytes = res.body().await.unwrap();
+    assert_eq!(bytes, "data: 1\n\ndata: 2\n\ndata: 3\n\n");
+
+    // stream error closes the connection without terminating the chunked body
+    let mut stream = std::net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(b"GET /err HTTP/1.1\r\n\r\n").unwrap();
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK"));
+    assert!(data.contains("data: 1\n\n"));
+    assert!(!data.ends_with("0\r\n\r\n"));
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn test_head_binary() {
     let srv = actix_test::start_with(actix_test::config().h1(), || {
@@ -297,6 +333,45 @@ async fn test_head_binary() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_auto_head() {
+    let srv =
+        actix_test::start_with(actix_test::config().h1(), || {
+            App::new().service(web::resource("/").auto_head(true).route(web::get().to(
+                || async {
+                    HttpResponse::Ok()
+                        .insert_header(("x-test", "auto-head"))
+                        .body(STR)
+                },
+            )))
+        });
+
+    let mut get_res = srv.get("/").send().await.unwrap();
+    assert_eq!(get_res.status(), StatusCode::OK);
+    assert_eq!(get_res.body().await.unwrap(), STR);
+
+    let mut head_res = srv.head("/").send().await.unwrap();
+    assert_eq!(head_res.status(), StatusCode::OK);
+    assert!(head_res.body().await.unwrap().is_empty());
+
+    for name in [header::CONTENT_LENGTH, header::CONTENT_TYPE] {
+        assert_eq!(head_res.headers().get(&name), get_res.headers().get(&name));
+    }
+    assert_eq!(head_res.headers().get("x-test").unwrap(), "auto-head");
+
+    // no body bytes are written to the connection
+    let mut stream = std::net::TcpStream::connect(srv.addr()).unwrap();
+    stream
+        .write_all(b"HEAD / HTTP/1.1\r\nconnection: close\r\n\r\n")
+        .unwrap();
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 200 OK"));
+    assert!(data.ends_with("\r\n\r\n"));
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn test_no_chunking() {
     let srv = actix_test::start_with(actix_test::config().h1(), || {
@@ -857,6 +932,35 @@ async fn test_slow_request() {
     srv.stop().await;
 }
 
+#[actix_rt::test]
+async fn test_slow_request_follow_up() {
+    use std::{net, thread};
+
+    let srv = actix_test::start_with(
+        actix_test::config().client_request_timeout(Duration::from_millis(200)),
+        || App::new().service(web::resource("/").route(web::to(HttpResponse::Ok))),
+    );
+
+    let mut stream = net::TcpStream::connect(srv.addr()).unwrap();
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+
+    let mut buf = [0; 1024];
+    let n = stream.read(&mut buf).unwrap();
+    assert!(buf[..n].starts_with(b"HTTP/1.1 200 OK"));
+
+    // send follow-up request head slowly, one line at a time
+    for line in ["GET / HTTP/1.1\r\n", "Host: localhost\r\n", "X-Slow: 1\r\n"] {
+        thread::sleep(Duration::from_millis(100));
+        let _ = stream.write_all(line.as_bytes());
+    }
+
+    let mut data = String::new();
+    let _ = stream.read_to_string(&mut data);
+    assert!(data.starts_with("HTTP/1.1 408 Request Timeout"));
+
+    srv.stop().await;
+}
+
 #[actix_rt::test]
 async fn test_normalize() {
     let srv = actix_test::start_with(actix_test::config().h1(), || {
@@ -946,3 +1050,64 @@ async fn test_accept_encoding_no_match() {
 
     srv.stop().await;
 }
+
+#[actix_rt::test]
+async fn test_reading_chunked_gzip_encoding() {
+    use futures_util::StreamExt as _;
+
+    let srv = actix_test::start_with(actix_test::config().h1(), || {
+        App::new()
+            .app_data(web::PayloadConfig::default().decompress(true))
+            .route(
+                "/bytes",
+                web::post().to(|body: Bytes| async move { HttpResponse::Ok().body(body) }),
+            )
+            .route(
+                "/payload",
+                web::post().to(|mut body: web::Payload| async move {
+                    let mut buf = web::BytesMut::new();
+                    while let Some(chunk) = body.next().await {
+                        buf.extend_from_slice(&chunk.unwrap());
+                    }
+                    HttpResponse::Ok().body(buf)
+                }),
+            )
+    });
+
+    let data = STR.repeat(10);
+    let encoded = utils::gzip::encode(&data);
+
+    for path in ["/bytes", "/payload"] {
+        let mut req = format!(
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nTransfer-Encoding: chunked\r\n\
+            Content-Encoding: gzip\r\nConnection: close\r\n\r\n",
+            path
+        )
+        .into_bytes();
+
+        // chunk boundaries at arbitrary points of the gzip stream
+        for chunk in encoded.chunks(7) {
+            req.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            req.extend_from_slice(chunk);
+            req.extend_from_slice(b"\r\n");
+        }
+        req.extend_from_slice(b"0\r\n\r\n");
+
+        let mut stream = std::net::TcpStream::connect(srv.addr()).unwrap();
+        stream.write_all(&req).unwrap();
+
+        let mut res = Vec::new();
+        stream.read_to_end(&mut res).unwrap();
+        let res = String::from_utf8(res).unwrap();
+
+        assert!(res.starts_with("HTTP/1.1 200 OK\r\n"), "{}", res);
+        assert!(
+            res.ends_with(&format!("\r\n\r\n{}", data)),
+            "{}: {}",
+            path,
+            res
+        );
+    }
+
+    srv.stop().await;
+}